@@ -0,0 +1,88 @@
+//! Counts the heap allocations of preparing the same frame of shapes, gradients, outlines and
+//! image items again and again, which reuses the vertices, indices and draws of the last frame and
+//! should allocate nothing once the first frames have grown them. Runs against a headless adapter
+//! and is skipped when none is available.
+//!
+//! Run with `cargo bench --bench allocations`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use wgpu_canvas::prelude::*;
+
+/// The system allocator counting the allocations and reallocations made through it.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe {System.alloc(layout)}
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe {System.dealloc(ptr, layout)}
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe {System.realloc(ptr, layout, new_size)}
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+/// Frames prepared before counting, which grow the buffers of the renderer to the frame.
+const WARM_UP: usize = 8;
+/// Frames counted.
+const FRAMES: usize = 100;
+
+/// A grid of `count` small shapes filled or stroked with the images, colors and gradients in turn.
+fn grid(count: usize, images: &[Image]) -> Vec<(Area, CanvasItem)> {
+    (0..count).map(|i| {
+        let area = Area(((i % 32) as f32 * 16.0, (i / 32) as f32 * 16.0), None);
+        let image = images[i % images.len()].clone();
+        let color = Color::from_hsv((i % 360) as f32, 1.0, 1.0);
+        match i % 9 {
+            0 => (area, CanvasItem::Image(Shape::Ellipse(0.0, (14.0, 14.0)), image, None)),
+            1 => (area, CanvasItem::Image(Shape::Rectangle(Border::default(), (14.0, 14.0)), image, None)),
+            2 => (area, CanvasItem::Image(Shape::RoundedRectangle(Border::default(), (14.0, 14.0), 4.0.into()), image, None)),
+            3 => (area, CanvasItem::ImageStroke(Shape::Ellipse(3.0, (14.0, 14.0)), image, None)),
+            4 => (area, CanvasItem::Shape(Shape::Ellipse(2.0, (14.0, 14.0)), color)),
+            5 => (area, CanvasItem::Shape(Shape::RoundedRectangle(Border::default(), (14.0, 14.0), 4.0.into()), color)),
+            6 => (area, CanvasItem::Fill(Shape::Rectangle(Border::default(), (14.0, 14.0)), Fill::Linear(color, Color::BLACK, 45.0, Easing::Linear))),
+            7 => (area, CanvasItem::Outline(Shape::Ellipse(0.0, (12.0, 12.0)), color, 1.0, 0.0)),
+            _ => (area, CanvasItem::TiledImage(image, (14.0, 14.0), (4.0, 4.0))),
+        }
+    }).collect()
+}
+
+fn main() {
+    let instance = wgpu::Instance::default();
+    let Some((device, queue)) = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default())).ok()
+        .and_then(|adapter| pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default())).ok()) else {
+        eprintln!("no adapter available, skipping the benchmark");
+        return;
+    };
+    let mut renderer = CanvasRenderer::new(&queue, &device, &FORMAT, wgpu::MultisampleState::default(), None);
+    let (mut image_atlas, mut font_atlas) = (ImageAtlas::default(), FontAtlas::default());
+    let images = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]].map(|color| image_atlas.add(RgbaImage::from_pixel(16, 16, image::Rgba(color))));
+
+    for count in [100, 1000] {
+        let items = grid(count, &images);
+        let mut prepare = || {
+            let items = items.clone();
+            let before = ALLOCATIONS.load(Ordering::Relaxed);
+            let errors = renderer.prepare(&device, &queue, 512.0, 512.0, &mut image_atlas, &mut font_atlas, items);
+            assert!(errors.is_empty(), "{errors:?}");
+            ALLOCATIONS.load(Ordering::Relaxed) - before
+        };
+        for _ in 0..WARM_UP {prepare();}
+        let allocations = (0..FRAMES).map(|_| prepare()).sum::<usize>();
+        println!("prepare/{count}: {} allocations per frame", allocations as f32 / FRAMES as f32);
+        assert_eq!(allocations, 0, "preparing an unchanged frame of {count} image items allocates");
+    }
+}
@@ -0,0 +1,168 @@
+//! Benchmarks of the hot paths of a frame. Shapes are rasterized on the GPU, so these run against
+//! a headless adapter and are skipped when none is available.
+//!
+//! Run with `cargo bench --bench shapes`, and with `--features rayon` to compare building frames
+//! across threads, which `build_frame_threads` measures on one thread and on every core.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use wgpu_canvas::prelude::*;
+
+struct Gpu {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    renderer: CanvasRenderer,
+    image_atlas: ImageAtlas,
+    font_atlas: FontAtlas,
+}
+
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+impl Gpu {
+    fn new() -> Option<Self> {
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default())).ok()?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default())).ok()?;
+        let renderer = CanvasRenderer::new(&queue, &device, &FORMAT, wgpu::MultisampleState::default(), None);
+        Some(Gpu{device, queue, renderer, image_atlas: ImageAtlas::default(), font_atlas: FontAtlas::default()})
+    }
+
+    fn target(&self, size: u32) -> wgpu::TextureView {
+        self.device.create_texture(&wgpu::TextureDescriptor{
+            label: None,
+            size: wgpu::Extent3d{width: size, height: size, depth_or_array_layers: 1},
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        }).create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    fn prepare(&mut self, size: u32, items: Vec<(Area, CanvasItem)>) {
+        let size = size as f32;
+        self.renderer.prepare(&self.device, &self.queue, size, size, &mut self.image_atlas, &mut self.font_atlas, items);
+    }
+
+    /// Render the prepared frame and wait for the GPU to finish it.
+    fn render(&self, target: &wgpu::TextureView) {
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor{
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment{
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations{load: wgpu::LoadOp::Clear(wgpu::Color::BLACK), store: wgpu::StoreOp::Store},
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.renderer.render(&mut render_pass);
+        }
+        self.queue.submit([encoder.finish()]);
+        self.device.poll(wgpu::PollType::Wait).unwrap();
+    }
+}
+
+/// A grid of `count` small shapes cycling through the shape kinds, half of them filled with `image`.
+fn grid(count: usize, image: &Image) -> Vec<(Area, CanvasItem)> {
+    let color = Color::from_rgb(40, 120, 255);
+    (0..count).map(|i| {
+        let area = Area(((i % 32) as f32 * 16.0, (i / 32) as f32 * 16.0), None);
+        let shape = match i % 4 {
+            0 => Shape::Ellipse(0.0, (14.0, 14.0)),
+            1 => Shape::Rectangle(Border::default(), (14.0, 14.0)),
+            2 => Shape::RoundedRectangle(2.0.into(), (14.0, 14.0), 4.0.into()),
+            _ => Shape::Star(0.0, (14.0, 14.0), 5, 0.5),
+        };
+        match i % 2 {
+            0 => (area, CanvasItem::Shape(shape, color)),
+            _ => (area, CanvasItem::Image(shape, image.clone(), None)),
+        }
+    }).collect()
+}
+
+fn benches(c: &mut Criterion) {
+    let Some(mut gpu) = Gpu::new() else {
+        eprintln!("no adapter available, skipping the benchmarks");
+        return;
+    };
+
+    let mut group = c.benchmark_group("ellipse_fill");
+    for size in [32, 128, 512] {
+        let target = gpu.target(size);
+        gpu.prepare(size, vec![(Area((0.0, 0.0), None), CanvasItem::Shape(Shape::Ellipse(0.0, (size as f32, size as f32)), Color::WHITE))]);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| b.iter(|| gpu.render(&target)));
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("image_upload");
+    for size in [64, 256, 1024] {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| b.iter_batched(
+            || RgbaImage::from_fn(size, size, |x, y| image::Rgba([x as u8, y as u8, (x ^ y) as u8, 255])),
+            |raw| {
+                let image = gpu.image_atlas.add(raw);
+                gpu.prepare(512, vec![(Area((0.0, 0.0), None), CanvasItem::Image(Shape::Rectangle(Border::default(), (512.0, 512.0)), image, None))]);
+            },
+            criterion::BatchSize::LargeInput
+        ));
+    }
+    group.finish();
+
+    let image = gpu.image_atlas.add(RgbaImage::from_pixel(16, 16, image::Rgba([255, 0, 0, 255])));
+    let target = gpu.target(512);
+
+    let mut group = c.benchmark_group("prepare");
+    for count in [100, 1000] {
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, &count| b.iter_batched(
+            || grid(count, &image),
+            |items| gpu.prepare(512, items),
+            criterion::BatchSize::SmallInput
+        ));
+    }
+    group.finish();
+
+    // Frames as tall as the grid so no item is culled.
+    let mut group = c.benchmark_group("build_frame");
+    for count in [1000, 50_000] {
+        let height = (count / 32 + 1) as f32 * 16.0;
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, &count| b.iter_batched(
+            || grid(count, &image),
+            |items| CanvasRenderer::build_frame(512.0, height, 1.0, 1.0, Camera::default(), items),
+            criterion::BatchSize::LargeInput
+        ));
+    }
+    group.finish();
+
+    // The same frames built on one thread of rayon and on as many as there are cores, which only
+    // differ on a machine with more than one core.
+    #[cfg(feature = "rayon")]
+    {
+        let mut group = c.benchmark_group("build_frame_threads");
+        let count = 50_000;
+        let height = (count / 32 + 1) as f32 * 16.0;
+        let cores = std::thread::available_parallelism().map_or(1, usize::from);
+        for threads in [1, cores] {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            group.bench_with_input(BenchmarkId::from_parameter(threads), &threads, |b, _| b.iter_batched(
+                || grid(count, &image),
+                |items| pool.install(|| CanvasRenderer::build_frame(512.0, height, 1.0, 1.0, Camera::default(), items)),
+                criterion::BatchSize::LargeInput
+            ));
+            if cores == 1 {break;}
+        }
+        group.finish();
+    }
+
+    let mut group = c.benchmark_group("render");
+    for count in [100, 1000] {
+        gpu.prepare(512, grid(count, &image));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, _| b.iter(|| gpu.render(&target)));
+    }
+    group.finish();
+}
+
+criterion_group!(shapes, benches);
+criterion_main!(shapes);
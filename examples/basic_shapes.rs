@@ -0,0 +1,173 @@
+//! Every shape of the canvas filled and stroked with a solid color.
+//!
+//! Needs no extra features: `cargo run --example basic_shapes`
+
+use std::sync::Arc;
+
+use winit::application::ApplicationHandler;
+use winit::event::WindowEvent;
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::window::{Window, WindowId};
+
+use wgpu_canvas::prelude::*;
+
+const TITLE: &str = "basic shapes";
+
+struct Scene;
+
+impl Scene {
+    fn new(_: &mut ImageAtlas, _: &mut FontAtlas) -> Self {Scene}
+
+    fn draw(&self, canvas: &mut Canvas) {
+        let blue = Color::from_hex("3b82f6").unwrap();
+        let white = Color::WHITE;
+        let tail = Tail{edge: Direction::Down, offset: 30.0, width: 16.0, height: 12.0};
+        let shapes = [
+            Shape::Ellipse(0.0, (100.0, 100.0)),
+            Shape::Rectangle(Border::default(), (100.0, 100.0)),
+            Shape::RoundedRectangle(Border::default(), (100.0, 100.0), Radius(24.0, 12.0)),
+            Shape::RoundedRectangleSides(Border::default(), (100.0, 100.0), 24.0.into(), CornerMask::TOP),
+            Shape::Chevron(Direction::Right, 10.0, (60.0, 100.0)),
+            Shape::SpeechBubble(0.0, (100.0, 100.0), 16.0.into(), tail),
+        ];
+        let strokes = [
+            Shape::Ellipse(6.0, (100.0, 100.0)),
+            Shape::Rectangle(Border(2.0, 6.0, 2.0, 6.0), (100.0, 100.0)),
+            Shape::RoundedRectangle(4.0.into(), (100.0, 100.0), Radius(24.0, 12.0)),
+            Shape::RoundedRectangleSides(4.0.into(), (100.0, 100.0), 24.0.into(), CornerMask::TOP),
+            Shape::Chevron(Direction::Left, 4.0, (60.0, 100.0)),
+            Shape::SpeechBubble(3.0, (100.0, 100.0), 16.0.into(), tail),
+        ];
+
+        for (i, (shape, stroke)) in shapes.iter().cloned().zip(strokes).enumerate() {
+            let x = 20.0 + i as f32 * 120.0;
+            canvas.draw(Area((x, 20.0), None), CanvasItem::Shape(shape, blue));
+            canvas.draw(Area((x, 140.0), None), CanvasItem::Shape(stroke, white));
+        }
+
+        canvas.draw(Area((20.0, 260.0), None), CanvasItem::Outline(shapes[2].clone(), blue, 2.0, 3.0));
+        canvas.draw(Area((20.0, 260.0), None), CanvasItem::Shape(shapes[2].clone(), white));
+        canvas.draw(Area((140.0, 260.0), None), CanvasItem::ProgressRing(ProgressRing{
+            size: 100.0,
+            thickness: 10.0,
+            progress: 0.7,
+            start_angle: -std::f32::consts::FRAC_PI_2,
+            color: blue,
+            track: Color::from_rgba8(255, 255, 255, 40),
+        }));
+
+        let heart = Path::new()
+            .move_to((50.0, 95.0))
+            .cubic_to((-20.0, 45.0), (15.0, -10.0), (50.0, 25.0))
+            .cubic_to((85.0, -10.0), (120.0, 45.0), (50.0, 95.0))
+            .close();
+        canvas.draw(Area((260.0, 260.0), None), CanvasItem::Shape(Shape::Path(0.0, heart.clone()), blue));
+        canvas.draw(Area((380.0, 260.0), None), CanvasItem::Shape(Shape::Path(4.0, heart), white));
+        canvas.draw(Area((500.0, 300.0), None), CanvasItem::Shape(Shape::Wave(2.0, (100.0, 6.0), 8.0), Color::from_rgb(255, 64, 64)));
+    }
+}
+
+struct Gpu {
+    window: Arc<Window>,
+    surface: wgpu::Surface<'static>,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    config: wgpu::SurfaceConfiguration,
+    renderer: CanvasRenderer,
+}
+
+impl Gpu {
+    async fn new(window: Arc<Window>) -> Self {
+        let instance = wgpu::Instance::default();
+        let surface = instance.create_surface(window.clone()).unwrap();
+        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions{
+            compatible_surface: Some(&surface),
+            ..Default::default()
+        }).await.unwrap();
+        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor::default()).await.unwrap();
+
+        let size = window.inner_size();
+        let mut config = surface.get_default_config(&adapter, size.width.max(1), size.height.max(1)).unwrap();
+        // Colors are blended in linear space, the surface converts them back to sRGB.
+        config.format = config.format.add_srgb_suffix();
+        surface.configure(&device, &config);
+
+        let renderer = CanvasRendererBuilder::new(&device, &queue)
+            .texture_format(config.format)
+            .clear_color(Color::BLACK)
+            .build()
+            .expect("surface format is renderable");
+        Gpu{window, surface, device, queue, config, renderer}
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.config.width = width.max(1);
+        self.config.height = height.max(1);
+        self.surface.configure(&self.device, &self.config);
+        self.window.request_redraw();
+    }
+
+    fn draw(&mut self, image_atlas: &mut ImageAtlas, font_atlas: &mut FontAtlas, items: Vec<(Area, CanvasItem)>) {
+        let (width, height) = (self.config.width as f32, self.config.height as f32);
+        self.renderer.prepare(&self.device, &self.queue, width, height, image_atlas, font_atlas, items);
+
+        let frame = self.surface.get_current_texture().unwrap();
+        let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor{
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment{
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations{load: wgpu::LoadOp::Clear(self.renderer.clear_color()), store: wgpu::StoreOp::Store},
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.renderer.render(&mut render_pass);
+        }
+        self.queue.submit([encoder.finish()]);
+        frame.present();
+    }
+}
+
+struct App {
+    scene: Scene,
+    image_atlas: ImageAtlas,
+    font_atlas: FontAtlas,
+    gpu: Option<Gpu>,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.gpu.is_none() {
+            let attributes = Window::default_attributes().with_title(TITLE);
+            let window = Arc::new(event_loop.create_window(attributes).unwrap());
+            self.gpu = Some(pollster::block_on(Gpu::new(window)));
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        let Some(gpu) = &mut self.gpu else {return};
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::Resized(size) => gpu.resize(size.width, size.height),
+            WindowEvent::RedrawRequested => {
+                let mut canvas = Canvas::new(gpu.config.width as f32, gpu.config.height as f32);
+                self.scene.draw(&mut canvas);
+                gpu.draw(&mut self.image_atlas, &mut self.font_atlas, canvas.into_items());
+            },
+            _ => {}
+        }
+    }
+}
+
+fn main() {
+    let mut image_atlas = ImageAtlas::default();
+    let mut font_atlas = FontAtlas::default();
+    let scene = Scene::new(&mut image_atlas, &mut font_atlas);
+    let mut app = App{scene, image_atlas, font_atlas, gpu: None};
+    EventLoop::new().unwrap().run_app(&mut app).unwrap();
+}
@@ -0,0 +1,138 @@
+//! Linear and conic gradients filling and stroking shapes, and as the background of the canvas.
+//!
+//! Needs no extra features: `cargo run --example gradients`
+
+use std::sync::Arc;
+
+use winit::application::ApplicationHandler;
+use winit::event::WindowEvent;
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::window::{Window, WindowId};
+
+use wgpu_canvas::prelude::*;
+
+const TITLE: &str = "gradients";
+
+struct Scene;
+
+impl Scene {
+    fn new(_: &mut ImageAtlas, _: &mut FontAtlas) -> Self {Scene}
+
+    fn draw(&self, canvas: &mut Canvas) {
+        let navy = Color::from_hex("0f172a").unwrap();
+        let slate = Color::from_hex("334155").unwrap();
+        canvas.draw_gradient_background(Fill::Linear(navy, slate, std::f32::consts::FRAC_PI_2, Easing::Linear));
+
+        let pink = Color::from_hex("ec4899").unwrap();
+        let amber = Color::from_hex("f59e0b").unwrap();
+        let gradients = [
+            Fill::Linear(pink, amber, 0.0, Easing::Linear),
+            Fill::Linear(pink, amber, std::f32::consts::FRAC_PI_4, Easing::EaseInOut(3.0)),
+            Fill::Conic(pink, amber, 0.0, Easing::Linear),
+        ];
+
+        for (i, gradient) in gradients.into_iter().enumerate() {
+            let x = 20.0 + i as f32 * 140.0;
+            canvas.draw(Area((x, 20.0), None), CanvasItem::Fill(
+                Shape::RoundedRectangle(Border::default(), (120.0, 120.0), 16.0.into()), gradient
+            ));
+            canvas.draw(Area((x, 160.0), None), CanvasItem::Fill(Shape::Ellipse(12.0, (120.0, 120.0)), gradient));
+        }
+    }
+}
+
+struct Gpu {
+    window: Arc<Window>,
+    surface: wgpu::Surface<'static>,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    config: wgpu::SurfaceConfiguration,
+    renderer: CanvasRenderer,
+}
+
+impl Gpu {
+    async fn new(window: Arc<Window>) -> Self {
+        let instance = wgpu::Instance::default();
+        let surface = instance.create_surface(window.clone()).unwrap();
+        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions{
+            compatible_surface: Some(&surface),
+            ..Default::default()
+        }).await.unwrap();
+        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor::default()).await.unwrap();
+
+        let size = window.inner_size();
+        let (renderer, config) = CanvasRenderer::new_from_surface(&device, &queue, &surface, &adapter, size.width, size.height).unwrap();
+        Gpu{window, surface, device, queue, config, renderer}
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.config.width = width.max(1);
+        self.config.height = height.max(1);
+        self.surface.configure(&self.device, &self.config);
+        self.window.request_redraw();
+    }
+
+    fn draw(&mut self, image_atlas: &mut ImageAtlas, font_atlas: &mut FontAtlas, items: Vec<(Area, CanvasItem)>) {
+        let (width, height) = (self.config.width as f32, self.config.height as f32);
+        self.renderer.prepare(&self.device, &self.queue, width, height, image_atlas, font_atlas, items);
+
+        let frame = self.surface.get_current_texture().unwrap();
+        let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor{
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment{
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations{load: wgpu::LoadOp::Clear(wgpu::Color::BLACK), store: wgpu::StoreOp::Store},
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.renderer.render(&mut render_pass);
+        }
+        self.queue.submit([encoder.finish()]);
+        frame.present();
+    }
+}
+
+struct App {
+    scene: Scene,
+    image_atlas: ImageAtlas,
+    font_atlas: FontAtlas,
+    gpu: Option<Gpu>,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.gpu.is_none() {
+            let attributes = Window::default_attributes().with_title(TITLE);
+            let window = Arc::new(event_loop.create_window(attributes).unwrap());
+            self.gpu = Some(pollster::block_on(Gpu::new(window)));
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        let Some(gpu) = &mut self.gpu else {return};
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::Resized(size) => gpu.resize(size.width, size.height),
+            WindowEvent::RedrawRequested => {
+                let mut canvas = Canvas::new(gpu.config.width as f32, gpu.config.height as f32);
+                self.scene.draw(&mut canvas);
+                gpu.draw(&mut self.image_atlas, &mut self.font_atlas, canvas.into_items());
+            },
+            _ => {}
+        }
+    }
+}
+
+fn main() {
+    let mut image_atlas = ImageAtlas::default();
+    let mut font_atlas = FontAtlas::default();
+    let scene = Scene::new(&mut image_atlas, &mut font_atlas);
+    let mut app = App{scene, image_atlas, font_atlas, gpu: None};
+    EventLoop::new().unwrap().run_app(&mut app).unwrap();
+}
@@ -0,0 +1,139 @@
+//! A PNG loaded from disk filling a rounded rectangle and an ellipse, and wrapped around the
+//! stroke of another rounded rectangle.
+//!
+//! Needs no extra features: `cargo run --example image_fill -- path/to/image.png`
+
+use std::sync::Arc;
+
+use winit::application::ApplicationHandler;
+use winit::event::WindowEvent;
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::window::{Window, WindowId};
+
+use wgpu_canvas::prelude::*;
+
+const TITLE: &str = "image fill";
+
+struct Scene(Image);
+
+impl Scene {
+    fn new(image_atlas: &mut ImageAtlas, _: &mut FontAtlas) -> Self {
+        let path = std::env::args().nth(1).expect("usage: image_fill <path to png>");
+        let image = image::open(path).expect("could not open the image").into_rgba8();
+        Scene(image_atlas.add(image))
+    }
+
+    fn draw(&self, canvas: &mut Canvas) {
+        let image = &self.0;
+        canvas.draw(Area((20.0, 20.0), None), CanvasItem::Image(
+            Shape::RoundedRectangle(Border::default(), (240.0, 160.0), 24.0.into()), image.clone(), None
+        ));
+        canvas.draw(Area((280.0, 20.0), None), CanvasItem::Image(
+            Shape::Ellipse(0.0, (160.0, 160.0)), image.clone(), None
+        ));
+        canvas.draw(Area((20.0, 200.0), None), CanvasItem::ImageStroke(
+            Shape::RoundedRectangle(12.0.into(), (240.0, 160.0), 24.0.into()), image.clone(), None
+        ));
+    }
+}
+
+struct Gpu {
+    window: Arc<Window>,
+    surface: wgpu::Surface<'static>,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    config: wgpu::SurfaceConfiguration,
+    renderer: CanvasRenderer,
+}
+
+impl Gpu {
+    async fn new(window: Arc<Window>) -> Self {
+        let instance = wgpu::Instance::default();
+        let surface = instance.create_surface(window.clone()).unwrap();
+        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions{
+            compatible_surface: Some(&surface),
+            ..Default::default()
+        }).await.unwrap();
+        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor::default()).await.unwrap();
+
+        let size = window.inner_size();
+        let mut config = surface.get_default_config(&adapter, size.width.max(1), size.height.max(1)).unwrap();
+        // Colors are blended in linear space, the surface converts them back to sRGB.
+        config.format = config.format.add_srgb_suffix();
+        surface.configure(&device, &config);
+
+        let renderer = CanvasRenderer::new(&queue, &device, &config.format, wgpu::MultisampleState::default(), None);
+        Gpu{window, surface, device, queue, config, renderer}
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.config.width = width.max(1);
+        self.config.height = height.max(1);
+        self.surface.configure(&self.device, &self.config);
+        self.window.request_redraw();
+    }
+
+    fn draw(&mut self, image_atlas: &mut ImageAtlas, font_atlas: &mut FontAtlas, items: Vec<(Area, CanvasItem)>) {
+        let (width, height) = (self.config.width as f32, self.config.height as f32);
+        self.renderer.prepare(&self.device, &self.queue, width, height, image_atlas, font_atlas, items);
+
+        let frame = self.surface.get_current_texture().unwrap();
+        let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor{
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment{
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations{load: wgpu::LoadOp::Clear(wgpu::Color::BLACK), store: wgpu::StoreOp::Store},
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.renderer.render(&mut render_pass);
+        }
+        self.queue.submit([encoder.finish()]);
+        frame.present();
+    }
+}
+
+struct App {
+    scene: Scene,
+    image_atlas: ImageAtlas,
+    font_atlas: FontAtlas,
+    gpu: Option<Gpu>,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.gpu.is_none() {
+            let attributes = Window::default_attributes().with_title(TITLE);
+            let window = Arc::new(event_loop.create_window(attributes).unwrap());
+            self.gpu = Some(pollster::block_on(Gpu::new(window)));
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        let Some(gpu) = &mut self.gpu else {return};
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::Resized(size) => gpu.resize(size.width, size.height),
+            WindowEvent::RedrawRequested => {
+                let mut canvas = Canvas::new(gpu.config.width as f32, gpu.config.height as f32);
+                self.scene.draw(&mut canvas);
+                gpu.draw(&mut self.image_atlas, &mut self.font_atlas, canvas.into_items());
+            },
+            _ => {}
+        }
+    }
+}
+
+fn main() {
+    let mut image_atlas = ImageAtlas::default();
+    let mut font_atlas = FontAtlas::default();
+    let scene = Scene::new(&mut image_atlas, &mut font_atlas);
+    let mut app = App{scene, image_atlas, font_atlas, gpu: None};
+    EventLoop::new().unwrap().run_app(&mut app).unwrap();
+}
@@ -0,0 +1,144 @@
+//! Multiline text wrapped to a fixed width, with spans of different sizes and colors. Emoji are
+//! drawn from the optional second font, such as a color emoji font.
+//!
+//! Needs no extra features: `cargo run --example text -- path/to/font.ttf [path/to/emoji.ttf]`
+
+use std::sync::Arc;
+
+use winit::application::ApplicationHandler;
+use winit::event::WindowEvent;
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::window::{Window, WindowId};
+
+use wgpu_canvas::prelude::*;
+
+const TITLE: &str = "text";
+
+struct Scene(Font);
+
+impl Scene {
+    fn new(_: &mut ImageAtlas, font_atlas: &mut FontAtlas) -> Self {
+        let usage = "usage: text <path to ttf or otf font> [path to fallback font]";
+        let mut fonts = std::env::args().skip(1).map(|path| std::fs::read(path).expect("could not read the font"));
+        let font = font_atlas.add(&fonts.next().expect(usage));
+        fonts.for_each(|fallback| {font_atlas.add(&fallback);});
+        Scene(font)
+    }
+
+    fn draw(&self, canvas: &mut Canvas) {
+        let white = Color::WHITE;
+        let gray = Color::from_rgb(160, 160, 160);
+        let text = Text::new(vec![
+            Span::new("Wrapped text\n", 32.0, 40.0, self.0.clone(), white),
+            Span::new(
+                "Text is laid out to the width it is given and wraps at word boundaries, \
+                 breaking words only when a single one does not fit on a line. ",
+                18.0, 26.0, self.0.clone(), gray
+            ),
+            Span::new("Spans can change size and color mid paragraph.\n", 18.0, 26.0, self.0.clone(), white),
+            Span::new("Hello \u{1F44B}\u{1F3FD} world", 18.0, 26.0, self.0.clone(), gray),
+        ], Some(360.0), Align::Left, None);
+        canvas.draw(Area((20.0, 20.0), None), CanvasItem::Text(text));
+    }
+}
+
+struct Gpu {
+    window: Arc<Window>,
+    surface: wgpu::Surface<'static>,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    config: wgpu::SurfaceConfiguration,
+    renderer: CanvasRenderer,
+}
+
+impl Gpu {
+    async fn new(window: Arc<Window>) -> Self {
+        let instance = wgpu::Instance::default();
+        let surface = instance.create_surface(window.clone()).unwrap();
+        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions{
+            compatible_surface: Some(&surface),
+            ..Default::default()
+        }).await.unwrap();
+        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor::default()).await.unwrap();
+
+        let size = window.inner_size();
+        let mut config = surface.get_default_config(&adapter, size.width.max(1), size.height.max(1)).unwrap();
+        // Colors are blended in linear space, the surface converts them back to sRGB.
+        config.format = config.format.add_srgb_suffix();
+        surface.configure(&device, &config);
+
+        let renderer = CanvasRenderer::new(&queue, &device, &config.format, wgpu::MultisampleState::default(), None);
+        Gpu{window, surface, device, queue, config, renderer}
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.config.width = width.max(1);
+        self.config.height = height.max(1);
+        self.surface.configure(&self.device, &self.config);
+        self.window.request_redraw();
+    }
+
+    fn draw(&mut self, image_atlas: &mut ImageAtlas, font_atlas: &mut FontAtlas, items: Vec<(Area, CanvasItem)>) {
+        let (width, height) = (self.config.width as f32, self.config.height as f32);
+        self.renderer.prepare(&self.device, &self.queue, width, height, image_atlas, font_atlas, items);
+
+        let frame = self.surface.get_current_texture().unwrap();
+        let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor{
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment{
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations{load: wgpu::LoadOp::Clear(wgpu::Color::BLACK), store: wgpu::StoreOp::Store},
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.renderer.render(&mut render_pass);
+        }
+        self.queue.submit([encoder.finish()]);
+        frame.present();
+    }
+}
+
+struct App {
+    scene: Scene,
+    image_atlas: ImageAtlas,
+    font_atlas: FontAtlas,
+    gpu: Option<Gpu>,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.gpu.is_none() {
+            let attributes = Window::default_attributes().with_title(TITLE);
+            let window = Arc::new(event_loop.create_window(attributes).unwrap());
+            self.gpu = Some(pollster::block_on(Gpu::new(window)));
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        let Some(gpu) = &mut self.gpu else {return};
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::Resized(size) => gpu.resize(size.width, size.height),
+            WindowEvent::RedrawRequested => {
+                let mut canvas = Canvas::new(gpu.config.width as f32, gpu.config.height as f32);
+                self.scene.draw(&mut canvas);
+                gpu.draw(&mut self.image_atlas, &mut self.font_atlas, canvas.into_items());
+            },
+            _ => {}
+        }
+    }
+}
+
+fn main() {
+    let mut image_atlas = ImageAtlas::default();
+    let mut font_atlas = FontAtlas::default();
+    let scene = Scene::new(&mut image_atlas, &mut font_atlas);
+    let mut app = App{scene, image_atlas, font_atlas, gpu: None};
+    EventLoop::new().unwrap().run_app(&mut app).unwrap();
+}
@@ -0,0 +1,110 @@
+//! Vertex and index buffers of the pipelines, which grow with the frames drawn and shrink again
+//! after a while of smaller ones.
+
+use std::hash::{DefaultHasher, Hasher};
+
+use wgpu::{Buffer, BufferUsages, CommandEncoderDescriptor, Device, Queue};
+use wgpu_dyn_buffer::{DynamicBufferDescriptor, DynamicBuffer};
+
+/// Frames over which the most bytes used are tracked before the buffers may shrink.
+const WINDOW: u32 = 240;
+/// Buffers shrink once the most bytes used over a window are less than this fraction of them.
+/// Buffers grow to a power of two, so buffers shrunk to fit are at least half used and keep
+/// their size.
+const SLACK: u64 = 4;
+/// Size of a new buffer.
+const MIN_SIZE: u64 = 4096;
+
+/// Length and hash of the bytes last written to a buffer, to tell a frame writing the same ones
+/// again without keeping a copy of them. Two frames of the same length colliding on a 64 bit hash
+/// are not expected to happen.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+struct Digest(usize, u64);
+
+impl Digest {
+    fn new(bytes: &[u8]) -> Self {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(bytes);
+        Digest(bytes.len(), hasher.finish())
+    }
+}
+
+/// Vertex and index buffer of a pipeline, with the digests of the vertices and indices last
+/// written to them.
+pub(crate) struct Buffers(DynamicBuffer, DynamicBuffer, Option<(Digest, Digest)>);
+
+impl Buffers {
+    pub(crate) fn new(device: &Device) -> Self {
+        let buffer = |usage: BufferUsages| DynamicBuffer::new(device, &DynamicBufferDescriptor{
+            label: None,
+            usage: usage | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+        });
+        Buffers(buffer(BufferUsages::VERTEX), buffer(BufferUsages::INDEX), None)
+    }
+
+    /// Whether the vertices and indices are the ones last written.
+    pub(crate) fn holds(&self, vertices: &[u8], indices: &[u8]) -> bool {
+        self.2 == Some((Digest::new(vertices), Digest::new(indices)))
+    }
+
+    /// Write the vertices and indices unless they are the ones last written, returns whether
+    /// they were written.
+    pub(crate) fn write(&mut self, device: &Device, queue: &Queue, vertices: &[u8], indices: &[u8]) -> bool {
+        let digests = Some((Digest::new(vertices), Digest::new(indices)));
+        if digests == self.2 {return false;}
+        self.0.write_buffer(device, queue, vertices);
+        self.1.write_buffer(device, queue, indices);
+        self.2 = digests;
+        true
+    }
+
+    /// Bytes of the vertices and indices last written.
+    pub(crate) fn used(&self) -> u64 {
+        self.2.map_or(0, |(vertices, indices)| (vertices.0 + indices.0) as u64)
+    }
+
+    /// Replace the buffers by ones that just fit the vertices and indices last written, copied
+    /// over on the GPU.
+    pub(crate) fn shrink_to_fit(&mut self, device: &Device, queue: &Queue) {
+        let old = std::mem::replace(self, Buffers::new(device));
+        let Some((vertices, indices)) = old.2 else {return;};
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor{label: None});
+        for (from, to, len) in [(&old.0, &mut self.0, vertices.0), (&old.1, &mut self.1, indices.0)] {
+            // Buffers hold whole words, so the copy may round up past the bytes last written.
+            let len = len.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT as usize);
+            if len == 0 {continue;}
+            // Grows the new buffer to fit, the zeros are overwritten by the copy.
+            to.write_buffer(device, queue, &vec![0; len]);
+            encoder.copy_buffer_to_buffer(from.as_ref(), 0, to.as_ref(), 0, len as u64);
+        }
+        queue.submit(Some(encoder.finish()));
+        self.2 = old.2;
+    }
+
+    pub(crate) fn vertices(&self) -> &Buffer {self.0.as_ref()}
+
+    pub(crate) fn indices(&self) -> &Buffer {self.1.as_ref()}
+
+    /// Bytes of both buffers.
+    pub(crate) fn capacity(&self) -> u64 {self.vertices().size() + self.indices().size()}
+}
+
+/// Most bytes used by the frames of a pipeline over the current window.
+#[derive(Default)]
+pub(crate) struct Usage {
+    peak: u64,
+    frames: u32,
+}
+
+impl Usage {
+    /// Count a frame using the bytes of buffers of the capacity, returns whether the buffers
+    /// should shrink to fit, once at the end of each window.
+    pub(crate) fn frame(&mut self, used: u64, capacity: u64) -> bool {
+        self.peak = self.peak.max(used);
+        self.frames += 1;
+        if self.frames < WINDOW {return false;}
+        let shrink = capacity > self.peak.max(MIN_SIZE) * SLACK;
+        *self = Usage::default();
+        shrink
+    }
+}
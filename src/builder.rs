@@ -0,0 +1,141 @@
+//! Configuring a [`CanvasRenderer`] by name rather than by position.
+
+use wgpu::{DepthStencilState, MultisampleState, TextureFormat, TextureFormatFeatureFlags, TextureUsages, Device, Queue};
+
+use crate::{CanvasRenderer, Color};
+
+/// Samples per pixel of the render target, smoothing the edges of shapes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Msaa {
+    #[default]
+    X1,
+    X2,
+    X4,
+    X8,
+}
+
+impl Msaa {
+    pub fn sample_count(self) -> u32 {
+        match self {
+            Msaa::X1 => 1,
+            Msaa::X2 => 2,
+            Msaa::X4 => 4,
+            Msaa::X8 => 8,
+        }
+    }
+}
+
+/// Why a [`CanvasRendererBuilder`] could not build a renderer with its settings on the device.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BuildError {
+    /// The format cannot be rendered to and blended on the device.
+    UnsupportedFormat(TextureFormat),
+    /// The format does not support the number of samples on the device.
+    UnsupportedSampleCount{format: TextureFormat, count: u32},
+    /// [`msaa`](CanvasRendererBuilder::msaa) and [`multisample`](CanvasRendererBuilder::multisample)
+    /// ask for different numbers of samples.
+    ConflictingSampleCount{msaa: u32, multisample: u32},
+    /// The depth stencil state is not of a depth or stencil format.
+    InvalidDepthStencil(TextureFormat),
+    /// The atlas size is empty or larger than the largest texture of the device.
+    InvalidAtlasSize{size: (u32, u32), max: u32},
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::UnsupportedFormat(format) => write!(f, "format {format:?} cannot be rendered to and blended on the device"),
+            BuildError::UnsupportedSampleCount{format, count} => write!(f, "format {format:?} does not support {count} samples on the device"),
+            BuildError::ConflictingSampleCount{msaa, multisample} => write!(f, "msaa of {msaa} samples conflicts with multisample state of {multisample}"),
+            BuildError::InvalidDepthStencil(format) => write!(f, "depth stencil format {format:?} has no depth or stencil"),
+            BuildError::InvalidAtlasSize{size, max} => write!(f, "atlas of {}x{} does not fit the largest texture of {max}x{max} the device allows", size.0, size.1),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Settings of a [`CanvasRenderer`], checked against the device before any of its resources are
+/// created. Renders to [`Rgba8UnormSrgb`](TextureFormat::Rgba8UnormSrgb) without multisampling
+/// or depth by default.
+pub struct CanvasRendererBuilder<'a> {
+    device: &'a Device,
+    queue: &'a Queue,
+    texture_format: TextureFormat,
+    multisample: MultisampleState,
+    msaa: Option<Msaa>,
+    depth_stencil: Option<DepthStencilState>,
+    atlas_size: Option<(u32, u32)>,
+    clear_color: Color,
+}
+
+impl<'a> CanvasRendererBuilder<'a> {
+    pub fn new(device: &'a Device, queue: &'a Queue) -> Self {
+        CanvasRendererBuilder{
+            device,
+            queue,
+            texture_format: TextureFormat::Rgba8UnormSrgb,
+            multisample: MultisampleState::default(),
+            msaa: None,
+            depth_stencil: None,
+            atlas_size: None,
+            clear_color: Color::TRANSPARENT,
+        }
+    }
+
+    /// Format of the render target.
+    pub fn texture_format(mut self, texture_format: TextureFormat) -> Self {self.texture_format = texture_format; self}
+
+    pub fn multisample(mut self, multisample: MultisampleState) -> Self {self.multisample = multisample; self}
+
+    /// Samples per pixel, the count of the [`multisample`](Self::multisample) state.
+    pub fn msaa(mut self, msaa: Msaa) -> Self {self.msaa = Some(msaa); self}
+
+    pub fn depth_stencil(mut self, depth_stencil: DepthStencilState) -> Self {self.depth_stencil = Some(depth_stencil); self}
+
+    /// Largest texture images are bound to, images larger than it are left out. Defaults to the
+    /// largest the device allows.
+    pub fn atlas_size(mut self, width: u32, height: u32) -> Self {self.atlas_size = Some((width, height)); self}
+
+    /// Color frames are cleared to, see [`CanvasRenderer::clear_color`]. Defaults to transparent.
+    pub fn clear_color(mut self, color: impl Into<Color>) -> Self {self.clear_color = color.into(); self}
+
+    pub fn build(self) -> Result<CanvasRenderer, BuildError> {
+        let features = self.texture_format.guaranteed_format_features(self.device.features());
+        let renderable = features.allowed_usages.contains(TextureUsages::RENDER_ATTACHMENT);
+        if !renderable || !features.flags.contains(TextureFormatFeatureFlags::BLENDABLE) {
+            return Err(BuildError::UnsupportedFormat(self.texture_format));
+        }
+
+        let mut multisample = self.multisample;
+        if let Some(msaa) = self.msaa {
+            let count = msaa.sample_count();
+            if multisample.count != 1 && multisample.count != count {
+                return Err(BuildError::ConflictingSampleCount{msaa: count, multisample: multisample.count});
+            }
+            multisample.count = count;
+        }
+        let count = multisample.count;
+        if !features.flags.sample_count_supported(count) {
+            return Err(BuildError::UnsupportedSampleCount{format: self.texture_format, count});
+        }
+
+        if let Some(depth_stencil) = &self.depth_stencil {
+            let format = depth_stencil.format;
+            if !format.is_depth_stencil_format() {return Err(BuildError::InvalidDepthStencil(format));}
+            if !format.guaranteed_format_features(self.device.features()).flags.sample_count_supported(count) {
+                return Err(BuildError::UnsupportedSampleCount{format, count});
+            }
+        }
+
+        let max = self.device.limits().max_texture_dimension_2d;
+        if let Some(size) = self.atlas_size.filter(|&(width, height)| width == 0 || height == 0 || width > max || height > max) {
+            return Err(BuildError::InvalidAtlasSize{size, max});
+        }
+
+        let mut renderer = CanvasRenderer::new(self.queue, self.device, &self.texture_format, multisample, self.depth_stencil);
+        renderer.image_renderer.set_atlas_size(self.atlas_size);
+        renderer.clear_color = self.clear_color;
+        Ok(renderer)
+    }
+}
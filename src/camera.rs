@@ -0,0 +1,85 @@
+//! Pan and zoom applied to the whole canvas on the GPU.
+
+use wgpu::{BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindGroupLayout, BindGroup, BufferUsages, ShaderStages, BindingType, Buffer, Device, Queue};
+
+/// Pan and zoom of everything a [`CanvasRenderer`](crate::CanvasRenderer) draws, such as the view
+/// of a node graph. A point of the canvas is drawn at `point * zoom + offset`, both in logical
+/// pixels. Moving the camera only rewrites a small uniform, the items stay as they were prepared.
+///
+/// Edges are smoothed over a pixel of the screen at any zoom, while strokes, radii and text grow
+/// and shrink with everything else.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub offset: (f32, f32),
+    pub zoom: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {Camera{offset: (0.0, 0.0), zoom: 1.0}}
+}
+
+impl Camera {
+    /// Where the point of the canvas is drawn on the screen.
+    pub fn to_screen(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (x * self.zoom + self.offset.0, y * self.zoom + self.offset.1)
+    }
+
+    /// The point of the canvas drawn at the point of the screen, such as under the cursor for
+    /// [`Canvas::hit_test`](crate::Canvas::hit_test).
+    pub fn to_canvas(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        ((x - self.offset.0) / self.zoom, (y - self.offset.1) / self.zoom)
+    }
+
+    /// The rectangle (x, y, width, height) of the frame in physical pixels, seen through the
+    /// camera, in the physical pixels of the canvas.
+    pub(crate) fn view(&self, width: f32, height: f32, scale: f32) -> (f32, f32, f32, f32) {
+        (-self.offset.0 * scale / self.zoom, -self.offset.1 * scale / self.zoom, width / self.zoom, height / self.zoom)
+    }
+}
+
+/// Uniform of the camera at group 0 of every shape and image pipeline, the offset in clip space
+/// and the zoom.
+pub(crate) struct CameraUniform {
+    pub(crate) layout: BindGroupLayout,
+    pub(crate) bind_group: BindGroup,
+    buffer: Buffer,
+    /// Camera last written, left as is when written again.
+    written: Option<[f32; 4]>,
+}
+
+impl CameraUniform {
+    pub(crate) fn new(device: &Device) -> Self {
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor{
+            label: None,
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer{ty: wgpu::BufferBindingType::Uniform, has_dynamic_offset: false, min_binding_size: None},
+                count: None,
+            }]
+        });
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor{
+            label: None,
+            size: 16,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor{
+            label: None,
+            layout: &layout,
+            entries: &[wgpu::BindGroupEntry{binding: 0, resource: buffer.as_entire_binding()}],
+        });
+        CameraUniform{layout, bind_group, buffer, written: None}
+    }
+
+    /// Write the camera for frames of the size in physical pixels at the scale factor.
+    pub(crate) fn write(&mut self, queue: &Queue, camera: Camera, width: f32, height: f32, scale: f32) {
+        let zoom = camera.zoom;
+        let x = zoom - 1.0 + 2.0 * camera.offset.0 * scale / width.max(1.0);
+        let y = 1.0 - zoom - 2.0 * camera.offset.1 * scale / height.max(1.0);
+        let uniform = [x, y, zoom, 0.0];
+        if self.written == Some(uniform) {return;}
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&uniform));
+        self.written = Some(uniform);
+    }
+}
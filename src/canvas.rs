@@ -0,0 +1,397 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use super::{Area, Border, CanvasItem, Color, Fill, FontAtlas, Image, ImageAtlas, Path, PatternFill, PatternKind, Radius, RgbaImage, Shape, Stroke, Text};
+
+/// Offset applied to the position of everything drawn on a [`Canvas`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Transform(pub f32, pub f32);
+
+impl Transform {
+    fn then(&self, other: Transform) -> Transform {
+        Transform(self.0 + other.0, self.1 + other.1)
+    }
+}
+
+/// Look of a bar drawn by [`Canvas::draw_progress_bar`]. The border is drawn inside the edge of
+/// the bar over both fills.
+#[derive(Clone, Copy, Debug)]
+pub struct ProgressBarStyle {
+    pub background: Fill,
+    pub foreground: Fill,
+    pub corner_radius: f32,
+    pub border: Option<BorderStyle>,
+}
+
+/// Border drawn by [`Canvas::draw_image_with_border`] and [`Canvas::draw_progress_bar`] inside
+/// the edge of the image or bar.
+#[derive(Clone, Copy, Debug)]
+pub struct BorderStyle {
+    pub stroke: Stroke,
+    pub fill: Fill,
+}
+
+/// Look of the lines drawn by [`Canvas::draw_grid`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LineStyle {
+    pub stroke: Stroke,
+    pub color: Color,
+}
+
+/// Shadow drawn by [`Canvas::draw_shadow_text`] under the text, moved by the offset and blurred
+/// by a Gaussian of the standard deviation `blur` in pixels, sharp at 0.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShadowOptions {
+    pub offset: (f32, f32),
+    pub blur: f32,
+    pub color: Color,
+}
+
+/// Head drawn by [`Canvas::draw_arrow`] at the end of the arrow, `size` pixels long and as wide.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ArrowHead {
+    pub style: ArrowHeadStyle,
+    pub size: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ArrowHeadStyle {
+    /// Two lines back from the tip in the width of the stroke.
+    Open,
+    /// Filled triangle with the line ending at its base.
+    Filled,
+    /// Plain line without a head.
+    #[default]
+    None,
+}
+
+/// Collects the items of a frame for [`CanvasRenderer::prepare`](crate::CanvasRenderer::prepare).
+///
+/// Positions and clips of drawn items are relative to the current transform and are cut to the
+/// current clip, both of which can only be changed for the duration of a closure.
+#[derive(Debug, Default)]
+pub struct Canvas {
+    size: (f32, f32),
+    transform: Transform,
+    clip: Option<(f32, f32, f32, f32)>,
+    items: Vec<(Area, CanvasItem)>,
+}
+
+impl Canvas {
+    pub fn new(width: f32, height: f32) -> Self {
+        Canvas{size: (width, height), ..Default::default()}
+    }
+
+    pub fn size(&self) -> (f32, f32) {self.size}
+
+    pub fn draw(&mut self, area: Area, item: CanvasItem) {
+        let t = self.transform;
+        let position = (area.0.0 + t.0, area.0.1 + t.1);
+        let bounds = area.1.map(|b| (b.0 + t.0, b.1 + t.1, b.2, b.3));
+        let bounds = match (bounds, self.clip) {
+            (Some(b), Some(c)) => Some(intersect(b, c)),
+            (b, c) => b.or(c),
+        };
+        self.items.push((Area(position, bounds), item));
+    }
+
+    /// Fill the whole canvas regardless of the current transform, only the clip applies.
+    pub fn draw_gradient_background(&mut self, fill: Fill) {
+        let shape = Shape::Rectangle(Border::default(), self.size);
+        self.items.push((Area((0.0, 0.0), self.clip), CanvasItem::Fill(shape, fill)));
+    }
+
+    /// Fill the whole canvas regardless of the current transform, only the clip applies.
+    pub fn draw_solid_background(&mut self, color: impl Into<Color>) {
+        let shape = Shape::Rectangle(Border::default(), self.size);
+        self.items.push((Area((0.0, 0.0), self.clip), CanvasItem::Shape(shape, color.into())));
+    }
+
+    /// Rectangle (x, y, width, height) filled or, with a non-zero stroke, stroked.
+    pub fn draw_rect(&mut self, rect: (f32, f32, f32, f32), fill: impl Into<Fill>, stroke: impl Into<Stroke>) {
+        let shape = Shape::Rectangle(Border::default(), (rect.2, rect.3)).stroked(stroke.into());
+        self.draw(Area((rect.0, rect.1), None), CanvasItem::Fill(shape, fill.into()));
+    }
+
+    /// Rectangle (x, y, width, height) with corners of the radius, filled or, with a non-zero
+    /// stroke, stroked.
+    pub fn draw_rounded_rect(&mut self, rect: (f32, f32, f32, f32), radius: f32, fill: impl Into<Fill>, stroke: impl Into<Stroke>) {
+        let shape = Shape::RoundedRectangle(Border::default(), (rect.2, rect.3), radius.into()).stroked(stroke.into());
+        self.draw(Area((rect.0, rect.1), None), CanvasItem::Fill(shape, fill.into()));
+    }
+
+    /// Ellipse with the horizontal and vertical radius around the center, filled or, with a
+    /// non-zero stroke, stroked.
+    pub fn draw_ellipse(&mut self, center: (f32, f32), radius: (f32, f32), fill: impl Into<Fill>, stroke: impl Into<Stroke>) {
+        let shape = Shape::Ellipse(0.0, (radius.0 * 2.0, radius.1 * 2.0)).stroked(stroke.into());
+        self.draw(Area((center.0 - radius.0, center.1 - radius.1), None), CanvasItem::Fill(shape, fill.into()));
+    }
+
+    /// Circle of the radius around the center, filled or, with a non-zero stroke, stroked.
+    pub fn draw_circle(&mut self, center: (f32, f32), radius: f32, fill: impl Into<Fill>, stroke: impl Into<Stroke>) {
+        self.draw_ellipse(center, (radius, radius), fill, stroke);
+    }
+
+    /// Smooth curve through the points, such as the line of a sparkline, drawn with the stroke.
+    /// The curve is a Catmull-Rom spline, each span between two points bending towards the
+    /// points around it, and is flattened into lines that stay within a tenth of a pixel of it.
+    /// Nothing is drawn for fewer than two points or without a stroke width.
+    pub fn draw_bezier_curve(&mut self, points: &[(f32, f32)], stroke: impl Into<Stroke>, color: impl Into<Fill>) {
+        let stroke = stroke.into();
+        if points.len() < 2 || stroke.width <= 0.0 {return;}
+        let shape = Shape::Path(0.0, catmull_rom(points)).stroked(stroke);
+        self.draw(Area((0.0, 0.0), None), CanvasItem::Fill(shape, color.into()));
+    }
+
+    /// Line from one point to the other drawn with the stroke, with the head at the end pointing
+    /// along it. Heads are always drawn solid and get no longer than the arrow. Nothing is drawn
+    /// when the points are the same.
+    pub fn draw_arrow(&mut self, from: (f32, f32), to: (f32, f32), head: ArrowHead, stroke: impl Into<Stroke>, color: impl Into<Fill>) {
+        let (stroke, color) = (stroke.into(), color.into());
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length == 0.0 {return;}
+        let (ux, uy) = (dx / length, dy / length);
+        let size = match head.style {
+            ArrowHeadStyle::None => 0.0,
+            _ => head.size.clamp(0.0, length),
+        };
+        let base = (to.0 - ux * size, to.1 - uy * size);
+        let wings = [(base.0 + uy * size / 2.0, base.1 - ux * size / 2.0), (base.0 - uy * size / 2.0, base.1 + ux * size / 2.0)];
+
+        let end = if head.style == ArrowHeadStyle::Filled {base} else {to};
+        if stroke.width > 0.0 && end != from {
+            let shaft = Shape::Path(0.0, Path::new().move_to(from).line_to(end)).stroked(stroke);
+            self.draw(Area((0.0, 0.0), None), CanvasItem::Fill(shaft, color));
+        }
+        if size <= 0.0 {return;}
+        let shape = match head.style {
+            ArrowHeadStyle::Filled => Shape::Path(0.0, Path::new().move_to(wings[0]).line_to(to).line_to(wings[1]).close()),
+            _ if stroke.width > 0.0 => Shape::Path(stroke.width, Path::new().move_to(wings[0]).line_to(to).line_to(wings[1])),
+            _ => return,
+        };
+        self.draw(Area((0.0, 0.0), None), CanvasItem::Fill(shape, color));
+    }
+
+    /// Lines between the cells of a grid of the columns and rows evenly dividing the rectangle
+    /// (x, y, width, height), such as the background of a table or chart, each drawn as an item of
+    /// its own in the style. The edges of the rectangle are left out, draw them with
+    /// [`draw_rect`](Self::draw_rect). Solid lines of whole pixels that line up with the pixels
+    /// are drawn sharper by [`CanvasItem::GridLines`].
+    pub fn draw_grid(&mut self, rect: (f32, f32, f32, f32), columns: u32, rows: u32, style: LineStyle) {
+        if style.stroke.width <= 0.0 {return;}
+        let (x, y, width, height) = rect;
+        let columns = (1..columns).map(|i| x + width * i as f32 / columns as f32).map(|x| ((x, y), (x, y + height)));
+        let rows = (1..rows).map(|i| y + height * i as f32 / rows as f32).map(|y| ((x, y), (x + width, y)));
+        for (from, to) in columns.chain(rows).collect::<Vec<_>>() {
+            let shape = Shape::Path(0.0, Path::new().move_to(from).line_to(to)).stroked(style.stroke);
+            self.draw(Area((0.0, 0.0), None), CanvasItem::Shape(shape, style.color));
+        }
+    }
+
+    /// Wheel of every hue at full saturation and value around the center, red to the right and
+    /// turning clockwise, such as that of a color picker. Drawn as wedges of a few degrees
+    /// each, see [`Color::from_hsv`].
+    pub fn draw_hsv_color_wheel(&mut self, center: (f32, f32), radius: f32) {
+        const WEDGES: u32 = 120;
+        if radius <= 0.0 {return;}
+        let step = std::f32::consts::TAU / WEDGES as f32;
+        let point = |angle: f32| (center.0 + radius * angle.cos(), center.1 + radius * angle.sin());
+        for i in 0..WEDGES {
+            let start = i as f32 * step;
+            let path = (0..=2).fold(Path::new().move_to(center), |path, j| path.line_to(point(start + step * j as f32 / 2.0)));
+            let color = Color::from_hsv((i as f32 + 0.5) * 360.0 / WEDGES as f32, 1.0, 1.0);
+            self.draw(Area((0.0, 0.0), None), CanvasItem::Shape(Shape::Path(0.0, path.close()), color));
+        }
+    }
+
+    /// White and light gray squares of 8 pixels filling the rectangle (x, y, width, height), as
+    /// drawn behind transparent images by image editors.
+    pub fn draw_transparency_checker(&mut self, rect: (f32, f32, f32, f32)) {
+        let checker = PatternFill{
+            kind: PatternKind::Checkerboard{cell_size: 8},
+            foreground: Color::WHITE,
+            background: Color::from_rgb(204, 204, 204),
+            scale: 1.0,
+        };
+        self.draw_rect(rect, checker, 0.0);
+    }
+
+    /// Text over a shadow of itself, which keeps it legible over busy backgrounds such as images.
+    /// The glyphs are laid out and rasterized by the font atlas, blurred and added to the image
+    /// atlas as an image drawn under the text. `scale` is the physical pixels per logical pixel
+    /// the frame is rendered at, the scale factor times the render scale of the renderer, which
+    /// the shadow is rasterized and blurred at to stay as sharp as the text over it. The image
+    /// atlas keeps the shadow while it is drawn every frame, so only the first call for the same
+    /// characters, fonts, sizes, layout, blur, color and scale rasterizes it.
+    pub fn draw_shadow_text(&mut self, font_atlas: &mut FontAtlas, image_atlas: &mut ImageAtlas, text: Text, area: Area, shadow: ShadowOptions, scale: f32) {
+        let blur = shadow.blur.max(0.0) * scale;
+        // The Gaussian blur reaches 2 standard deviations out.
+        let padding = (blur * 2.0).ceil() as u32 + 1;
+        let image = image_atlas.derived(shadow_key(&text, &shadow, scale), || {
+            let mask = text.mask(font_atlas, scale, padding);
+            let mask = if blur > 0.0 {image::imageops::blur(&mask, blur)} else {mask};
+            let Color{r, g, b, a} = shadow.color;
+            RgbaImage::from_fn(mask.width(), mask.height(), |x, y| {
+                image::Rgba([r, g, b, ((mask.get_pixel(x, y).0[0] as u32 * a as u32 + 127) / 255) as u8])
+            })
+        });
+        let size = (image.size().0 as f32 / scale, image.size().1 as f32 / scale);
+        let padding = padding as f32 / scale;
+        let position = (area.0.0 + shadow.offset.0 - padding, area.0.1 + shadow.offset.1 - padding);
+        let shape = Shape::Rectangle(Border::default(), size);
+        self.draw(Area(position, area.1), CanvasItem::Image(shape, image, None));
+        self.draw(area, CanvasItem::Text(text));
+    }
+
+    /// Image at its own size in pixels at the position of the area, cut to its bounds.
+    pub fn draw_image(&mut self, image: Image, area: Area) {
+        let size = image.size();
+        let shape = Shape::Rectangle(Border::default(), (size.0 as f32, size.1 as f32));
+        self.draw(area, CanvasItem::Image(shape, image, None));
+    }
+
+    /// Image at its own size with corners of the radius, such as an avatar, see
+    /// [`draw_image`](Self::draw_image).
+    pub fn draw_image_rounded(&mut self, image: Image, area: Area, radius: f32) {
+        let size = image.size();
+        let shape = Shape::RoundedRectangle(Border::default(), (size.0 as f32, size.1 as f32), radius.into());
+        self.draw(area, CanvasItem::Image(shape, image, None));
+    }
+
+    /// Image covering the circle of the radius around the center, cropped to keep its aspect,
+    /// such as an avatar.
+    pub fn draw_image_circle(&mut self, image: Image, center: (f32, f32), radius: f32) {
+        let shape = Shape::Ellipse(0.0, (radius * 2.0, radius * 2.0));
+        self.draw(Area((center.0 - radius, center.1 - radius), None), CanvasItem::Image(shape, image, None));
+    }
+
+    /// Image at its own size cut to the ellipse that fits it, see [`draw_image`](Self::draw_image).
+    pub fn draw_image_ellipse(&mut self, image: Image, area: Area) {
+        let size = image.size();
+        self.draw(area, CanvasItem::Image(Shape::Ellipse(0.0, (size.0 as f32, size.1 as f32)), image, None));
+    }
+
+    /// Image in the shape with the border stroked over its edge, such as a framed photo. The
+    /// stroke of the shape is replaced by the border, chevrons and waves are drawn without one.
+    pub fn draw_image_with_border(&mut self, image: Image, shape: Shape, area: Area, border: BorderStyle) {
+        let shape = shape.with_stroke(0.0);
+        self.draw(area, CanvasItem::Image(shape.clone(), image, None));
+        let stroked = shape.stroked(border.stroke);
+        if border.stroke.width > 0.0 && stroked.stroke() != Border::default() {
+            self.draw(area, CanvasItem::Fill(stroked, border.fill));
+        }
+    }
+
+    /// Repeat the image in tiles of the size starting at the position of the area and covering its
+    /// bounds, or the rest of the canvas without bounds. Only tiles that can be seen within the
+    /// current clip are drawn, the ones on the edges are cut. The tiles are drawn as a single
+    /// [`CanvasItem::TiledImage`].
+    pub fn draw_tiled_image(&mut self, image: Image, tile_size: (u32, u32), area: Area) {
+        let (width, height) = (tile_size.0.max(1) as f32, tile_size.1.max(1) as f32);
+        let t = self.transform;
+        let canvas = (-t.0, -t.1, self.size.0, self.size.1);
+        let visible = intersect(area.1.unwrap_or(canvas), canvas);
+        let visible = match self.clip {
+            Some(c) => intersect(visible, (c.0 - t.0, c.1 - t.1, c.2, c.3)),
+            None => visible,
+        };
+        let (x, y) = area.0;
+        if visible.2 <= 0.0 || visible.3 <= 0.0 {return;}
+        let (column, columns) = tiles(visible.0 - x, visible.2, width);
+        let (row, rows) = tiles(visible.1 - y, visible.3, height);
+        let position = (x + column * width, y + row * height);
+        let size = (columns * width, rows * height);
+        self.draw(Area(position, Some(visible)), CanvasItem::TiledImage(image, size, (width, height)));
+    }
+
+    /// Progress bar of the size with the foreground covering `value` (0 to 1) of its width from
+    /// the left.
+    pub fn draw_progress_bar(&mut self, value: f32, style: ProgressBarStyle, size: (f32, f32), area: Area) {
+        let radius = Radius::circular(style.corner_radius);
+        self.draw(area, CanvasItem::Fill(Shape::RoundedRectangle(Border::default(), size, radius), style.background));
+        let width = size.0 * value.clamp(0.0, 1.0);
+        if width > 0.0 {
+            let shape = Shape::RoundedRectangle(Border::default(), (width, size.1), radius);
+            self.draw(area, CanvasItem::Fill(shape, style.foreground));
+        }
+        if let Some(border) = style.border {
+            self.draw(area, CanvasItem::Fill(Shape::RoundedRectangle(Border::default(), size, radius).stroked(border.stroke), border.fill));
+        }
+    }
+
+    /// Draw with `transform` added to the current transform, it is removed again once `f` returns
+    /// or panics.
+    pub fn with_transform(&mut self, transform: Transform, f: impl FnOnce(&mut Canvas)) {
+        let transform = self.transform.then(transform);
+        self.scoped(transform, self.clip, f);
+    }
+
+    /// Draw with everything outside of `clip` (x, y, width, height) cut away, the clip is
+    /// relative to the current transform and is removed again once `f` returns or panics.
+    pub fn with_clip(&mut self, clip: (f32, f32, f32, f32), f: impl FnOnce(&mut Canvas)) {
+        let t = self.transform;
+        let clip = (clip.0 + t.0, clip.1 + t.1, clip.2, clip.3);
+        let clip = Some(self.clip.map(|c| intersect(c, clip)).unwrap_or(clip));
+        self.scoped(self.transform, clip, f);
+    }
+
+    pub fn into_items(self) -> Vec<(Area, CanvasItem)> {self.items}
+
+    /// Indices of the items drawn so far under the point, front to back, see [`hit_test`](crate::hit_test).
+    /// The point is relative to the canvas rather than the current transform.
+    pub fn hit_test(&self, point: (f32, f32), hollow: bool) -> Vec<usize> {crate::hit_test(point, &self.items, hollow)}
+
+    fn scoped(&mut self, transform: Transform, clip: Option<(f32, f32, f32, f32)>, f: impl FnOnce(&mut Canvas)) {
+        let restore = Restore(self.transform, self.clip, self);
+        restore.2.transform = transform;
+        restore.2.clip = clip;
+        f(&mut *restore.2);
+    }
+}
+
+/// Puts the transform and clip of a [`Canvas`] back when dropped, even while unwinding.
+struct Restore<'a>(Transform, Option<(f32, f32, f32, f32)>, &'a mut Canvas);
+
+impl Drop for Restore<'_> {
+    fn drop(&mut self) {
+        self.2.transform = self.0;
+        self.2.clip = self.1;
+    }
+}
+
+/// Cubic curves through the points with the tangent at each point parallel to the line between
+/// its neighbours, the ends taking themselves as their missing neighbour.
+fn catmull_rom(points: &[(f32, f32)]) -> Path {
+    let at = |i: usize| points[i.min(points.len() - 1)];
+    (0..points.len() - 1).fold(Path::new().move_to(points[0]), |path, i| {
+        let (p0, p1, p2, p3) = (at(i.saturating_sub(1)), at(i), at(i + 1), at(i + 2));
+        let c1 = (p1.0 + (p2.0 - p0.0) / 6.0, p1.1 + (p2.1 - p0.1) / 6.0);
+        let c2 = (p2.0 - (p3.0 - p1.0) / 6.0, p2.1 - (p3.1 - p1.1) / 6.0);
+        path.cubic_to(c1, c2, p2)
+    })
+}
+
+/// First and number of tiles of the size that cover the span from the offset.
+fn tiles(offset: f32, span: f32, size: f32) -> (f32, f32) {
+    let first = (offset / size).floor();
+    (first, ((offset + span) / size).ceil() - first)
+}
+
+/// Hash of what the shadow of the text is rasterized from, its colors and cursor aside.
+fn shadow_key(text: &Text, shadow: &ShadowOptions, scale: f32) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for span in &text.spans {
+        (&span.text, span.font.0, span.font_size.to_bits(), span.line_height.to_bits()).hash(&mut hasher);
+    }
+    (text.width.map(f32::to_bits), std::mem::discriminant(&text.align)).hash(&mut hasher);
+    (shadow.blur.to_bits(), shadow.color, scale.to_bits()).hash(&mut hasher);
+    hasher.finish()
+}
+
+pub(crate) fn intersect(a: (f32, f32, f32, f32), b: (f32, f32, f32, f32)) -> (f32, f32, f32, f32) {
+    let x = a.0.max(b.0);
+    let y = a.1.max(b.1);
+    let x2 = (a.0 + a.2).min(b.0 + b.2);
+    let y2 = (a.1 + a.3).min(b.1 + b.3);
+    (x, y, (x2 - x).max(0.0), (y2 - y).max(0.0))
+}
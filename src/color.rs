@@ -1,19 +1,385 @@
 mod renderer;
 
-pub use renderer::ColorRenderer;
+pub use renderer::{ColorRenderer, ColorFrame};
 
-#[derive(Clone, Copy, Debug)]
-pub struct Color(pub u8, pub u8, pub u8, pub u8);
+/// Color in sRGB with straight alpha, each channel from 0 to 255.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
 
 impl Color {
-    pub fn from_hex(color: &'static str, alpha: u8) -> Self {
-        let ce = "Color was not a Hex Value";
-        let c = hex::decode(color.strip_prefix('#').unwrap_or(color)).expect(ce);
-        Color(c[0], c[1], c[2], alpha)
+    pub const WHITE: Color = Color::from_rgb(255, 255, 255);
+    pub const BLACK: Color = Color::from_rgb(0, 0, 0);
+    pub const TRANSPARENT: Color = Color::from_rgba8(0, 0, 0, 0);
+
+    /// Opaque color of the channels.
+    pub const fn from_rgb(r: u8, g: u8, b: u8) -> Self {Color{r, g, b, a: 255}}
+
+    /// Alias of [`from_rgba8`](Self::from_rgba8).
+    pub const fn from_rgba(r: u8, g: u8, b: u8, a: u8) -> Self {Self::from_rgba8(r, g, b, a)}
+
+    /// Color of the channels with straight alpha.
+    pub const fn from_rgba8(r: u8, g: u8, b: u8, a: u8) -> Self {Color{r, g, b, a}}
+
+    /// Color written as `#RGB`, `#RRGGBB` or `#RRGGBBAA`, the same as [`str::parse`].
+    pub fn from_hex(color: &str) -> Result<Self, ColorParseError> {color.parse()}
+
+    /// Opaque color of the hue in degrees and the saturation and lightness from 0 to 1.
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let (s, l) = (saturation.clamp(0.0, 1.0), lightness.clamp(0.0, 1.0));
+        let h = hue.rem_euclid(360.0) / 30.0;
+        let a = s * l.min(1.0 - l);
+        let f = |n: f32| {
+            let k = (n + h) % 12.0;
+            l - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+        };
+        let c = |v: f32| (v * 255.0).round() as u8;
+        Color::from_rgb(c(f(0.0)), c(f(8.0)), c(f(4.0)))
+    }
+
+    /// Hue in degrees from 0 to 360 and saturation and lightness from 0 to 1 of the color, the
+    /// inverse of [`from_hsl`](Self::from_hsl) ignoring the alpha. Grays have a hue of 0.
+    pub fn to_hsl(self) -> (f32, f32, f32) {
+        let (hue, max, chroma) = self.hue();
+        let lightness = max - chroma / 2.0;
+        let saturation = match chroma {
+            0.0 => 0.0,
+            _ => chroma / (1.0 - (2.0 * lightness - 1.0).abs()),
+        };
+        (hue, saturation, lightness)
+    }
+
+    /// Opaque color of the hue in degrees and the saturation and value from 0 to 1, as used by
+    /// color pickers.
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let (s, v) = (saturation.clamp(0.0, 1.0), value.clamp(0.0, 1.0));
+        let h = hue.rem_euclid(360.0) / 60.0;
+        let f = |n: f32| {
+            let k = (n + h) % 6.0;
+            v - v * s * k.min(4.0 - k).clamp(0.0, 1.0)
+        };
+        let c = |v: f32| (v * 255.0).round() as u8;
+        Color::from_rgb(c(f(5.0)), c(f(3.0)), c(f(1.0)))
+    }
+
+    /// Hue in degrees from 0 to 360 and saturation and value from 0 to 1 of the color, the inverse
+    /// of [`from_hsv`](Self::from_hsv) ignoring the alpha. Grays have a hue of 0.
+    pub fn to_hsv(self) -> (f32, f32, f32) {
+        let (hue, max, chroma) = self.hue();
+        let saturation = match max {
+            0.0 => 0.0,
+            _ => chroma / max,
+        };
+        (hue, saturation, max)
+    }
+
+    /// Opaque color of the lightness from 0 to 1, chroma from 0 to about 0.4 and hue in degrees
+    /// in OKLCH, where steps of hue or lightness look alike across the wheel, such as for palettes.
+    /// Colors outside of sRGB are clipped to it.
+    pub fn from_oklch(lightness: f32, chroma: f32, hue: f32) -> Self {
+        let (a, b) = (chroma * hue.to_radians().cos(), chroma * hue.to_radians().sin());
+        let l = (lightness + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+        let m = (lightness - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+        let s = (lightness - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+        let c = |v: f32| srgb(v.clamp(0.0, 1.0));
+        Color::from_rgb(
+            c(4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s),
+            c(-1.268_438 * l + 2.609_757_4 * m - 0.341_319_4 * s),
+            c(-0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s),
+        )
+    }
+
+    /// Lightness, chroma and hue in degrees from 0 to 360 of the color in OKLCH, the inverse of
+    /// [`from_oklch`](Self::from_oklch) ignoring the alpha. Grays have a hue of 0.
+    pub fn to_oklch(self) -> (f32, f32, f32) {
+        let [r, g, b] = [self.r, self.g, self.b].map(linear);
+        let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+        let lightness = 0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s;
+        let a = 1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s;
+        let b = 0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s;
+        let chroma = a.hypot(b);
+        let hue = match chroma < 1e-4 {
+            true => 0.0,
+            false => b.atan2(a).to_degrees().rem_euclid(360.0),
+        };
+        (lightness, chroma, hue)
+    }
+
+    /// Hue in degrees, largest channel and difference of the largest and smallest channels.
+    fn hue(self) -> (f32, f32, f32) {
+        let [r, g, b] = [self.r, self.g, self.b].map(|c| c as f32 / 255.0);
+        let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+        let chroma = max - min;
+        let hue = match max {
+            _ if chroma == 0.0 => 0.0,
+            m if m == r => ((g - b) / chroma).rem_euclid(6.0),
+            m if m == g => (b - r) / chroma + 2.0,
+            _ => (r - g) / chroma + 4.0,
+        };
+        (hue * 60.0, max, chroma)
+    }
+
+    /// The color with the alpha from 0 to 1.
+    pub fn with_alpha(self, alpha: f32) -> Self {
+        Color{a: (alpha.clamp(0.0, 1.0) * 255.0).round() as u8, ..self}
+    }
+
+    /// Blend from this color at 0 to the other at 1, the colors in linear light as the shaders
+    /// blend them and the alpha straight.
+    pub fn lerp(self, other: Color, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let c = |a: u8, b: u8| srgb(linear(a) + (linear(b) - linear(a)) * t);
+        let alpha = (self.a as f32 + (other.a as f32 - self.a as f32) * t).round() as u8;
+        Color{r: c(self.r, other.r), g: c(self.g, other.g), b: c(self.b, other.b), a: alpha}
+    }
+
+    /// The color with each channel multiplied by its alpha.
+    pub fn premultiplied_alpha(self) -> Self {
+        let c = |v: u8| ((v as u32 * self.a as u32 + 127) / 255) as u8;
+        Color{r: c(self.r), g: c(self.g), b: c(self.b), a: self.a}
+    }
+
+    /// Color of channels multiplied by the alpha, the inverse of
+    /// [`premultiplied_alpha`](Self::premultiplied_alpha). Transparent colors are black.
+    pub fn from_premultiplied(r: u8, g: u8, b: u8, a: u8) -> Self {
+        let c = |v: u8| match a {
+            0 => 0,
+            a => ((v.min(a) as u32 * 255 + a as u32 / 2) / a as u32) as u8,
+        };
+        Color{r: c(r), g: c(g), b: c(b), a}
     }
 
     pub(crate) fn color(&self) -> [f32; 4] {
         let c = |f: u8| (((f as f32 / u8::MAX as f32) + 0.055) / 1.055).powf(2.4);
-        [c(self.0), c(self.1), c(self.2), c(self.3)]
+        [c(self.r), c(self.g), c(self.b), c(self.a)]
+    }
+}
+
+/// Channel in linear light from 0 to 1.
+fn linear(c: u8) -> f32 {
+    match c as f32 / 255.0 {
+        c if c <= 0.04045 => c / 12.92,
+        c => ((c + 0.055) / 1.055).powf(2.4),
     }
 }
+
+fn srgb(v: f32) -> u8 {
+    (match v {
+        v if v <= 0.0031308 => v * 12.92,
+        v => 1.055 * v.powf(1.0 / 2.4) - 0.055,
+    } * 255.0).round() as u8
+}
+
+/// Error of parsing a [`Color`] that is not written as `#RGB`, `#RRGGBB` or `#RRGGBBAA`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColorParseError(String);
+
+impl std::fmt::Display for ColorParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid color {:?}, expected #RGB, #RRGGBB or #RRGGBBAA", self.0)
+    }
+}
+
+impl std::error::Error for ColorParseError {}
+
+/// Parses hex colors in the forms `#RGB`, `#RRGGBB` and `#RRGGBBAA`, the `#` is optional. Colors
+/// without alpha are opaque.
+impl std::str::FromStr for Color {
+    type Err = ColorParseError;
+
+    fn from_str(color: &str) -> Result<Self, Self::Err> {
+        let error = || ColorParseError(color.to_string());
+        let digits = color.strip_prefix('#').unwrap_or(color);
+        let digits = match digits.len() {
+            3 => digits.chars().flat_map(|c| [c, c]).collect(),
+            6 | 8 => digits.to_string(),
+            _ => return Err(error()),
+        };
+        match hex::decode(digits).map_err(|_| error())?[..] {
+            [r, g, b] => Ok(Color::from_rgb(r, g, b)),
+            [r, g, b, a] => Ok(Color{r, g, b, a}),
+            _ => Err(error()),
+        }
+    }
+}
+
+impl From<(u8, u8, u8, u8)> for Color {
+    fn from((r, g, b, a): (u8, u8, u8, u8)) -> Self {Color{r, g, b, a}}
+}
+
+impl From<Color> for (u8, u8, u8, u8) {
+    fn from(color: Color) -> Self {(color.r, color.g, color.b, color.a)}
+}
+
+impl From<Color> for ::image::Rgba<u8> {
+    fn from(color: Color) -> Self {::image::Rgba([color.r, color.g, color.b, color.a])}
+}
+
+/// How a [`Fill`] moves from its first color to its second along its way from 0 to 1.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Leaves the first color slowly.
+    EaseIn,
+    /// Arrives at the second color slowly.
+    EaseOut,
+    /// Leaves and arrives slowly with the given steepness in between, 1 is linear and larger values
+    /// hold each color longer. Steepness below 1 is taken as 1.
+    EaseInOut(f32),
+    /// Changes from the first color to the second at once halfway.
+    Step,
+}
+
+impl Easing {
+    /// Share of the second color at the point from 0 to 1 along the gradient.
+    pub fn ease(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut(steepness) => {
+                let (a, b) = (t.powf(steepness.max(1.0)), (1.0 - t).powf(steepness.max(1.0)));
+                a / (a + b)
+            },
+            Easing::Step => if t < 0.5 {0.0} else {1.0},
+        }
+    }
+
+    /// The easing and its steepness as the shaders take them.
+    fn code(self) -> [f32; 2] {
+        match self {
+            Easing::Linear => [0.0, 1.0],
+            Easing::EaseIn => [1.0, 1.0],
+            Easing::EaseOut => [2.0, 1.0],
+            Easing::EaseInOut(steepness) => [3.0, steepness.max(1.0)],
+            Easing::Step => [4.0, 1.0],
+        }
+    }
+}
+
+/// Marks repeated across a shape by a [`PatternFill`], lengths are in units of its scale and
+/// angles in radians.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PatternKind {
+    /// Stripes of the width running along the angle, with gaps as wide between them. Zero is
+    /// horizontal.
+    Stripes{angle: f32, width: f32},
+    /// Diagonal lines a unit wide crossing each other, the spacing apart.
+    Crosshatch{spacing: f32},
+    /// Dots of the radius on a grid of the spacing.
+    Dots{spacing: f32, radius: f32},
+    /// Squares of the size alternating between the foreground, starting at the top left, and the
+    /// background.
+    Checkerboard{cell_size: u32},
+}
+
+/// Pattern of foreground marks on a background, repeated from the top left of the shape.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PatternFill {
+    pub kind: PatternKind,
+    pub foreground: Color,
+    pub background: Color,
+    /// Length of a unit of the pattern in pixels.
+    pub scale: f32,
+}
+
+impl PatternFill {
+    /// Kind and lengths in pixels as the shaders take them.
+    fn code(&self) -> [f32; 4] {
+        let s = self.scale;
+        match self.kind {
+            PatternKind::Stripes{angle, width} => [2.0, angle, width * s, 0.0],
+            PatternKind::Crosshatch{spacing} => [3.0, spacing * s, s, 0.0],
+            PatternKind::Dots{spacing, radius} => [4.0, spacing * s, radius * s, 0.0],
+            PatternKind::Checkerboard{cell_size} => [5.0, cell_size as f32 * s, 0.0, 0.0],
+        }
+    }
+
+    /// Distance from the point, relative to the top left of the shape, to the nearest foreground
+    /// mark, below zero inside of it. The same as the shaders.
+    pub(crate) fn distance(&self, p: (f32, f32)) -> f32 {
+        let [_, a, b, _] = self.code();
+        // Closest repeat of the value to zero, halves round to even like the shaders.
+        let wrap = |v: f32, period: f32| v - period * (v / period).round_ties_even();
+        match self.kind {
+            PatternKind::Stripes{..} => {
+                let width = b.max(0.001);
+                let d = -p.0 * a.sin() + p.1 * a.cos() - width / 2.0;
+                wrap(d, 2.0 * width).abs() - width / 2.0
+            },
+            PatternKind::Crosshatch{..} => {
+                let spacing = a.max(0.001);
+                let d = ((p.0 + p.1) / 2f32.sqrt(), (p.0 - p.1) / 2f32.sqrt());
+                wrap(d.0, spacing).abs().min(wrap(d.1, spacing).abs()) - b / 2.0
+            },
+            PatternKind::Dots{..} => {
+                let spacing = a.max(0.001);
+                wrap(p.0 - spacing / 2.0, spacing).hypot(wrap(p.1 - spacing / 2.0, spacing)) - b
+            },
+            PatternKind::Checkerboard{..} => {
+                let size = a.max(0.001);
+                let cell = ((p.0 / size).floor(), (p.1 / size).floor());
+                let edge = wrap(p.0 - size / 2.0, size).abs().max(wrap(p.1 - size / 2.0, size).abs());
+                let depth = size / 2.0 - edge;
+                if (cell.0 + cell.1).rem_euclid(2.0) == 0.0 {-depth} else {depth}
+            },
+        }
+    }
+}
+
+/// Paint of a shape, a two color gradient or a repeating pattern, angles are in radians with zero
+/// pointing right. Plain colors convert into a linear gradient between the same color.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fill {
+    /// Blends from the first to the second color across the bounding box of the shape.
+    Linear(Color, Color, f32, Easing),
+    /// Blends around the center of the shape from the first color at the given angle to the second
+    /// color opposite of it and back again, so rings close without a seam.
+    Conic(Color, Color, f32, Easing),
+    Pattern(PatternFill),
+}
+
+impl Fill {
+    pub(crate) fn paint(&self) -> ([f32; 4], [f32; 4], [f32; 4]) {
+        let (start, end, kind, angle, easing) = match *self {
+            Fill::Linear(start, end, angle, easing) => (start, end, 0.0, angle, easing),
+            Fill::Conic(start, end, angle, easing) => (start, end, 1.0, angle, easing),
+            Fill::Pattern(pattern) => return (pattern.foreground.color(), pattern.background.color(), pattern.code()),
+        };
+        let [easing, steepness] = easing.code();
+        (start.color(), end.color(), [kind, angle, easing, steepness])
+    }
+
+    /// The fill with the lengths of its pattern multiplied by the factor.
+    pub(crate) fn scaled(self, factor: f32) -> Fill {
+        match self {
+            Fill::Pattern(pattern) => Fill::Pattern(PatternFill{scale: pattern.scale * factor, ..pattern}),
+            fill => fill,
+        }
+    }
+}
+
+impl From<PatternFill> for Fill {
+    fn from(pattern: PatternFill) -> Self {Fill::Pattern(pattern)}
+}
+
+impl From<Color> for Fill {
+    fn from(color: Color) -> Self {Fill::Linear(color, color, 0.0, Easing::Linear)}
+}
+
+impl From<(u8, u8, u8, u8)> for Fill {
+    fn from(color: (u8, u8, u8, u8)) -> Self {Color::from(color).into()}
+}
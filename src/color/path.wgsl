@@ -0,0 +1,56 @@
+struct ShapeInput {
+    @location(0) uv: vec2<f32>,
+    @location(1) position: vec2<f32>,
+    @location(2) size: vec2<f32>,
+    @location(3) bounds: vec4<f32>,
+    @location(4) z: f32,
+    @location(5) stroke: vec4<f32>,
+    @location(6) color: vec4<f32>,
+    @location(7) end_color: vec4<f32>,
+    @location(8) gradient: vec4<f32>
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) size: vec2<f32>,
+    @location(2) @interpolate(flat) bounds: vec4<f32>,
+    @location(3) @interpolate(flat) color: vec4<f32>,
+    @location(4) @interpolate(flat) end_color: vec4<f32>,
+    @location(5) @interpolate(flat) gradient: vec4<f32>
+};
+
+@vertex
+fn vs_main(
+    shape: ShapeInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(view(shape.position), shape.z, 1.0);
+    out.uv = shape.uv;
+
+    out.size = shape.size;
+
+    out.bounds = shape.bounds;
+    out.color = shape.color;
+    out.end_color = shape.end_color;
+    out.gradient = shape.gradient;
+
+    return out;
+}
+
+fn shade(in: VertexOutput) -> vec4<f32> {
+    if clipped(in.uv, in.bounds) {
+        discard;
+    }
+    return paint(in.uv, in.size, in.color, in.end_color, in.gradient);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return shade(in);
+}
+
+@fragment
+fn fs_pick(in: VertexOutput) -> @location(0) u32 {
+    return pick(in.position.z, shade(in)[3]);
+}
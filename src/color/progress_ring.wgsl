@@ -0,0 +1,85 @@
+struct ShapeInput {
+    @location(0) uv: vec2<f32>,
+    @location(1) position: vec2<f32>,
+    @location(2) size: vec2<f32>,
+    @location(3) bounds: vec4<f32>,
+    @location(4) z: f32,
+    @location(5) stroke: vec4<f32>,
+    @location(6) color: vec4<f32>,
+    @location(7) track: vec4<f32>,
+    @location(8) arc: vec2<f32>
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) size: vec2<f32>,
+    @location(2) @interpolate(flat) bounds: vec4<f32>,
+    @location(3) @interpolate(flat) stroke: vec4<f32>,
+    @location(4) @interpolate(flat) color: vec4<f32>,
+    @location(5) @interpolate(flat) track: vec4<f32>,
+    @location(6) @interpolate(flat) arc: vec2<f32>
+};
+
+@vertex
+fn vs_main(
+    shape: ShapeInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(view(shape.position), shape.z, 1.0);
+    out.uv = shape.uv;
+
+    out.size = shape.size;
+
+    out.bounds = shape.bounds;
+    out.stroke = shape.stroke;
+    out.color = shape.color;
+    out.track = shape.track;
+    out.arc = shape.arc;
+
+    return out;
+}
+
+fn shade(in: VertexOutput) -> vec4<f32> {
+    if clipped(in.uv, in.bounds) {
+        discard;
+    }
+    let outer = min(in.size.x, in.size.y) / 2.0;
+    let half = in.stroke[0] / 2.0;
+    let middle = outer - half;
+    let p = in.uv - in.size / 2.0;
+
+    // Signed distance to the band of the ring, and to the arc which is the band up to the sweep
+    // and a round cap on either end of it.
+    let ring = abs(length(p) - middle) - half;
+    let sweep = in.arc.x * 2.0 * PI;
+    let turn = fract((atan2(p.y, p.x) - in.arc.y) / (2.0 * PI)) * 2.0 * PI;
+    var arc = ring;
+    if turn > sweep {
+        let start = middle * vec2<f32>(cos(in.arc.y), sin(in.arc.y));
+        let end = middle * vec2<f32>(cos(in.arc.y + sweep), sin(in.arc.y + sweep));
+        arc = min(length(p - start), length(p - end)) - half;
+    }
+
+    let track = in.track[3] * clamp(0.5 - ring, 0.0, 1.0);
+    var progress = 0.0;
+    if in.arc.x > 0.0 {
+        progress = in.color[3] * clamp(0.5 - arc, 0.0, 1.0);
+    }
+    let alpha = progress + track * (1.0 - progress);
+    if alpha <= 0.0 {
+        discard;
+    }
+    let color = (in.color.rgb * progress + in.track.rgb * track * (1.0 - progress)) / alpha;
+    return vec4<f32>(color, alpha);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return shade(in);
+}
+
+@fragment
+fn fs_pick(in: VertexOutput) -> @location(0) u32 {
+    return pick(in.position.z, shade(in)[3]);
+}
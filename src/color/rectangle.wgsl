@@ -4,8 +4,10 @@ struct ShapeInput {
     @location(2) size: vec2<f32>,
     @location(3) bounds: vec4<f32>,
     @location(4) z: f32,
-    @location(5) stroke: f32,
-    @location(6) color: vec4<f32>
+    @location(5) stroke: vec4<f32>,
+    @location(6) color: vec4<f32>,
+    @location(7) end_color: vec4<f32>,
+    @location(8) gradient: vec4<f32>
 }
 
 struct VertexOutput {
@@ -13,8 +15,10 @@ struct VertexOutput {
     @location(0) uv: vec2<f32>,
     @location(1) @interpolate(flat) size: vec2<f32>,
     @location(2) @interpolate(flat) bounds: vec4<f32>,
-    @location(3) @interpolate(flat) stroke: f32,
-    @location(4) @interpolate(flat) color: vec4<f32>
+    @location(3) @interpolate(flat) stroke: vec4<f32>,
+    @location(4) @interpolate(flat) color: vec4<f32>,
+    @location(5) @interpolate(flat) end_color: vec4<f32>,
+    @location(6) @interpolate(flat) gradient: vec4<f32>
 };
 
 @vertex
@@ -22,7 +26,7 @@ fn vs_main(
     shape: ShapeInput,
 ) -> VertexOutput {
     var out: VertexOutput;
-    out.position = vec4<f32>(shape.position, shape.z, 1.0);
+    out.position = vec4<f32>(view(shape.position), shape.z, 1.0);
     out.uv = shape.uv;
 
     out.size = shape.size;
@@ -30,22 +34,25 @@ fn vs_main(
     out.bounds = shape.bounds;
     out.stroke = shape.stroke;
     out.color = shape.color;
+    out.end_color = shape.end_color;
+    out.gradient = shape.gradient;
 
     return out;
 }
 
+fn shade(in: VertexOutput) -> vec4<f32> {
+    if clipped(in.uv, in.bounds) || rectangle_alpha(in.uv, in.size, in.stroke) == 0.0 {
+        discard;
+    }
+    return paint(in.uv, in.size, in.color, in.end_color, in.gradient);
+}
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    if in.uv.x < in.bounds[0] || in.uv.x > in.bounds[2] ||
-       in.uv.y < in.bounds[1] || in.uv.y > in.bounds[3] {
-        discard;
-    }
-    if in.stroke > 0 {
-        if in.uv.x > in.stroke && in.uv.x < in.size.x-in.stroke &&
-           in.uv.y > in.stroke && in.uv.y < in.size.y-in.stroke {
-            discard;
-        }
-    }
-    return in.color;
+    return shade(in);
+}
+
+@fragment
+fn fs_pick(in: VertexOutput) -> @location(0) u32 {
+    return pick(in.position.z, shade(in)[3]);
 }
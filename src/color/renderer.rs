@@ -1,14 +1,22 @@
-use wgpu::{PipelineCompilationOptions, RenderPipelineDescriptor, PipelineLayoutDescriptor, DepthStencilState, MultisampleState, RenderPipeline, PrimitiveState, FragmentState, TextureFormat, BufferUsages, IndexFormat, VertexState, RenderPass, Device, Queue, VertexBufferLayout, ShaderModule};
-use wgpu_dyn_buffer::{DynamicBufferDescriptor, DynamicBuffer};
+use wgpu::{PipelineCompilationOptions, BindGroup, RenderPipelineDescriptor, PipelineLayoutDescriptor, DepthStencilState, MultisampleState, RenderPipeline, PrimitiveState, FragmentState, TextureFormat, IndexFormat, VertexState, RenderPass, Device, Queue, VertexBufferLayout, ShaderModule};
 
-use crate::shape::{Vertex, ShapeVertex, RoundedRectangleVertex, ColorVertex};
-use crate::{Area, Shape};
-use super::Color;
+use crate::shape::{Vertex, Mesh, ShapeVertex, RoundedRectangleVertex, ChevronVertex, SpeechBubbleVertex, StarVertex, WaveVertex, ColorVertex, ProgressRingVertex};
+use crate::{create_shader, pick, Area, CanvasError, CornerMask, ProgressRing, Shape};
+use crate::parallel;
+use crate::camera::CameraUniform;
+use crate::buffers::{Buffers, Usage};
+use super::Fill;
 
 pub struct ColorRenderer {
     ellipse_renderer: GenericColorRenderer,
     rectangle_renderer: GenericColorRenderer,
     rounded_rectangle_renderer: GenericColorRenderer,
+    chevron_renderer: GenericColorRenderer,
+    speech_bubble_renderer: GenericColorRenderer,
+    star_renderer: GenericColorRenderer,
+    path_renderer: GenericColorRenderer,
+    wave_renderer: GenericColorRenderer,
+    progress_ring_renderer: GenericColorRenderer,
 }
 
 impl ColorRenderer {
@@ -18,61 +26,224 @@ impl ColorRenderer {
         texture_format: &TextureFormat,
         multisample: MultisampleState,
         depth_stencil: Option<DepthStencilState>,
+        camera: &CameraUniform,
     ) -> Self {
-        let shader = device.create_shader_module(wgpu::include_wgsl!("ellipse.wgsl"));
-        let ellipse_renderer = GenericColorRenderer::new(device, texture_format, multisample, depth_stencil.clone(), shader, ColorVertex::<ShapeVertex>::layout());
-        let shader = device.create_shader_module(wgpu::include_wgsl!("rectangle.wgsl"));
-        let rectangle_renderer = GenericColorRenderer::new(device, texture_format, multisample, depth_stencil.clone(), shader, ColorVertex::<ShapeVertex>::layout());
-        let shader = device.create_shader_module(wgpu::include_wgsl!("rounded_rectangle.wgsl"));
-        let rounded_rectangle_renderer = GenericColorRenderer::new(device, texture_format, multisample, depth_stencil.clone(), shader, ColorVertex::<RoundedRectangleVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "ellipse.wgsl"));
+        let ellipse_renderer = GenericColorRenderer::new(device, texture_format, multisample, depth_stencil.clone(), camera, shader, ColorVertex::<ShapeVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "rectangle.wgsl"));
+        let rectangle_renderer = GenericColorRenderer::new(device, texture_format, multisample, depth_stencil.clone(), camera, shader, ColorVertex::<ShapeVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "rounded_rectangle.wgsl"));
+        let rounded_rectangle_renderer = GenericColorRenderer::new(device, texture_format, multisample, depth_stencil.clone(), camera, shader, ColorVertex::<RoundedRectangleVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "chevron.wgsl"));
+        let chevron_renderer = GenericColorRenderer::new(device, texture_format, multisample, depth_stencil.clone(), camera, shader, ColorVertex::<ChevronVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "speech_bubble.wgsl"));
+        let speech_bubble_renderer = GenericColorRenderer::new(device, texture_format, multisample, depth_stencil.clone(), camera, shader, ColorVertex::<SpeechBubbleVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "star.wgsl"));
+        let star_renderer = GenericColorRenderer::new(device, texture_format, multisample, depth_stencil.clone(), camera, shader, ColorVertex::<StarVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "wave.wgsl"));
+        let wave_renderer = GenericColorRenderer::new(device, texture_format, multisample, depth_stencil.clone(), camera, shader, ColorVertex::<WaveVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "path.wgsl"));
+        let path_renderer = GenericColorRenderer::new(device, texture_format, multisample, depth_stencil.clone(), camera, shader, ColorVertex::<ShapeVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "progress_ring.wgsl"));
+        let progress_ring_renderer = GenericColorRenderer::new(device, texture_format, multisample, depth_stencil.clone(), camera, shader, ProgressRingVertex::layout());
         ColorRenderer{
             ellipse_renderer,
             rectangle_renderer,
-            rounded_rectangle_renderer
+            rounded_rectangle_renderer,
+            chevron_renderer,
+            speech_bubble_renderer,
+            star_renderer,
+            path_renderer,
+            wave_renderer,
+            progress_ring_renderer
         }
     }
 
-    /// Prepare for rendering this frame; create all resources that will be
-    /// used during the next render that do not already exist.
-    pub fn prepare(
-        &mut self,
-        device: &Device,
-        queue: &Queue,
+    /// Vertices of the items in the order of the pipelines of the renderer, without touching the
+    /// GPU, written over the frame in the storage of the last one. The items are drained, keeping
+    /// their capacity. Paths that could not be tessellated are left out.
+    pub fn build(
         width: f32,
         height: f32,
-        items: Vec<(u16, Area, Shape, Color)>,
+        items: &mut Vec<ColorItem>,
+        rings: &mut Vec<RingItem>,
+        frame: &mut ColorFrame,
+        errors: &mut Vec<CanvasError>,
     ) {
-
-        let (ellipses, rects, rounded_rects) = items.into_iter().fold(
-            (vec![], vec![], vec![]),
-            |mut a, (z, area, shape, color)| {
+        for meshes in &mut frame.0 {meshes.clear();}
+        let (mut built, path_errors) = parallel::fold_into(
+            (std::mem::take(frame), vec![]),
+            items,
+            Default::default,
+            |(ColorFrame(mut a), mut errors): (ColorFrame, Vec<CanvasError>), (z, area, shape, fill)| {
                 match shape {
-                    Shape::Ellipse(stroke, size) => a.0.push(ColorVertex::new(ShapeVertex::new(width, height, z, area, stroke, size), color)),
-                    Shape::Rectangle(stroke, size) => a.1.push(ColorVertex::new(ShapeVertex::new(width, height, z, area, stroke, size), color)),
+                    Shape::Ellipse(stroke, size) => a[0].push(&ColorVertex::new(ShapeVertex::new(width, height, z, area, stroke.into(), size), fill), &QUAD),
+                    Shape::Rectangle(stroke, size) => a[1].push(&ColorVertex::new(ShapeVertex::new(width, height, z, area, stroke, size), fill), &QUAD),
                     Shape::RoundedRectangle(stroke, size, corner_radius) =>
-                        a.2.push(ColorVertex::new(RoundedRectangleVertex::new(width, height, z, area, stroke, size, corner_radius), color)),
+                        a[2].push(&ColorVertex::new(RoundedRectangleVertex::new(width, height, z, area, stroke, size, corner_radius, CornerMask::all()), fill), &QUAD),
+                    Shape::RoundedRectangleSides(stroke, size, corner_radius, corners) =>
+                        a[2].push(&ColorVertex::new(RoundedRectangleVertex::new(width, height, z, area, stroke, size, corner_radius, corners), fill), &QUAD),
+                    Shape::Chevron(direction, thickness, size) =>
+                        a[3].push(&ColorVertex::new(ChevronVertex::new(width, height, z, area, direction, thickness, size), fill), &QUAD),
+                    Shape::SpeechBubble(stroke, size, corner_radius, tail) =>
+                        a[4].push(&ColorVertex::new(SpeechBubbleVertex::new(width, height, z, area, stroke, size, corner_radius, tail), fill), &QUAD),
+                    Shape::Star(stroke, size, points, inner_ratio) =>
+                        a[5].push(&ColorVertex::new(StarVertex::new(width, height, z, area, stroke, size, points, inner_ratio), fill), &QUAD),
+                    Shape::Path(stroke, path) => match ShapeVertex::path(width, height, z, area, stroke, &path) {
+                        Ok(mesh) => {
+                            let Mesh(vertices, indices) = ColorVertex::mesh(mesh, fill);
+                            a[6].push(&vertices, &indices);
+                        },
+                        Err(error) => errors.push(error),
+                    },
+                    Shape::Wave(thickness, size, wavelength) =>
+                        a[7].push(&ColorVertex::new(WaveVertex::new(width, height, z, area, thickness, size, wavelength), fill), &QUAD),
                 }
-                a
-            }
+                (ColorFrame(a), errors)
+            },
+            |(mut a, mut errors), (b, other)| {
+                a.extend(b);
+                errors.extend(other);
+                (a, errors)
+            },
         );
-        self.ellipse_renderer.prepare(device, queue, ellipses);
-        self.rectangle_renderer.prepare(device, queue, rects);
-        self.rounded_rectangle_renderer.prepare(device, queue, rounded_rects);
+        errors.extend(path_errors);
+        for (z, area, ring) in rings.drain(..) {
+            built.0[8].push(&ProgressRingVertex::new(width, height, z, area, &ring), &QUAD);
+        }
+        *frame = built;
+    }
+
+    /// Upload the vertices of a frame from [`build`](Self::build) for the next render, returns
+    /// whether any pipeline had vertices or indices other than those it last uploaded.
+    pub fn upload(&mut self, device: &Device, queue: &Queue, frame: &ColorFrame) -> bool {
+        self.renderers_mut().into_iter().zip(&frame.0).fold(false, |changed, (renderer, meshes)| renderer.prepare(device, queue, meshes) || changed)
+    }
+
+    /// Replace the buffers of every pipeline by ones that just fit the last frame.
+    pub fn shrink_to_fit(&mut self, device: &Device, queue: &Queue) {
+        for renderer in self.renderers_mut() {
+            renderer.shrink_to_fit(device, queue);
+        }
+    }
+
+    /// Bytes of the vertex and index buffers of every pipeline.
+    pub fn buffer_capacity(&self) -> u64 {
+        self.renderers().into_iter().map(|renderer| renderer.buffers.capacity()).sum()
     }
 
     /// Render using caller provided render pass.
     pub fn render(&self, render_pass: &mut RenderPass<'_>) {
-        self.ellipse_renderer.render(render_pass);
-        self.rectangle_renderer.render(render_pass);
-        self.rounded_rectangle_renderer.render(render_pass);
+        for renderer in self.renderers() {
+            renderer.render(render_pass);
+        }
+    }
+
+    /// Render the ids of the items into the picking pass.
+    pub fn render_picking(&self, render_pass: &mut RenderPass<'_>) {
+        for renderer in self.renderers() {
+            renderer.render_picking(render_pass);
+        }
+    }
+
+    fn renderers(&self) -> [&GenericColorRenderer; 9] {
+        [
+            &self.ellipse_renderer,
+            &self.rectangle_renderer,
+            &self.rounded_rectangle_renderer,
+            &self.chevron_renderer,
+            &self.speech_bubble_renderer,
+            &self.star_renderer,
+            &self.path_renderer,
+            &self.wave_renderer,
+            &self.progress_ring_renderer,
+        ]
+    }
+
+    fn renderers_mut(&mut self) -> [&mut GenericColorRenderer; 9] {
+        [
+            &mut self.ellipse_renderer,
+            &mut self.rectangle_renderer,
+            &mut self.rounded_rectangle_renderer,
+            &mut self.chevron_renderer,
+            &mut self.speech_bubble_renderer,
+            &mut self.star_renderer,
+            &mut self.path_renderer,
+            &mut self.wave_renderer,
+            &mut self.progress_ring_renderer,
+        ]
+    }
+}
+
+/// Color draws of a frame built by [`ColorRenderer::build`], the meshes of each pipeline of the
+/// renderer.
+#[derive(PartialEq)]
+pub struct ColorFrame([Meshes; 9]);
+
+impl Default for ColorFrame {
+    fn default() -> Self {ColorFrame(STRIDES.map(|stride| Meshes(vec![], vec![], stride)))}
+}
+
+impl ColorFrame {
+    /// Append the meshes of another frame of the same size, drawn after these.
+    pub fn extend(&mut self, other: ColorFrame) {
+        for (meshes, other) in self.0.iter_mut().zip(other.0) {
+            meshes.extend(other);
+        }
+    }
+}
+
+type ColorItem = (u16, Area, Shape, Fill);
+type RingItem = (u16, Area, ProgressRing);
+
+/// Bytes of a vertex of each pipeline of the [`ColorRenderer`].
+const STRIDES: [usize; 9] = [
+    size_of::<ColorVertex<ShapeVertex>>(),
+    size_of::<ColorVertex<ShapeVertex>>(),
+    size_of::<ColorVertex<RoundedRectangleVertex>>(),
+    size_of::<ColorVertex<ChevronVertex>>(),
+    size_of::<ColorVertex<SpeechBubbleVertex>>(),
+    size_of::<ColorVertex<StarVertex>>(),
+    size_of::<ColorVertex<ShapeVertex>>(),
+    size_of::<ColorVertex<WaveVertex>>(),
+    size_of::<ProgressRingVertex>(),
+];
+
+/// Indices of the two triangles of a quad.
+const QUAD: [u16; 6] = [0, 1, 2, 1, 2, 3];
+
+/// Vertices of one pipeline with their size in bytes and the indices into them.
+#[derive(PartialEq)]
+struct Meshes(Vec<u8>, Vec<u32>, usize);
+
+impl Meshes {
+    /// Empty the meshes for the next frame, keeping their storage.
+    fn clear(&mut self) {
+        self.0.clear();
+        self.1.clear();
+    }
+
+    /// Append the triangles of the vertices.
+    fn push<V: bytemuck::Pod>(&mut self, vertices: &[V], indices: &[u16]) {
+        let l = (self.0.len() / self.2) as u32;
+        self.0.extend_from_slice(bytemuck::cast_slice(vertices));
+        self.1.extend(indices.iter().map(|i| l + *i as u32));
+    }
+
+    fn extend(&mut self, Meshes(vertices, indices, _): Meshes) {
+        let l = (self.0.len() / self.2) as u32;
+        self.0.extend(vertices);
+        self.1.extend(indices.into_iter().map(|i| l + i));
     }
 }
 
 pub struct GenericColorRenderer {
+    camera: BindGroup,
     render_pipeline: RenderPipeline,
-    vertex_buffer: DynamicBuffer,
-    index_buffer: DynamicBuffer,
-    indices: u32
+    pick_pipeline: RenderPipeline,
+    buffers: Buffers,
+    indices: u32,
+    usage: Usage,
 }
 
 impl GenericColorRenderer {
@@ -82,10 +253,15 @@ impl GenericColorRenderer {
         texture_format: &TextureFormat,
         multisample: MultisampleState,
         depth_stencil: Option<DepthStencilState>,
+        camera: &CameraUniform,
         shader: ShaderModule,
         vertex_layout: VertexBufferLayout
     ) -> Self {
-        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor::default());
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor{
+            label: None,
+            bind_group_layouts: &[&camera.layout],
+            push_constant_ranges: &[],
+        });
 
         let render_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
             label: None,
@@ -94,7 +270,7 @@ impl GenericColorRenderer {
                 module: &shader,
                 entry_point: Some("vs_main"),
                 compilation_options: PipelineCompilationOptions::default(),
-                buffers: &[vertex_layout]
+                buffers: std::slice::from_ref(&vertex_layout)
             },
             fragment: Some(FragmentState {
                 module: &shader,
@@ -114,53 +290,53 @@ impl GenericColorRenderer {
             multiview: None,
             cache: None
         });
-
-        let vertex_buffer = DynamicBuffer::new(device, &DynamicBufferDescriptor {
-            label: None,
-            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
-        });
-
-        let index_buffer = DynamicBuffer::new(device, &DynamicBufferDescriptor {
-            label: None,
-            usage: BufferUsages::INDEX | BufferUsages::COPY_DST,
-        });
+        let pick_pipeline = pick::pipeline(device, &pipeline_layout, &shader, vertex_layout);
 
         GenericColorRenderer{
+            camera: camera.bind_group.clone(),
             render_pipeline,
-            vertex_buffer,
-            index_buffer,
-            indices: 0
+            pick_pipeline,
+            buffers: Buffers::new(device),
+            indices: 0,
+            usage: Usage::default(),
         }
     }
 
     /// Prepare for rendering this frame; create all resources that will be
-    /// used during the next render that do not already exist.
-    pub fn prepare<V: bytemuck::Pod>(
-        &mut self,
-        device: &Device,
-        queue: &Queue,
-        vertices: Vec<[V; 4]>,
-    ) {
-
-        let (vertices, indices) = vertices.into_iter().fold(
-            (vec![], vec![]), |mut a, vertices| {
-                let l = a.0.len() as u16;
-                a.0.extend(vertices);
-                a.1.extend([l, l+1, l+2, l+1, l+2, l+3]);
-                a
-            }
-        );
+    /// used during the next render that do not already exist. Returns whether the meshes were
+    /// written, they are not when they are the ones last written. The buffers shrink after a
+    /// while of frames using much less of them.
+    fn prepare(&mut self, device: &Device, queue: &Queue, meshes: &Meshes) -> bool {
+        let changed = self.buffers.write(device, queue, &meshes.0, bytemuck::cast_slice(&meshes.1));
+        if changed {
+            self.indices = meshes.1.len() as u32;
+        }
+        if self.usage.frame(self.buffers.used(), self.buffers.capacity()) {
+            self.shrink_to_fit(device, queue);
+        }
+        changed
+    }
 
-        self.indices = indices.len() as u32;
-        self.vertex_buffer.write_buffer(device, queue, bytemuck::cast_slice(&vertices));
-        self.index_buffer.write_buffer(device, queue, bytemuck::cast_slice(&indices));
+    /// Replace the buffers by ones that just fit the meshes they hold.
+    fn shrink_to_fit(&mut self, device: &Device, queue: &Queue) {
+        self.buffers.shrink_to_fit(device, queue);
     }
 
     /// Render using caller provided render pass.
     pub fn render(&self, render_pass: &mut RenderPass<'_>) {
-        render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.set_vertex_buffer(0, self.vertex_buffer.as_ref().slice(..));
-        render_pass.set_index_buffer(self.index_buffer.as_ref().slice(..), IndexFormat::Uint16);
+        self.draw(render_pass, &self.render_pipeline);
+    }
+
+    /// Render the ids of the items into the picking pass.
+    pub fn render_picking(&self, render_pass: &mut RenderPass<'_>) {
+        self.draw(render_pass, &self.pick_pipeline);
+    }
+
+    fn draw(&self, render_pass: &mut RenderPass<'_>, pipeline: &RenderPipeline) {
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &self.camera, &[]);
+        render_pass.set_vertex_buffer(0, self.buffers.vertices().slice(..));
+        render_pass.set_index_buffer(self.buffers.indices().slice(..), IndexFormat::Uint32);
         render_pass.draw_indexed(0..self.indices, 0, 0..1);
     }
 }
@@ -4,9 +4,12 @@ struct ShapeInput {
     @location(2) size: vec2<f32>,
     @location(3) bounds: vec4<f32>,
     @location(4) z: f32,
-    @location(5) stroke: f32,
-    @location(6) corner_radius: f32,
-    @location(7) color: vec4<f32>,
+    @location(5) stroke: vec4<f32>,
+    @location(6) radius_x: vec4<f32>,
+    @location(7) radius_y: vec4<f32>,
+    @location(8) color: vec4<f32>,
+    @location(9) end_color: vec4<f32>,
+    @location(10) gradient: vec4<f32>
 }
 
 struct VertexOutput {
@@ -14,9 +17,12 @@ struct VertexOutput {
     @location(0) uv: vec2<f32>,
     @location(1) @interpolate(flat) size: vec2<f32>,
     @location(2) @interpolate(flat) bounds: vec4<f32>,
-    @location(3) @interpolate(flat) stroke: f32,
-    @location(4) @interpolate(flat) corner_radius: f32,
-    @location(5) @interpolate(flat) color: vec4<f32>,
+    @location(3) @interpolate(flat) stroke: vec4<f32>,
+    @location(4) @interpolate(flat) radius_x: vec4<f32>,
+    @location(5) @interpolate(flat) radius_y: vec4<f32>,
+    @location(6) @interpolate(flat) color: vec4<f32>,
+    @location(7) @interpolate(flat) end_color: vec4<f32>,
+    @location(8) @interpolate(flat) gradient: vec4<f32>
 };
 
 @vertex
@@ -24,69 +30,37 @@ fn vs_main(
     shape: ShapeInput,
 ) -> VertexOutput {
     var out: VertexOutput;
-    out.position = vec4<f32>(shape.position, shape.z, 1.0);
+    out.position = vec4<f32>(view(shape.position), shape.z, 1.0);
     out.uv = shape.uv;
 
     out.size = shape.size;
 
     out.bounds = shape.bounds;
     out.stroke = shape.stroke;
-    out.corner_radius = shape.corner_radius;
+    out.radius_x = shape.radius_x;
+    out.radius_y = shape.radius_y;
     out.color = shape.color;
+    out.end_color = shape.end_color;
+    out.gradient = shape.gradient;
 
     return out;
 }
 
-fn alpha(uv: vec2<f32>, size: vec2<f32>, stroke: f32, cr: f32) -> f32 {
-    var x = 0.0;
-    var y = 0.0;
-
-    if uv.x < cr && uv.y < cr {
-        x = (cr-uv.x);
-        y = (cr-uv.y);
-    } else if uv.x > size[0]-cr && uv.y < cr {
-        x = ((size[0]-cr)-uv.x);
-        y = (cr-uv.y);
-    } else if uv.x < cr && uv.y > size[1]-cr {
-        x = (cr-uv.x);
-        y = ((size[1]-cr)-uv.y);
-    } else if uv.x > size[0]-cr && uv.y > size[1]-cr {
-        x = ((size[0]-cr)-uv.x);
-        y = ((size[1]-cr)-uv.y);
-    } else {
-        if stroke > 0 {
-            if uv.x > stroke && uv.x < size.x-stroke &&
-               uv.y > stroke && uv.y < size.y-stroke {
-                return 0.0;
-            }
-            return 1.0;
-        }
-    }
-
-    let a = (size.x / 2.0);
-    let b = (size.y / 2.0);
-    let dx = x / (cr - 1.0);
-    let dy = y / (cr - 1.0);
-    let d = dx*dx+dy*dy;
-    let p = (2.0/cr);
-
-    var s = 1.0;
-    if stroke > 0 && stroke < cr {
-        let sx = x / (cr-stroke-0.5);
-        let sy = y / (cr-stroke-0.5);
-        let sd = sx*sx+sy*sy;
-        s = smoothstep(1.0, 1.0+p, sd);
+fn shade(in: VertexOutput) -> vec4<f32> {
+    if clipped(in.uv, in.bounds) {
+        discard;
     }
-
-    return (1.0-smoothstep(1.0, 1.0+p, d)) * s;
+    let alpha = rounded_rectangle_alpha(in.uv, in.size, in.stroke, in.radius_x, in.radius_y);
+    let color = paint(in.uv, in.size, in.color, in.end_color, in.gradient);
+    return vec4<f32>(color[0], color[1], color[2], color[3]*alpha);
 }
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    if in.uv.x < in.bounds[0] || in.uv.x > in.bounds[2] ||
-       in.uv.y < in.bounds[1] || in.uv.y > in.bounds[3] {
-        discard;
-    }
-    let alpha = alpha(in.uv, in.size, in.stroke, in.corner_radius);
-    return vec4<f32>(in.color[0], in.color[1], in.color[2], in.color[3]*alpha);
+    return shade(in);
+}
+
+@fragment
+fn fs_pick(in: VertexOutput) -> @location(0) u32 {
+    return pick(in.position.z, shade(in)[3]);
 }
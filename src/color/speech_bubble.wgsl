@@ -0,0 +1,66 @@
+struct ShapeInput {
+    @location(0) uv: vec2<f32>,
+    @location(1) position: vec2<f32>,
+    @location(2) size: vec2<f32>,
+    @location(3) bounds: vec4<f32>,
+    @location(4) z: f32,
+    @location(5) stroke: vec4<f32>,
+    @location(6) radius: vec2<f32>,
+    @location(7) tail: vec4<f32>,
+    @location(8) color: vec4<f32>,
+    @location(9) end_color: vec4<f32>,
+    @location(10) gradient: vec4<f32>
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) size: vec2<f32>,
+    @location(2) @interpolate(flat) bounds: vec4<f32>,
+    @location(3) @interpolate(flat) stroke: vec4<f32>,
+    @location(4) @interpolate(flat) radius: vec2<f32>,
+    @location(5) @interpolate(flat) tail: vec4<f32>,
+    @location(6) @interpolate(flat) color: vec4<f32>,
+    @location(7) @interpolate(flat) end_color: vec4<f32>,
+    @location(8) @interpolate(flat) gradient: vec4<f32>
+};
+
+@vertex
+fn vs_main(
+    shape: ShapeInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(view(shape.position), shape.z, 1.0);
+    out.uv = shape.uv;
+
+    out.size = shape.size;
+
+    out.bounds = shape.bounds;
+    out.stroke = shape.stroke;
+    out.radius = shape.radius;
+    out.tail = shape.tail;
+    out.color = shape.color;
+    out.end_color = shape.end_color;
+    out.gradient = shape.gradient;
+
+    return out;
+}
+
+fn shade(in: VertexOutput) -> vec4<f32> {
+    if clipped(in.uv, in.bounds) {
+        discard;
+    }
+    let alpha = speech_bubble_alpha(in.uv, in.size, in.stroke[0], in.radius, in.tail);
+    let color = paint(in.uv, in.size, in.color, in.end_color, in.gradient);
+    return vec4<f32>(color[0], color[1], color[2], color[3]*alpha);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return shade(in);
+}
+
+@fragment
+fn fs_pick(in: VertexOutput) -> @location(0) u32 {
+    return pick(in.position.z, shade(in)[3]);
+}
@@ -0,0 +1,463 @@
+const PI: f32 = 3.14159265358979;
+
+// Pan and zoom of the canvas, the offset in clip space in xy and the zoom in z.
+@group(0) @binding(0)
+var<uniform> camera: vec4<f32>;
+
+// Clip space position of a vertex seen through the camera.
+fn view(position: vec2<f32>) -> vec2<f32> {
+    return position * camera.z + camera.xy;
+}
+
+// True when the fragment lies outside of the clip bounds (x, y, x2, y2) of the shape.
+fn clipped(uv: vec2<f32>, bounds: vec4<f32>) -> bool {
+    return uv.x < bounds[0] || uv.x > bounds[2] ||
+           uv.y < bounds[1] || uv.y > bounds[3];
+}
+
+// Id written to the picking target by a fragment at the depth of an item, its index in the list
+// plus one as zero is left for pixels without an item. Fragments less than half opaque are left
+// to the items below them.
+fn pick(depth: f32, alpha: f32) -> u32 {
+    if alpha < 0.5 {
+        discard;
+    }
+    return u32(round(depth * 65535.0)) + 1u;
+}
+
+// Applies the tint of an image item, a tint without alpha keeps the colors of the texture.
+fn tint(color: vec4<f32>, tint: vec4<f32>) -> vec4<f32> {
+    if tint[3] > 0.0 {
+        return vec4<f32>(tint[0], tint[1], tint[2], tint[3]*color[3]);
+    }
+    return color;
+}
+
+// Color of a paint at uv, the gradient holds the kind (0 linear, 1 conic) and the angle.
+// Solid colors are painted as a linear gradient between two equal colors.
+// Gradients are given as their kind, angle, easing and steepness of the easing, patterns as their
+// kind and lengths with the foreground as the start color, see `Fill`.
+fn paint(uv: vec2<f32>, size: vec2<f32>, start: vec4<f32>, end: vec4<f32>, gradient: vec4<f32>) -> vec4<f32> {
+    if gradient.x >= 2.0 {
+        return mix(end, start, pattern(uv, gradient));
+    }
+    let p = uv - size / 2.0;
+    var t = 0.0;
+    if gradient.x == 0.0 {
+        let dir = vec2<f32>(cos(gradient.y), sin(gradient.y));
+        let extent = abs(dir.x) * size.x + abs(dir.y) * size.y;
+        t = dot(p, dir) / max(extent, 0.001) + 0.5;
+    } else {
+        let n = p / max(size, vec2<f32>(0.001));
+        let turn = fract((atan2(n.y, n.x) - gradient.y) / (2.0 * PI));
+        t = 1.0 - abs(turn * 2.0 - 1.0);
+    }
+    return mix(start, end, ease(clamp(t, 0.0, 1.0), gradient.z, gradient.w));
+}
+
+// Share of the foreground of the pattern at the point from the top left of the shape, smoothed
+// over a pixel, see `PatternFill::distance`.
+fn pattern(p: vec2<f32>, pattern: vec4<f32>) -> f32 {
+    var dist = 0.0;
+    switch u32(pattern.x) {
+        case 2u: {
+            let width = max(pattern.z, 0.001);
+            let d = dot(p, vec2<f32>(-sin(pattern.y), cos(pattern.y))) - width / 2.0;
+            dist = abs(d - 2.0 * width * round(d / (2.0 * width))) - width / 2.0;
+        }
+        case 3u: {
+            let spacing = max(pattern.y, 0.001);
+            let d = vec2<f32>(p.x + p.y, p.x - p.y) / sqrt(2.0);
+            let r = abs(d - spacing * round(d / spacing));
+            dist = min(r.x, r.y) - pattern.z / 2.0;
+        }
+        case 4u: {
+            let spacing = max(pattern.y, 0.001);
+            let q = p - spacing / 2.0;
+            dist = length(q - spacing * round(q / spacing)) - pattern.z;
+        }
+        default: {
+            let size = max(pattern.y, 0.001);
+            let cell = floor(p / size);
+            let q = p - size / 2.0;
+            let r = abs(q - size * round(q / size));
+            let depth = size / 2.0 - max(r.x, r.y);
+            dist = select(depth, -depth, (cell.x + cell.y) - 2.0 * floor((cell.x + cell.y) / 2.0) == 0.0);
+        }
+    }
+    return clamp(0.5 - dist * camera.z, 0.0, 1.0);
+}
+
+// Share of the end color of a gradient in the order of the variants of `Easing`.
+fn ease(t: f32, easing: f32, steepness: f32) -> f32 {
+    switch u32(easing) {
+        case 1u: {return t * t;}
+        case 2u: {return 1.0 - (1.0 - t) * (1.0 - t);}
+        case 3u: {
+            let a = pow(max(t, 1e-6), steepness);
+            return a / (a + pow(max(1.0 - t, 1e-6), steepness));
+        }
+        case 4u: {return step(0.5, t);}
+        default: {return t;}
+    }
+}
+
+fn ellipse_alpha(uv: vec2<f32>, size: vec2<f32>, stroke: f32) -> f32 {
+    let r = size / 2.0;
+    let d = uv - r;
+    // Ellipses are drawn a pixel of the screen inside their size, or less when they are small.
+    let px = min(1.0 / camera.z, min(r.x, r.y) / 2.0);
+    let alpha = smoothstep(0.0, px, ellipse_depth(d, r));
+
+    // Strokes that reach the center leave no hole, the ellipse is filled.
+    if stroke > 0 && stroke < min(r.x, r.y) {
+        let sr = r - stroke;
+        let spx = min(px, min(sr.x, sr.y) / 2.0);
+        return alpha * (1.0 - smoothstep(0.0, spx, ellipse_depth(d, sr)));
+    }
+    return alpha;
+}
+
+// Stroke widths are given per side in the order top, right, bottom, left.
+fn rectangle_alpha(uv: vec2<f32>, size: vec2<f32>, stroke: vec4<f32>) -> f32 {
+    if any(stroke > vec4<f32>(0.0)) {
+        if uv.x > stroke[3] && uv.x < size.x-stroke[1] &&
+           uv.y > stroke[0] && uv.y < size.y-stroke[2] {
+            return 0.0;
+        }
+    }
+    return 1.0;
+}
+
+// Signed distance to the box from lo to hi with elliptical corners, the horizontal and vertical
+// radii are given per corner in the order top left, top right, bottom right, bottom left.
+fn rounded_box_distance(uv: vec2<f32>, lo: vec2<f32>, hi: vec2<f32>, rx: vec4<f32>, ry: vec4<f32>) -> f32 {
+    let center = (lo + hi) / 2.0;
+    var r = vec2<f32>(rx[0], ry[0]);
+    var corner = lo;
+    if uv.x >= center.x && uv.y < center.y {
+        r = vec2<f32>(rx[1], ry[1]);
+        corner = vec2<f32>(hi.x, lo.y);
+    } else if uv.x >= center.x && uv.y >= center.y {
+        r = vec2<f32>(rx[2], ry[2]);
+        corner = hi;
+    } else if uv.x < center.x && uv.y >= center.y {
+        r = vec2<f32>(rx[3], ry[3]);
+        corner = vec2<f32>(lo.x, hi.y);
+    }
+
+    var dist = max(max(lo.x - uv.x, uv.x - hi.x), max(lo.y - uv.y, uv.y - hi.y));
+    let outward = sign(corner - center);
+    let c = corner - outward * r;
+    if r.x > 0.0 && r.y > 0.0 && all((uv - c) * outward > vec2<f32>(0.0)) {
+        let d = abs(uv - c);
+        let k = length(d / r);
+        dist = (k - 1.0) * k / max(length(d / (r * r)), 0.0001);
+    }
+    return dist;
+}
+
+// Coverage of a pixel of the screen by a shape given the signed distance to its outline.
+fn coverage(dist: f32) -> f32 {
+    return clamp(0.5 - dist * camera.z, 0.0, 1.0);
+}
+
+fn rounded_box_coverage(uv: vec2<f32>, lo: vec2<f32>, hi: vec2<f32>, rx: vec4<f32>, ry: vec4<f32>) -> f32 {
+    return coverage(rounded_box_distance(uv, lo, hi, rx, ry));
+}
+
+// The corner radii are given per corner in the order top left, top right, bottom right, bottom left.
+// The inner corners of a stroke are elliptical when the widths of the adjacent sides differ.
+fn rounded_rectangle_alpha(uv: vec2<f32>, size: vec2<f32>, stroke: vec4<f32>, rx: vec4<f32>, ry: vec4<f32>) -> f32 {
+    let outer = rounded_box_coverage(uv, vec2<f32>(0.0), size, rx, ry);
+    if all(stroke == vec4<f32>(0.0)) {
+        return outer;
+    }
+
+    let lo = vec2<f32>(stroke[3], stroke[0]);
+    let hi = size - vec2<f32>(stroke[1], stroke[2]);
+    let irx = max(rx - vec4<f32>(stroke[3], stroke[1], stroke[1], stroke[3]), vec4<f32>(0.0));
+    let iry = max(ry - vec4<f32>(stroke[0], stroke[0], stroke[2], stroke[2]), vec4<f32>(0.0));
+    return max(outer - rounded_box_coverage(uv, lo, hi, irx, iry), 0.0);
+}
+
+// Distance from p to the line segment from a to b.
+fn segment_distance(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>) -> f32 {
+    let pa = p - a;
+    let ba = b - a;
+    let h = clamp(dot(pa, ba) / dot(ba, ba), 0.0, 1.0);
+    return length(pa - ba * h);
+}
+
+// Two round capped segments of the given thickness meeting in a round join at the tip. The
+// direction is the number of clockwise quarter turns from pointing right, the chevron is drawn
+// pointing right in a mirrored or transposed frame so opposite directions mirror exactly.
+fn chevron_alpha(uv: vec2<f32>, size: vec2<f32>, thickness: f32, direction: f32) -> f32 {
+    var p = uv;
+    var s = size;
+    if direction == 1.0 {
+        p = uv.yx;
+        s = size.yx;
+    } else if direction == 2.0 {
+        p = vec2<f32>(size.x - uv.x, uv.y);
+    } else if direction == 3.0 {
+        p = vec2<f32>(size.y - uv.y, uv.x);
+        s = size.yx;
+    }
+
+    let r = thickness / 2.0;
+    let tip = vec2<f32>(s.x - r, s.y / 2.0);
+    let d = min(segment_distance(p, vec2<f32>(r, r), tip), segment_distance(p, vec2<f32>(r, s.y - r), tip)) - r;
+    return coverage(d);
+}
+
+// Coverage of a pixel of the screen by a band of the width centered on a line at the distance,
+// exact for straight bands and bands thinner than a pixel.
+fn band_coverage(dist: f32, width: f32) -> f32 {
+    let d = dist * camera.z;
+    let w = width * camera.z;
+    return clamp(min(d + 0.5, w / 2.0) - max(d - 0.5, -w / 2.0), 0.0, 1.0);
+}
+
+// Sine wave along the middle of the size rising from its left edge, swinging as far as the
+// thickness allows. Only points of the curve within reach of the pixel can cover it, the closest
+// is sampled among them and refined by Newton's method.
+fn wave_alpha(uv: vec2<f32>, size: vec2<f32>, thickness: f32, wavelength: f32) -> f32 {
+    let amplitude = max(size.y - thickness, 0.0) / 2.0;
+    let k = 6.2831853 / max(wavelength, 0.001);
+    let p = vec2<f32>(uv.x, size.y / 2.0 - uv.y);
+    let reach = thickness / 2.0 + 0.5;
+
+    var t = p.x;
+    var d = abs(amplitude * sin(k * t) - p.y);
+    for (var i = 0; i <= 8; i++) {
+        let s = p.x + reach * (f32(i) / 4.0 - 1.0);
+        let ds = length(vec2<f32>(s - p.x, amplitude * sin(k * s) - p.y));
+        if ds < d {
+            t = s;
+            d = ds;
+        }
+    }
+    var n = t;
+    for (var i = 0; i < 3; i++) {
+        let f = amplitude * sin(k * n);
+        let f1 = amplitude * k * cos(k * n);
+        let g = (n - p.x) + (f - p.y) * f1;
+        let g1 = 1.0 + f1 * f1 - (f - p.y) * k * k * f;
+        n = clamp(n - g / max(g1, 0.001), t - reach / 4.0, t + reach / 4.0);
+    }
+    d = min(d, length(vec2<f32>(n - p.x, amplitude * sin(k * n) - p.y)));
+    return band_coverage(d, thickness);
+}
+
+// Signed distance to the triangle a, b, c.
+fn triangle_distance(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>, c: vec2<f32>) -> f32 {
+    let e0 = b - a;
+    let e1 = c - b;
+    let e2 = a - c;
+    let v0 = p - a;
+    let v1 = p - b;
+    let v2 = p - c;
+    let pq0 = v0 - e0 * clamp(dot(v0, e0) / dot(e0, e0), 0.0, 1.0);
+    let pq1 = v1 - e1 * clamp(dot(v1, e1) / dot(e1, e1), 0.0, 1.0);
+    let pq2 = v2 - e2 * clamp(dot(v2, e2) / dot(e2, e2), 0.0, 1.0);
+    let s = sign(e0.x * e2.y - e0.y * e2.x);
+    let d = min(min(
+        vec2<f32>(dot(pq0, pq0), s * (v0.x * e0.y - v0.y * e0.x)),
+        vec2<f32>(dot(pq1, pq1), s * (v1.x * e1.y - v1.y * e1.x))),
+        vec2<f32>(dot(pq2, pq2), s * (v2.x * e2.y - v2.y * e2.x)));
+    return -sqrt(d.x) * sign(d.y);
+}
+
+// Rounded rectangle with a triangular tail, the tail holds the edge it sits on (as the direction
+// of a chevron), the offset of its middle along that edge, its width and its height. The shape is
+// evaluated with the tail on the bottom edge in a mirrored or transposed frame.
+fn speech_bubble_alpha(uv: vec2<f32>, size: vec2<f32>, stroke: f32, radius: vec2<f32>, tail: vec4<f32>) -> f32 {
+    var p = uv;
+    var s = size;
+    var r = radius;
+    if tail[0] == 0.0 {
+        p = uv.yx;
+        s = size.yx;
+        r = radius.yx;
+    } else if tail[0] == 2.0 {
+        p = vec2<f32>(uv.y, size.x - uv.x);
+        s = size.yx;
+        r = radius.yx;
+    } else if tail[0] == 3.0 {
+        p = vec2<f32>(uv.x, size.y - uv.y);
+    }
+
+    let base = s.y - tail[3];
+    let body = rounded_box_distance(p, vec2<f32>(0.0), vec2<f32>(s.x, base), vec4<f32>(r.x), vec4<f32>(r.y));
+
+    // The tail reaches into the body past the rounding of the corners so the two overlap without
+    // a seam, but never sticks out of the sides of the body when placed near a corner.
+    var tail_distance = body;
+    if tail[3] > 0.0 {
+        let half = tail[2] / 2.0;
+        let middle = clamp(tail[1], half, s.x - half);
+        let depth = clamp(r.y, 1.0, base / 2.0);
+        let spread = half * (tail[3] + depth) / tail[3];
+        let tip = vec2<f32>(middle, s.y);
+        let triangle = triangle_distance(p, tip, vec2<f32>(middle - spread, base - depth), vec2<f32>(middle + spread, base - depth));
+        tail_distance = max(triangle, abs(p.x - s.x / 2.0) - s.x / 2.0);
+    }
+
+    // The inside of the stroke blends the two smoothly, a plain union of both leaves specks
+    // uncovered where the inner outlines of the body and the tail meet.
+    let d = min(body, tail_distance);
+    if stroke > 0.0 {
+        let h = clamp(0.5 + 0.5 * (tail_distance - body) / stroke, 0.0, 1.0);
+        let inner = mix(tail_distance, body, h) - stroke * h * (1.0 - h) + stroke;
+        return max(coverage(d) - coverage(inner), 0.0);
+    }
+    return coverage(d);
+}
+
+// Star with the given number of points, one pointing up, inscribed in the circle that fits the
+// size. The inner vertices lie at the inner ratio of the outer radius.
+fn star_alpha(uv: vec2<f32>, size: vec2<f32>, stroke: f32, points: f32, inner_ratio: f32) -> f32 {
+    let outer = min(size.x, size.y) / 2.0;
+    let p = uv - size / 2.0;
+
+    // Fold p into the half of a point between its tip and the next inner vertex, where the
+    // outline is a single segment.
+    let sector = PI / points;
+    let turn = fract((atan2(p.y, p.x) + PI / 2.0) / (2.0 * sector)) * 2.0 * sector;
+    let angle = min(turn, 2.0 * sector - turn);
+    let q = length(p) * vec2<f32>(cos(angle), sin(angle));
+
+    let tip = vec2<f32>(outer, 0.0);
+    let inner = outer * inner_ratio * vec2<f32>(cos(sector), sin(sector));
+    let edge = inner - tip;
+    let side = edge.x * (q.y - tip.y) - edge.y * (q.x - tip.x);
+    let d = segment_distance(q, tip, inner) * -sign(side);
+
+    if stroke > 0.0 {
+        return max(coverage(d) - coverage(d + stroke), 0.0);
+    }
+    return coverage(d);
+}
+
+// Color of an image stroke at texture coordinates that wrap around the outline within the
+// region, sampled with derivatives that skip the jump from the end of the region back to its
+// start so no smaller mipmap is picked along the seam.
+fn sample_stroke(t: texture_2d<f32>, s: sampler, texture: vec2<f32>, region: vec4<f32>) -> vec4<f32> {
+    let width = max(region.z, 0.000001);
+    let dx = dpdx(texture);
+    let dy = dpdy(texture);
+    return textureSampleGrad(
+        t, s, texture,
+        vec2<f32>(dx.x - round(dx.x / width) * width, dx.y),
+        vec2<f32>(dy.x - round(dy.x / width) * width, dy.y)
+    );
+}
+
+// Length of the outline of an ellipse with the radii r from its top left clockwise to the point
+// at the eccentric angle t, from -3/4 PI to 5/4 PI, by Simpson's rule.
+fn ellipse_arc(r: vec2<f32>, t: f32) -> f32 {
+    let start = -PI * 0.75;
+    let h = (t - start) / 32.0;
+    var sum = 0.0;
+    for (var i = 0; i <= 32; i++) {
+        let s = start + f32(i) * h;
+        let weight = select(select(2.0, 4.0, i % 2 == 1), 1.0, i == 0 || i == 32);
+        sum += weight * length(r * vec2<f32>(sin(s), cos(s)));
+    }
+    return sum * h / 3.0;
+}
+
+// Texture coordinates for the stroke band: x runs clockwise around the outline starting at the
+// top left by distance along it, y runs across the stroke from the outer edge inwards.
+fn ellipse_stroke_texture(uv: vec2<f32>, size: vec2<f32>, stroke: f32) -> vec2<f32> {
+    let ab = size / 2.0;
+    let p = uv - ab;
+    let angle = atan2(p.y / ab.y, p.x / ab.x);
+    let t = select(angle, angle + 2.0 * PI, angle < -PI * 0.75);
+    let x = fract(ellipse_arc(ab, t) / ellipse_arc(ab, PI * 1.25));
+
+    // Strokes past the smaller radius meet in the middle, the ellipse is filled.
+    let outer = 1.0 / length(p / ab);
+    let inner = 1.0 / length(p / max(ab - min(stroke, min(ab.x, ab.y)), vec2<f32>(0.001)));
+    let y = clamp((outer - 1.0) / (outer - inner), 0.0, 1.0);
+    return vec2<f32>(x, y);
+}
+
+// Length of a quarter of the outline of an ellipse with the radii r.
+fn quarter_ellipse(r: vec2<f32>) -> f32 {
+    return PI / 4.0 * (3.0 * (r.x + r.y) - sqrt((3.0 * r.x + r.y) * (r.x + 3.0 * r.y)));
+}
+
+// Distance from d, relative to the center of an ellipse with the radii r, inwards to its outline.
+fn ellipse_depth(d: vec2<f32>, r: vec2<f32>) -> f32 {
+    if all(d == vec2<f32>(0.0)) {return min(r.x, r.y);}
+    // The direction alone sets the gradient, so points close to the center keep their depth.
+    let n = d / max(abs(d.x), abs(d.y));
+    return (1.0 - length(d / r)) * length(n / r) / length(n / (r * r));
+}
+
+fn rounded_rectangle_stroke_texture(uv: vec2<f32>, size: vec2<f32>, stroke: vec4<f32>, rx: vec4<f32>, ry: vec4<f32>) -> vec2<f32> {
+    let tl = vec2<f32>(rx[0], ry[0]);
+    let tr = vec2<f32>(rx[1], ry[1]);
+    let br = vec2<f32>(rx[2], ry[2]);
+    let bl = vec2<f32>(rx[3], ry[3]);
+    let arcs = vec4<f32>(quarter_ellipse(tl), quarter_ellipse(tr), quarter_ellipse(br), quarter_ellipse(bl));
+    let top = size.x - tl.x - tr.x;
+    let right = size.y - tr.y - br.y;
+    let bottom = size.x - br.x - bl.x;
+    let left = size.y - bl.y - tl.y;
+
+    // Distance along the outline to the start of each side, measured from the middle of the top left corner.
+    let start_top = arcs[0] / 2.0;
+    let start_right = start_top + top + arcs[1];
+    let start_bottom = start_right + right + arcs[2];
+    let start_left = start_bottom + bottom + arcs[3];
+    let perimeter = start_left + left + arcs[0] / 2.0;
+
+    var along = 0.0;
+    var depth = 0.0;
+    var width = 0.0;
+    if uv.x < tl.x && uv.y < tl.y {
+        let d = uv - tl;
+        along = (atan2(d.y / tl.y, d.x / tl.x) + PI * 0.75) / (PI * 0.5) * arcs[0];
+        depth = ellipse_depth(d, tl);
+        width = (stroke[3] + stroke[0]) / 2.0;
+    } else if uv.x > size.x - tr.x && uv.y < tr.y {
+        let d = uv - vec2<f32>(size.x - tr.x, tr.y);
+        along = start_top + top + (atan2(d.y / tr.y, d.x / tr.x) + PI * 0.5) / (PI * 0.5) * arcs[1];
+        depth = ellipse_depth(d, tr);
+        width = (stroke[0] + stroke[1]) / 2.0;
+    } else if uv.x > size.x - br.x && uv.y > size.y - br.y {
+        let d = uv - (size - br);
+        along = start_right + right + atan2(d.y / br.y, d.x / br.x) / (PI * 0.5) * arcs[2];
+        depth = ellipse_depth(d, br);
+        width = (stroke[1] + stroke[2]) / 2.0;
+    } else if uv.x < bl.x && uv.y > size.y - bl.y {
+        let d = uv - vec2<f32>(bl.x, size.y - bl.y);
+        along = start_bottom + bottom + (atan2(d.y / bl.y, d.x / bl.x) - PI * 0.5) / (PI * 0.5) * arcs[3];
+        depth = ellipse_depth(d, bl);
+        width = (stroke[2] + stroke[3]) / 2.0;
+    } else {
+        let edges = vec4<f32>(uv.y, size.x - uv.x, size.y - uv.y, uv.x);
+        depth = min(min(edges[0], edges[1]), min(edges[2], edges[3]));
+        if depth == edges[0] {
+            along = start_top + (uv.x - tl.x);
+            width = stroke[0];
+        } else if depth == edges[1] {
+            along = start_right + (uv.y - tr.y);
+            width = stroke[1];
+        } else if depth == edges[2] {
+            along = start_bottom + (size.x - br.x - uv.x);
+            width = stroke[2];
+        } else {
+            along = start_left + (size.y - bl.y - uv.y);
+            width = stroke[3];
+        }
+    }
+
+    // Strokes past half of the smaller side meet in the middle, no point lies deeper than that.
+    let x = fract(along / perimeter + 1.0);
+    let y = clamp(depth / max(min(width, min(size.x, size.y) / 2.0), 0.001), 0.0, 1.0);
+    return vec2<f32>(x, y);
+}
@@ -10,7 +10,7 @@ pub enum CursorAction {
     MoveNewline,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Cursor {
     pub line: usize, // Layout line number
     pub index: usize, // Glyph index
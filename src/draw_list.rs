@@ -0,0 +1,53 @@
+//! Lists of items kept across frames, prepared again only once they change.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::{Area, CanvasItem};
+
+mod svg;
+#[cfg(feature = "serde")]
+mod record;
+#[cfg(feature = "serde")]
+pub use record::{DrawListRecord, DrawItem, DrawText, DrawSpan, ImageRef, Pixels, ReplayError};
+pub(crate) use svg::number;
+
+/// Version of the next change of any list, so no two lists that differ share one.
+static NEXT_VERSION: AtomicU64 = AtomicU64::new(1);
+
+/// Items of a frame kept around to be drawn again by
+/// [`CanvasRenderer::prepare_draw_list`](crate::CanvasRenderer::prepare_draw_list), which skips
+/// preparing a list it has just prepared at the same version. Every change gives the list a new
+/// version, clones share the version of their list.
+#[derive(Clone, Debug, Default)]
+pub struct DrawList {
+    items: Vec<(Area, CanvasItem)>,
+    version: u64,
+}
+
+impl DrawList {
+    pub fn new() -> Self {DrawList::default()}
+
+    pub fn push(&mut self, area: Area, item: CanvasItem) {
+        self.items_mut().push((area, item));
+    }
+
+    pub fn clear(&mut self) {self.items_mut().clear();}
+
+    pub fn items(&self) -> &[(Area, CanvasItem)] {&self.items}
+
+    /// The items to change in place, which gives the list a new version whether or not they are.
+    pub fn items_mut(&mut self) -> &mut Vec<(Area, CanvasItem)> {
+        self.version = NEXT_VERSION.fetch_add(1, Ordering::Relaxed);
+        &mut self.items
+    }
+
+    pub fn version(&self) -> u64 {self.version}
+
+    pub fn into_items(self) -> Vec<(Area, CanvasItem)> {self.items}
+}
+
+impl From<Vec<(Area, CanvasItem)>> for DrawList {
+    fn from(items: Vec<(Area, CanvasItem)>) -> Self {
+        DrawList{items, version: NEXT_VERSION.fetch_add(1, Ordering::Relaxed)}
+    }
+}
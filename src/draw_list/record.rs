@@ -0,0 +1,206 @@
+//! Draw lists that can be saved and replayed, such as for snapshot tests and bug reports.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Area, CanvasItem, Color, Decoration, Fill, Font, GridLines, Image, ImageAtlas, ProgressRing, RgbaImage, Shape, Span, Text};
+use crate::text::family;
+use glyphon::cosmic_text::Align;
+
+/// Items of a frame that can be serialized, replayed by [`replay`](Self::replay) into the same
+/// items. Images are referred to by key, the hash of their content unless given one of their own
+/// by [`record_with`](Self::record_with), and their pixels are either [`embed`](Self::embed)ded
+/// or supplied again on replay. Fonts are referred to by the name of their family and supplied
+/// on replay. Cursors of texts are not recorded as they are not drawn.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DrawListRecord {
+    /// Pixels of the images by key, `None` for images supplied on replay.
+    pub images: BTreeMap<String, Option<Pixels>>,
+    pub items: Vec<(Area, DrawItem)>,
+}
+
+/// Pixels of an image embedded in a [`DrawListRecord`], eight hex digits RRGGBBAA per pixel row by row.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pixels {
+    pub width: u32,
+    pub height: u32,
+    pub data: String,
+}
+
+/// Image of a [`DrawListRecord`] by key, cut to the region (x, y, width, height) of it if any.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageRef {
+    pub key: String,
+    pub region: Option<(u32, u32, u32, u32)>,
+}
+
+/// [`CanvasItem`] with its images and fonts referred to by key.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum DrawItem {
+    Shape(Shape, Color),
+    Fill(Shape, Fill),
+    Image(Shape, ImageRef, Option<Color>),
+    ImageStroke(Shape, ImageRef, Option<Color>),
+    TiledImage(ImageRef, (f32, f32), (f32, f32)),
+    Outline(Shape, Color, f32, f32),
+    ProgressRing(ProgressRing),
+    GridLines(GridLines),
+    Text(DrawText),
+}
+
+/// [`Text`] with the fonts of its spans referred to by family.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DrawText {
+    pub spans: Vec<DrawSpan>,
+    pub width: Option<f32>,
+    #[serde(with = "AlignDef")]
+    pub align: Align,
+}
+
+/// [`Span`] with its font referred to by family.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DrawSpan {
+    pub text: String,
+    pub font_size: f32,
+    pub line_height: f32,
+    pub font: String,
+    pub color: Color,
+    pub decoration: Decoration,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Align")]
+enum AlignDef {
+    Left,
+    Right,
+    Center,
+    Justified,
+    End,
+}
+
+/// Error of [`DrawListRecord::replay`] for an image or font it was not given.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    /// Image neither embedded nor supplied, by key.
+    MissingImage(String),
+    /// Font not supplied, by family.
+    MissingFont(String),
+    /// Embedded pixels that are not hex or not of their size, by key.
+    InvalidPixels(String),
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::MissingImage(key) => write!(f, "image {key:?} was neither embedded nor supplied"),
+            ReplayError::MissingFont(family) => write!(f, "font {family:?} was not supplied"),
+            ReplayError::InvalidPixels(key) => write!(f, "pixels of image {key:?} are invalid"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl DrawListRecord {
+    /// Record the items, referring to images by the hash of their content.
+    pub fn record(items: &[(Area, CanvasItem)]) -> Self {Self::record_with(items, |_| None)}
+
+    /// Record the items, referring to images by the key given for them, or by the hash of their
+    /// content without one. Regions of an image share the key of the whole image.
+    pub fn record_with(items: &[(Area, CanvasItem)], mut key: impl FnMut(&Image) -> Option<String>) -> Self {
+        let mut list = DrawListRecord::default();
+        let mut reference = |image: &Image, images: &mut BTreeMap<String, Option<Pixels>>| {
+            let key = key(image).unwrap_or_else(|| Self::key(image));
+            images.entry(key.clone()).or_insert(None);
+            ImageRef{key, region: image.region()}
+        };
+        for (area, item) in items {
+            let images = &mut list.images;
+            let item = match item {
+                CanvasItem::Shape(shape, color) => DrawItem::Shape(shape.clone(), *color),
+                CanvasItem::Fill(shape, fill) => DrawItem::Fill(shape.clone(), *fill),
+                CanvasItem::Image(shape, image, color) => DrawItem::Image(shape.clone(), reference(image, images), *color),
+                CanvasItem::ImageStroke(shape, image, color) => DrawItem::ImageStroke(shape.clone(), reference(image, images), *color),
+                CanvasItem::TiledImage(image, size, tile) => DrawItem::TiledImage(reference(image, images), *size, *tile),
+                CanvasItem::Outline(shape, color, width, offset) => DrawItem::Outline(shape.clone(), *color, *width, *offset),
+                CanvasItem::ProgressRing(ring) => DrawItem::ProgressRing(*ring),
+                CanvasItem::GridLines(grid) => DrawItem::GridLines(grid.clone()),
+                CanvasItem::Text(text) => DrawItem::Text(DrawText{
+                    spans: text.spans.iter().map(|span| DrawSpan{
+                        text: span.text.clone(),
+                        font_size: span.font_size,
+                        line_height: span.line_height,
+                        font: family(&span.font),
+                        color: span.color,
+                        decoration: span.decoration,
+                    }).collect(),
+                    width: text.width,
+                    align: text.align,
+                }),
+            };
+            list.items.push((*area, item));
+        }
+        list
+    }
+
+    /// Key of the image by the hash of its content, as [`record`](Self::record) refers to it.
+    pub fn key(image: &Image) -> String {format!("{:016x}", image.hash())}
+
+    /// Embed the pixels of the image of the key so replaying needs no image for it.
+    pub fn embed(&mut self, key: &str, pixels: &RgbaImage) {
+        let data = hex::encode(pixels.as_raw());
+        self.images.insert(key.to_string(), Some(Pixels{width: pixels.width(), height: pixels.height(), data}));
+    }
+
+    /// The recorded items, adding embedded images and those `images` supplies by key to the atlas
+    /// and taking the fonts `fonts` supplies by family.
+    pub fn replay(
+        &self,
+        image_atlas: &mut ImageAtlas,
+        mut images: impl FnMut(&str) -> Option<RgbaImage>,
+        mut fonts: impl FnMut(&str) -> Option<Font>
+    ) -> Result<Vec<(Area, CanvasItem)>, ReplayError> {
+        let mut added = BTreeMap::new();
+        for (key, pixels) in &self.images {
+            let raw = match pixels {
+                Some(Pixels{width, height, data}) => {
+                    let raw = hex::decode(data).map_err(|_| ReplayError::InvalidPixels(key.clone()))?;
+                    RgbaImage::from_raw(*width, *height, raw).ok_or_else(|| ReplayError::InvalidPixels(key.clone()))?
+                },
+                None => images(key).ok_or_else(|| ReplayError::MissingImage(key.clone()))?,
+            };
+            added.insert(key.as_str(), image_atlas.add(raw));
+        }
+        let image = |reference: &ImageRef| -> Result<Image, ReplayError> {
+            let image = added.get(reference.key.as_str()).ok_or_else(|| ReplayError::MissingImage(reference.key.clone()))?;
+            Ok(match reference.region {
+                Some(region) => image_atlas.insert_region(image, region),
+                None => image.clone(),
+            })
+        };
+        self.items.iter().map(|(area, item)| Ok((*area, match item {
+            DrawItem::Shape(shape, color) => CanvasItem::Shape(shape.clone(), *color),
+            DrawItem::Fill(shape, fill) => CanvasItem::Fill(shape.clone(), *fill),
+            DrawItem::Image(shape, reference, color) => CanvasItem::Image(shape.clone(), image(reference)?, *color),
+            DrawItem::ImageStroke(shape, reference, color) => CanvasItem::ImageStroke(shape.clone(), image(reference)?, *color),
+            DrawItem::TiledImage(reference, size, tile) => CanvasItem::TiledImage(image(reference)?, *size, *tile),
+            DrawItem::Outline(shape, color, width, offset) => CanvasItem::Outline(shape.clone(), *color, *width, *offset),
+            DrawItem::ProgressRing(ring) => CanvasItem::ProgressRing(*ring),
+            DrawItem::GridLines(grid) => CanvasItem::GridLines(grid.clone()),
+            DrawItem::Text(text) => CanvasItem::Text(Text{
+                spans: text.spans.iter().map(|span| Ok(Span{
+                    text: span.text.clone(),
+                    font_size: span.font_size,
+                    line_height: span.line_height,
+                    font: fonts(&span.font).ok_or_else(|| ReplayError::MissingFont(span.font.clone()))?,
+                    color: span.color,
+                    decoration: span.decoration,
+                })).collect::<Result<_, ReplayError>>()?,
+                width: text.width,
+                align: text.align,
+                cursor: None,
+            }),
+        }))).collect()
+    }
+}
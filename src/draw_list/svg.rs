@@ -0,0 +1,464 @@
+//! Vector export of draw lists, such as to review a frame in a design tool.
+
+use super::DrawList;
+use crate::{Area, Border, CanvasItem, Color, CornerMask, Easing, Fill, FillRule, FontAtlas, Image, LineCap, LineJoin, ProgressRing, RgbaImage, Shape, Text};
+use crate::shape::{corner_radii, quarter_turns, star};
+use crate::text::family;
+
+type Point = (f32, f32);
+type Rect = (f32, f32, f32, f32);
+
+impl DrawList {
+    /// The items as an SVG document of the size, in the order they are drawn. Shapes, strokes and
+    /// linear gradients become their SVG equivalents and images are embedded as PNGs cut to their
+    /// shape. As the atlas releases the pixels of images once they are uploaded, `pixels` supplies
+    /// those of the whole image a handle belongs to, items of images it has none for are left out.
+    /// Texts are laid out with the fonts of the atlas and name the family of their font.
+    ///
+    /// What SVG has no equivalent for, such as conic gradients, speech bubbles, image strokes and
+    /// images in shapes that are only a stroke, is rasterized into an embedded image instead, one
+    /// pixel for every pixel of the canvas. Image strokes are rasterized with the image stretched
+    /// across the shape rather than wrapped along its outline.
+    pub fn to_svg(&self, size: (f32, f32), font_atlas: &mut FontAtlas, mut pixels: impl FnMut(&Image) -> Option<RgbaImage>) -> String {
+        let mut svg = Svg{defs: String::new(), body: String::new(), ids: 0, pixels: &mut pixels};
+        for (area, item) in self.items() {
+            svg.item(area, item, font_atlas);
+        }
+        let (w, h) = (number(size.0), number(size.1));
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n<defs>\n{}</defs>\n{}</svg>\n",
+            svg.defs, svg.body
+        )
+    }
+}
+
+/// Document being written, items refer to the gradients, clips and patterns in its definitions
+/// by id.
+struct Svg<'a> {
+    defs: String,
+    body: String,
+    ids: usize,
+    pixels: &'a mut dyn FnMut(&Image) -> Option<RgbaImage>,
+}
+
+/// How an element draws a shape, left open to take the paint.
+enum Outline {
+    Fill(String),
+    /// Stroke of the width centered on the element.
+    Stroke(String, f32),
+    /// Stroke of the width inside of the element.
+    Inset(String, f32),
+}
+
+impl Svg<'_> {
+    fn id(&mut self) -> String {
+        self.ids += 1;
+        format!("d{}", self.ids)
+    }
+
+    fn item(&mut self, area: &Area, item: &CanvasItem, font_atlas: &mut FontAtlas) {
+        let clip = area.1.map(|(x, y, w, h)| {
+            let id = self.id();
+            self.defs += &format!("<clipPath id=\"{id}\"><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/></clipPath>\n", number(x), number(y), number(w), number(h));
+            id
+        });
+        if let Some(id) = &clip {self.body += &format!("<g clip-path=\"url(#{id})\">\n");}
+        match item {
+            CanvasItem::Shape(shape, color) => self.shape(area, shape, &(*color).into()),
+            CanvasItem::Fill(shape, fill) => self.shape(area, shape, fill),
+            CanvasItem::Image(shape, image, tint) => self.image(area, shape, image, *tint),
+            CanvasItem::ImageStroke(shape, image, tint) => {
+                let Some(raw) = self.pixels(image, *tint) else {return self.close(clip)};
+                let (x, y, w, h) = frame(shape);
+                self.raster(area, shape, |p| sample(&raw, ((p.0 - x) / w, (p.1 - y) / h)));
+            },
+            CanvasItem::TiledImage(image, size, tile) => {
+                let Some(raw) = self.pixels(image, None) else {return self.close(clip)};
+                let id = self.id();
+                let (tw, th) = (number(tile.0), number(tile.1));
+                self.defs += &format!(
+                    "<pattern id=\"{id}\" patternUnits=\"userSpaceOnUse\" width=\"{tw}\" height=\"{th}\"><image width=\"{tw}\" height=\"{th}\" preserveAspectRatio=\"none\" xlink:href=\"{}\"/></pattern>\n",
+                    png(&raw)
+                );
+                self.place(area.0, format!("<rect width=\"{}\" height=\"{}\" fill=\"url(#{id})\"/>", number(size.0), number(size.1)));
+            },
+            CanvasItem::Outline(shape, color, width, offset) => {
+                let grow = width + offset;
+                self.shape(&Area((area.0.0 - grow, area.0.1 - grow), area.1), &shape.outline(*width, *offset), &(*color).into());
+            },
+            CanvasItem::ProgressRing(ring) => self.ring(area.0, ring),
+            CanvasItem::GridLines(grid) => for ((x, y), (w, h)) in grid.rectangles(area.0) {
+                self.body += &format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" {}/>\n", number(x), number(y), number(w), number(h), color("fill", grid.color));
+            },
+            CanvasItem::Text(text) => self.text(area.0, text, font_atlas),
+        }
+        self.close(clip)
+    }
+
+    fn close(&mut self, clip: Option<String>) {
+        if clip.is_some() {self.body += "</g>\n";}
+    }
+
+    /// Add the content with its origin at the position.
+    fn place(&mut self, (x, y): Point, content: String) {
+        self.body += &format!("<g transform=\"translate({} {})\">{content}</g>\n", number(x), number(y));
+    }
+
+    fn shape(&mut self, area: &Area, shape: &Shape, fill: &Fill) {
+        let frame = frame(shape);
+        let content = match outline(shape) {
+            Some(Outline::Fill(element)) => self.paint("fill", fill, frame).map(|paint| format!("{element} {paint}/>")),
+            Some(Outline::Stroke(element, width)) => self.paint("stroke", fill, frame).map(|paint|
+                format!("{element} fill=\"none\" stroke-width=\"{}\" {paint}/>", number(width))
+            ),
+            Some(Outline::Inset(element, width)) => self.paint("stroke", fill, frame).map(|paint| {
+                let id = self.id();
+                self.defs += &format!("<clipPath id=\"{id}\">{element}/></clipPath>\n");
+                format!("{element} fill=\"none\" stroke-width=\"{}\" clip-path=\"url(#{id})\" {paint}/>", number(width * 2.0))
+            }),
+            None => None,
+        };
+        match content {
+            Some(content) => self.place(area.0, content),
+            None => self.raster(area, shape, |p| fill_at(fill, frame, p)),
+        }
+    }
+
+    /// The image covering the frame of the shape while keeping its aspect, cut to the shape.
+    fn image(&mut self, area: &Area, shape: &Shape, image: &Image, tint: Option<Color>) {
+        let Some(raw) = self.pixels(image, tint) else {return};
+        let (x, y, w, h) = frame(shape);
+        match region(shape) {
+            Some(element) => {
+                let id = self.id();
+                self.defs += &format!("<clipPath id=\"{id}\">{element}/></clipPath>\n");
+                self.place(area.0, format!(
+                    "<image x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" preserveAspectRatio=\"xMidYMid slice\" clip-path=\"url(#{id})\" xlink:href=\"{}\"/>",
+                    number(x), number(y), number(w), number(h), png(&raw)
+                ));
+            },
+            None => {
+                let (u, v, u2, v2) = fit(&raw, (w, h));
+                self.raster(area, shape, |p| sample(&raw, (u + (u2 - u) * (p.0 - x) / w, v + (v2 - v) * (p.1 - y) / h)));
+            },
+        }
+    }
+
+    fn ring(&mut self, position: Point, ring: &ProgressRing) {
+        let (c, half) = (ring.size / 2.0, ring.thickness / 2.0);
+        let middle = c - half;
+        let circle = |paint: String| format!(
+            "<circle cx=\"{0}\" cy=\"{0}\" r=\"{1}\" fill=\"none\" stroke-width=\"{2}\" {paint}/>", number(c), number(middle), number(ring.thickness)
+        );
+        let mut content = circle(color("stroke", ring.track));
+        let progress = ring.progress.clamp(0.0, 1.0);
+        if progress >= 1.0 {
+            content += &circle(color("stroke", ring.color));
+        } else if progress > 0.0 {
+            let (a, b) = (ring.start_angle, ring.start_angle + progress * std::f32::consts::TAU);
+            let point = |angle: f32| format!("{} {}", number(c + middle * angle.cos()), number(c + middle * angle.sin()));
+            content += &format!(
+                "<path d=\"M{} A{r} {r} 0 {} 1 {}\" fill=\"none\" stroke-width=\"{}\" stroke-linecap=\"round\" {}/>",
+                point(a), (progress > 0.5) as u8, point(b), number(ring.thickness), color("stroke", ring.color), r = number(middle)
+            );
+        }
+        self.place(position, content);
+    }
+
+    fn text(&mut self, position: Point, text: &Text, font_atlas: &mut FontAtlas) {
+        let mut content = String::new();
+        for ((x, y), span, characters) in text.pieces(font_atlas) {
+            let span = &text.spans[span];
+            content += &format!(
+                "<text x=\"{}\" y=\"{}\" font-family=\"{}\" font-size=\"{}\" xml:space=\"preserve\" {}>{}</text>",
+                number(x), number(y), escape(&family(&span.font)), number(span.font_size), color("fill", span.color), escape(&characters)
+            );
+        }
+        for ((x, y, w, h), line) in text.decorations(font_atlas) {
+            content += &format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" {}/>", number(x), number(y), number(w), number(h), color("fill", line));
+        }
+        self.place(position, content);
+    }
+
+    /// Pixels of the image, cut to its region and tinted like the shaders do.
+    fn pixels(&mut self, image: &Image, tint: Option<Color>) -> Option<RgbaImage> {
+        let raw = (self.pixels)(&image.root())?;
+        let mut raw = match image.region() {
+            Some((x, y, w, h)) => ::image::imageops::crop_imm(&raw, x, y, w, h).to_image(),
+            None => raw,
+        };
+        if let Some(Color{r, g, b, a}) = tint.filter(|t| t.a > 0) {
+            raw.pixels_mut().for_each(|p| p.0 = [r, g, b, (a as u16 * p.0[3] as u16 / 255) as u8]);
+        }
+        Some(raw)
+    }
+
+    /// Embed what of the shape is drawn in the area as an image with a pixel for every pixel of
+    /// the canvas. Each pixel blends the color at those of its 4 by 4 samples the shape covers,
+    /// the color is given points relative to the position of the area.
+    fn raster(&mut self, area: &Area, shape: &Shape, color: impl Fn(Point) -> [f32; 4] + Sync + Send) {
+        let (x, y, w, h) = shape.bounds(area);
+        let (x, y, x2, y2) = (x.floor(), y.floor(), (x + w).ceil(), (y + h).ceil());
+        if x2 <= x || y2 <= y {return;}
+        let raw = crate::parallel::image((x2 - x) as u32, (y2 - y) as u32, |px, py| {
+            let mut sum = [0.0f32; 4];
+            for i in 0..16 {
+                let sx = x + px as f32 + (i % 4) as f32 / 4.0 + 0.125 - area.0.0;
+                let sy = y + py as f32 + (i / 4) as f32 / 4.0 + 0.125 - area.0.1;
+                if !shape.covers((sx, sy), true) {continue;}
+                let c = color((sx, sy));
+                sum = [sum[0] + c[0] * c[3], sum[1] + c[1] * c[3], sum[2] + c[2] * c[3], sum[3] + c[3]];
+            }
+            let alpha = sum[3].max(f32::EPSILON);
+            [srgb(sum[0] / alpha), srgb(sum[1] / alpha), srgb(sum[2] / alpha), (sum[3] / 16.0 * 255.0).round() as u8]
+        });
+        self.body += &format!(
+            "<image x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" xlink:href=\"{}\"/>\n",
+            number(x), number(y), number(x2 - x), number(y2 - y), png(&raw)
+        );
+    }
+
+    /// Attributes painting the fill or stroke, by `attribute`, with the gradient across the frame,
+    /// none for conic gradients which SVG has no equivalent of and patterns.
+    fn paint(&mut self, attribute: &str, fill: &Fill, (x, y, w, h): Rect) -> Option<String> {
+        match *fill {
+            Fill::Linear(start, end, ..) if start == end => Some(color(attribute, start)),
+            Fill::Linear(start, end, angle, easing) => {
+                // The shaders blend in linear light across the extent of the frame along the angle.
+                let direction = (angle.cos(), angle.sin());
+                let reach = (direction.0.abs() * w + direction.1.abs() * h).max(0.001) / 2.0;
+                let (cx, cy) = (x + w / 2.0, y + h / 2.0);
+                // Eased gradients are sampled into stops close enough to blend between linearly.
+                let stops: Vec<(f32, Color)> = match easing {
+                    Easing::Linear => vec![(0.0, start), (1.0, end)],
+                    Easing::Step => vec![(0.0, start), (0.5, start), (0.5, end), (1.0, end)],
+                    easing => (0..=16).map(|i| i as f32 / 16.0).map(|t| (t, start.lerp(end, easing.ease(t)))).collect(),
+                };
+                let stop = |(offset, c): &(f32, Color)| format!("<stop offset=\"{}\" stop-color=\"{}\" stop-opacity=\"{}\"/>", number(*offset), hex(*c), number(c.a as f32 / 255.0));
+                let id = self.id();
+                self.defs += &format!(
+                    "<linearGradient id=\"{id}\" gradientUnits=\"userSpaceOnUse\" color-interpolation=\"linearRGB\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\">{}</linearGradient>\n",
+                    number(cx - direction.0 * reach), number(cy - direction.1 * reach), number(cx + direction.0 * reach), number(cy + direction.1 * reach),
+                    stops.iter().map(stop).collect::<String>()
+                );
+                Some(format!("{attribute}=\"url(#{id})\""))
+            },
+            Fill::Conic(..) | Fill::Pattern(_) => None,
+        }
+    }
+}
+
+/// Element drawing the shape if SVG has one for it.
+fn outline(shape: &Shape) -> Option<Outline> {
+    let (w, h) = shape.size();
+    let (rx, ry) = corner_radii((w, h), match shape {
+        Shape::RoundedRectangle(_, _, radius) => *radius,
+        _ => Default::default(),
+    }, CornerMask::all());
+    match shape {
+        Shape::Ellipse(stroke, _) if *stroke <= 0.0 || *stroke >= w.min(h) / 2.0 => Some(Outline::Fill(format!(
+            "<ellipse cx=\"{}\" cy=\"{}\" rx=\"{0}\" ry=\"{1}\"", number(w / 2.0), number(h / 2.0)
+        ))),
+        Shape::Rectangle(stroke, _) if *stroke == Border::default() => Some(Outline::Fill(format!(
+            "<rect width=\"{}\" height=\"{}\"", number(w), number(h)
+        ))),
+        Shape::RoundedRectangle(stroke, _, _) if *stroke == Border::default() => Some(Outline::Fill(format!(
+            "<rect width=\"{}\" height=\"{}\" rx=\"{}\" ry=\"{}\"", number(w), number(h), number(rx[0]), number(ry[0])
+        ))),
+        // Stroked along the middle of the stroke, where the corners are rounded less by half of it.
+        Shape::RoundedRectangle(Border(t, r, b, l), _, _) if t == r && t == b && t == l && t * 2.0 < w.min(h) && rx[0].min(ry[0]) >= *t => Some(Outline::Stroke(format!(
+            "<rect x=\"{0}\" y=\"{0}\" width=\"{1}\" height=\"{2}\" rx=\"{3}\" ry=\"{4}\"", number(t / 2.0), number(w - t), number(h - t), number(rx[0] - t / 2.0), number(ry[0] - t / 2.0)
+        ), *t)),
+        Shape::Ellipse(stroke, _) => Some(Outline::Stroke(format!(
+            "<ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\"", number(w / 2.0), number(h / 2.0), number((w - stroke) / 2.0), number((h - stroke) / 2.0)
+        ), *stroke)),
+        Shape::Rectangle(Border(t, r, b, l), _) if *t > 0.0 && t == r && t == b && t == l && t * 2.0 < w.min(h) => Some(Outline::Stroke(format!(
+            "<rect x=\"{0}\" y=\"{0}\" width=\"{1}\" height=\"{2}\"", number(t / 2.0), number(w - t), number(h - t)
+        ), *t)),
+        Shape::Chevron(direction, thickness, _) => {
+            let turns = quarter_turns(*direction) as u32;
+            let s = if turns % 2 == 1 {(h, w)} else {(w, h)};
+            let r = thickness / 2.0;
+            let points = [(r, r), (s.0 - r, s.1 / 2.0), (r, s.1 - r)].map(|q| match turns {
+                1 => (q.1, q.0),
+                2 => (w - q.0, q.1),
+                3 => (q.1, h - q.0),
+                _ => q,
+            });
+            Some(Outline::Stroke(format!("<polyline points=\"{}\" stroke-linecap=\"round\" stroke-linejoin=\"round\"", points_of(points)), *thickness))
+        },
+        Shape::Wave(thickness, _, wavelength) => {
+            let amplitude = (h - thickness).max(0.0) / 2.0;
+            let wavelength = wavelength.max(0.001);
+            let steps = (w / wavelength * 16.0).ceil().clamp(1.0, 4096.0) as usize;
+            let points = (0..=steps).map(|i| {
+                let x = w * i as f32 / steps as f32;
+                (x, h / 2.0 - amplitude * (std::f32::consts::TAU * x / wavelength).sin())
+            });
+            Some(Outline::Stroke(format!("<polyline points=\"{}\"", points_of(points)), *thickness))
+        },
+        Shape::Star(stroke, size, points, inner_ratio) if *stroke > 0.0 => Some(Outline::Inset(
+            format!("<polygon points=\"{}\"", points_of(star(*size, *points, *inner_ratio))), *stroke
+        )),
+        Shape::Path(stroke, path) if *stroke > 0.0 => {
+            let (_, cap, join) = path.style();
+            let cap = match cap {
+                LineCap::Butt => "butt",
+                LineCap::Round => "round",
+                LineCap::Square => "square",
+            };
+            let join = match join {
+                LineJoin::Miter(limit) => format!("miter\" stroke-miterlimit=\"{}", number(limit.max(1.0))),
+                LineJoin::Round => "round".to_string(),
+                LineJoin::Bevel => "bevel".to_string(),
+            };
+            Some(Outline::Stroke(format!("<path d=\"{}\" stroke-linecap=\"{cap}\" stroke-linejoin=\"{join}\"", path.svg_data()), *stroke))
+        },
+        _ => region(shape).map(Outline::Fill),
+    }
+}
+
+/// Element filling what is drawn of the shape, leaving out the inside of a stroke, if SVG has
+/// one for it.
+fn region(shape: &Shape) -> Option<String> {
+    let (w, h) = shape.size();
+    let (stroke, (rx, ry)) = match shape {
+        Shape::Ellipse(stroke, _) => (Border::uniform(*stroke), ([w / 2.0; 4], [h / 2.0; 4])),
+        Shape::Rectangle(stroke, _) => (*stroke, ([0.0; 4], [0.0; 4])),
+        Shape::RoundedRectangle(stroke, _, radius) => (*stroke, corner_radii((w, h), *radius, CornerMask::all())),
+        Shape::RoundedRectangleSides(stroke, _, radius, corners) => (*stroke, corner_radii((w, h), *radius, *corners)),
+        Shape::Star(stroke, size, points, inner_ratio) if *stroke <= 0.0 =>
+            return Some(format!("<polygon points=\"{}\"", points_of(star(*size, *points, *inner_ratio)))),
+        Shape::Path(stroke, path) if *stroke <= 0.0 => {
+            let rule = match path.style().0 {
+                FillRule::NonZero => "nonzero",
+                FillRule::EvenOdd => "evenodd",
+            };
+            return Some(format!("<path d=\"{}\" fill-rule=\"{rule}\" clip-rule=\"{rule}\"", path.svg_data()));
+        },
+        _ => return None,
+    };
+    let mut data = rounded((0.0, 0.0), (w, h), rx, ry);
+    // Strokes whose sides meet fill the shape, as they do in the shaders.
+    let Border(t, r, b, l) = stroke;
+    if stroke != Border::default() && l + r < w && t + b < h {
+        let irx = [rx[0] - l, rx[1] - r, rx[2] - r, rx[3] - l].map(|r| r.max(0.0));
+        let iry = [ry[0] - t, ry[1] - t, ry[2] - b, ry[3] - b].map(|r| r.max(0.0));
+        data += &format!(" {}", rounded((l, t), (w - r, h - b), irx, iry));
+    }
+    Some(format!("<path d=\"{data}\" fill-rule=\"evenodd\" clip-rule=\"evenodd\""))
+}
+
+/// Path data of the box from lo to hi with elliptical corners in the order top left, top right,
+/// bottom right, bottom left, clockwise from the top left.
+fn rounded(lo: Point, hi: Point, rx: [f32; 4], ry: [f32; 4]) -> String {
+    let n = number;
+    format!(
+        "M{} {} H{} A{} {} 0 0 1 {} {} V{} A{} {} 0 0 1 {} {} H{} A{} {} 0 0 1 {} {} V{} A{} {} 0 0 1 {} {} Z",
+        n(lo.0 + rx[0]), n(lo.1), n(hi.0 - rx[1]), n(rx[1]), n(ry[1]), n(hi.0), n(lo.1 + ry[1]),
+        n(hi.1 - ry[2]), n(rx[2]), n(ry[2]), n(hi.0 - rx[2]), n(hi.1),
+        n(lo.0 + rx[3]), n(rx[3]), n(ry[3]), n(lo.0), n(hi.1 - ry[3]),
+        n(lo.1 + ry[0]), n(rx[0]), n(ry[0]), n(lo.0 + rx[0]), n(lo.1)
+    )
+}
+
+/// Rectangle (x, y, width, height) relative to the position of the shape that gradients and
+/// images are mapped across.
+fn frame(shape: &Shape) -> Rect {
+    match shape {
+        Shape::Path(stroke, path) => path.bounds(*stroke),
+        shape => (0.0, 0.0, shape.size().0, shape.size().1),
+    }
+}
+
+/// Color of the gradient at the point within the frame, in linear light, as in `common.wgsl`.
+fn fill_at(fill: &Fill, (x, y, w, h): Rect, p: Point) -> [f32; 4] {
+    let p = (p.0 - x - w / 2.0, p.1 - y - h / 2.0);
+    let (start, end, t, easing) = match *fill {
+        Fill::Linear(start, end, angle, easing) => {
+            let direction = (angle.cos(), angle.sin());
+            let extent = direction.0.abs() * w + direction.1.abs() * h;
+            (start, end, (p.0 * direction.0 + p.1 * direction.1) / extent.max(0.001) + 0.5, easing)
+        },
+        Fill::Conic(start, end, angle, easing) => {
+            let n = (p.0 / w.max(0.001), p.1 / h.max(0.001));
+            let turn = ((n.1.atan2(n.0) - angle) / std::f32::consts::TAU).rem_euclid(1.0);
+            (start, end, 1.0 - (turn * 2.0 - 1.0).abs(), easing)
+        },
+        Fill::Pattern(pattern) => {
+            let covered = pattern.distance((p.0 + w / 2.0, p.1 + h / 2.0)) < 0.0;
+            (pattern.background, pattern.foreground, covered as u8 as f32, Easing::Linear)
+        },
+    };
+    let (start, end, t) = (linear(start), linear(end), easing.ease(t));
+    std::array::from_fn(|c| start[c] + (end[c] - start[c]) * t)
+}
+
+/// Texture coordinates (x, y, x2, y2) of the image covering the size while keeping its aspect.
+fn fit(raw: &RgbaImage, (w, h): (f32, f32)) -> Rect {
+    let (wr, hr) = (w / raw.width().max(1) as f32, h / raw.height().max(1) as f32);
+    match hr > wr {
+        true => ((1.0 - wr / hr) / 2.0, 0.0, (1.0 + wr / hr) / 2.0, 1.0),
+        false => (0.0, (1.0 - hr / wr) / 2.0, 1.0, (1.0 + hr / wr) / 2.0),
+    }
+}
+
+/// Color of the pixel of the image nearest to the texture coordinates, in linear light.
+fn sample(raw: &RgbaImage, (u, v): Point) -> [f32; 4] {
+    if raw.width() == 0 || raw.height() == 0 {return [0.0; 4];}
+    let x = ((u * raw.width() as f32) as i64).clamp(0, raw.width() as i64 - 1) as u32;
+    let y = ((v * raw.height() as f32) as i64).clamp(0, raw.height() as i64 - 1) as u32;
+    let [r, g, b, a] = raw.get_pixel(x, y).0;
+    linear(Color::from_rgba8(r, g, b, a))
+}
+
+/// Channels of the color in linear light with straight alpha, from 0 to 1.
+fn linear(color: Color) -> [f32; 4] {
+    let c = |v: u8| ((v as f32 / 255.0 + 0.055) / 1.055).powf(2.4);
+    [c(color.r), c(color.g), c(color.b), color.a as f32 / 255.0]
+}
+
+fn srgb(v: f32) -> u8 {
+    ((v.clamp(0.0, 1.0).powf(1.0 / 2.4) * 1.055 - 0.055).max(0.0) * 255.0).round() as u8
+}
+
+/// Attributes painting the fill or stroke, by `attribute`, with the color.
+fn color(attribute: &str, color: Color) -> String {
+    match color.a {
+        255 => format!("{attribute}=\"{}\"", hex(color)),
+        alpha => format!("{attribute}=\"{}\" {attribute}-opacity=\"{}\"", hex(color), number(alpha as f32 / 255.0)),
+    }
+}
+
+fn hex(color: Color) -> String {format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)}
+
+fn points_of(points: impl IntoIterator<Item = Point>) -> String {
+    points.into_iter().map(|(x, y)| format!("{},{}", number(x), number(y))).collect::<Vec<_>>().join(" ")
+}
+
+/// The number rounded to a thousandth of a pixel, without trailing zeros.
+pub(crate) fn number(v: f32) -> String {
+    format!("{}", (v * 1000.0).round() / 1000.0 + 0.0)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Data URL of the pixels encoded as a PNG.
+fn png(raw: &RgbaImage) -> String {
+    let mut bytes = std::io::Cursor::new(vec![]);
+    raw.write_to(&mut bytes, ::image::ImageFormat::Png).expect("PNG encoding into memory failed");
+    format!("data:image/png;base64,{}", base64(bytes.get_ref()))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    bytes.chunks(3).flat_map(|chunk| {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        (0..4).map(move |i| match i <= chunk.len() {
+            true => ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char,
+            false => '=',
+        })
+    }).collect()
+}
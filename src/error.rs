@@ -0,0 +1,49 @@
+//! Errors of items that could not be drawn.
+
+/// Why an item was left out of a frame by [`CanvasRenderer::prepare`](crate::CanvasRenderer::prepare),
+/// or why an operation failed. Items are named by their index in the list of the frame, sizes are
+/// in physical pixels.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CanvasError {
+    /// The path of the item could not be turned into triangles, such as when it needs more
+    /// vertices than fit the indices of a single item.
+    Rasterization{item: usize, size: (f32, f32), reason: String},
+    /// The operation is not supported, such as reading back a texture of a format other than
+    /// 8 bit RGBA or BGRA.
+    Unsupported(String),
+    /// A GPU resource could not be created, such as a texture larger than the device allows or
+    /// glyphs that do not fit the text atlas. Errors that do not belong to a single item name
+    /// none.
+    Allocation{item: Option<usize>, reason: String},
+    /// The item cannot be drawn as given, such as a text without spans.
+    InvalidInput{item: usize, reason: String},
+    /// A frame could not be encoded or written, such as a PNG saved to a path that cannot be
+    /// created.
+    Encoding(String),
+}
+
+impl CanvasError {
+    /// Index of the item the error belongs to, if any.
+    pub fn item(&self) -> Option<usize> {
+        match self {
+            CanvasError::Rasterization{item, ..} | CanvasError::InvalidInput{item, ..} => Some(*item),
+            CanvasError::Allocation{item, ..} => *item,
+            CanvasError::Unsupported(_) | CanvasError::Encoding(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for CanvasError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CanvasError::Rasterization{item, size, reason} => write!(f, "item {item}: path of {}x{} could not be rasterized: {reason}", size.0, size.1),
+            CanvasError::Unsupported(operation) => write!(f, "unsupported: {operation}"),
+            CanvasError::Allocation{item: Some(item), reason} => write!(f, "item {item}: {reason}"),
+            CanvasError::Allocation{item: None, reason} => write!(f, "{reason}"),
+            CanvasError::InvalidInput{item, reason} => write!(f, "item {item}: {reason}"),
+            CanvasError::Encoding(reason) => write!(f, "frame could not be encoded or written: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for CanvasError {}
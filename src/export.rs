@@ -0,0 +1,143 @@
+//! One call rendering of items to images and PNG files without a window.
+
+use std::io::Cursor;
+
+use wgpu::{Device, Queue, Texture, TextureFormat, TextureUsages};
+
+use crate::{Area, CanvasError, CanvasItem, CanvasRenderer, FontAtlas, ImageAtlas, RgbaImage};
+
+const FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+/// Samples of the offscreen target, which every renderable format supports.
+const SAMPLE_COUNT: u32 = 4;
+
+/// Prepare and render the items on a transparent frame of the size and read it back. Edges are
+/// smoothed with 4x multisampling, resolved before the read back. Fails with the first error of
+/// [`CanvasRenderer::prepare`] when an item cannot be drawn, and with the error of
+/// [`read_texture`] when the frame cannot be read back, such as once the device is lost.
+#[allow(clippy::too_many_arguments)]
+pub fn render_to_image(
+    device: &Device,
+    queue: &Queue,
+    width: u32,
+    height: u32,
+    image_atlas: &mut ImageAtlas,
+    font_atlas: &mut FontAtlas,
+    items: Vec<(Area, CanvasItem)>,
+) -> Result<RgbaImage, CanvasError> {
+    let (width, height) = (width.max(1), height.max(1));
+    let multisample = wgpu::MultisampleState{count: SAMPLE_COUNT, ..Default::default()};
+    let mut renderer = CanvasRenderer::new(queue, device, &FORMAT, multisample, None);
+    if let Some(error) = renderer.prepare(device, queue, width as f32, height as f32, image_atlas, font_atlas, items).into_iter().next() {
+        return Err(error);
+    }
+
+    let texture = |sample_count: u32, usage: TextureUsages| device.create_texture(&wgpu::TextureDescriptor{
+        label: None,
+        size: wgpu::Extent3d{width, height, depth_or_array_layers: 1},
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format: FORMAT,
+        usage,
+        view_formats: &[],
+    });
+    let samples = texture(SAMPLE_COUNT, TextureUsages::RENDER_ATTACHMENT);
+    let resolved = texture(1, TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC);
+    let view = samples.create_view(&wgpu::TextureViewDescriptor::default());
+    let resolve_target = resolved.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor{
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment{
+                view: &view,
+                resolve_target: Some(&resolve_target),
+                ops: wgpu::Operations{load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT), store: wgpu::StoreOp::Discard},
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        renderer.render(&mut render_pass);
+    }
+    queue.submit([encoder.finish()]);
+    read_texture(device, queue, &resolved)
+}
+
+/// Like [`render_to_image`] but encoded as PNG.
+#[allow(clippy::too_many_arguments)]
+pub fn render_to_png_bytes(
+    device: &Device,
+    queue: &Queue,
+    width: u32,
+    height: u32,
+    image_atlas: &mut ImageAtlas,
+    font_atlas: &mut FontAtlas,
+    items: Vec<(Area, CanvasItem)>,
+) -> Result<Vec<u8>, CanvasError> {
+    let mut bytes = Cursor::new(Vec::new());
+    render_to_image(device, queue, width, height, image_atlas, font_atlas, items)?
+        .write_to(&mut bytes, image::ImageFormat::Png).map_err(encoding)?;
+    Ok(bytes.into_inner())
+}
+
+/// Like [`render_to_image`] but saved as a PNG file at the path.
+#[allow(clippy::too_many_arguments)]
+pub fn render_to_png(
+    device: &Device,
+    queue: &Queue,
+    width: u32,
+    height: u32,
+    image_atlas: &mut ImageAtlas,
+    font_atlas: &mut FontAtlas,
+    items: Vec<(Area, CanvasItem)>,
+    path: impl AsRef<std::path::Path>,
+) -> Result<(), CanvasError> {
+    render_to_image(device, queue, width, height, image_atlas, font_atlas, items)?
+        .save_with_format(path, image::ImageFormat::Png).map_err(encoding)
+}
+
+fn encoding(error: image::ImageError) -> CanvasError {CanvasError::Encoding(error.to_string())}
+
+/// Copy the first mip level of a texture with 8 bit RGBA or BGRA texels, such as a frame
+/// rendered by the caller, back from the GPU and wait for it. Rows are copied of any width and
+/// BGRA is swapped to RGBA. The texture needs [`COPY_SRC`](TextureUsages::COPY_SRC).
+pub fn read_texture(device: &Device, queue: &Queue, texture: &Texture) -> Result<RgbaImage, CanvasError> {
+    let bgra = match texture.format().remove_srgb_suffix() {
+        TextureFormat::Rgba8Unorm => false,
+        TextureFormat::Bgra8Unorm => true,
+        format => return Err(CanvasError::Unsupported(format!("reading back textures of format {format:?}"))),
+    };
+    let (width, height) = (texture.width(), texture.height());
+    let row = width * 4;
+    let padded = row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor{
+        label: None,
+        size: (padded * height) as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::TexelCopyBufferInfo{
+            buffer: &buffer,
+            layout: wgpu::TexelCopyBufferLayout{offset: 0, bytes_per_row: Some(padded), rows_per_image: Some(height)},
+        },
+        wgpu::Extent3d{width, height, depth_or_array_layers: 1}
+    );
+    queue.submit([encoder.finish()]);
+
+    let (sender, mapped) = std::sync::mpsc::channel();
+    buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {sender.send(result).ok();});
+    let failed = |error: &dyn std::fmt::Display| CanvasError::Allocation{item: None, reason: format!("read back of {width}x{height} failed: {error}")};
+    device.poll(wgpu::PollType::Wait).map_err(|error| failed(&error))?;
+    // The callback is dropped without being called when the device is lost.
+    mapped.recv().map_err(|error| failed(&error))?.map_err(|error| failed(&error))?;
+    let mut pixels = buffer.slice(..).get_mapped_range().chunks_exact(padded as usize)
+        .flat_map(|texels| texels[..row as usize].to_vec())
+        .collect::<Vec<_>>();
+    if bgra {pixels.chunks_exact_mut(4).for_each(|texel| texel.swap(0, 2));}
+    Ok(RgbaImage::from_raw(width, height, pixels).expect("rows are copied whole"))
+}
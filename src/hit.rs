@@ -0,0 +1,190 @@
+//! Which items lie under a point, by the same geometry the shaders draw them with.
+
+use super::{Area, Border, CanvasItem, CornerMask, Shape};
+use crate::shape::{corner_radii, quarter_turns, speech_bubble};
+
+type Point = (f32, f32);
+
+/// Indices of the items under the point, front to back, that is from the last item drawn to the
+/// first. Points outside the clip of an item never hit it. Stroked shapes are hit inside their
+/// outline unless `hollow`, then only their stroke is; items that draw a band of their own, such
+/// as outlines, image strokes, progress rings and grid lines, count as stroked. Text is not hit
+/// as its layout needs the [`FontAtlas`](crate::FontAtlas), test its
+/// [`size`](crate::Text::size) instead.
+///
+/// The items are those of a [`Canvas`](crate::Canvas), whose transforms are already applied to
+/// their positions and clips.
+pub fn hit_test(point: Point, items: &[(Area, CanvasItem)], hollow: bool) -> Vec<usize> {
+    (0..items.len()).rev().filter(|i| hits(point, &items[*i], hollow)).collect()
+}
+
+fn hits((x, y): Point, (area, item): &(Area, CanvasItem), hollow: bool) -> bool {
+    if !clipped_in(area, (x, y)) {return false;}
+    let p = (x - area.0.0, y - area.0.1);
+    match item {
+        CanvasItem::Shape(shape, _) | CanvasItem::Fill(shape, _) | CanvasItem::Image(shape, _, _) |
+        CanvasItem::ImageStroke(shape, _, _) => shape.covers(p, hollow),
+        CanvasItem::TiledImage(_, size, _) => Shape::Rectangle(Border::default(), *size).covers(p, hollow),
+        CanvasItem::Outline(shape, _, width, offset) => {
+            let grow = width + offset;
+            shape.outline(*width, *offset).covers((p.0 + grow, p.1 + grow), hollow)
+        },
+        CanvasItem::ProgressRing(ring) => Shape::Ellipse(ring.thickness, (ring.size, ring.size)).covers(p, hollow),
+        CanvasItem::GridLines(grid) => match hollow {
+            true => grid.rectangles(area.0).into_iter().any(|((rx, ry), (w, h))|
+                x >= rx && y >= ry && x < rx + w && y < ry + h
+            ),
+            false => Shape::Rectangle(Border::default(), grid.size).covers(p, false),
+        },
+        CanvasItem::Text(_) => false,
+    }
+}
+
+/// Whether the point lies within the bounds of the area, if it has any.
+fn clipped_in(area: &Area, (x, y): Point) -> bool {
+    area.1.is_none_or(|(bx, by, bw, bh)| x >= bx && y >= by && x < bx + bw && y < by + bh)
+}
+
+impl Shape {
+    /// Whether the point lies within the shape drawn in the area, by the same math the shaders
+    /// cover pixels with. Stroked shapes contain the points inside their outline unless `hollow`,
+    /// then only those of their stroke.
+    pub fn contains(&self, area: &Area, point: Point, hollow: bool) -> bool {
+        clipped_in(area, point) && self.covers((point.0 - area.0.0, point.1 - area.0.1), hollow)
+    }
+
+    /// Whether the point relative to the position of the shape lies within it.
+    pub(crate) fn covers(&self, p: Point, hollow: bool) -> bool {
+        let size = self.size();
+        match self {
+            Shape::Ellipse(stroke, _) => {
+                // Ellipses are drawn a pixel inside their size, fading out over the pixel past the outline.
+                let (a, b) = (size.0 / 2.0, size.1 / 2.0);
+                let inside = |ra: f32, rb: f32| ((a - p.0) / (ra - 1.0)).powi(2) + ((b - p.1) / (rb - 1.0)).powi(2) <= 1.0 + 1.0 / a;
+                inside(a, b) && !(hollow && *stroke > 0.0 && *stroke < a.min(b) && inside(a - stroke, b - stroke))
+            },
+            Shape::Rectangle(stroke, _) => {
+                let (lo, hi) = ((stroke.3, stroke.0), (size.0 - stroke.1, size.1 - stroke.2));
+                let inner = p.0 > lo.0 && p.0 < hi.0 && p.1 > lo.1 && p.1 < hi.1;
+                p.0 >= 0.0 && p.1 >= 0.0 && p.0 <= size.0 && p.1 <= size.1 && !(hollow && stroked(stroke) && inner)
+            },
+            Shape::RoundedRectangle(stroke, _, radius) => rounded_rectangle(p, size, *stroke, corner_radii(size, *radius, CornerMask::all()), hollow),
+            Shape::RoundedRectangleSides(stroke, _, radius, corners) => rounded_rectangle(p, size, *stroke, corner_radii(size, *radius, *corners), hollow),
+            Shape::Chevron(direction, thickness, _) => {
+                let (q, s) = match quarter_turns(*direction) as u32 {
+                    1 => ((p.1, p.0), (size.1, size.0)),
+                    2 => ((size.0 - p.0, p.1), size),
+                    3 => ((size.1 - p.1, p.0), (size.1, size.0)),
+                    _ => (p, size),
+                };
+                let r = thickness / 2.0;
+                let tip = (s.0 - r, s.1 / 2.0);
+                segment_distance(q, (r, r), tip).min(segment_distance(q, (r, s.1 - r), tip)) <= r
+            },
+            Shape::SpeechBubble(stroke, _, radius, tail) => {
+                let (radius, tail) = speech_bubble(size, *radius, *tail);
+                let (q, s, r) = match tail[0] as u32 {
+                    0 => ((p.1, p.0), (size.1, size.0), (radius[1], radius[0])),
+                    2 => ((p.1, size.0 - p.0), (size.1, size.0), (radius[1], radius[0])),
+                    3 => ((p.0, size.1 - p.1), size, (radius[0], radius[1])),
+                    _ => (p, size, (radius[0], radius[1])),
+                };
+                let base = s.1 - tail[3];
+                let mut d = rounded_box_distance(q, (0.0, 0.0), (s.0, base), [r.0; 4], [r.1; 4]);
+                if tail[3] > 0.0 {
+                    let half = tail[2] / 2.0;
+                    let middle = tail[1].clamp(half, (s.0 - half).max(half));
+                    let depth = r.1.clamp(1.0, (base / 2.0).max(1.0));
+                    let spread = half * (tail[3] + depth) / tail[3];
+                    let triangle = triangle_distance(q, (middle, s.1), (middle - spread, base - depth), (middle + spread, base - depth));
+                    d = d.min(triangle.max((q.0 - s.0 / 2.0).abs() - s.0 / 2.0));
+                }
+                banded(d, *stroke, hollow)
+            },
+            Shape::Star(stroke, _, points, inner_ratio) => {
+                let points = (*points).max(2) as f32;
+                let outer = size.0.min(size.1) / 2.0;
+                let c = (p.0 - size.0 / 2.0, p.1 - size.1 / 2.0);
+                let sector = std::f32::consts::PI / points;
+                let turn = ((c.1.atan2(c.0) + std::f32::consts::FRAC_PI_2) / (2.0 * sector)).rem_euclid(1.0) * 2.0 * sector;
+                let angle = turn.min(2.0 * sector - turn);
+                let length = c.0.hypot(c.1);
+                let q = (length * angle.cos(), length * angle.sin());
+                let tip = (outer, 0.0);
+                let ratio = inner_ratio.clamp(0.0, 1.0);
+                let inner = (outer * ratio * sector.cos(), outer * ratio * sector.sin());
+                let side = (inner.0 - tip.0) * q.1 - (inner.1 - tip.1) * (q.0 - tip.0);
+                banded(segment_distance(q, tip, inner) * -side.signum(), *stroke, hollow)
+            },
+            Shape::Path(stroke, path) => path.contains(p, *stroke) || (!hollow && *stroke > 0.0 && path.contains(p, 0.0)),
+            Shape::Wave(thickness, _, wavelength) => {
+                let amplitude = (size.1 - thickness).max(0.0) / 2.0;
+                let k = std::f32::consts::TAU / wavelength.max(0.001);
+                let reach = thickness / 2.0;
+                let q = (p.0, size.1 / 2.0 - p.1);
+                p.0 >= 0.0 && p.0 <= size.0 && (0..=64).any(|i| {
+                    let t = q.0 + reach * (i as f32 / 32.0 - 1.0);
+                    (t - q.0).hypot(amplitude * (k * t).sin() - q.1) <= reach
+                })
+            },
+        }
+    }
+}
+
+/// Whether any side of the border has a width, otherwise it fills the shape.
+fn stroked(stroke: &Border) -> bool {
+    *stroke != Border::default()
+}
+
+/// Whether a point at the signed distance to an outline is hit, with a stroke of the width inside
+/// the outline.
+fn banded(d: f32, stroke: f32, hollow: bool) -> bool {
+    d <= 0.0 && !(hollow && stroke > 0.0 && d < -stroke)
+}
+
+fn rounded_rectangle(p: Point, size: Point, stroke: Border, (rx, ry): ([f32; 4], [f32; 4]), hollow: bool) -> bool {
+    if rounded_box_distance(p, (0.0, 0.0), size, rx, ry) > 0.0 {return false;}
+    if !hollow || !stroked(&stroke) {return true;}
+    let lo = (stroke.3, stroke.0);
+    let hi = (size.0 - stroke.1, size.1 - stroke.2);
+    let irx = [rx[0] - stroke.3, rx[1] - stroke.1, rx[2] - stroke.1, rx[3] - stroke.3].map(|r| r.max(0.0));
+    let iry = [ry[0] - stroke.0, ry[1] - stroke.0, ry[2] - stroke.2, ry[3] - stroke.2].map(|r| r.max(0.0));
+    rounded_box_distance(p, lo, hi, irx, iry) >= 0.0
+}
+
+/// Signed distance to the box from lo to hi with elliptical corners, as in `common.wgsl`.
+fn rounded_box_distance(p: Point, lo: Point, hi: Point, rx: [f32; 4], ry: [f32; 4]) -> f32 {
+    let center = ((lo.0 + hi.0) / 2.0, (lo.1 + hi.1) / 2.0);
+    let (r, corner) = match (p.0 >= center.0, p.1 >= center.1) {
+        (false, false) => ((rx[0], ry[0]), lo),
+        (true, false) => ((rx[1], ry[1]), (hi.0, lo.1)),
+        (true, true) => ((rx[2], ry[2]), hi),
+        (false, true) => ((rx[3], ry[3]), (lo.0, hi.1)),
+    };
+
+    let mut dist = (lo.0 - p.0).max(p.0 - hi.0).max((lo.1 - p.1).max(p.1 - hi.1));
+    let outward = ((corner.0 - center.0).signum(), (corner.1 - center.1).signum());
+    let c = (corner.0 - outward.0 * r.0, corner.1 - outward.1 * r.1);
+    if r.0 > 0.0 && r.1 > 0.0 && (p.0 - c.0) * outward.0 > 0.0 && (p.1 - c.1) * outward.1 > 0.0 {
+        let d = ((p.0 - c.0).abs(), (p.1 - c.1).abs());
+        let k = (d.0 / r.0).hypot(d.1 / r.1);
+        dist = (k - 1.0) * k / (d.0 / (r.0 * r.0)).hypot(d.1 / (r.1 * r.1)).max(0.0001);
+    }
+    dist
+}
+
+fn segment_distance(p: Point, a: Point, b: Point) -> f32 {
+    let (pa, ba) = ((p.0 - a.0, p.1 - a.1), (b.0 - a.0, b.1 - a.1));
+    let h = ((pa.0 * ba.0 + pa.1 * ba.1) / (ba.0 * ba.0 + ba.1 * ba.1).max(f32::EPSILON)).clamp(0.0, 1.0);
+    (pa.0 - ba.0 * h).hypot(pa.1 - ba.1 * h)
+}
+
+/// Signed distance to the triangle a, b, c.
+fn triangle_distance(p: Point, a: Point, b: Point, c: Point) -> f32 {
+    let inside = [(a, b), (b, c), (c, a)].map(|(e0, e1)| (e1.0 - e0.0) * (p.1 - e0.1) - (e1.1 - e0.1) * (p.0 - e0.0));
+    let d = segment_distance(p, a, b).min(segment_distance(p, b, c)).min(segment_distance(p, c, a));
+    match inside.iter().all(|s| *s >= 0.0) || inside.iter().all(|s| *s <= 0.0) {
+        true => -d,
+        false => d,
+    }
+}
@@ -1,115 +1,472 @@
-use wgpu::{BindGroup, TextureViewDescriptor, TexelCopyBufferLayout, TextureAspect, Origin3d, TextureUsages, TexelCopyTextureInfo, Extent3d, TextureDimension, TextureDescriptor, TextureFormat, BindGroupLayout, Device, Queue, Sampler};
+use wgpu::{BindGroup, TextureViewDescriptor, TexelCopyBufferLayout, TexelCopyBufferInfo, TextureAspect, Origin3d, TextureUsages, TexelCopyTextureInfo, Extent3d, TextureDimension, TextureDescriptor, TextureFormat, BindGroupLayout, BufferUsages, Texture, Features, Adapter, Backend, Device, Queue, Sampler};
 
 pub use image::RgbaImage;
 
 use std::hash::{DefaultHasher, Hasher, Hash};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
 mod renderer;
-pub(crate) use renderer::ImageRenderer;
+mod bc7;
+pub(crate) use renderer::{ImageRenderer, ImageFrame};
+#[cfg(feature = "tokio")]
+pub use renderer::PreparedFrame;
 
+/// Handle to an image in the [`ImageAtlas`], optionally restricted to a region (x, y, width, height)
+/// of the underlying texture.
 #[derive(Clone, Debug, Ord, PartialOrd, PartialEq, Eq)]
-pub struct Image(Arc<u64>, u32, u32);
+pub struct Image(Arc<u64>, u32, u32, Option<(u32, u32, u32, u32)>);
 
 impl Image {
-    pub fn size(&self) -> (u32, u32) {(self.1, self.2)}
+    pub fn size(&self) -> (u32, u32) {
+        self.3.map(|r| (r.2, r.3)).unwrap_or((self.1, self.2))
+    }
+
+    /// Texture coordinates of this image within its atlas allocation as (x, y, width, height).
+    /// Regions are inset by half a texel so linear filtering does not bleed in their neighbours.
+    pub(crate) fn uv(&self) -> (f32, f32, f32, f32) {
+        let (tw, th) = (self.1 as f32, self.2 as f32);
+        match self.3 {
+            Some((x, y, w, h)) => {
+                let iw = (w as f32 - 1.0).max(0.0);
+                let ih = (h as f32 - 1.0).max(0.0);
+                let x = x as f32 + (w as f32 - iw) / 2.0;
+                let y = y as f32 + (h as f32 - ih) / 2.0;
+                (x / tw, y / th, iw / tw, ih / th)
+            },
+            None => (0.0, 0.0, 1.0, 1.0)
+        }
+    }
+
+    /// Whether this handle is a region of its texture rather than all of it.
+    pub(crate) fn is_region(&self) -> bool {self.3.is_some()}
+
+    /// Region (x, y, width, height) of the texture this handle is restricted to, if any.
+    pub(crate) fn region(&self) -> Option<(u32, u32, u32, u32)> {self.3}
+
+    /// Hash of the pixels of the image.
+    #[cfg(feature = "serde")]
+    pub(crate) fn hash(&self) -> u64 {*self.0}
+
+    pub(crate) fn root(&self) -> Image {Image(self.0.clone(), self.1, self.2, None)}
 }
 
 pub type InnerImage = Arc<BindGroup>;
 
+/// Pixels of an image until it is first bound, after that the bind group of its texture and the
+/// pixels when they are retained.
 #[derive(Debug)]
-pub struct ImageAtlas(Option<BTreeMap<Image, (RgbaImage, Option<InnerImage>)>>);
+enum Entry {
+    Pixels(RgbaImage),
+    Bound(InnerImage, Option<RgbaImage>),
+}
+
+/// Texels waiting to be copied to the texture bound for their image, in rows of the given number
+/// of bytes.
+#[derive(Debug)]
+struct PendingUpload {
+    texture: Texture,
+    mip_level: u32,
+    /// Size of the level in texels, whole blocks for compressed textures.
+    size: Extent3d,
+    data: Vec<u8>,
+    bytes_per_row: u32,
+    rows: u32,
+}
+
+/// How an [`ImageAtlas`] creates the textures of its images.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ImageAtlasDescriptor {
+    /// Give textures every mipmap level down to a single pixel, each a quarter of the one above
+    /// it, so images drawn smaller than their size, such as thumbnails, are smooth rather than
+    /// grainy. Textures take a third more memory. Regions of a shared texture blend in their
+    /// neighbours once drawn small enough.
+    pub generate_mipmaps: bool,
+    /// Keep the pixels of images once uploaded, so [`ImageAtlas::recreate`] can upload them again
+    /// without asking for them after the device is lost. Images take their memory twice.
+    pub retain_pixels: bool,
+}
+
+/// Images by handle. Pixels are uploaded to a texture of their own when the image is first drawn
+/// and then released unless retained by the [`ImageAtlasDescriptor`], so large images are not
+/// kept in memory twice. The uploads of all images
+/// first drawn in a frame share one staging buffer and one submit.
+///
+/// With [`try_use_compression`](Self::try_use_compression) images are compressed to BC7 before
+/// they are uploaded, taking a quarter of the memory at a small loss of quality. Mipmaps are
+/// generated on the CPU when enabled by the [`ImageAtlasDescriptor`].
+///
+/// The backend of the adapter is kept while images are compressed, as the GL backend cannot copy
+/// padded rows of blocks from a staging buffer. Images the canvas derives from other items, such
+/// as the shadows of texts, are kept by the hash of what they were made from.
+#[derive(Debug)]
+pub struct ImageAtlas(Option<BTreeMap<Image, Entry>>, Vec<PendingUpload>, Option<Backend>, ImageAtlasDescriptor, HashMap<u64, (Image, bool)>);
 
 impl ImageAtlas {
+    pub fn new(descriptor: ImageAtlasDescriptor) -> Self {
+        ImageAtlas(Some(BTreeMap::new()), vec![], None, descriptor, HashMap::new())
+    }
+
     pub fn add(&mut self, raw: RgbaImage) -> Image {
         let size = raw.dimensions();
         let mut hasher = DefaultHasher::new();
         raw.hash(&mut hasher);
         let key = hasher.finish();
 
-        let image = Image(Arc::new(key), size.0, size.1);
+        let image = Image(Arc::new(key), size.0, size.1, None);
         match self.0.as_mut().unwrap().get_key_value(&image) {
             Some((image, _)) => image.clone(),
             None => {
-                self.0.as_mut().unwrap().insert(image.clone(), (raw, None));
+                self.0.as_mut().unwrap().insert(image.clone(), Entry::Pixels(raw));
                 image
             }
         }
     }
 
+    /// Create a handle to a sub-rectangle (x, y, width, height) of an existing image, such as a
+    /// sprite in a sprite sheet. No pixels are copied, the region shares the texture of its parent
+    /// and keeps it alive. The region is relative to `image` and clamped to its size.
+    pub fn insert_region(&self, image: &Image, region: (u32, u32, u32, u32)) -> Image {
+        let (px, py, pw, ph) = image.3.unwrap_or((0, 0, image.1, image.2));
+        let x = region.0.min(pw);
+        let y = region.1.min(ph);
+        let w = region.2.min(pw - x);
+        let h = region.3.min(ph - y);
+        Image(image.0.clone(), image.1, image.2, Some((px + x, py + y, w, h)))
+    }
+
+    /// Forget the textures of all images after the device they were created on was lost, such as
+    /// when the GPU resets, so they are uploaded to the device of the renderer they are next drawn
+    /// with, see [`CanvasRenderer::recreate`](crate::CanvasRenderer::recreate). Handles stay valid.
+    /// The pixels of images that were released are asked of `reload`, images it returns none for,
+    /// or pixels of another size, are removed and returned. Their handles draw nothing until the
+    /// image is added again.
+    pub fn recreate(&mut self, mut reload: impl FnMut(&Image) -> Option<RgbaImage>) -> Vec<Image> {
+        self.1.clear();
+        // Derived images are made again when next drawn.
+        self.4.clear();
+        let mut lost = vec![];
+        self.0 = Some(self.0.take().unwrap().into_iter().filter_map(|(image, entry)| {
+            let raw = match entry {
+                Entry::Pixels(raw) | Entry::Bound(_, Some(raw)) => Some(raw),
+                Entry::Bound(_, None) => reload(&image).filter(|raw| raw.dimensions() == (image.1, image.2)),
+            };
+            match raw {
+                Some(raw) => Some((image, Entry::Pixels(raw))),
+                None => {lost.push(image); None},
+            }
+        }).collect());
+        lost
+    }
+
+    /// Compress images to BC7 from now on if the adapter supports it, returning whether it does.
+    /// The device has to be created with [`Features::TEXTURE_COMPRESSION_BC`] as well, otherwise
+    /// and for images whose sides are not multiples of 4, which BC7 stores in blocks of, images
+    /// are uploaded uncompressed. Images drawn before keep their textures.
+    pub fn try_use_compression(&mut self, adapter: &Adapter) -> bool {
+        let usages = TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST;
+        let features = adapter.get_texture_format_features(TextureFormat::Bc7RgbaUnormSrgb);
+        let supported = adapter.features().contains(Features::TEXTURE_COMPRESSION_BC) && features.allowed_usages.contains(usages);
+        self.2 = supported.then(|| adapter.get_info().backend);
+        supported
+    }
+
     pub(crate) fn trim_and_bind(
         &mut self,
         queue: &Queue,
         device: &Device,
         layout: &BindGroupLayout,
-        sampler: &Sampler
+        sampler: &Sampler,
+        repeat: &Sampler,
+        max: (u32, u32),
     ) {
-        self.0 = Some(self.0.take().unwrap().into_iter().filter_map(|(image, v)|
-            //TODO: use match Arc::strong_count instead of try_unwrap, And .is_some instead of unwrap_or_else
-            Arc::try_unwrap(image.0).err().map(|k| {
-                let inner_image = v.1.unwrap_or_else(|| {
-                    let size = Extent3d {
-                        width: image.1,
-                        height: image.2,
-                        depth_or_array_layers: 1,
-                    };
-
-                    let texture = device.create_texture(
-                        &TextureDescriptor {
-                            size,
-                            mip_level_count: 1,
-                            sample_count: 1,
-                            dimension: TextureDimension::D2,
-                            format: TextureFormat::Rgba8UnormSrgb,
-                            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
-                            label: None,
-                            view_formats: &[],
-                        }
-                    );
-
-                    queue.write_texture(
-                        TexelCopyTextureInfo {
-                            texture: &texture,
-                            mip_level: 0,
-                            origin: Origin3d::ZERO,
-                            aspect: TextureAspect::All,
-                        },
-                        &v.0,
-                        TexelCopyBufferLayout{
-                            offset: 0,
-                            bytes_per_row: Some(4 * image.1),
-                            rows_per_image: Some(image.2),
+        self.forget_unused_derived();
+        // Most frames neither drop nor add images, the map is left as is.
+        let settled = |(image, v): (&Image, &Entry)| Arc::strong_count(&image.0) > 1 &&
+            !matches!(v, Entry::Pixels(raw) if bindable(raw.dimensions(), max).is_ok());
+        if self.0.as_ref().unwrap().iter().all(settled) {return;}
+        let compress = self.2.is_some() && device.features().contains(Features::TEXTURE_COMPRESSION_BC);
+        let (mipmaps, retain) = (self.3.generate_mipmaps, self.3.retain_pixels);
+        //TODO: use match Arc::strong_count instead of try_unwrap, And .is_some instead of unwrap_or_else
+        let (unbound, mut entries) = self.0.take().unwrap().into_iter()
+            .filter_map(|(image, v)| Arc::try_unwrap(image.0).err().map(|k| (Image(k, image.1, image.2, None), v)))
+            // Images too large are left unbound, the items drawing them are reported by the renderer.
+            .partition::<Vec<_>, _>(|(_, v)| matches!(v, Entry::Pixels(raw) if bindable(raw.dimensions(), max).is_ok()));
+
+        // The levels of every image first drawn in the frame are built together, across threads
+        // with the `rayon` feature, as generating mipmaps and compressing them is slow for large
+        // images.
+        let levels = crate::parallel::map_each(unbound, |(image, v)| {
+            let Entry::Pixels(raw) = v else {unreachable!()};
+            let (width, height) = raw.dimensions();
+            let format = match compress && width % 4 == 0 && height % 4 == 0 {
+                true => TextureFormat::Bc7RgbaUnormSrgb,
+                false => TextureFormat::Rgba8UnormSrgb,
+            };
+            let retained = retain.then(|| raw.clone());
+            let levels = match mipmaps {
+                true => levels(raw),
+                false => vec![raw],
+            };
+            let levels = levels.into_iter().map(|raw| match format.is_compressed() {
+                true => bc7::encode(&raw),
+                false => raw.into_raw(),
+            }).collect::<Vec<_>>();
+            (image, format, (width, height), levels, retained)
+        });
+
+        for (image, format, size, levels, retained) in levels {
+            let (texture, inner_image) = bind(device, layout, sampler, repeat, format, size, levels.len() as u32);
+            for (mip_level, data) in levels.into_iter().enumerate() {
+                let size = texture.size().mip_level_size(mip_level as u32, TextureDimension::D2).physical_size(format);
+                let (bytes_per_row, rows) = match format.is_compressed() {
+                    true => (size.width * 4, size.height / 4),
+                    false => (size.width * 4, size.height),
+                };
+                self.1.push(PendingUpload{texture: texture.clone(), mip_level: mip_level as u32, size, data, bytes_per_row, rows});
+            }
+            entries.push((image, Entry::Bound(inner_image, retained)));
+        }
+        self.0 = Some(entries.into_iter().collect());
+        self.flush(device, queue);
+    }
+
+    /// Copy the texels of every image bound since the last flush to their textures in a single
+    /// submit, their rows padded into one staging buffer, and release them. Compressed textures
+    /// are copied the same way as rows of whole blocks. The GL backend cannot skip the padding of
+    /// rows of blocks, on it those that need padding are written by the queue instead, which sends
+    /// them with the next submit.
+    pub(crate) fn flush(&mut self, device: &Device, queue: &Queue) {
+        if self.1.is_empty() {return;}
+        let gl = self.2 == Some(Backend::Gl);
+        self.1.retain(|PendingUpload{texture, mip_level, size, data, bytes_per_row, rows}| {
+            if !(gl && texture.format().is_compressed() && bytes_per_row % wgpu::COPY_BYTES_PER_ROW_ALIGNMENT != 0) {return true;}
+            let layout = TexelCopyBufferLayout{offset: 0, bytes_per_row: Some(*bytes_per_row), rows_per_image: Some(*rows)};
+            let destination = TexelCopyTextureInfo{mip_level: *mip_level, ..texture.as_image_copy()};
+            queue.write_texture(destination, data, layout, *size);
+            false
+        });
+        if self.1.is_empty() {return;}
+        let padded = |bytes_per_row: u32| bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let size = self.1.iter().map(|p| (padded(p.bytes_per_row) * p.rows) as u64).sum::<u64>();
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor{
+            label: None,
+            size,
+            usage: BufferUsages::COPY_SRC,
+            mapped_at_creation: true,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut staging = buffer.slice(..).get_mapped_range_mut();
+            let mut offset = 0;
+            for PendingUpload{texture, mip_level, size, data, bytes_per_row, rows} in self.1.drain(..) {
+                let (row, padded) = (bytes_per_row as usize, padded(bytes_per_row));
+                for (y, texels) in data.chunks_exact(row.max(1)).enumerate() {
+                    let start = offset + y * padded as usize;
+                    staging[start..start + row].copy_from_slice(texels);
+                }
+                encoder.copy_buffer_to_texture(
+                    TexelCopyBufferInfo{
+                        buffer: &buffer,
+                        layout: TexelCopyBufferLayout{
+                            offset: offset as u64,
+                            bytes_per_row: Some(padded),
+                            rows_per_image: Some(rows),
                         },
-                        size
-                    );
-
-                    let texture_view = texture.create_view(&TextureViewDescriptor::default());
-
-                    Arc::new(device.create_bind_group(
-                        &wgpu::BindGroupDescriptor {
-                            layout,
-                            entries: &[
-                                wgpu::BindGroupEntry {
-                                    binding: 0,
-                                    resource: wgpu::BindingResource::TextureView(&texture_view),
-                                },
-                                wgpu::BindGroupEntry {
-                                    binding: 1,
-                                    resource: wgpu::BindingResource::Sampler(sampler),
-                                }
-                            ],
-                            label: None,
-                        }
-                    ))
-                });
-                (Image(k, image.1, image.2), (v.0, Some(inner_image)))
-            })
-        ).collect());
-    }
-
-    pub(crate) fn get(&self, key: &Image) -> InnerImage {
-        self.0.as_ref().unwrap().get(key).as_ref().unwrap().1.clone().unwrap()
+                    },
+                    TexelCopyTextureInfo {
+                        texture: &texture,
+                        mip_level,
+                        origin: Origin3d::ZERO,
+                        aspect: TextureAspect::All,
+                    },
+                    size
+                );
+                offset += (padded * rows) as usize;
+            }
+        }
+        buffer.unmap();
+        queue.submit([encoder.finish()]);
+    }
+
+    /// Image derived from other items, such as the shadow of a text, by a hash of what it is made
+    /// from. It is made by `make` unless it was asked for since the atlas was last bound before.
+    pub(crate) fn derived(&mut self, key: u64, make: impl FnOnce() -> RgbaImage) -> Image {
+        if let Some((image, used)) = self.4.get_mut(&key) {
+            *used = true;
+            return image.clone();
+        }
+        let image = self.add(make());
+        self.4.insert(key, (image.clone(), true));
+        image
+    }
+
+    /// Drop the derived images not asked for since the last call, which are trimmed once no item
+    /// draws them.
+    fn forget_unused_derived(&mut self) {
+        self.4.retain(|_, (_, used)| std::mem::take(used));
+    }
+
+    /// Texture of the image, none when it could not be bound, see [`bindable`].
+    pub(crate) fn get(&self, key: &Image) -> Option<InnerImage> {
+        match self.0.as_ref().unwrap().get(&key.root())? {
+            Entry::Bound(inner_image, _) => Some(inner_image.clone()),
+            Entry::Pixels(_) => None,
+        }
+    }
+}
+
+/// Whether pixels of the size fit a texture of at most the size `max`, the error of the items
+/// drawing them when they do not.
+pub(crate) fn bindable((width, height): (u32, u32), (max_width, max_height): (u32, u32)) -> Result<(), String> {
+    match (width, height) {
+        (0, _) | (_, 0) => Err(format!("image of {width}x{height} has no pixels")),
+        _ if width > max_width || height > max_height => Err(format!("image of {width}x{height} exceeds the largest texture of {max_width}x{max_height} the renderer allows")),
+        _ => Ok(()),
+    }
+}
+
+/// The image followed by each of its mipmap levels, halving the size down to a single pixel.
+fn levels(raw: RgbaImage) -> Vec<RgbaImage> {
+    let mut levels = vec![raw];
+    while let Some(level) = levels.last().filter(|l| l.width() > 1 || l.height() > 1) {
+        levels.push(half(level));
+    }
+    levels
+}
+
+/// The image at half its size, each pixel the average of the two by two it covers. Colors are
+/// averaged in linear light weighted by their alpha, so transparent pixels add no color.
+fn half(raw: &RgbaImage) -> RgbaImage {
+    let (width, height) = raw.dimensions();
+    let linear = |c: u8| match c as f32 / 255.0 {
+        c if c <= 0.04045 => c / 12.92,
+        c => ((c + 0.055) / 1.055).powf(2.4),
+    };
+    crate::parallel::image((width / 2).max(1), (height / 2).max(1), |x, y| {
+        let pixels = [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dy)| raw.get_pixel((x * 2 + dx).min(width - 1), (y * 2 + dy).min(height - 1)).0);
+        let alpha = pixels.iter().map(|p| p[3] as f32 / 255.0).sum::<f32>();
+        let channel = |c: usize| match alpha > 0.0 {
+            true => pixels.iter().map(|p| linear(p[c]) * p[3] as f32 / 255.0).sum::<f32>() / alpha,
+            false => 0.0,
+        };
+        let srgb = |v: f32| (match v {
+            v if v <= 0.0031308 => v * 12.92,
+            v => 1.055 * v.powf(1.0 / 2.4) - 0.055,
+        } * 255.0).round() as u8;
+        [srgb(channel(0)), srgb(channel(1)), srgb(channel(2)), (alpha / 4.0 * 255.0).round() as u8]
+    })
+}
+
+/// Texture of the format and size with the number of mipmap levels for the pixels of an image,
+/// bound with the samplers.
+fn bind(device: &Device, layout: &BindGroupLayout, sampler: &Sampler, repeat: &Sampler, format: TextureFormat, (width, height): (u32, u32), mip_level_count: u32) -> (Texture, InnerImage) {
+    let size = Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    };
+
+    let texture = device.create_texture(
+        &TextureDescriptor {
+            size,
+            mip_level_count,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            label: None,
+            view_formats: &[],
+        }
+    );
+
+    let texture_view = texture.create_view(&TextureViewDescriptor::default());
+
+    let bind_group = Arc::new(device.create_bind_group(
+        &wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(repeat),
+                }
+            ],
+            label: None,
+        }
+    ));
+    (texture, bind_group)
+}
+
+impl Default for ImageAtlas {fn default() -> Self {ImageAtlas::new(ImageAtlasDescriptor::default())}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Images get a bind group when the atlas is trimmed and bound, and lose it once their last
+    /// handle is dropped, bound again from scratch when added back. Fails without an adapter
+    /// unless `WGPU_CANVAS_SKIP_UNAVAILABLE` is set.
+    #[test]
+    fn atlas_binds_while_handles_live() {
+        let instance = wgpu::Instance::default();
+        let device = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default())).ok()
+            .and_then(|adapter| pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default())).ok());
+        let Some((device, queue)) = device else {
+            assert!(std::env::var_os("WGPU_CANVAS_SKIP_UNAVAILABLE").is_some(), "no adapter available, set WGPU_CANVAS_SKIP_UNAVAILABLE=1 to skip the tests that need it");
+            return;
+        };
+        let layout = renderer::bind_group_layout(&device);
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+        let max = (64, 64);
+        let mut atlas = ImageAtlas::default();
+        let trim = |atlas: &mut ImageAtlas| atlas.trim_and_bind(&queue, &device, &layout, &sampler, &sampler, max);
+
+        let solid = |color: [u8; 4]| RgbaImage::from_pixel(4, 4, image::Rgba(color));
+        let red = atlas.add(solid([255, 0, 0, 255]));
+        let blue = atlas.add(solid([0, 0, 255, 255]));
+        assert!(atlas.get(&red).is_none(), "image is bound before the atlas is");
+        trim(&mut atlas);
+        let bound = atlas.get(&red).expect("image is not bound");
+        assert!(atlas.get(&blue).is_some(), "image is not bound");
+
+        // A key equal to the handle, which does not keep the image in the atlas.
+        let key = Image(Arc::new(*red.0), red.1, red.2, None);
+        drop(red);
+        trim(&mut atlas);
+        assert!(atlas.get(&key).is_none(), "image is still bound after its last handle is dropped");
+        assert!(atlas.get(&blue).is_some(), "image that stayed is no longer bound");
+
+        let red = atlas.add(solid([255, 0, 0, 255]));
+        assert!(atlas.get(&red).is_none(), "image added back is bound before the atlas is");
+        trim(&mut atlas);
+        let rebound = atlas.get(&red).expect("image added back is not bound");
+        assert!(!Arc::ptr_eq(&bound, &rebound), "image added back reuses its dropped bind group");
+    }
+
+    /// Derived images are made once while they are asked for between binds, and made again once
+    /// a bind passed without them.
+    #[test]
+    fn derived_images_are_made_once() {
+        let mut atlas = ImageAtlas::default();
+        let solid = || RgbaImage::from_pixel(4, 4, image::Rgba([255, 0, 0, 255]));
+        let made = atlas.derived(1, solid);
+        let again = atlas.derived(1, || panic!("image derived again while kept"));
+        assert!(Arc::ptr_eq(&made.0, &again.0), "derived image is not reused");
+
+        atlas.forget_unused_derived();
+        atlas.derived(1, || panic!("image derived again after a bind it was asked for"));
+        atlas.forget_unused_derived();
+        atlas.forget_unused_derived();
+        let mut remade = false;
+        atlas.derived(1, || {remade = true; solid()});
+        assert!(remade, "image not asked for since the last bind is still kept");
     }
 }
-impl Default for ImageAtlas {fn default() -> Self {ImageAtlas(Some(BTreeMap::new()))}}
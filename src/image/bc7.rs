@@ -0,0 +1,172 @@
+//! BC7 encoder writing every block in mode 6, a single pair of RGBA endpoints with 16 colors
+//! between them, which suits photos and gradients and keeps the encoder small.
+
+use super::RgbaImage;
+
+/// Weights of the second endpoint out of 64 for each of the 16 colors of a block.
+const WEIGHTS: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+type Pixel = [f32; 4];
+
+/// The pixels as rows of 16 byte blocks of 4 by 4 pixels. Blocks past the edges repeat the last
+/// row and column, as mipmap levels smaller than a block still take up a whole one.
+pub(crate) fn encode(raw: &RgbaImage) -> Vec<u8> {
+    let (width, height) = raw.dimensions();
+    let (columns, rows) = (width.div_ceil(4), height.div_ceil(4));
+    crate::parallel::rows(rows as usize, columns as usize * 16, |by, row| {
+        let by = by as u32 * 4;
+        for (bx, bytes) in (0..width).step_by(4).zip(row.chunks_exact_mut(16)) {
+            let pixel = |x: u32, y: u32| raw.get_pixel(x.min(width - 1), y.min(height - 1)).0.map(|c| c as f32);
+            let pixels: [Pixel; 16] = std::array::from_fn(|i| pixel(bx + i as u32 % 4, by + i as u32 / 4));
+            bytes.copy_from_slice(&block(&pixels));
+        }
+    })
+}
+
+fn block(pixels: &[Pixel; 16]) -> [u8; 16] {
+    let (low, high) = extremes(pixels);
+    // Each endpoint is 7 bits per channel with a shared lowest bit, the best of which is kept.
+    let (endpoints, indices, _) = (0..4).map(|p| {
+        let endpoints = [quantize(low, p & 1), quantize(high, p >> 1)];
+        let palette: [Pixel; 16] = std::array::from_fn(|i| interpolate(&endpoints, i));
+        let (indices, error) = pixels.iter().fold((vec![], 0.0), |(mut indices, error), pixel| {
+            let (index, e) = palette.iter().map(|c| distance(c, pixel)).enumerate()
+                .min_by(|a, b| a.1.total_cmp(&b.1)).unwrap();
+            indices.push(index as u32);
+            (indices, error + e)
+        });
+        (endpoints, indices, error)
+    }).min_by(|a, b| a.2.total_cmp(&b.2)).unwrap();
+
+    // The first index is stored without its highest bit, which the endpoints are swapped to clear.
+    let (endpoints, indices) = match indices[0] >= 8 {
+        true => ([endpoints[1], endpoints[0]], indices.iter().map(|i| 15 - i).collect()),
+        false => (endpoints, indices),
+    };
+
+    let mut bits = Bits::default();
+    bits.push(1 << 6, 7);
+    for (low, high) in endpoints[0].iter().zip(endpoints[1]) {
+        bits.push((low >> 1) as u128, 7);
+        bits.push((high >> 1) as u128, 7);
+    }
+    bits.push((endpoints[0][0] & 1) as u128, 1);
+    bits.push((endpoints[1][0] & 1) as u128, 1);
+    for (i, index) in indices.into_iter().enumerate() {
+        bits.push(index as u128, if i == 0 {3} else {4});
+    }
+    bits.0.to_le_bytes()
+}
+
+/// Ends of the line through the pixels along which their colors vary most, found by power
+/// iteration on their covariance.
+fn extremes(pixels: &[Pixel; 16]) -> (Pixel, Pixel) {
+    let mean = pixels.iter().fold([0.0; 4], |m, p| std::array::from_fn(|c| m[c] + p[c] / 16.0));
+    let centered = pixels.map(|p| std::array::from_fn::<f32, 4, _>(|c| p[c] - mean[c]));
+    let covariance: [[f32; 4]; 4] = std::array::from_fn(|a| std::array::from_fn(|b| centered.iter().map(|p| p[a] * p[b]).sum()));
+    let mut axis = [1.0, 1.0, 1.0, 1.0];
+    for _ in 0..8 {
+        let next: Pixel = std::array::from_fn(|a| (0..4).map(|b| covariance[a][b] * axis[b]).sum());
+        let length = next.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if length < f32::EPSILON {break;}
+        axis = next.map(|v| v / length);
+    }
+    let along = centered.map(|p| (0..4).map(|c| p[c] * axis[c]).sum::<f32>());
+    let (min, max) = along.iter().fold((0.0f32, 0.0f32), |(min, max), t| (min.min(*t), max.max(*t)));
+    let point = |t: f32| std::array::from_fn(|c| (mean[c] + axis[c] * t).clamp(0.0, 255.0));
+    (point(min), point(max))
+}
+
+/// Nearest endpoint with the lowest bit of every channel set to `p`.
+fn quantize(color: Pixel, p: u8) -> [u8; 4] {
+    color.map(|c| ((((c - p as f32) / 2.0).round().clamp(0.0, 127.0) as u8) << 1) | p)
+}
+
+fn interpolate(endpoints: &[[u8; 4]; 2], index: usize) -> Pixel {
+    let w = WEIGHTS[index];
+    std::array::from_fn(|c| (((64 - w) * endpoints[0][c] as u32 + w * endpoints[1][c] as u32 + 32) >> 6) as f32)
+}
+
+fn distance(a: &Pixel, b: &Pixel) -> f32 {
+    (0..4).map(|c| (a[c] - b[c]) * (a[c] - b[c])).sum()
+}
+
+/// Bits of a block filled from the lowest.
+#[derive(Default)]
+struct Bits(u128, u32);
+
+impl Bits {
+    fn push(&mut self, value: u128, count: u32) {
+        self.0 |= value << self.1;
+        self.1 += count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pixels of a mode 6 block as the format describes them, written apart from the encoder.
+    fn decode(block: &[u8]) -> [[u8; 4]; 16] {
+        const SPEC_WEIGHTS: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+        let bits = u128::from_le_bytes(block.try_into().unwrap());
+        let read = |from: u32, count: u32| ((bits >> from) & ((1 << count) - 1)) as u32;
+        assert_eq!(read(0, 7), 1 << 6, "block is not in mode 6");
+        let p = [read(63, 1), read(64, 1)];
+        let endpoint = |e: usize| -> [u32; 4] {std::array::from_fn(|c| read(7 + (c as u32 * 2 + e as u32) * 7, 7) << 1 | p[e])};
+        let (e0, e1) = (endpoint(0), endpoint(1));
+        std::array::from_fn(|i| {
+            let index = if i == 0 {read(65, 3)} else {read(68 + (i as u32 - 1) * 4, 4)};
+            let w = SPEC_WEIGHTS[index as usize];
+            std::array::from_fn(|c| (((64 - w) * e0[c] + w * e1[c] + 32) >> 6) as u8)
+        })
+    }
+
+    /// Largest difference of any channel between the image and its blocks decoded.
+    fn round_trip(raw: &RgbaImage) -> u8 {
+        let encoded = encode(raw);
+        let columns = raw.width().div_ceil(4) as usize;
+        raw.enumerate_pixels().map(|(x, y, pixel)| {
+            let block = (y as usize / 4) * columns + x as usize / 4;
+            let decoded = decode(&encoded[block * 16..block * 16 + 16])[(y % 4 * 4 + x % 4) as usize];
+            (0..4).map(|c| pixel.0[c].abs_diff(decoded[c])).max().unwrap()
+        }).max().unwrap()
+    }
+
+    #[test]
+    fn solid_blocks_round_trip() {
+        for color in [[0, 0, 0, 255], [255, 255, 255, 255], [200, 13, 77, 255], [1, 128, 254, 3]] {
+            let error = round_trip(&RgbaImage::from_pixel(8, 8, image::Rgba(color)));
+            assert!(error <= 1, "solid {color:?} is off by {error}");
+        }
+    }
+
+    /// Colors along a line within each block, which is all the two endpoints of mode 6 can hold.
+    #[test]
+    fn gradient_blocks_round_trip() {
+        let image = RgbaImage::from_fn(16, 16, |x, y| {
+            let t = (x + y) as u8 * 8;
+            image::Rgba([t, 255 - t, t / 2 + 40, 255])
+        });
+        let error = round_trip(&image);
+        assert!(error <= 4, "gradient is off by {error}");
+    }
+
+    /// Alpha varies on its own within the blocks, down to fully transparent, as along the edges
+    /// of shapes.
+    #[test]
+    fn alpha_blocks_round_trip() {
+        let image = RgbaImage::from_fn(8, 8, |x, y| image::Rgba([255, 160, 0, ((x + y) * 255 / 14) as u8]));
+        let error = round_trip(&image);
+        assert!(error <= 4, "alpha is off by {error}");
+    }
+
+    /// Blocks past the edges of images that are not a multiple of 4 repeat the last row and
+    /// column instead of taking up range with pixels that are not drawn.
+    #[test]
+    fn partial_blocks_round_trip() {
+        let image = RgbaImage::from_fn(6, 3, |x, _| image::Rgba([x as u8 * 40, 90, 255 - x as u8 * 30, 255]));
+        let error = round_trip(&image);
+        assert!(error <= 4, "partial blocks are off by {error}");
+    }
+}
@@ -4,7 +4,7 @@ struct ShapeInput {
     @location(2) size: vec2<f32>,
     @location(3) bounds: vec4<f32>,
     @location(4) z: f32,
-    @location(5) stroke: f32,
+    @location(5) stroke: vec4<f32>,
     @location(6) texture: vec2<f32>,
     @location(7) color: vec4<f32>
 }
@@ -14,7 +14,7 @@ struct VertexOutput {
     @location(0) uv: vec2<f32>,
     @location(1) @interpolate(flat) size: vec2<f32>,
     @location(2) @interpolate(flat) bounds: vec4<f32>,
-    @location(3) @interpolate(flat) stroke: f32,
+    @location(3) @interpolate(flat) stroke: vec4<f32>,
     @location(4) texture: vec2<f32>,
     @location(5) @interpolate(flat) color: vec4<f32>
 };
@@ -24,7 +24,7 @@ fn vs_main(
     shape: ShapeInput,
 ) -> VertexOutput {
     var out: VertexOutput;
-    out.position = vec4<f32>(shape.position, shape.z, 1.0);
+    out.position = vec4<f32>(view(shape.position), shape.z, 1.0);
     out.uv = shape.uv;
 
     out.size = shape.size;
@@ -37,26 +37,24 @@ fn vs_main(
     return out;
 }
 
-@group(0) @binding(0)
+@group(1) @binding(0)
 var t_diffuse: texture_2d<f32>;
-@group(0) @binding(1)
+@group(1) @binding(1)
 var s_diffuse: sampler;
 
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    if in.uv.x < in.bounds[0] || in.uv.x > in.bounds[2] ||
-       in.uv.y < in.bounds[1] || in.uv.y > in.bounds[3] {
+fn shade(in: VertexOutput) -> vec4<f32> {
+    if clipped(in.uv, in.bounds) || rectangle_alpha(in.uv, in.size, in.stroke) == 0.0 {
         discard;
     }
-    if in.stroke > 0 {
-        if in.uv.x > in.stroke && in.uv.x < in.size.x-in.stroke &&
-           in.uv.y > in.stroke && in.uv.y < in.size.y-in.stroke {
-            discard;
-        }
-    }
-    var color = textureSample(t_diffuse, s_diffuse, in.texture);
-    if in.color[3] > 0.0 {
-        color = vec4<f32>(in.color[0], in.color[1], in.color[2], in.color[3]*color[3]);
-    }
-    return color;
+    return tint(textureSample(t_diffuse, s_diffuse, in.texture), in.color);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return shade(in);
+}
+
+@fragment
+fn fs_pick(in: VertexOutput) -> @location(0) u32 {
+    return pick(in.position.z, shade(in)[3]);
 }
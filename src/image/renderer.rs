@@ -1,18 +1,33 @@
-use wgpu::{PipelineCompilationOptions, BindGroupLayoutDescriptor, RenderPipelineDescriptor, PipelineLayoutDescriptor, TextureViewDimension, BindGroupLayoutEntry, DepthStencilState, TextureSampleType, MultisampleState, BindGroupLayout, RenderPipeline, PrimitiveState, FragmentState, TextureFormat, ShaderStages, BufferUsages, IndexFormat, VertexState, BindingType, RenderPass, Device, Queue, VertexBufferLayout, ShaderModule, Sampler, SamplerBindingType};
-use wgpu_dyn_buffer::{DynamicBufferDescriptor, DynamicBuffer};
+use wgpu::{PipelineCompilationOptions, BindGroup, BindGroupLayoutDescriptor, RenderPipelineDescriptor, PipelineLayoutDescriptor, TextureViewDimension, BindGroupLayoutEntry, DepthStencilState, TextureSampleType, MultisampleState, BindGroupLayout, RenderPipeline, PrimitiveState, FragmentState, TextureFormat, ShaderStages, IndexFormat, VertexState, BindingType, RenderPass, Device, Queue, VertexBufferLayout, ShaderModule, Sampler, SamplerBindingType, Buffer};
 
-use std::collections::HashMap;
-use crate::{Area, Color, Shape};
-use super::{ImageAtlas, InnerImage, Image};
+use std::sync::Arc;
+use crate::{create_shader, pick, Area, Border, CanvasError, Color, CornerMask, Shape};
+use crate::canvas::intersect;
+use crate::parallel;
+use crate::camera::CameraUniform;
+use crate::buffers::{Buffers, Usage};
+use super::{bindable, ImageAtlas, InnerImage, Image};
 
-use crate::shape::{Vertex, ImageVertex, ShapeVertex, RoundedRectangleVertex};
+use crate::shape::{Vertex, Mesh, ImageVertex, ImageStrokeVertex, ShapeVertex, RoundedRectangleVertex, ChevronVertex, SpeechBubbleVertex, StarVertex, WaveVertex, TiledImageVertex};
 
 pub struct ImageRenderer {
     bind_group_layout: BindGroupLayout,
     sampler: Sampler,
+    repeat: Sampler,
     ellipse_renderer: GenericImageRenderer,
     rectangle_renderer: GenericImageRenderer,
     rounded_rectangle_renderer: GenericImageRenderer,
+    chevron_renderer: GenericImageRenderer,
+    speech_bubble_renderer: GenericImageRenderer,
+    star_renderer: GenericImageRenderer,
+    path_renderer: GenericImageRenderer,
+    wave_renderer: GenericImageRenderer,
+    tiled_renderer: GenericImageRenderer,
+    ellipse_stroke_renderer: GenericImageRenderer,
+    rectangle_stroke_renderer: GenericImageRenderer,
+    rounded_rectangle_stroke_renderer: GenericImageRenderer,
+    /// Largest texture (width, height) images are bound to, when smaller than the device allows.
+    atlas_size: Option<(u32, u32)>,
 }
 
 impl ImageRenderer {
@@ -22,28 +37,9 @@ impl ImageRenderer {
         texture_format: &TextureFormat,
         multisample: MultisampleState,
         depth_stencil: Option<DepthStencilState>,
+        camera: &CameraUniform,
     ) -> Self {
-        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor{
-            label: None,
-            entries: &[
-                BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Texture {
-                        multisampled: false,
-                        view_dimension: TextureViewDimension::D2,
-                        sample_type: TextureSampleType::Float{filterable: true},
-                    },
-                    count: None,
-                },
-                BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
-                    count: None,
-                }
-            ]
-        });
+        let bind_group_layout = bind_group_layout(device);
 
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
@@ -55,81 +51,508 @@ impl ImageRenderer {
             ..Default::default()
         });
 
-        let shader = device.create_shader_module(wgpu::include_wgsl!("ellipse.wgsl"));
-        let ellipse_renderer = GenericImageRenderer::new(device, texture_format, multisample, depth_stencil.clone(), &bind_group_layout, shader, ImageVertex::<ShapeVertex>::layout());
-        let shader = device.create_shader_module(wgpu::include_wgsl!("rectangle.wgsl"));
-        let rectangle_renderer = GenericImageRenderer::new(device, texture_format, multisample, depth_stencil.clone(), &bind_group_layout, shader, ImageVertex::<ShapeVertex>::layout());
-        let shader = device.create_shader_module(wgpu::include_wgsl!("rounded_rectangle.wgsl"));
-        let rounded_rectangle_renderer = GenericImageRenderer::new(device, texture_format, multisample, depth_stencil.clone(), &bind_group_layout, shader, ImageVertex::<RoundedRectangleVertex>::layout());
+        let repeat = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            address_mode_w: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        // Images fade out at the outlines of their shapes and strokes also at the edges of their
+        // holes, where the items beneath them show through.
+        let target = wgpu::ColorTargetState{format: *texture_format, blend: Some(wgpu::BlendState::ALPHA_BLENDING), write_mask: wgpu::ColorWrites::ALL};
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "ellipse.wgsl"));
+        let ellipse_renderer = GenericImageRenderer::new(device, target.clone(), multisample, depth_stencil.clone(), camera, &bind_group_layout, shader, ImageVertex::<ShapeVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "rectangle.wgsl"));
+        let rectangle_renderer = GenericImageRenderer::new(device, target.clone(), multisample, depth_stencil.clone(), camera, &bind_group_layout, shader, ImageVertex::<ShapeVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "rounded_rectangle.wgsl"));
+        let rounded_rectangle_renderer = GenericImageRenderer::new(device, target.clone(), multisample, depth_stencil.clone(), camera, &bind_group_layout, shader, ImageVertex::<RoundedRectangleVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "chevron.wgsl"));
+        let chevron_renderer = GenericImageRenderer::new(device, target.clone(), multisample, depth_stencil.clone(), camera, &bind_group_layout, shader, ImageVertex::<ChevronVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "speech_bubble.wgsl"));
+        let speech_bubble_renderer = GenericImageRenderer::new(device, target.clone(), multisample, depth_stencil.clone(), camera, &bind_group_layout, shader, ImageVertex::<SpeechBubbleVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "star.wgsl"));
+        let star_renderer = GenericImageRenderer::new(device, target.clone(), multisample, depth_stencil.clone(), camera, &bind_group_layout, shader, ImageVertex::<StarVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "path.wgsl"));
+        let path_renderer = GenericImageRenderer::new(device, target.clone(), multisample, depth_stencil.clone(), camera, &bind_group_layout, shader, ImageVertex::<ShapeVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "wave.wgsl"));
+        let wave_renderer = GenericImageRenderer::new(device, target.clone(), multisample, depth_stencil.clone(), camera, &bind_group_layout, shader, ImageVertex::<WaveVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "tiled.wgsl"));
+        let tiled_renderer = GenericImageRenderer::new(device, target.clone(), multisample, depth_stencil.clone(), camera, &bind_group_layout, shader, TiledImageVertex::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "ellipse_stroke.wgsl"));
+        let ellipse_stroke_renderer = GenericImageRenderer::new(device, target.clone(), multisample, depth_stencil.clone(), camera, &bind_group_layout, shader, ImageStrokeVertex::<ShapeVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "rectangle_stroke.wgsl"));
+        let rectangle_stroke_renderer = GenericImageRenderer::new(device, target.clone(), multisample, depth_stencil.clone(), camera, &bind_group_layout, shader, ImageStrokeVertex::<ShapeVertex>::layout());
+        let shader = create_shader(device, concat_wgsl!("../common.wgsl", "rounded_rectangle_stroke.wgsl"));
+        let rounded_rectangle_stroke_renderer = GenericImageRenderer::new(device, target, multisample, depth_stencil.clone(), camera, &bind_group_layout, shader, ImageStrokeVertex::<RoundedRectangleVertex>::layout());
         ImageRenderer{
             bind_group_layout,
             sampler,
+            repeat,
             ellipse_renderer,
             rectangle_renderer,
-            rounded_rectangle_renderer
+            rounded_rectangle_renderer,
+            chevron_renderer,
+            speech_bubble_renderer,
+            star_renderer,
+            path_renderer,
+            wave_renderer,
+            tiled_renderer,
+            ellipse_stroke_renderer,
+            rectangle_stroke_renderer,
+            rounded_rectangle_stroke_renderer,
+            atlas_size: None,
         }
     }
 
-    /// Prepare for rendering this frame; create all resources that will be
-    /// used during the next render that do not already exist.
-    pub fn prepare(
-        &mut self,
-        device: &Device,
-        queue: &Queue,
+    /// Bind images to textures of at most the size (width, height) rather than the largest the
+    /// device allows, images larger than it are left out.
+    pub fn set_atlas_size(&mut self, atlas_size: Option<(u32, u32)>) {self.atlas_size = atlas_size;}
+
+    pub fn atlas_size(&self) -> Option<(u32, u32)> {self.atlas_size}
+
+    /// Largest texture (width, height) images are bound to on the device.
+    fn max_size(&self, device: &Device) -> (u32, u32) {
+        let max = device.limits().max_texture_dimension_2d;
+        self.atlas_size.map_or((max, max), |(width, height)| (width.min(max), height.min(max)))
+    }
+
+    /// Vertices of the items, strokes and tiles in the order of the pipelines of the renderer,
+    /// grouped by the image they draw, without touching the GPU, written over the frame in the
+    /// storage of the last one. The items are drained, keeping their capacity. Paths that could
+    /// not be tessellated are left out.
+    pub fn build(
         width: f32,
         height: f32,
-        image_atlas: &mut ImageAtlas,
-        items: Vec<(u16, Area, Shape, Image, Option<Color>)>,
+        items: &mut Vec<ImageItem>,
+        strokes: &mut Vec<ImageItem>,
+        tiles: &mut Vec<TileItem>,
+        frame: &mut ImageFrame,
+        errors: &mut Vec<CanvasError>,
     ) {
-        image_atlas.trim_and_bind(queue, device, &self.bind_group_layout, &self.sampler);
-
-        let (ellipses, rects, rounded_rects) = items.into_iter().fold(
-            (vec![], vec![], vec![]),
-            |mut a, (z, area, shape, key, color)| {
-                let image = image_atlas.get(&key);
-                match shape {
-                    Shape::Ellipse(stroke, size) => a.0.push((ImageVertex::new(ShapeVertex::new(width, height, z, area, stroke, size), &key, size, color), image)),
-                    Shape::Rectangle(stroke, size) => a.1.push((ImageVertex::new(ShapeVertex::new(width, height, z, area, stroke, size), &key, size, color), image)),
-                    Shape::RoundedRectangle(stroke, size, corner_radius) =>
-                        a.2.push((ImageVertex::new(RoundedRectangleVertex::new(width, height, z, area, stroke, size, corner_radius), &key, size, color), image)),
-                }
-                a
-            }
-        );
-        self.ellipse_renderer.prepare(device, queue, ellipses);
-        self.rectangle_renderer.prepare(device, queue, rects);
-        self.rounded_rectangle_renderer.prepare(device, queue, rounded_rects);
+        frame.1.clear();
+        frame.1.extend(items.iter().chain(strokes.iter()).map(|(z, _, _, key, _)| (*z, key.root())));
+        frame.1.extend(tiles.iter().map(|(z, _, key, _, _)| (*z, key.root())));
+        batches(width, height, items, strokes, tiles, &mut frame.0, errors);
+    }
+
+    /// Bind the images newly added to the atlas and upload the vertices of a frame from
+    /// [`build`](Self::build) for the next render. Returns the items drawing images that have no
+    /// texture, which are left out.
+    pub fn upload(&mut self, device: &Device, queue: &Queue, image_atlas: &mut ImageAtlas, frame: &ImageFrame) -> (bool, Vec<CanvasError>) {
+        let errors = self.bind(device, queue, image_atlas, &frame.1);
+        let changed = self.renderers_mut().into_iter().zip(&frame.0).fold(false, |changed, (renderer, batch)| renderer.prepare(device, queue, image_atlas, batch) || changed);
+        (changed, errors)
+    }
+
+    /// Replace the buffers of every pipeline by ones that just fit the last frame.
+    pub fn shrink_to_fit(&mut self, device: &Device, queue: &Queue) {
+        for renderer in self.renderers_mut() {
+            renderer.shrink_to_fit(device, queue);
+        }
+    }
+
+    /// Bytes of the vertex and index buffers of every pipeline.
+    pub fn buffer_capacity(&self) -> u64 {
+        self.renderers().into_iter().map(GenericImageRenderer::capacity).sum()
+    }
+
+    /// Like [`upload`](Self::upload) but the vertices are uploaded to buffers of their own, leaving
+    /// the buffers of the renderer untouched.
+    #[cfg(feature = "tokio")]
+    pub fn upload_frame(&self, device: &Device, queue: &Queue, image_atlas: &mut ImageAtlas, ImageFrame(batches, images): ImageFrame) -> (PreparedFrame, Vec<CanvasError>) {
+        let errors = self.bind(device, queue, image_atlas, &images);
+        let batches = batches.map(|Batch(vertices, indices, draws)| {
+            let draws = draws.into_iter().filter_map(|(key, indices)| Some((image_atlas.get(&key)?, indices))).collect();
+            Batch(vertices, indices, draws).upload(device)
+        });
+        (PreparedFrame(batches), errors)
+    }
+
+    /// Bind the images newly added to the atlas, returns the items drawing images that have no
+    /// texture.
+    fn bind(&self, device: &Device, queue: &Queue, image_atlas: &mut ImageAtlas, images: &[(u16, Image)]) -> Vec<CanvasError> {
+        let max = self.max_size(device);
+        image_atlas.trim_and_bind(queue, device, &self.bind_group_layout, &self.sampler, &self.repeat, max);
+        images.iter().filter(|(_, key)| image_atlas.get(key).is_none()).map(|(z, key)| unbound(*z, key, max)).collect()
     }
 
     /// Render using caller provided render pass.
     pub fn render(&self, render_pass: &mut RenderPass<'_>) {
-        self.ellipse_renderer.render(render_pass);
-        self.rectangle_renderer.render(render_pass);
-        self.rounded_rectangle_renderer.render(render_pass);
+        for renderer in self.renderers() {
+            renderer.render(render_pass);
+        }
+    }
+
+    /// Render a frame from [`prepare_async`](Self::prepare_async) using caller provided render pass.
+    #[cfg(feature = "tokio")]
+    pub fn render_frame(&self, frame: &PreparedFrame, render_pass: &mut RenderPass<'_>) {
+        for (renderer, (vertex_buffer, index_buffer, indices)) in self.renderers().into_iter().zip(&frame.0) {
+            renderer.draw(render_pass, &renderer.render_pipeline, vertex_buffer, index_buffer, indices);
+        }
+    }
+
+    /// Render the ids of the items of the most recently prepared frame into the picking pass.
+    pub fn render_picking(&self, render_pass: &mut RenderPass<'_>) {
+        for renderer in self.renderers() {
+            renderer.render_picking(render_pass);
+        }
+    }
+
+    /// Render the ids of the items of a frame from [`prepare_async`](Self::prepare_async) into the
+    /// picking pass.
+    #[cfg(feature = "tokio")]
+    pub fn render_frame_picking(&self, frame: &PreparedFrame, render_pass: &mut RenderPass<'_>) {
+        for (renderer, (vertex_buffer, index_buffer, indices)) in self.renderers().into_iter().zip(&frame.0) {
+            renderer.draw(render_pass, &renderer.pick_pipeline, vertex_buffer, index_buffer, indices);
+        }
+    }
+
+    fn renderers(&self) -> [&GenericImageRenderer; 12] {
+        [
+            &self.ellipse_renderer,
+            &self.rectangle_renderer,
+            &self.rounded_rectangle_renderer,
+            &self.chevron_renderer,
+            &self.speech_bubble_renderer,
+            &self.star_renderer,
+            &self.path_renderer,
+            &self.wave_renderer,
+            &self.tiled_renderer,
+            &self.ellipse_stroke_renderer,
+            &self.rectangle_stroke_renderer,
+            &self.rounded_rectangle_stroke_renderer,
+        ]
+    }
+
+    fn renderers_mut(&mut self) -> [&mut GenericImageRenderer; 12] {
+        [
+            &mut self.ellipse_renderer,
+            &mut self.rectangle_renderer,
+            &mut self.rounded_rectangle_renderer,
+            &mut self.chevron_renderer,
+            &mut self.speech_bubble_renderer,
+            &mut self.star_renderer,
+            &mut self.path_renderer,
+            &mut self.wave_renderer,
+            &mut self.tiled_renderer,
+            &mut self.ellipse_stroke_renderer,
+            &mut self.rectangle_stroke_renderer,
+            &mut self.rounded_rectangle_stroke_renderer,
+        ]
+    }
+}
+
+/// Image draws of a frame uploaded by [`ImageRenderer::upload_frame`], one set of vertex and
+/// index buffers per pipeline of the renderer.
+#[cfg(feature = "tokio")]
+pub struct PreparedFrame([(Buffer, Buffer, Indices); 12]);
+
+/// Image draws of a frame built by [`ImageRenderer::build`], the vertices of each pipeline of the
+/// renderer grouped by image, and the items drawing each image.
+#[derive(Default, PartialEq)]
+pub struct ImageFrame([Batch<Image>; 12], Vec<(u16, Image)>);
+
+/// Index ranges to draw per texture, in the order of the images of the batch.
+type Indices = Vec<(InnerImage, Vec<(u32, u32)>)>;
+
+/// Image item of the given shape, image and tint.
+type ImageItem = (u16, Area, Shape, Image, Option<Color>);
+
+/// Error of an item drawing an image that has no texture.
+fn unbound(z: u16, key: &Image, max: (u32, u32)) -> CanvasError {
+    let (width, height) = key.root().size();
+    let item = z as usize;
+    match bindable((width, height), max) {
+        Err(reason) if width == 0 || height == 0 => CanvasError::InvalidInput{item, reason},
+        Err(reason) => CanvasError::Allocation{item: Some(item), reason},
+        Ok(()) => CanvasError::InvalidInput{item, reason: format!("image of {width}x{height} is not in the atlas")},
+    }
+}
+
+/// Tiled image item of the given size and tile size.
+type TileItem = (u16, Area, Image, (f32, f32), (f32, f32));
+
+/// Vertices of the items and strokes in the order of the pipelines of the [`ImageRenderer`],
+/// written over the batches, leaving out paths that could not be tessellated. The items are
+/// drained.
+fn batches(
+    width: f32,
+    height: f32,
+    items: &mut Vec<ImageItem>,
+    strokes: &mut Vec<ImageItem>,
+    tiles: &mut Vec<TileItem>,
+    batches: &mut Batches,
+    errors: &mut Vec<CanvasError>,
+) {
+    for batch in batches.iter_mut() {batch.clear();}
+    let (built, path_errors) = parallel::fold_into(
+        (std::mem::take(batches), vec![]),
+        items,
+        Default::default,
+        |(mut a, mut errors): (Batches, Vec<CanvasError>), (z, area, shape, key, color)| {
+            let image = key.root();
+            match shape {
+                Shape::Ellipse(stroke, size) => a[0].push(&ImageVertex::new(ShapeVertex::new(width, height, z, area, stroke.into(), size), &key, size, color), &QUAD, &image),
+                Shape::Rectangle(stroke, size) => a[1].push(&ImageVertex::new(ShapeVertex::new(width, height, z, area, stroke, size), &key, size, color), &QUAD, &image),
+                Shape::RoundedRectangle(stroke, size, corner_radius) =>
+                    a[2].push(&ImageVertex::new(RoundedRectangleVertex::new(width, height, z, area, stroke, size, corner_radius, CornerMask::all()), &key, size, color), &QUAD, &image),
+                Shape::RoundedRectangleSides(stroke, size, corner_radius, corners) =>
+                    a[2].push(&ImageVertex::new(RoundedRectangleVertex::new(width, height, z, area, stroke, size, corner_radius, corners), &key, size, color), &QUAD, &image),
+                Shape::Chevron(direction, thickness, size) =>
+                    a[3].push(&ImageVertex::new(ChevronVertex::new(width, height, z, area, direction, thickness, size), &key, size, color), &QUAD, &image),
+                Shape::SpeechBubble(stroke, size, corner_radius, tail) =>
+                    a[4].push(&ImageVertex::new(SpeechBubbleVertex::new(width, height, z, area, stroke, size, corner_radius, tail), &key, size, color), &QUAD, &image),
+                Shape::Star(stroke, size, points, inner_ratio) =>
+                    a[5].push(&ImageVertex::new(StarVertex::new(width, height, z, area, stroke, size, points, inner_ratio), &key, size, color), &QUAD, &image),
+                Shape::Path(stroke, path) => match ShapeVertex::path(width, height, z, area, stroke, &path) {
+                    Ok(mesh) => {
+                        let Mesh(vertices, indices) = ImageVertex::mesh(mesh, &key, color);
+                        a[6].push(&vertices, &indices, &image);
+                    },
+                    Err(error) => errors.push(error),
+                },
+                Shape::Wave(thickness, size, wavelength) =>
+                    a[7].push(&ImageVertex::new(WaveVertex::new(width, height, z, area, thickness, size, wavelength), &key, size, color), &QUAD, &image),
+            }
+            (a, errors)
+        },
+        merge,
+    );
+    errors.extend(path_errors);
+
+    // Speech bubbles, stars and paths are stroked by their fill pipelines, after the fills.
+    let (built, path_errors) = parallel::fold_into(
+        (built, vec![]),
+        strokes,
+        Default::default,
+        |(mut a, mut errors): (Batches, Vec<CanvasError>), (z, area, shape, key, color)| {
+            if shape.stroke() == Border::default() {return (a, errors);}
+            let image = key.root();
+            match shape {
+                Shape::Ellipse(stroke, size) => a[9].push(&ImageStrokeVertex::new(ShapeVertex::new(width, height, z, area, stroke.into(), size), &key, color), &QUAD, &image),
+                Shape::Rectangle(stroke, size) => a[10].push(&ImageStrokeVertex::new(ShapeVertex::new(width, height, z, area, stroke, size), &key, color), &QUAD, &image),
+                Shape::RoundedRectangle(stroke, size, corner_radius) =>
+                    a[11].push(&ImageStrokeVertex::new(RoundedRectangleVertex::new(width, height, z, area, stroke, size, corner_radius, CornerMask::all()), &key, color), &QUAD, &image),
+                Shape::RoundedRectangleSides(stroke, size, corner_radius, corners) =>
+                    a[11].push(&ImageStrokeVertex::new(RoundedRectangleVertex::new(width, height, z, area, stroke, size, corner_radius, corners), &key, color), &QUAD, &image),
+                Shape::Chevron(_, _, _) | Shape::Wave(_, _, _) => {},
+                Shape::SpeechBubble(stroke, size, corner_radius, tail) =>
+                    a[4].push(&ImageVertex::new(SpeechBubbleVertex::new(width, height, z, area, stroke, size, corner_radius, tail), &key, size, color), &QUAD, &image),
+                Shape::Star(stroke, size, points, inner_ratio) =>
+                    a[5].push(&ImageVertex::new(StarVertex::new(width, height, z, area, stroke, size, points, inner_ratio), &key, size, color), &QUAD, &image),
+                Shape::Path(stroke, path) => match ShapeVertex::path(width, height, z, area, stroke, &path) {
+                    Ok(mesh) => {
+                        let Mesh(vertices, indices) = ImageVertex::mesh(mesh, &key, color);
+                        a[6].push(&vertices, &indices, &image);
+                    },
+                    Err(error) => errors.push(error),
+                },
+            }
+            (a, errors)
+        },
+        merge,
+    );
+    errors.extend(path_errors);
+    *batches = built;
+
+    // The repeating sampler wraps around the whole texture, regions of it are tiled with a
+    // rectangle per tile instead.
+    for (z, area, key, size, tile_size) in tiles.drain(..) {
+        if tile_size.0 <= 0.0 || tile_size.1 <= 0.0 {continue;}
+        let image = key.root();
+        if !key.is_region() {
+            batches[8].push(&TiledImageVertex::new(width, height, z, area, size, tile_size), &QUAD, &image);
+            continue;
+        }
+        let (x, y) = area.0;
+        let bounds = intersect(area.bounds(), (x, y, size.0, size.1));
+        let stretch = (key.size().0 as f32, key.size().1 as f32);
+        for row in 0..(size.1 / tile_size.1).ceil() as u32 {
+            for column in 0..(size.0 / tile_size.0).ceil() as u32 {
+                let area = Area((x + column as f32 * tile_size.0, y + row as f32 * tile_size.1), Some(bounds));
+                let shape = ShapeVertex::new(width, height, z, area, Border::default(), tile_size);
+                batches[1].push(&ImageVertex::new(shape, &key, stretch, None), &QUAD, &image);
+            }
+        }
+    }
+
+    for batch in batches.iter_mut() {batch.finish();}
+}
+
+/// Batches of runs of items folded on other threads, joined in order.
+fn merge((mut a, mut errors): (Batches, Vec<CanvasError>), (b, other): (Batches, Vec<CanvasError>)) -> (Batches, Vec<CanvasError>) {
+    for ((batch, other), stride) in a.iter_mut().zip(b).zip(STRIDES) {batch.append(other, stride);}
+    errors.extend(other);
+    (a, errors)
+}
+
+/// Batches of the pipelines of the [`ImageRenderer`] in order.
+type Batches = [Batch<Image>; 12];
+
+/// Bytes of a vertex of each pipeline of the [`ImageRenderer`].
+const STRIDES: [usize; 12] = [
+    size_of::<ImageVertex<ShapeVertex>>(),
+    size_of::<ImageVertex<ShapeVertex>>(),
+    size_of::<ImageVertex<RoundedRectangleVertex>>(),
+    size_of::<ImageVertex<ChevronVertex>>(),
+    size_of::<ImageVertex<SpeechBubbleVertex>>(),
+    size_of::<ImageVertex<StarVertex>>(),
+    size_of::<ImageVertex<ShapeVertex>>(),
+    size_of::<ImageVertex<WaveVertex>>(),
+    size_of::<TiledImageVertex>(),
+    size_of::<ImageStrokeVertex<ShapeVertex>>(),
+    size_of::<ImageStrokeVertex<ShapeVertex>>(),
+    size_of::<ImageStrokeVertex<RoundedRectangleVertex>>(),
+];
+
+/// Indices of the two triangles of a quad.
+const QUAD: [u16; 6] = [0, 1, 2, 1, 2, 3];
+
+/// Layout of the bind groups of the images, a texture with a clamping and a repeating sampler.
+pub(super) fn bind_group_layout(device: &Device) -> BindGroupLayout {
+    device.create_bind_group_layout(&BindGroupLayoutDescriptor{
+        label: None,
+        entries: &[
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: TextureViewDimension::D2,
+                    sample_type: TextureSampleType::Float{filterable: true},
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 1,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 2,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None,
+            }
+        ]
+    })
+}
+
+/// Vertex and index data of one pipeline with the index ranges to draw per image, or per texture
+/// once bound.
+#[derive(PartialEq)]
+struct Batch<K>(Vec<u8>, Vec<u32>, Vec<(K, Vec<(u32, u32)>)>);
+
+impl<K> Default for Batch<K> {
+    fn default() -> Self {Batch(vec![], vec![], vec![])}
+}
+
+impl Batch<Image> {
+    /// Empty the batch for the next frame, keeping its storage and the images it drew until
+    /// [`finish`](Self::finish) finds which of them the frame still draws.
+    fn clear(&mut self) {
+        self.0.clear();
+        self.1.clear();
+        for (_, ranges) in &mut self.2 {ranges.clear();}
+    }
+
+    /// Append the triangles of the vertices drawing the image.
+    fn push<V: bytemuck::Pod>(&mut self, vertices: &[V], indices: &[u16], image: &Image) {
+        let (l, start) = ((self.0.len() / size_of::<V>()) as u32, self.1.len() as u32);
+        self.0.extend_from_slice(bytemuck::cast_slice(vertices));
+        self.1.extend(indices.iter().map(|i| l + *i as u32));
+        let end = self.1.len() as u32;
+        extend_ranges(self.ranges(image), (start, end));
+    }
+
+    /// Append the batch of a later run of items, the vertices of which are `stride` bytes each.
+    fn append(&mut self, Batch(vertices, indices, draws): Batch<Image>, stride: usize) {
+        let (l, start) = ((self.0.len() / stride) as u32, self.1.len() as u32);
+        self.0.extend(vertices);
+        self.1.extend(indices.into_iter().map(|i| l + i));
+        for (image, ranges) in draws {
+            let indices = self.ranges(&image);
+            for (s, e) in ranges {extend_ranges(indices, (start + s, start + e));}
+        }
+    }
+
+    /// Index ranges of the image, kept in the order of the images.
+    fn ranges(&mut self, image: &Image) -> &mut Vec<(u32, u32)> {
+        let i = match self.2.binary_search_by(|(key, _)| key.cmp(image)) {
+            Ok(i) => i,
+            Err(i) => {
+                self.2.insert(i, (image.clone(), vec![]));
+                i
+            },
+        };
+        &mut self.2[i].1
+    }
+
+    /// Leave out the images the frame no longer draws.
+    fn finish(&mut self) {
+        self.2.retain(|(_, ranges)| !ranges.is_empty());
+    }
+}
+
+/// Add the index range to the ranges of an image, extending the last range when the new one
+/// continues it, so runs of quads drawing the same image take one draw call.
+fn extend_ranges(ranges: &mut Vec<(u32, u32)>, (start, end): (u32, u32)) {
+    match ranges.last_mut() {
+        Some(last) if last.1 == start => last.1 = end,
+        _ => ranges.push((start, end)),
+    }
+}
+
+impl Batch<InnerImage> {
+    #[cfg(feature = "tokio")]
+    fn upload(self, device: &Device) -> (Buffer, Buffer, Indices) {
+        use wgpu::{BufferUsages, util::{BufferInitDescriptor, DeviceExt}};
+        let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor{
+            label: None,
+            contents: &self.0,
+            usage: BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&BufferInitDescriptor{
+            label: None,
+            contents: bytemuck::cast_slice(&self.1),
+            usage: BufferUsages::INDEX,
+        });
+        (vertex_buffer, index_buffer, self.2)
     }
 }
 
+/// Keeps two sets of buffers, so preparing a frame that changed writes to the set the frame before
+/// last was drawn from, never to the one the last frame may still be rendering from.
 pub struct GenericImageRenderer {
+    camera: BindGroup,
     render_pipeline: RenderPipeline,
-    vertex_buffer: DynamicBuffer,
-    index_buffer: DynamicBuffer,
-    indices: HashMap<InnerImage, Vec<(u32, u32)>>,
+    pick_pipeline: RenderPipeline,
+    buffers: [(Buffers, Indices); 2],
+    current: usize,
+    usage: Usage,
 }
 
 impl GenericImageRenderer {
     /// Create all unchanging resources here.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         device: &Device,
-        texture_format: &TextureFormat,
+        target: wgpu::ColorTargetState,
         multisample: MultisampleState,
         depth_stencil: Option<DepthStencilState>,
+        camera: &CameraUniform,
         bind_group_layout: &BindGroupLayout,
         shader: ShaderModule,
         vertex_layout: VertexBufferLayout
     ) -> Self {
         let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor{
             label: None,
-            bind_group_layouts: &[bind_group_layout],
+            bind_group_layouts: &[&camera.layout, bind_group_layout],
             push_constant_ranges: &[],
         });
 
@@ -140,13 +563,13 @@ impl GenericImageRenderer {
                 module: &shader,
                 entry_point: Some("vs_main"),
                 compilation_options: PipelineCompilationOptions::default(),
-                buffers: &[vertex_layout]
+                buffers: std::slice::from_ref(&vertex_layout)
             },
             fragment: Some(FragmentState {
                 module: &shader,
                 entry_point: Some("fs_main"),
                 compilation_options: PipelineCompilationOptions::default(),
-                targets: &[Some((*texture_format).into())]
+                targets: &[Some(target)]
             }),
             primitive: PrimitiveState::default(),
             depth_stencil,
@@ -154,68 +577,120 @@ impl GenericImageRenderer {
             multiview: None,
             cache: None
         });
-
-        let vertex_buffer = DynamicBuffer::new(device, &DynamicBufferDescriptor {
-            label: None,
-            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
-        });
-
-        let index_buffer = DynamicBuffer::new(device, &DynamicBufferDescriptor {
-            label: None,
-            usage: BufferUsages::INDEX | BufferUsages::COPY_DST,
-        });
+        let pick_pipeline = pick::pipeline(device, &pipeline_layout, &shader, vertex_layout);
 
         GenericImageRenderer{
+            camera: camera.bind_group.clone(),
             render_pipeline,
-            vertex_buffer,
-            index_buffer,
-            indices: HashMap::new(),
+            pick_pipeline,
+            buffers: [(), ()].map(|_| (Buffers::new(device), vec![])),
+            current: 0,
+            usage: Usage::default(),
         }
     }
 
     /// Prepare for rendering this frame; create all resources that will be
-    /// used during the next render that do not already exist.
-    pub fn prepare<V: bytemuck::Pod>(
-        &mut self,
-        device: &Device,
-        queue: &Queue,
-        image_vertices: Vec<([V; 4], InnerImage)>,
-    ) {
-        self.indices.clear();
-
-        let (vertices, indices, indices_buffer) = image_vertices.into_iter().fold(
-            (vec![], vec![], HashMap::<InnerImage, Vec<(u32, u32)>>::new()),
-            |mut a, (vertices, image)| {
-                let start = a.1.len();
-
-                let l = a.0.len() as u16;
-                a.0.extend(vertices);
-                a.1.extend([l, l+1, l+2, l+1, l+2, l+3]);
-
-                let index = (start as u32, a.1.len() as u32);
-                match a.2.get_mut(&image) {
-                    Some(indices) => indices.push(index),
-                    None => {a.2.insert(image, vec![index]);}
-                }
-                a
-            }
-        );
+    /// used during the next render that do not already exist. Returns whether the batch was
+    /// written, it is not when it is the one last written, drawing the same textures. A batch that
+    /// changed is written to the other set of buffers, which are drawn from from then on. The
+    /// buffers shrink after a while of frames using much less of them.
+    fn prepare(&mut self, device: &Device, queue: &Queue, image_atlas: &ImageAtlas, Batch(vertices, indices, draws): &Batch<Image>) -> bool {
+        let indices: &[u8] = bytemuck::cast_slice(indices);
+        let bound = || draws.iter().filter_map(|(key, ranges)| Some((image_atlas.get(key)?, ranges)));
+        let (buffers, last) = &self.buffers[self.current];
+        let changed = !buffers.holds(vertices, indices) || last.len() != bound().count() ||
+            last.iter().zip(bound()).any(|((last, drawn), (image, ranges))| !Arc::ptr_eq(last, &image) || drawn != ranges);
+        if changed {
+            self.current = 1 - self.current;
+            let (buffers, draws) = &mut self.buffers[self.current];
+            // Skipped when the frame before last wrote the same vertices and indices.
+            buffers.write(device, queue, vertices, indices);
+            draws.clear();
+            draws.extend(bound().map(|(image, ranges)| (image, ranges.clone())));
+        }
+        let largest = self.buffers.iter().map(|(buffers, _)| buffers.capacity()).max().unwrap_or(0);
+        if self.usage.frame(self.buffers[self.current].0.used(), largest) {
+            self.shrink_to_fit(device, queue);
+        }
+        changed
+    }
 
-        self.indices = indices_buffer;
-        self.vertex_buffer.write_buffer(device, queue, bytemuck::cast_slice(&vertices));
-        self.index_buffer.write_buffer(device, queue, bytemuck::cast_slice(&indices));
+    /// Replace the current buffers by ones that just fit the frame they hold, and the others by
+    /// new ones, which are not drawn from until the next frame that changed is written to them.
+    fn shrink_to_fit(&mut self, device: &Device, queue: &Queue) {
+        self.buffers[1 - self.current] = (Buffers::new(device), vec![]);
+        self.buffers[self.current].0.shrink_to_fit(device, queue);
     }
 
-    /// Render using caller provided render pass.
+    /// Bytes of both sets of buffers.
+    fn capacity(&self) -> u64 {
+        self.buffers.iter().map(|(buffers, _)| buffers.capacity()).sum()
+    }
+
+    /// Render the most recently prepared frame using caller provided render pass.
     pub fn render(&self, render_pass: &mut RenderPass<'_>) {
-        render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.set_vertex_buffer(0, self.vertex_buffer.as_ref().slice(..));
-        render_pass.set_index_buffer(self.index_buffer.as_ref().slice(..), IndexFormat::Uint16);
-        for (bind_group, indices) in &self.indices {
-            render_pass.set_bind_group(0, Some(&**bind_group), &[]);
+        let (buffers, draws) = &self.buffers[self.current];
+        self.draw(render_pass, &self.render_pipeline, buffers.vertices(), buffers.indices(), draws);
+    }
+
+    /// Render the ids of the items of the most recently prepared frame into the picking pass.
+    pub fn render_picking(&self, render_pass: &mut RenderPass<'_>) {
+        let (buffers, draws) = &self.buffers[self.current];
+        self.draw(render_pass, &self.pick_pipeline, buffers.vertices(), buffers.indices(), draws);
+    }
+
+    fn draw(&self, render_pass: &mut RenderPass<'_>, pipeline: &RenderPipeline, vertex_buffer: &Buffer, index_buffer: &Buffer, indices: &Indices) {
+        if indices.is_empty() {return;}
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &self.camera, &[]);
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.set_index_buffer(index_buffer.slice(..), IndexFormat::Uint32);
+        for (bind_group, indices) in indices {
+            render_pass.set_bind_group(1, Some(&**bind_group), &[]);
             for (start, end) in indices {
                 render_pass.draw_indexed(*start..*end, 0, 0..1);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    /// Quads of interleaved images, the later half appended as from another thread, are drawn by
+    /// ranges that together cover each index once, and each quad by the image it was given.
+    #[test]
+    fn index_ranges_follow_image_runs() {
+        let mut atlas = ImageAtlas::default();
+        let images = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]].map(|color| atlas.add(RgbaImage::from_pixel(4, 4, image::Rgba(color))));
+        let order = [0, 1, 1, 0, 2, 1];
+        let (mut batch, mut later) = (Batch::default(), Batch::default());
+        for (i, image) in order.iter().enumerate() {
+            let v = i as u32 * 4;
+            let half = if i < order.len() / 2 {&mut batch} else {&mut later};
+            half.push(&[v, v + 1, v + 2, v + 3], &QUAD, &images[*image]);
+        }
+        batch.append(later, size_of::<u32>());
+        batch.finish();
+        let Batch(vertices, indices, draws) = batch;
+        let vertices = vertices.chunks_exact(4).map(|v| u32::from_ne_bytes(v.try_into().unwrap())).collect::<Vec<_>>();
+        assert_eq!(indices.len(), 36);
+
+        let mut ranges = draws.iter().flat_map(|(image, ranges)| ranges.iter().map(move |range| (*range, image))).collect::<Vec<_>>();
+        ranges.sort_by_key(|(range, _)| *range);
+        assert_eq!(ranges.first().map(|((start, _), _)| *start), Some(0));
+        assert_eq!(ranges.last().map(|((_, end), _)| *end), Some(36));
+        for pair in ranges.windows(2) {
+            assert_eq!(pair[0].0.1, pair[1].0.0, "ranges {:?} and {:?} overlap or leave a gap", pair[0].0, pair[1].0);
+        }
+        for ((start, end), image) in ranges {
+            assert!(start < end, "empty range at {start}");
+            for i in start..end {
+                let quad = vertices[indices[i as usize] as usize] as usize / 4;
+                assert!(*image == images[order[quad]], "index {i} draws quad {quad} with the wrong image");
+            }
+        }
+    }
+}
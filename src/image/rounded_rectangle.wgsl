@@ -4,10 +4,11 @@ struct ShapeInput {
     @location(2) size: vec2<f32>,
     @location(3) bounds: vec4<f32>,
     @location(4) z: f32,
-    @location(5) stroke: f32,
-    @location(6) corner_radius: f32,
-    @location(7) texture: vec2<f32>,
-    @location(8) color: vec4<f32>
+    @location(5) stroke: vec4<f32>,
+    @location(6) radius_x: vec4<f32>,
+    @location(7) radius_y: vec4<f32>,
+    @location(8) texture: vec2<f32>,
+    @location(9) color: vec4<f32>
 }
 
 struct VertexOutput {
@@ -15,10 +16,11 @@ struct VertexOutput {
     @location(0) uv: vec2<f32>,
     @location(1) @interpolate(flat) size: vec2<f32>,
     @location(2) @interpolate(flat) bounds: vec4<f32>,
-    @location(3) @interpolate(flat) stroke: f32,
-    @location(4) @interpolate(flat) corner_radius: f32,
-    @location(5) texture: vec2<f32>,
-    @location(6) @interpolate(flat) color: vec4<f32>
+    @location(3) @interpolate(flat) stroke: vec4<f32>,
+    @location(4) @interpolate(flat) radius_x: vec4<f32>,
+    @location(5) @interpolate(flat) radius_y: vec4<f32>,
+    @location(6) texture: vec2<f32>,
+    @location(7) @interpolate(flat) color: vec4<f32>
 };
 
 @vertex
@@ -26,79 +28,41 @@ fn vs_main(
     shape: ShapeInput,
 ) -> VertexOutput {
     var out: VertexOutput;
-    out.position = vec4<f32>(shape.position, shape.z, 1.0);
+    out.position = vec4<f32>(view(shape.position), shape.z, 1.0);
     out.uv = shape.uv;
 
     out.size = shape.size;
 
     out.bounds = shape.bounds;
     out.stroke = shape.stroke;
-    out.corner_radius = shape.corner_radius;
+    out.radius_x = shape.radius_x;
+    out.radius_y = shape.radius_y;
     out.texture = shape.texture;
     out.color = shape.color;
 
     return out;
 }
 
-fn alpha(uv: vec2<f32>, size: vec2<f32>, stroke: f32, cr: f32) -> f32 {
-    var x = 0.0;
-    var y = 0.0;
-
-    if uv.x < cr && uv.y < cr {
-        x = (cr-uv.x);
-        y = (cr-uv.y);
-    } else if uv.x > size[0]-cr && uv.y < cr {
-        x = ((size[0]-cr)-uv.x);
-        y = (cr-uv.y);
-    } else if uv.x < cr && uv.y > size[1]-cr {
-        x = (cr-uv.x);
-        y = ((size[1]-cr)-uv.y);
-    } else if uv.x > size[0]-cr && uv.y > size[1]-cr {
-        x = ((size[0]-cr)-uv.x);
-        y = ((size[1]-cr)-uv.y);
-    } else {
-        if stroke > 0 {
-            if uv.x > stroke && uv.x < size.x-stroke &&
-               uv.y > stroke && uv.y < size.y-stroke {
-                return 0.0;
-            }
-            return 1.0;
-        }
-    }
-
-    let a = (size.x / 2.0);
-    let b = (size.y / 2.0);
-    let dx = x / (cr - 1.0);
-    let dy = y / (cr - 1.0);
-    let d = dx*dx+dy*dy;
-    let p = (2.0/cr);
-
-    var s = 1.0;
-    if stroke > 0 && stroke < cr {
-        let sx = x / (cr-stroke-0.5);
-        let sy = y / (cr-stroke-0.5);
-        let sd = sx*sx+sy*sy;
-        s = smoothstep(1.0, 1.0+p, sd);
-    }
-
-    return (1.0-smoothstep(1.0, 1.0+p, d)) * s;
-}
-
-@group(0) @binding(0)
+@group(1) @binding(0)
 var t_diffuse: texture_2d<f32>;
-@group(0) @binding(1)
+@group(1) @binding(1)
 var s_diffuse: sampler;
 
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    if in.uv.x < in.bounds[0] || in.uv.x > in.bounds[2] ||
-       in.uv.y < in.bounds[1] || in.uv.y > in.bounds[3] {
+fn shade(in: VertexOutput) -> vec4<f32> {
+    if clipped(in.uv, in.bounds) {
         discard;
     }
-    var color = textureSample(t_diffuse, s_diffuse, in.texture);
-    if in.color[3] > 0.0 {
-        color = vec4<f32>(in.color[0], in.color[1], in.color[2], in.color[3]*color[3]);
-    }
-    let alpha = alpha(in.uv, in.size, in.stroke, in.corner_radius);
+    let alpha = rounded_rectangle_alpha(in.uv, in.size, in.stroke, in.radius_x, in.radius_y);
+    let color = tint(textureSample(t_diffuse, s_diffuse, in.texture), in.color);
     return vec4<f32>(color[0], color[1], color[2], color[3]*alpha);
 }
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return shade(in);
+}
+
+@fragment
+fn fs_pick(in: VertexOutput) -> @location(0) u32 {
+    return pick(in.position.z, shade(in)[3]);
+}
@@ -0,0 +1,69 @@
+struct ShapeInput {
+    @location(0) uv: vec2<f32>,
+    @location(1) position: vec2<f32>,
+    @location(2) size: vec2<f32>,
+    @location(3) bounds: vec4<f32>,
+    @location(4) z: f32,
+    @location(5) stroke: vec4<f32>,
+    @location(6) radius_x: vec4<f32>,
+    @location(7) radius_y: vec4<f32>,
+    @location(8) region: vec4<f32>,
+    @location(9) color: vec4<f32>
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) size: vec2<f32>,
+    @location(2) @interpolate(flat) bounds: vec4<f32>,
+    @location(3) @interpolate(flat) stroke: vec4<f32>,
+    @location(4) @interpolate(flat) radius_x: vec4<f32>,
+    @location(5) @interpolate(flat) radius_y: vec4<f32>,
+    @location(6) @interpolate(flat) region: vec4<f32>,
+    @location(7) @interpolate(flat) color: vec4<f32>
+};
+
+@vertex
+fn vs_main(
+    shape: ShapeInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(view(shape.position), shape.z, 1.0);
+    out.uv = shape.uv;
+
+    out.size = shape.size;
+
+    out.bounds = shape.bounds;
+    out.stroke = shape.stroke;
+    out.radius_x = shape.radius_x;
+    out.radius_y = shape.radius_y;
+    out.region = shape.region;
+    out.color = shape.color;
+
+    return out;
+}
+
+@group(1) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(1) @binding(1)
+var s_diffuse: sampler;
+
+fn shade(in: VertexOutput) -> vec4<f32> {
+    if clipped(in.uv, in.bounds) {
+        discard;
+    }
+    let alpha = rounded_rectangle_alpha(in.uv, in.size, in.stroke, in.radius_x, in.radius_y);
+    let texture = in.region.xy + rounded_rectangle_stroke_texture(in.uv, in.size, in.stroke, in.radius_x, in.radius_y) * in.region.zw;
+    let color = tint(sample_stroke(t_diffuse, s_diffuse, texture, in.region), in.color);
+    return vec4<f32>(color[0], color[1], color[2], color[3]*alpha);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return shade(in);
+}
+
+@fragment
+fn fs_pick(in: VertexOutput) -> @location(0) u32 {
+    return pick(in.position.z, shade(in)[3]);
+}
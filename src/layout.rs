@@ -0,0 +1,107 @@
+//! Helpers placing areas within each other, treating the bounds of an area as the rectangle it
+//! lays out in. Edges the helpers compute are rounded to whole pixels, so areas that share an
+//! edge neither overlap nor leave a gap between them. Areas without bounds have nothing to lay out
+//! in and are returned as they are by [`Area::inset`] and the splits.
+
+use super::Area;
+
+/// Point of a parent area a [`Placement`] is aligned to, the same point of the placed area sits on it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Fractions of the width and height from the top left to the anchor.
+    fn fractions(&self) -> (f32, f32) {
+        match self {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::Top => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::Left => (0.0, 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Right => (1.0, 0.5),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::Bottom => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+/// Area of a size waiting to be placed in a parent by [`of`](Self::of), built with [`Area::sized`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Placement {
+    size: (f32, f32),
+    anchor: Anchor,
+}
+
+impl Placement {
+    pub fn anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Area of the size at its anchor within the parent, its position rounded to whole pixels.
+    /// The area keeps its own size as bounds, cut to those of the parent. A parent without
+    /// bounds is taken to be the point at its position.
+    pub fn of(self, parent: Area) -> Area {
+        let (px, py, pw, ph) = parent.1.unwrap_or((parent.0.0, parent.0.1, 0.0, 0.0));
+        let (fx, fy) = self.anchor.fractions();
+        let x = (px + (pw - self.size.0) * fx).round();
+        let y = (py + (ph - self.size.1) * fy).round();
+        let bounds = (x, y, self.size.0, self.size.1);
+        Area((x, y), Some(parent.1.map(|b| crate::canvas::intersect(bounds, b)).unwrap_or(bounds)))
+    }
+
+    /// Area of the size in the middle of the parent.
+    pub fn centered_in(self, parent: Area) -> Area {self.anchor(Anchor::Center).of(parent)}
+}
+
+impl Area {
+    /// Area covering the rectangle (x, y, width, height), bounded by it.
+    pub fn rect(x: f32, y: f32, width: f32, height: f32) -> Area {Area((x, y), Some((x, y, width, height)))}
+
+    /// Area of the size to place in a parent, at its top left unless anchored elsewhere.
+    pub fn sized(width: f32, height: f32) -> Placement {
+        Placement{size: (width, height), anchor: Anchor::default()}
+    }
+
+    /// The area moved by the offset along with its bounds.
+    pub fn offset(self, dx: f32, dy: f32) -> Area {
+        Area((self.0.0 + dx, self.0.1 + dy), self.1.map(|(x, y, w, h)| (x + dx, y + dy, w, h)))
+    }
+
+    /// The bounds shrunk by the padding on every side, no smaller than nothing, with the position
+    /// moved along with their top left.
+    pub fn inset(self, padding: f32) -> Area {
+        let Some((x, y, w, h)) = self.1 else {return self};
+        let (w2, h2) = ((w - padding * 2.0).max(0.0), (h - padding * 2.0).max(0.0));
+        let (x2, y2) = (x + (w - w2) / 2.0, y + (h - h2) / 2.0);
+        Area((self.0.0 + x2 - x, self.0.1 + y2 - y), Some((x2, y2, w2, h2)))
+    }
+
+    /// The bounds split side by side, the left part taking the fraction (0 to 1) of the width. The
+    /// edge between the parts is rounded to a whole pixel and shared by both.
+    pub fn split_horizontal(self, fraction: f32) -> (Area, Area) {
+        let Some((x, y, w, h)) = self.1 else {return (self, self)};
+        let edge = (x + w * fraction.clamp(0.0, 1.0)).round().clamp(x, x + w);
+        (Area::rect(x, y, edge - x, h), Area::rect(edge, y, x + w - edge, h))
+    }
+
+    /// The bounds split one above the other, the top part taking the fraction (0 to 1) of the
+    /// height. The edge between the parts is rounded to a whole pixel and shared by both.
+    pub fn split_vertical(self, fraction: f32) -> (Area, Area) {
+        let Some((x, y, w, h)) = self.1 else {return (self, self)};
+        let edge = (y + h * fraction.clamp(0.0, 1.0)).round().clamp(y, y + h);
+        (Area::rect(x, y, w, edge - y), Area::rect(x, edge, w, y + h - edge))
+    }
+}
@@ -1,33 +1,213 @@
-use wgpu::{DepthStencilState, MultisampleState, TextureFormat, RenderPass, Device, Queue};
+use wgpu::{DepthStencilState, MultisampleState, TextureFormat, RenderPass, Device, Queue, Adapter, Surface, SurfaceCapabilities, SurfaceConfiguration, CompositeAlphaMode, PresentMode, Limits, Features};
+
+/// Build a [`wgpu::ShaderModuleDescriptor`] from WGSL files concatenated in the given order,
+/// letting shaders share the helpers in `common.wgsl`. Paths are relative to the calling file.
+macro_rules! concat_wgsl {
+    ($($path:literal),+ $(,)?) => {
+        wgpu::ShaderModuleDescriptor {
+            label: Some(concat!($($path, " "),+)),
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(concat!($(include_str!($path), "\n"),+))),
+        }
+    };
+}
+
+/// Create a shader module. Debug builds validate the WGSL with naga first and panic with its
+/// diagnostic, as the driver otherwise compiles shaders lazily and reports errors poorly.
+pub(crate) fn create_shader(device: &Device, descriptor: wgpu::ShaderModuleDescriptor) -> wgpu::ShaderModule {
+    #[cfg(debug_assertions)]
+    if let wgpu::ShaderSource::Wgsl(source) = &descriptor.source {
+        use wgpu::naga::valid::{Capabilities, ValidationFlags, Validator};
+        let path = descriptor.label.unwrap_or("shader");
+        let module = wgpu::naga::front::wgsl::parse_str(source)
+            .unwrap_or_else(|e| panic!("{}", e.emit_to_string_with_path(source, path)));
+        Validator::new(ValidationFlags::all(), Capabilities::all()).validate(&module)
+            .unwrap_or_else(|e| panic!("{}", e.emit_to_string_with_path(source, path)));
+    }
+    device.create_shader_module(descriptor)
+}
 
 mod shape;
+mod path;
 mod color;
 mod image;
 mod text;
 mod cursor;
+mod canvas;
+mod layout;
+mod hit;
+mod pick;
+mod scale;
+mod camera;
+mod draw_list;
+mod export;
+mod error;
+mod builder;
+mod parallel;
+mod buffers;
+pub mod prelude;
 
-use color::ColorRenderer;
-use image::ImageRenderer;
+use camera::CameraUniform;
+use color::{ColorRenderer, ColorFrame};
+use image::{ImageRenderer, ImageFrame};
 use text::TextRenderer;
 
-pub use color::Color;
-pub use image::{ImageAtlas, Image};
-pub use text::{FontAtlas, Font, Text, Span, Align, Cursor, CursorAction};
+pub use canvas::{Canvas, Transform, ProgressBarStyle, BorderStyle, ShadowOptions, ArrowHead, ArrowHeadStyle, LineStyle};
+pub use path::{Path, FillRule, LineCap, LineJoin};
+pub use color::{Color, ColorParseError, Fill, Easing, PatternFill, PatternKind};
+pub use image::{ImageAtlas, ImageAtlasDescriptor, Image, RgbaImage};
+#[cfg(feature = "tokio")]
+pub use image::PreparedFrame;
+pub use text::{FontAtlas, Font, Text, Span, Decoration, Align, Cursor, CursorAction};
+pub use layout::{Anchor, Placement};
+pub use hit::hit_test;
+pub use pick::Picker;
+pub use camera::Camera;
+pub use draw_list::DrawList;
+pub use export::{render_to_image, render_to_png, render_to_png_bytes, read_texture};
+pub use error::CanvasError;
+pub use builder::{CanvasRendererBuilder, Msaa, BuildError};
+#[cfg(feature = "serde")]
+pub use draw_list::{DrawListRecord, DrawItem, DrawText, DrawSpan, ImageRef, Pixels, ReplayError};
 
-#[derive(Debug, Clone, Copy)]
+/// Position of an item with the bounds (x, y, width, height) it is cut to, if any, in pixels.
+/// Positions may be fractional, shapes are covered relative to where they are so they move
+/// smoothly between pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Area(pub (f32, f32), pub Option<(f32, f32, f32, f32)>);
 
 impl Area {
-    pub(crate) fn bounds(&self, width: f32, height: f32) -> (f32, f32, f32, f32) {
-        self.1.unwrap_or((0.0, 0.0, width, height))
+    /// The bounds of the area, without any so large that nothing drawn reaches past them as the
+    /// camera may show any part of the canvas.
+    pub(crate) fn bounds(&self) -> (f32, f32, f32, f32) {
+        self.1.unwrap_or((f32::MIN / 4.0, f32::MIN / 4.0, f32::MAX / 2.0, f32::MAX / 2.0))
     }
 }
 
-#[derive(Clone, Debug, Copy)]
+/// Stroke widths of the sides of a rectangle in the order top, right, bottom, left. A side with
+/// zero width is not drawn, a border without any width fills the shape.
+#[derive(Clone, Debug, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Border(pub f32, pub f32, pub f32, pub f32);
+
+impl Border {
+    pub fn uniform(width: f32) -> Self {Border(width, width, width, width)}
+}
+
+impl From<f32> for Border {
+    fn from(width: f32) -> Self {Border::uniform(width)}
+}
+
+/// Pattern a [`Stroke`] is drawn in along the outline of a shape.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StrokeStyle {
+    #[default]
+    Solid,
+    /// Dashes of the length with gaps between them, starting at the top left of the outline.
+    Dashed{dash: f32, gap: f32},
+    /// Round dots as wide as the stroke with their centers the spacing apart.
+    Dotted{spacing: f32},
+}
+
+/// Stroke of the width inside the outline of a shape drawn in its pattern, see
+/// [`Shape::stroked`]. Widths convert into solid strokes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stroke {
+    pub width: f32,
+    pub style: StrokeStyle,
+}
+
+impl Stroke {
+    pub fn solid(width: f32) -> Self {Stroke{width, style: StrokeStyle::Solid}}
+    pub fn dashed(width: f32, dash: f32, gap: f32) -> Self {Stroke{width, style: StrokeStyle::Dashed{dash, gap}}}
+    pub fn dotted(width: f32, spacing: f32) -> Self {Stroke{width, style: StrokeStyle::Dotted{spacing}}}
+}
+
+impl From<f32> for Stroke {
+    fn from(width: f32) -> Self {Stroke::solid(width)}
+}
+
+/// Horizontal and vertical radius of the elliptical corners of a rounded rectangle.
+#[derive(Clone, Debug, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Radius(pub f32, pub f32);
+
+impl Radius {
+    pub fn circular(radius: f32) -> Self {Radius(radius, radius)}
+}
+
+impl From<f32> for Radius {
+    fn from(radius: f32) -> Self {Radius::circular(radius)}
+}
+
+bitflags::bitflags! {
+    /// Corners of a rectangle that are rounded, the others stay square.
+    #[derive(Clone, Debug, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct CornerMask: u8 {
+        const TOP_LEFT = 1;
+        const TOP_RIGHT = 1 << 1;
+        const BOTTOM_RIGHT = 1 << 2;
+        const BOTTOM_LEFT = 1 << 3;
+        const TOP = Self::TOP_LEFT.bits() | Self::TOP_RIGHT.bits();
+        const BOTTOM = Self::BOTTOM_LEFT.bits() | Self::BOTTOM_RIGHT.bits();
+        const LEFT = Self::TOP_LEFT.bits() | Self::BOTTOM_LEFT.bits();
+        const RIGHT = Self::TOP_RIGHT.bits() | Self::BOTTOM_RIGHT.bits();
+    }
+}
+
+/// Direction a [`Shape::Chevron`] points in.
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Triangular tail of a [`Shape::SpeechBubble`] on the edge it points out of. The offset is the
+/// distance from the start of the edge (its left or top end) to the middle of the tail, which is
+/// kept within the edge.
+#[derive(Clone, Debug, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tail {
+    pub edge: Direction,
+    pub offset: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Shape {
     Ellipse(f32, (f32, f32)),
-    Rectangle(f32, (f32, f32)),
-    RoundedRectangle(f32, (f32, f32), f32),
+    Rectangle(Border, (f32, f32)),
+    RoundedRectangle(Border, (f32, f32), Radius),
+    /// Rounded rectangle that only rounds the corners in the mask.
+    RoundedRectangleSides(Border, (f32, f32), Radius, CornerMask),
+    /// Two round capped segments of the given thickness joined at a tip on the side of the
+    /// direction. Chevrons have no stroke of their own, they are always drawn filled.
+    Chevron(Direction, f32, (f32, f32)),
+    /// Rounded rectangle with a tail, drawn as a single outline. The size includes the tail, the
+    /// body is what remains after taking the height of the tail off its edge. Image strokes map
+    /// the texture across the shape rather than along its outline.
+    SpeechBubble(f32, (f32, f32), Radius, Tail),
+    /// Star with the given number of points, one pointing up, inscribed in the circle that fits
+    /// the size. The inner vertices sit at the ratio (0 to 1) of the outer radius. Clip the area
+    /// to draw part of a star, such as the half star of a rating. Image strokes map the texture
+    /// across the shape rather than along its outline.
+    Star(f32, (f32, f32), u32, f32),
+    /// Path filled or, with a non-zero width, stroked. Its size is that of its bounding box,
+    /// which may not start at the position of the area.
+    Path(f32, Path),
+    /// Sine wave of the given thickness along the width of the size, such as the squiggly
+    /// underline of a misspelled word, with a wavelength in pixels. It starts at the middle of the
+    /// left edge rising and swings as far as the height allows, so waves of the same wavelength
+    /// line up when they start at whole wavelengths. Waves are always drawn filled.
+    Wave(f32, (f32, f32), f32),
 }
 
 impl Shape {
@@ -36,21 +216,305 @@ impl Shape {
             Shape::Ellipse(_, size) => *size,
             Shape::Rectangle(_, size) => *size,
             Shape::RoundedRectangle(_, size, _) => *size,
+            Shape::RoundedRectangleSides(_, size, _, _) => *size,
+            Shape::Chevron(_, _, size) => *size,
+            Shape::SpeechBubble(_, size, _, _) => *size,
+            Shape::Star(_, size, _, _) => *size,
+            Shape::Path(stroke, path) => {
+                let bounds = path.bounds(*stroke);
+                (bounds.2, bounds.3)
+            },
+            Shape::Wave(_, size, _) => *size,
+        }
+    }
+
+    pub fn stroke(&self) -> Border {
+        match self {
+            Shape::Ellipse(stroke, _) => Border::uniform(*stroke),
+            Shape::Rectangle(stroke, _) => *stroke,
+            Shape::RoundedRectangle(stroke, _, _) => *stroke,
+            Shape::RoundedRectangleSides(stroke, _, _, _) => *stroke,
+            Shape::Chevron(_, _, _) => Border::default(),
+            Shape::SpeechBubble(stroke, _, _, _) => Border::uniform(*stroke),
+            Shape::Star(stroke, _, _, _) => Border::uniform(*stroke),
+            Shape::Path(stroke, _) => Border::uniform(*stroke),
+            Shape::Wave(_, _, _) => Border::default(),
+        }
+    }
+
+    /// The shape stroked with the uniform width, zero fills it. Chevrons and waves have no stroke
+    /// and are returned unchanged.
+    pub fn with_stroke(self, width: f32) -> Shape {
+        match self {
+            Shape::Ellipse(_, size) => Shape::Ellipse(width, size),
+            Shape::Rectangle(_, size) => Shape::Rectangle(width.into(), size),
+            Shape::RoundedRectangle(_, size, radius) => Shape::RoundedRectangle(width.into(), size, radius),
+            Shape::RoundedRectangleSides(_, size, radius, corners) => Shape::RoundedRectangleSides(width.into(), size, radius, corners),
+            Shape::SpeechBubble(_, size, radius, tail) => Shape::SpeechBubble(width, size, radius, tail),
+            Shape::Star(_, size, points, inner_ratio) => Shape::Star(width, size, points, inner_ratio),
+            Shape::Path(_, path) => Shape::Path(width, path),
+            shape @ (Shape::Chevron(..) | Shape::Wave(..)) => shape,
+        }
+    }
+
+    /// The shape drawn with the stroke, a zero width fills it. Dashed and dotted strokes of
+    /// ellipses, rectangles and rounded rectangles become a [`Shape::Path`] of the pieces of the
+    /// stroke, whose gradients and images are mapped across the pieces rather than the shape,
+    /// paths are cut into pieces along themselves. Dashes are measured in pixels along the
+    /// outline, around ellipses they are stretched to repeat a whole number of times. Other
+    /// shapes have no outline to lay the pattern along and take a solid stroke.
+    pub fn stroked(self, stroke: Stroke) -> Shape {
+        let (dash, gap, cap) = match stroke.style {
+            StrokeStyle::Dashed{dash, gap} => (dash, gap, LineCap::Butt),
+            StrokeStyle::Dotted{spacing} => (0.0, spacing, LineCap::Round),
+            StrokeStyle::Solid => return self.with_stroke(stroke.width),
+        };
+        let (dash, gap) = match self {
+            // No dash is cut short where the outline closes, the perimeter is estimated by Ramanujan.
+            Shape::Ellipse(_, size) if dash + gap > 0.0 => {
+                let half = (stroke.width / 2.0).min(size.0 / 2.0).min(size.1 / 2.0);
+                let (a, b) = (size.0 / 2.0 - half, size.1 / 2.0 - half);
+                let perimeter = std::f32::consts::PI * (3.0 * (a + b) - ((3.0 * a + b) * (a + 3.0 * b)).sqrt());
+                let period = dash + gap;
+                let scale = perimeter / (perimeter / period).round().max(1.0) / period;
+                (dash * scale, gap * scale)
+            },
+            _ => (dash, gap),
+        };
+        match self.centerline(stroke.width) {
+            Some(path) if stroke.width > 0.0 => Shape::Path(stroke.width, path.line_cap(cap).dashed(dash, gap)),
+            _ => self.with_stroke(stroke.width),
         }
     }
+
+    /// Closed path along the middle of a stroke of the width, clockwise from the top left, or the
+    /// path itself which strokes are centered on.
+    fn centerline(&self, width: f32) -> Option<Path> {
+        let size = self.size();
+        let (rx, ry) = match self {
+            Shape::Ellipse(..) => ([size.0 / 2.0; 4], [size.1 / 2.0; 4]),
+            Shape::Rectangle(..) => ([0.0; 4], [0.0; 4]),
+            Shape::RoundedRectangle(_, _, radius) => shape::corner_radii(size, *radius, CornerMask::all()),
+            Shape::RoundedRectangleSides(_, _, radius, corners) => shape::corner_radii(size, *radius, *corners),
+            Shape::Path(_, path) => return Some(path.clone()),
+            _ => return None,
+        };
+        let half = (width / 2.0).min(size.0 / 2.0).min(size.1 / 2.0);
+        let (rx, ry) = (rx.map(|r| (r - half).max(0.0)), ry.map(|r| (r - half).max(0.0)));
+        let (x, y, x2, y2) = (half, half, size.0 - half, size.1 - half);
+        // Control points of quarter ellipses from a towards the corner c and on to b.
+        let k = 0.552_284_8;
+        let arc = |path: Path, a: (f32, f32), c: (f32, f32), b: (f32, f32)| path.cubic_to(
+            (a.0 + (c.0 - a.0) * k, a.1 + (c.1 - a.1) * k), (b.0 + (c.0 - b.0) * k, b.1 + (c.1 - b.1) * k), b
+        );
+        let path = Path::new().move_to((x + rx[0], y)).line_to((x2 - rx[1], y));
+        let path = arc(path, (x2 - rx[1], y), (x2, y), (x2, y + ry[1])).line_to((x2, y2 - ry[2]));
+        let path = arc(path, (x2, y2 - ry[2]), (x2, y2), (x2 - rx[2], y2)).line_to((x + rx[3], y2));
+        let path = arc(path, (x + rx[3], y2), (x, y2), (x, y2 - ry[3])).line_to((x, y + ry[0]));
+        Some(arc(path, (x, y + ry[0]), (x, y), (x + rx[0], y)).close())
+    }
+
+    /// Smallest rectangle (x, y, width, height) around what is drawn of the shape in the area,
+    /// including its stroke and cut to the bounds of the area. Stars are bound by their points.
+    pub fn bounds(&self, area: &Area) -> (f32, f32, f32, f32) {
+        let (x, y) = area.0;
+        let (w, h) = self.size();
+        let bounds = match self {
+            Shape::Path(stroke, path) => {
+                let (px, py, pw, ph) = path.bounds(*stroke);
+                (x + px, y + py, pw, ph)
+            },
+            Shape::Star(_, size, points, inner_ratio) => {
+                let vertices = shape::star(*size, *points, *inner_ratio).into_iter().map(|p| (x + p.0, y + p.1));
+                let (x, y, x2, y2) = vertices.fold(
+                    (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+                    |(x, y, x2, y2), p| (x.min(p.0), y.min(p.1), x2.max(p.0), y2.max(p.1))
+                );
+                (x, y, x2 - x, y2 - y)
+            },
+            _ => (x, y, w, h),
+        };
+        match area.1 {
+            Some(clip) => canvas::intersect(bounds, clip),
+            None => bounds,
+        }
+    }
+
+    /// Ring of `width` around the shape with a gap of `offset` to its edge. Rounded corners grow
+    /// with the ring so it stays concentric, square corners stay square. Chevrons, paths and
+    /// waves are outlined along their bounding box.
+    pub(crate) fn outline(&self, width: f32, offset: f32) -> Shape {
+        let grow = width + offset;
+        let size = self.size();
+        let size = (size.0 + grow * 2.0, size.1 + grow * 2.0);
+        let radius = |r: &Radius| Radius(
+            if r.0 > 0.0 {r.0 + grow} else {0.0},
+            if r.1 > 0.0 {r.1 + grow} else {0.0}
+        );
+        match self {
+            Shape::Ellipse(_, _) => Shape::Ellipse(width, size),
+            Shape::Rectangle(_, _) => Shape::Rectangle(width.into(), size),
+            Shape::RoundedRectangle(_, _, r) => Shape::RoundedRectangle(width.into(), size, radius(r)),
+            Shape::RoundedRectangleSides(_, _, r, corners) => Shape::RoundedRectangleSides(width.into(), size, radius(r), *corners),
+            Shape::Chevron(_, _, _) | Shape::Wave(_, _, _) => Shape::Rectangle(width.into(), size),
+            Shape::SpeechBubble(_, _, r, tail) => Shape::SpeechBubble(width, size, radius(r), Tail{
+                offset: tail.offset + grow,
+                width: tail.width + grow * 2.0,
+                ..*tail
+            }),
+            Shape::Star(_, _, points, inner_ratio) => Shape::Star(width, size, *points, *inner_ratio),
+            Shape::Path(stroke, path) => {
+                let (x, y, w, h) = path.bounds(*stroke);
+                let (x, y) = (x + width / 2.0, y + width / 2.0);
+                let (x2, y2) = (x + w + offset * 2.0 + width, y + h + offset * 2.0 + width);
+                Shape::Path(width, Path::new().move_to((x, y)).line_to((x2, y)).line_to((x2, y2)).line_to((x, y2)).close())
+            },
+        }
+    }
+}
+
+/// Outline of a [`Shape`] without its stroke, for APIs that take the stroke separately. Pair it
+/// with a stroke width to get the shape, zero fills it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ShapeKind {
+    Ellipse{size: (f32, f32)},
+    Rectangle{size: (f32, f32)},
+    RoundedRectangle{size: (f32, f32), radius: f32},
+}
+
+impl ShapeKind {
+    pub fn size(&self) -> (f32, f32) {
+        match self {
+            ShapeKind::Ellipse{size} | ShapeKind::Rectangle{size} | ShapeKind::RoundedRectangle{size, ..} => *size,
+        }
+    }
+}
+
+impl From<(ShapeKind, f32)> for Shape {
+    fn from((kind, stroke): (ShapeKind, f32)) -> Self {
+        match kind {
+            ShapeKind::Ellipse{size} => Shape::Ellipse(stroke, size),
+            ShapeKind::Rectangle{size} => Shape::Rectangle(stroke.into(), size),
+            ShapeKind::RoundedRectangle{size, radius} => Shape::RoundedRectangle(stroke.into(), size, radius.into()),
+        }
+    }
+}
+
+impl From<ShapeKind> for Shape {
+    fn from(kind: ShapeKind) -> Self {(kind, 0.0).into()}
+}
+
+/// Circular progress indicator, a track around the whole circle with an arc on top that covers
+/// `progress` (0 to 1) of it clockwise from `start_angle` (radians, zero pointing right). The arc
+/// has round caps and is hidden entirely at zero progress.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProgressRing {
+    pub size: f32,
+    pub thickness: f32,
+    pub progress: f32,
+    pub start_angle: f32,
+    pub color: Color,
+    pub track: Color,
+}
+
+/// Where the lines of [`GridLines`] run along one axis, relative to the start of the grid.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GridSpacing {
+    /// A line every given number of pixels from the start up to and including the end.
+    Every(f32),
+    /// A line at each of the positions.
+    At(Vec<f32>),
+}
+
+/// Horizontal lines at the rows and vertical lines at the columns across a grid of the size, such
+/// as the rules of a table. Lines are snapped to whole pixels so thin lines stay sharp, lines at the
+/// ends are kept inside the grid and lines that snap to the same pixel are drawn once. Translucent
+/// rows and columns blend twice where they cross.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridLines {
+    pub size: (f32, f32),
+    pub rows: GridSpacing,
+    pub columns: GridSpacing,
+    pub thickness: f32,
+    pub color: Color,
+}
+
+impl GridLines {
+    /// Position and size of the rectangle of every line when drawn at the position.
+    fn rectangles(&self, (x, y): (f32, f32)) -> Vec<((f32, f32), (f32, f32))> {
+        let width = self.thickness.round().max(1.0);
+        let (x, y, x2, y2) = (x.round(), y.round(), (x + self.size.0).round(), (y + self.size.1).round());
+        let starts = |spacing: &GridSpacing, start: f32, end: f32| {
+            let positions = match spacing {
+                GridSpacing::Every(step) if *step > 0.0 =>
+                    (0..).map(|i| i as f32 * step).take_while(|p| *p <= end - start).collect(),
+                GridSpacing::Every(_) => vec![],
+                GridSpacing::At(positions) => positions.clone(),
+            };
+            let mut starts = positions.into_iter()
+                .map(|p| (start + p - width / 2.0).round().clamp(start, (end - width).max(start)))
+                .collect::<Vec<_>>();
+            starts.sort_by(f32::total_cmp);
+            starts.dedup();
+            starts
+        };
+        let rows = starts(&self.rows, y, y2).into_iter().map(|row| ((x, row), (x2 - x, width)));
+        let columns = starts(&self.columns, x, x2).into_iter().map(|column| ((column, y), (width, y2 - y)));
+        rows.chain(columns).collect()
+    }
 }
 
 #[derive(Clone, Debug)]
 pub enum CanvasItem {
     Shape(Shape, Color),
+    /// Shape painted with a gradient or pattern, stroked shapes carry it along their stroke.
+    Fill(Shape, Fill),
     Image(Shape, Image, Option<Color>),
+    /// Stroke of the shape painted with an image, the texture wraps once around the outline
+    /// starting at the top left. The inside of the shape is left transparent.
+    ImageStroke(Shape, Image, Option<Color>),
+    /// Rectangle of the first size covered by the image repeated from its top left, each time
+    /// stretched to the second size.
+    TiledImage(Image, (f32, f32), (f32, f32)),
+    /// Outline of the given width drawn outside of the shape, separated from it by the offset.
+    /// The area is that of the shape itself. Widths that are not positive are reported as
+    /// [`CanvasError::InvalidInput`].
+    Outline(Shape, Color, f32, f32),
+    ProgressRing(ProgressRing),
+    GridLines(GridLines),
     Text(Text),
 }
 
+/// Version of a draw list with the frame size, scale factor and render scale, and camera it was
+/// prepared for.
+type Prepared = (u64, (f32, f32), (f32, f32), Camera);
+
 pub struct CanvasRenderer {
     color_renderer: ColorRenderer,
     image_renderer: ImageRenderer,
-    text_renderer: TextRenderer
+    text_renderer: TextRenderer,
+    camera_uniform: CameraUniform,
+    camera: Camera,
+    size: (f32, f32),
+    scale_factor: f32,
+    render_scale: f32,
+    /// Version of the draw list last prepared with what it was prepared for.
+    prepared: Option<Prepared>,
+    /// Format, multisampling and depth of the render target, kept to create the pipelines again.
+    target: (TextureFormat, MultisampleState, Option<DepthStencilState>),
+    clear_color: Color,
+    /// Limits and features of the device the renderer was created on.
+    device: (Limits, Features),
+    /// Whether the last preparation wrote no vertices nor indices.
+    unchanged: bool,
+    /// Frame last built by [`prepare`](Self::prepare), the next one is built in its storage.
+    frame: Option<FrameData>,
+    /// Lists the items of the last frame were sorted into, kept for their capacity.
+    lists: SplitItems,
 }
 
 impl CanvasRenderer {
@@ -62,18 +526,146 @@ impl CanvasRenderer {
         multisample: MultisampleState,
         depth_stencil: Option<DepthStencilState>,
     ) -> Self {
+        let camera_uniform = CameraUniform::new(device);
         CanvasRenderer{
-            color_renderer: ColorRenderer::new(device, texture_format, multisample, depth_stencil.clone()),
-            image_renderer: ImageRenderer::new(device, texture_format, multisample, depth_stencil.clone()),
-            text_renderer: TextRenderer::new(device, queue, texture_format, multisample, depth_stencil),
+            color_renderer: ColorRenderer::new(device, texture_format, multisample, depth_stencil.clone(), &camera_uniform),
+            image_renderer: ImageRenderer::new(device, texture_format, multisample, depth_stencil.clone(), &camera_uniform),
+            text_renderer: TextRenderer::new(device, queue, texture_format, multisample, depth_stencil.clone()),
+            camera_uniform,
+            camera: Camera::default(),
+            size: (1.0, 1.0),
+            scale_factor: 1.0,
+            render_scale: 1.0,
+            prepared: None,
+            target: (*texture_format, multisample, depth_stencil),
+            clear_color: Color::TRANSPARENT,
+            device: (device.limits(), device.features()),
+            unchanged: false,
+            frame: None,
+            lists: Default::default(),
         }
     }
 
+    /// Create a renderer for the surface and configure the surface for frames of the size as
+    /// chosen by [`surface_configuration`](Self::surface_configuration). The configuration is
+    /// returned to configure the surface again when it is resized. Fails when the surface cannot
+    /// be presented by the adapter.
+    pub fn new_from_surface(
+        device: &Device,
+        queue: &Queue,
+        surface: &Surface,
+        adapter: &Adapter,
+        width: u32,
+        height: u32,
+    ) -> Result<(Self, SurfaceConfiguration), CanvasError> {
+        let config = CanvasRenderer::surface_configuration(&surface.get_capabilities(adapter), width, height)
+            .ok_or_else(|| CanvasError::Unsupported("presenting the surface with the adapter".to_string()))?;
+        surface.configure(device, &config);
+        let renderer = CanvasRenderer::new(queue, device, &config.format, MultisampleState::default(), None);
+        Ok((renderer, config))
+    }
+
+    /// Configuration of a surface with the capabilities for frames of the size, none when it has
+    /// no formats. Prefers an sRGB format, as colors are blended in linear space, premultiplied
+    /// alpha, which is what blending over a transparent frame gives, so frames cleared to a
+    /// transparent color show what is behind the window, and mailbox presentation, which does not
+    /// tear nor wait for the display. Falls back to the first format, the alpha mode the surface
+    /// picks and vsync.
+    pub fn surface_configuration(capabilities: &SurfaceCapabilities, width: u32, height: u32) -> Option<SurfaceConfiguration> {
+        let format = capabilities.formats.iter().find(|format| format.is_srgb()).or(capabilities.formats.first())?;
+        Some(SurfaceConfiguration{
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: *format,
+            width: width.max(1),
+            height: height.max(1),
+            present_mode: match capabilities.present_modes.contains(&PresentMode::Mailbox) {
+                true => PresentMode::Mailbox,
+                false => PresentMode::Fifo,
+            },
+            desired_maximum_frame_latency: 2,
+            alpha_mode: match capabilities.alpha_modes.contains(&CompositeAlphaMode::PreMultiplied) {
+                true => CompositeAlphaMode::PreMultiplied,
+                false => CompositeAlphaMode::Auto,
+            },
+            view_formats: vec![],
+        })
+    }
+
+    /// Create every pipeline, buffer and atlas of the renderer again on the device, such as a new
+    /// one after the device was lost when the GPU reset, keeping the camera, scales and the
+    /// settings of the [`CanvasRendererBuilder`].
+    /// Nothing is drawn until the next [`prepare`](Self::prepare), call
+    /// [`ImageAtlas::recreate`] before it so images are uploaded to the device again.
+    pub fn recreate(&mut self, queue: &Queue, device: &Device) {
+        let (texture_format, multisample, depth_stencil) = self.target.clone();
+        let atlas_size = self.image_renderer.atlas_size();
+        *self = CanvasRenderer{
+            camera: self.camera,
+            scale_factor: self.scale_factor,
+            render_scale: self.render_scale,
+            clear_color: self.clear_color,
+            ..CanvasRenderer::new(queue, device, &texture_format, multisample, depth_stencil)
+        };
+        self.image_renderer.set_atlas_size(atlas_size);
+    }
+
+    /// Color to clear the render target to before [`render`](Self::render), as set by
+    /// [`CanvasRendererBuilder::clear_color`], converted for the format of the target.
+    pub fn clear_color(&self) -> wgpu::Color {
+        let Color{r, g, b, a} = self.clear_color;
+        let linear = |c: u8| match c as f64 / 255.0 {
+            c if !self.target.0.is_srgb() => c,
+            c if c <= 0.04045 => c / 12.92,
+            c => ((c + 0.055) / 1.055).powf(2.4),
+        };
+        wgpu::Color{r: linear(r), g: linear(g), b: linear(b), a: a as f64 / 255.0}
+    }
+
+    /// Move the camera over the prepared items without preparing them again, only texts are
+    /// placed anew. Items are left out by [`prepare`](Self::prepare) when they lie outside of
+    /// the view of the camera at that time, prepare again once the camera reveals more of the
+    /// canvas than it showed then. Fails without drawing texts when their glyphs do not fit the
+    /// text atlas.
+    pub fn set_camera(&mut self, device: &Device, queue: &Queue, font_atlas: &mut FontAtlas, camera: Camera) -> Result<(), CanvasError> {
+        self.camera = camera;
+        self.camera_uniform.write(queue, camera, self.size.0, self.size.1, self.scale_factor);
+        self.text_renderer.place(device, queue, font_atlas, camera)
+    }
+
+    pub fn camera(&self) -> Camera {self.camera}
+
+    /// Limits of the device the renderer was created or last recreated on.
+    pub fn device_limits(&self) -> &Limits {&self.device.0}
+
+    /// Features of the device the renderer was created or last recreated on.
+    pub fn device_features(&self) -> Features {self.device.1}
+
+    /// Physical pixels per logical pixel, such as 2 on a high density display. Items are given in
+    /// logical pixels and drawn at the physical size of the frame passed to
+    /// [`prepare`](Self::prepare), so strokes and edges stay sharp at any scale. Defaults to 1.
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.scale_factor = scale_factor;
+    }
+
+    pub fn scale_factor(&self) -> f32 {self.scale_factor}
+
+    /// Scale of every item after the scale factor, such as the zoom of a map or a minimap of a
+    /// canvas. Unlike the zoom of the [`Camera`], it takes a [`prepare`](Self::prepare) to apply,
+    /// and the camera offset is not scaled by it. Defaults to 1.
+    pub fn set_render_scale(&mut self, render_scale: f32) {
+        self.render_scale = render_scale;
+    }
+
+    pub fn render_scale(&self) -> f32 {self.render_scale}
+
     /// Prepare for rendering this frame; create all resources that will be
     /// used during the next render that do not already exist.
     ///
     /// Items are given a z_index based on the order in which they are presented. First item in the
     /// vector will be printed in the back of the stack(z = u16::MAX-1)
+    ///
+    /// Items that cannot be drawn, such as images larger than the device allows, are left out and
+    /// returned as errors in the order of the items while the others are drawn.
     #[allow(clippy::too_many_arguments)]
     pub fn prepare(
         &mut self,
@@ -84,20 +676,146 @@ impl CanvasRenderer {
         image_atlas: &mut ImageAtlas,
         font_atlas: &mut FontAtlas,
         items: Vec<(Area, CanvasItem)>,
-    ) {
-        let (colors, images, texts) = items.into_iter().enumerate().fold((vec![], vec![], vec![]), |mut a, (i, (area, item))| {
-            let z = i as u16;
-            match item {
-                CanvasItem::Shape(shape, color) => a.0.push((z, area, shape, color)),
-                CanvasItem::Image(shape, image, color) => a.1.push((z, area, shape, image, color)),
-                CanvasItem::Text(text) => a.2.push((z, area, text)),
-            }
-            a
-        });
-
-        self.color_renderer.prepare(device, queue, width, height, colors);
-        self.image_renderer.prepare(device, queue, width, height, image_atlas, images);
-        self.text_renderer.prepare(device, queue, width, height, font_atlas, texts);
+    ) -> Vec<CanvasError> {
+        let mut frame = self.frame.take().unwrap_or_else(FrameData::empty);
+        frame.build(width, height, self.scale_factor, self.render_scale, self.camera, items, &mut self.lists);
+        let errors = self.upload_from(device, queue, image_atlas, font_atlas, &mut frame);
+        self.frame = Some(frame);
+        errors
+    }
+
+    /// The first half of [`prepare`](Self::prepare): the vertices of the items of a frame of the
+    /// size as seen through the camera at the scale factor, built without the GPU so it can run
+    /// on any thread, such as the one laying out the items, or a frame ahead. Items are scaled by
+    /// the render scale after the scale factor, such as the renderer's
+    /// [`render_scale`](Self::render_scale). The camera offset is in logical pixels of the frame,
+    /// so the view it shows is that of the scale factor alone.
+    pub fn build_frame(width: f32, height: f32, scale_factor: f32, render_scale: f32, camera: Camera, items: Vec<(Area, CanvasItem)>) -> FrameData {
+        let mut frame = FrameData::empty();
+        frame.build(width, height, scale_factor, render_scale, camera, items, &mut Default::default());
+        frame
+    }
+
+    /// The second half of [`prepare`](Self::prepare): bind new images, lay out the texts and
+    /// upload the vertices of a frame from [`build_frame`](Self::build_frame) for the next
+    /// render. The camera, scale factor and render scale of the frame become those of the
+    /// renderer.
+    pub fn upload(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        image_atlas: &mut ImageAtlas,
+        font_atlas: &mut FontAtlas,
+        mut frame: FrameData,
+    ) -> Vec<CanvasError> {
+        self.upload_from(device, queue, image_atlas, font_atlas, &mut frame)
+    }
+
+    /// [`upload`](Self::upload) of a frame left to be built over again, its texts and errors
+    /// taken.
+    fn upload_from(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        image_atlas: &mut ImageAtlas,
+        font_atlas: &mut FontAtlas,
+        frame: &mut FrameData,
+    ) -> Vec<CanvasError> {
+        let FrameData{size: (width, height), scale_factor, render_scale, camera, ..} = *frame;
+        let mut errors = std::mem::take(&mut frame.errors);
+        let texts = std::mem::take(&mut frame.texts);
+        (self.camera, self.scale_factor, self.render_scale) = (camera, scale_factor, render_scale);
+        self.view(queue, width, height);
+        decorate(&mut frame.colors, font_atlas, &texts, (width, height), scale_factor * render_scale, &mut errors);
+        let colors_changed = self.color_renderer.upload(device, queue, &frame.colors);
+        let (images_changed, image_errors) = self.image_renderer.upload(device, queue, image_atlas, &frame.images);
+        self.unchanged = !colors_changed && !images_changed;
+        errors.extend(image_errors);
+        errors.extend(self.text_renderer.prepare(device, queue, width, height, scale_factor, render_scale, camera, font_atlas, texts).err());
+        errors.sort_by_key(CanvasError::item);
+        errors
+    }
+
+    /// Whether the last [`prepare`](Self::prepare) or [`upload`](Self::upload) found the vertices
+    /// and indices of every pipeline as it last wrote them and left the buffers as they were, such
+    /// as for the frames of a still interface. Texts are laid out and placed every time. Always
+    /// false after [`prepare_async`](Self::prepare_async), which uploads images to new buffers.
+    pub fn frame_unchanged(&self) -> bool {self.unchanged}
+
+    /// Replace the vertex and index buffers of every pipeline by ones that just fit the last
+    /// frame, such as once idle after a frame of many items. Buffers also shrink by themselves
+    /// after a few seconds of frames using much less of them.
+    pub fn shrink_to_fit(&mut self, device: &Device, queue: &Queue) {
+        self.color_renderer.shrink_to_fit(device, queue);
+        self.image_renderer.shrink_to_fit(device, queue);
+    }
+
+    /// Bytes of the vertex and index buffers of every pipeline, the frames of
+    /// [`prepare_async`](Self::prepare_async) aside.
+    pub fn buffer_capacity(&self) -> u64 {
+        self.color_renderer.buffer_capacity() + self.image_renderer.buffer_capacity()
+    }
+
+    /// Like [`prepare`](Self::prepare) but nothing is prepared or uploaded again when the list
+    /// was the last one prepared, at the same version, frame size, scales and camera. Errors
+    /// are returned by the preparation that runs into them only.
+    #[allow(clippy::too_many_arguments)]
+    pub fn prepare_draw_list(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        width: f32,
+        height: f32,
+        image_atlas: &mut ImageAtlas,
+        font_atlas: &mut FontAtlas,
+        list: &DrawList,
+    ) -> Vec<CanvasError> {
+        let prepared = Some((list.version(), (width, height), (self.scale_factor, self.render_scale), self.camera));
+        if self.prepared == prepared {return vec![];}
+        let errors = self.prepare(device, queue, width, height, image_atlas, font_atlas, list.items().to_vec());
+        self.prepared = prepared;
+        errors
+    }
+
+    /// Like [`prepare`](Self::prepare) but the frame is built by [`build_frame`](Self::build_frame)
+    /// on the blocking thread pool of tokio, and the vertices of images are uploaded to buffers of
+    /// their own rather than those of the renderer. The returned frame is drawn with
+    /// [`render_frame`](Self::render_frame), alongside the items left out of it.
+    #[cfg(feature = "tokio")]
+    #[allow(clippy::too_many_arguments)]
+    pub async fn prepare_async(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        width: f32,
+        height: f32,
+        image_atlas: &mut ImageAtlas,
+        font_atlas: &mut FontAtlas,
+        items: Vec<(Area, CanvasItem)>,
+    ) -> (PreparedFrame, Vec<CanvasError>) {
+        let (scale_factor, render_scale, camera) = (self.scale_factor, self.render_scale, self.camera);
+        let frame = tokio::task::spawn_blocking(move || CanvasRenderer::build_frame(width, height, scale_factor, render_scale, camera, items)).await;
+        let FrameData{mut colors, images, texts, mut errors, ..} = match frame {
+            Ok(frame) => frame,
+            Err(error) => std::panic::resume_unwind(error.into_panic()),
+        };
+        self.view(queue, width, height);
+        decorate(&mut colors, font_atlas, &texts, (width, height), scale_factor * render_scale, &mut errors);
+        self.color_renderer.upload(device, queue, &colors);
+        self.unchanged = false;
+        errors.extend(self.text_renderer.prepare(device, queue, width, height, scale_factor, render_scale, camera, font_atlas, texts).err());
+        let (frame, image_errors) = self.image_renderer.upload_frame(device, queue, image_atlas, images);
+        errors.extend(image_errors);
+        errors.sort_by_key(CanvasError::item);
+        (frame, errors)
+    }
+
+    /// Write the camera for frames of the size and return what it shows of the canvas.
+    fn view(&mut self, queue: &Queue, width: f32, height: f32) -> (f32, f32, f32, f32) {
+        self.prepared = None;
+        self.size = (width, height);
+        self.camera_uniform.write(queue, self.camera, width, height, self.scale_factor);
+        self.camera.view(width, height, self.scale_factor)
     }
 
     /// Render using caller provided render pass.
@@ -106,4 +824,145 @@ impl CanvasRenderer {
         self.image_renderer.render(render_pass);
         self.text_renderer.render(render_pass);
     }
+
+    /// Render a frame from [`prepare_async`](Self::prepare_async) using caller provided render pass.
+    #[cfg(feature = "tokio")]
+    pub fn render_frame<'a>(&'a self, frame: &'a PreparedFrame, render_pass: &mut RenderPass<'a>) {
+        self.color_renderer.render(render_pass);
+        self.image_renderer.render_frame(frame, render_pass);
+        self.text_renderer.render(render_pass);
+    }
+
+    /// Render the ids of the items of the most recently prepared frame for a [`Picker`], as
+    /// the draw of [`Picker::pick`].
+    pub fn render_picking(&self, render_pass: &mut RenderPass<'_>) {
+        self.color_renderer.render_picking(render_pass);
+        self.image_renderer.render_picking(render_pass);
+    }
+
+    /// Render the ids of the items of a frame from [`prepare_async`](Self::prepare_async) for a
+    /// [`Picker`], as the draw of [`Picker::pick`].
+    #[cfg(feature = "tokio")]
+    pub fn render_frame_picking(&self, frame: &PreparedFrame, render_pass: &mut RenderPass<'_>) {
+        self.color_renderer.render_picking(render_pass);
+        self.image_renderer.render_frame_picking(frame, render_pass);
+    }
+}
+
+/// Vertices of the items of a frame built by [`CanvasRenderer::build_frame`], to upload with
+/// [`CanvasRenderer::upload`]. Texts are laid out when uploaded.
+#[derive(PartialEq)]
+pub struct FrameData {
+    size: (f32, f32),
+    scale_factor: f32,
+    render_scale: f32,
+    camera: Camera,
+    colors: ColorFrame,
+    images: ImageFrame,
+    texts: TextItems,
+    errors: Vec<CanvasError>,
+}
+
+impl FrameData {
+    /// Frame of no items, to build frames in.
+    fn empty() -> Self {
+        FrameData{
+            size: (0.0, 0.0), scale_factor: 1.0, render_scale: 1.0, camera: Camera::default(), colors: ColorFrame::default(),
+            images: ImageFrame::default(), texts: vec![], errors: vec![],
+        }
+    }
+
+    /// Build the frame of the items over this one, reusing the storage of its vertices and of the
+    /// lists the items are sorted into.
+    #[allow(clippy::too_many_arguments)]
+    fn build(&mut self, width: f32, height: f32, scale_factor: f32, render_scale: f32, camera: Camera, items: Vec<(Area, CanvasItem)>, lists: &mut SplitItems) {
+        self.errors.clear();
+        split(scaled(items, scale_factor * render_scale), camera.view(width, height, scale_factor), lists, &mut self.errors);
+        let (colors, images, strokes, tiles, texts, rings) = lists;
+        ColorRenderer::build(width, height, colors, rings, &mut self.colors, &mut self.errors);
+        ImageRenderer::build(width, height, images, strokes, tiles, &mut self.images, &mut self.errors);
+        self.texts = std::mem::take(texts);
+        (self.size, self.scale_factor, self.render_scale, self.camera) = ((width, height), scale_factor, render_scale, camera);
+    }
+}
+
+type ColorItems = Vec<(u16, Area, Shape, Fill)>;
+type ImageItems = Vec<(u16, Area, Shape, Image, Option<Color>)>;
+type TileItems = Vec<(u16, Area, Image, (f32, f32), (f32, f32))>;
+type TextItems = Vec<(u16, Area, Text)>;
+type RingItems = Vec<(u16, Area, ProgressRing)>;
+
+type SplitItems = (ColorItems, ImageItems, ImageItems, TileItems, TextItems, RingItems);
+
+/// Sort the items by renderer into the lists, giving each the z_index of its position in the list. Items that
+/// lie entirely outside of the view (x, y, width, height) are left out, keeping the z_index of
+/// the others, as are items that cannot be drawn, which are returned as errors.
+fn split(items: Vec<(Area, CanvasItem)>, view: (f32, f32, f32, f32), a: &mut SplitItems, errors: &mut Vec<CanvasError>) {
+    for (i, (area, item)) in items.into_iter().enumerate() {
+        let Ok(z) = u16::try_from(i) else {
+            errors.push(CanvasError::InvalidInput{item: i, reason: format!("only the first {} items of a frame fit its depth range", u16::MAX as usize + 1)});
+            continue;
+        };
+        if !visible(&area, &item, view) {continue;}
+        match item {
+            CanvasItem::Shape(shape, color) => a.0.push((z, area, shape, color.into())),
+            CanvasItem::Fill(shape, fill) => a.0.push((z, area, shape, fill)),
+            CanvasItem::Image(shape, image, color) => a.1.push((z, area, shape, image, color)),
+            CanvasItem::ImageStroke(shape, image, color) => a.2.push((z, area, shape, image, color)),
+            CanvasItem::TiledImage(image, size, tile_size) => a.3.push((z, area, image, size, tile_size)),
+            CanvasItem::Outline(_, _, width, _) if width <= 0.0 => errors.push(CanvasError::InvalidInput{item: i, reason: format!("outline width of {width} is not positive")}),
+            CanvasItem::Outline(shape, color, width, offset) => {
+                let grow = width + offset;
+                let area = Area((area.0.0 - grow, area.0.1 - grow), area.1);
+                a.0.push((z, area, shape.outline(width, offset), color.into()))
+            },
+            CanvasItem::ProgressRing(ring) => a.5.push((z, area, ring)),
+            CanvasItem::GridLines(grid) => a.0.extend(grid.rectangles(area.0).into_iter().map(|(position, size)|
+                (z, Area(position, area.1), Shape::Rectangle(Border::default(), size), grid.color.into())
+            )),
+            CanvasItem::Text(text) if text.spans.is_empty() => errors.push(CanvasError::InvalidInput{item: i, reason: "text has no spans".to_string()}),
+            CanvasItem::Text(text) => a.4.push((z, area, text)),
+        }
+    }
+}
+
+/// Whether any of what the item draws in the area can lie within the view. Texts are always
+/// kept, their size is only known once laid out.
+fn visible(area: &Area, item: &CanvasItem, view: (f32, f32, f32, f32)) -> bool {
+    let rectangle = |size: (f32, f32)| Shape::Rectangle(Border::default(), size).bounds(area);
+    let (_, _, w, h) = canvas::intersect(view, match item {
+        CanvasItem::Shape(shape, _) | CanvasItem::Fill(shape, _) | CanvasItem::Image(shape, _, _) |
+        CanvasItem::ImageStroke(shape, _, _) => shape.bounds(area),
+        CanvasItem::TiledImage(_, size, _) => rectangle(*size),
+        CanvasItem::Outline(shape, _, width, offset) => {
+            let grow = width + offset;
+            shape.outline(*width, *offset).bounds(&Area((area.0.0 - grow, area.0.1 - grow), area.1))
+        },
+        CanvasItem::ProgressRing(ring) => rectangle((ring.size, ring.size)),
+        CanvasItem::GridLines(grid) => rectangle(grid.size),
+        CanvasItem::Text(_) => return true,
+    });
+    w > 0.0 && h > 0.0
+}
+
+/// Items in logical pixels scaled to physical ones.
+fn scaled(items: Vec<(Area, CanvasItem)>, factor: f32) -> Vec<(Area, CanvasItem)> {
+    if factor == 1.0 {return items;}
+    items.into_iter().map(|(area, item)| (area.scaled(factor), item.scaled(factor))).collect()
+}
+
+/// Append the underlines and strikethroughs of the texts to the colors of a frame of the size as
+/// rectangles, the texts are laid out in logical pixels and drawn scaled by the factor.
+fn decorate(colors: &mut ColorFrame, font_atlas: &mut FontAtlas, texts: &TextItems, (width, height): (f32, f32), factor: f32, errors: &mut Vec<CanvasError>) {
+    let mut rectangles = texts.iter().flat_map(|(z, area, text)| {
+        let (z, area) = (*z, *area);
+        text.decorations(font_atlas).into_iter().map(move |((x, y, w, h), color)| {
+            let shape = Shape::Rectangle(Border::default(), (w * factor, h * factor));
+            (z, Area((area.0.0 + x * factor, area.0.1 + y * factor), area.1), shape, color.into())
+        })
+    }).collect::<ColorItems>();
+    if rectangles.is_empty() {return;}
+    let mut decorations = ColorFrame::default();
+    ColorRenderer::build(width, height, &mut rectangles, &mut vec![], &mut decorations, errors);
+    colors.extend(decorations);
 }
@@ -0,0 +1,68 @@
+//! Per item work of building a frame and per row work of building images, spread across threads
+//! with the `rayon` feature. Results are combined in order either way, so frames and images come
+//! out the same byte for byte.
+
+use crate::RgbaImage;
+
+/// Fewest items spread across threads, fewer are handled on the calling thread as handing them to
+/// the threads takes longer than the work.
+#[cfg(feature = "rayon")]
+const MIN_ITEMS: usize = 1024;
+
+/// Whether the items are spread across threads.
+#[cfg(feature = "rayon")]
+fn spread(items: usize) -> bool {items >= MIN_ITEMS && rayon::current_num_threads() > 1}
+
+/// The items mapped in order, spread across threads with the `rayon` feature whenever there is
+/// more than one, for work long enough for each item on its own, such as encoding an image.
+pub(crate) fn map_each<T: Send, U: Send>(items: Vec<T>, f: impl Fn(T) -> U + Sync + Send) -> Vec<U> {
+    #[cfg(feature = "rayon")]
+    if items.len() > 1 && rayon::current_num_threads() > 1 {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+        return items.into_par_iter().map(f).collect();
+    }
+    items.into_iter().map(f).collect()
+}
+
+/// Bytes of `count` rows of `len` each, every row filled in by `f` with its index.
+pub(crate) fn rows(count: usize, len: usize, f: impl Fn(usize, &mut [u8]) + Sync + Send) -> Vec<u8> {
+    let mut bytes = vec![0; count * len];
+    #[cfg(feature = "rayon")]
+    if spread(bytes.len()) {
+        use rayon::iter::{IndexedParallelIterator, ParallelIterator};
+        use rayon::slice::ParallelSliceMut;
+        bytes.par_chunks_mut(len).enumerate().for_each(|(i, row)| f(i, row));
+        return bytes;
+    }
+    bytes.chunks_mut(len.max(1)).enumerate().for_each(|(i, row)| f(i, row));
+    bytes
+}
+
+/// Image of the size with each pixel given by `f` of its position, built row by row.
+pub(crate) fn image(width: u32, height: u32, f: impl Fn(u32, u32) -> [u8; 4] + Sync + Send) -> RgbaImage {
+    let bytes = rows(height as usize, width as usize * 4, |y, row| {
+        for (x, pixel) in row.chunks_exact_mut(4).enumerate() {pixel.copy_from_slice(&f(x as u32, y as u32));}
+    });
+    RgbaImage::from_raw(width, height, bytes).unwrap()
+}
+
+/// The items drained and folded into `init`, which keeps the storage of the accumulator from frame
+/// to frame. With the `rayon` feature runs of the items are folded on each thread starting from
+/// `identity` and joined onto `init` in order by `merge`.
+pub(crate) fn fold_into<T: Send, A: Send>(
+    init: A,
+    items: &mut Vec<T>,
+    identity: impl Fn() -> A + Sync + Send,
+    fold: impl Fn(A, T) -> A + Sync + Send,
+    merge: impl Fn(A, A) -> A + Sync + Send,
+) -> A {
+    #[cfg(feature = "rayon")]
+    if spread(items.len()) {
+        use rayon::iter::{ParallelDrainRange, ParallelIterator};
+        let runs = items.par_drain(..).fold(&identity, fold).collect::<Vec<_>>();
+        return runs.into_iter().fold(init, merge);
+    }
+    #[cfg(not(feature = "rayon"))]
+    let _ = (identity, merge);
+    items.drain(..).fold(init, fold)
+}
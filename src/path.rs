@@ -0,0 +1,302 @@
+use std::sync::Arc;
+
+#[cfg(not(feature = "lyon"))]
+mod scanline;
+#[cfg(feature = "lyon")]
+mod lyon;
+
+type Point = (f32, f32);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Verb {
+    MoveTo(Point),
+    LineTo(Point),
+    QuadTo(Point, Point),
+    CubicTo(Point, Point, Point),
+    Close,
+}
+
+/// Which points a filled [`Path`] covers, given the number of times its contours wind around them
+/// counting clockwise turns up and counter-clockwise turns down.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FillRule {
+    /// Points wound around any number of times other than zero, so contours turning the other way
+    /// than the one around them cut holes.
+    #[default]
+    NonZero,
+    /// Points wound around an odd number of times, so every nested contour cuts a hole.
+    EvenOdd,
+}
+
+/// Shape of the ends of the open contours of a stroked [`Path`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineCap {
+    /// Ends exactly at the end point.
+    #[default]
+    Butt,
+    /// Half circle around the end point.
+    Round,
+    /// Extends past the end point by half the stroke width.
+    Square,
+}
+
+/// Shape of the outside of the corners of a stroked [`Path`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineJoin {
+    /// Sharp corner where the edges of the stroke meet, cut off like a bevel when the tip would
+    /// reach further from the corner than the limit times half the stroke width.
+    Miter(f32),
+    /// Arc around the corner.
+    #[default]
+    Round,
+    /// Straight cut across the corner.
+    Bevel,
+}
+
+/// Outline made of lines and quadratic or cubic Bézier curves, in pixels relative to the position
+/// of the area it is drawn in. Paths are built by chaining calls, starting each contour with
+/// [`move_to`](Self::move_to). Filling a path closes each contour and fills it by its
+/// [`FillRule`], strokes turn corners by their [`LineJoin`] and end in their [`LineCap`]. Gradients and images are mapped across
+/// the bounding box of the path.
+///
+/// Curves and round joins are flattened to lines that stay within the
+/// [`tolerance`](Self::tolerance) of them. With the `lyon` feature paths are tessellated by lyon
+/// instead, whose strokes may overlap themselves where they turn sharply or cross.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Path {
+    verbs: Arc<Vec<Verb>>,
+    tolerance: f32,
+    fill_rule: FillRule,
+    line_cap: LineCap,
+    line_join: LineJoin,
+}
+
+impl Default for Path {
+    fn default() -> Self {Path{verbs: Arc::default(), tolerance: 0.1, fill_rule: FillRule::default(), line_cap: LineCap::default(), line_join: LineJoin::default()}}
+}
+
+impl Path {
+    pub fn new() -> Self {Path::default()}
+
+    /// Largest distance in pixels between a curve and the lines it is drawn with, 0.1 by default.
+    pub fn tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance.max(0.001);
+        self
+    }
+
+    pub fn fill_rule(mut self, fill_rule: FillRule) -> Self {
+        self.fill_rule = fill_rule;
+        self
+    }
+
+    pub fn line_cap(mut self, line_cap: LineCap) -> Self {
+        self.line_cap = line_cap;
+        self
+    }
+
+    pub fn line_join(mut self, line_join: LineJoin) -> Self {
+        self.line_join = line_join;
+        self
+    }
+
+    /// Start a new contour at the point.
+    pub fn move_to(self, point: Point) -> Self {self.push(Verb::MoveTo(point))}
+
+    pub fn line_to(self, point: Point) -> Self {self.push(Verb::LineTo(point))}
+
+    /// Quadratic curve to the point bending towards the control point.
+    pub fn quad_to(self, control: Point, point: Point) -> Self {self.push(Verb::QuadTo(control, point))}
+
+    /// Cubic curve to the point leaving towards the first and arriving from the second control point.
+    pub fn cubic_to(self, control: Point, control2: Point, point: Point) -> Self {
+        self.push(Verb::CubicTo(control, control2, point))
+    }
+
+    /// Connect the current contour back to its start, the next contour starts there as well.
+    pub fn close(self) -> Self {self.push(Verb::Close)}
+
+    /// The path cut into dashes of the length with gaps between them, each contour starting with
+    /// a dash. Dashes of zero length are points, which strokes draw as dots unless their caps are
+    /// butt. Curves are flattened to lines first.
+    pub fn dashed(&self, dash: f32, gap: f32) -> Path {
+        let (dash, gap) = (dash.max(0.0), gap.max(0.0));
+        if dash + gap <= 0.0 {return self.clone();}
+        let mut path = Path{verbs: Arc::default(), ..self.clone()};
+        for (mut points, closed) in self.flatten() {
+            if closed {points.push(points[0]);}
+            path = path.move_to(points[0]);
+            let (mut on, mut left) = (true, dash);
+            for line in points.windows(2) {
+                let (a, b) = (line[0], line[1]);
+                let span = length(sub(b, a));
+                let mut done = 0.0;
+                while span - done > left {
+                    done += left;
+                    let p = add(a, scale(sub(b, a), done / span));
+                    path = if on {path.line_to(p)} else {path.move_to(p)};
+                    on = !on;
+                    left = if on {dash} else {gap};
+                }
+                left -= span - done;
+                if on {path = path.line_to(b);}
+            }
+        }
+        path
+    }
+
+    /// Bounding box (x, y, width, height) of the path drawn with the given stroke, zero fills it.
+    pub fn bounds(&self, stroke: f32) -> (f32, f32, f32, f32) {
+        let cap = match self.line_cap {
+            LineCap::Square => std::f32::consts::SQRT_2,
+            _ => 1.0,
+        };
+        let join = match self.line_join {
+            LineJoin::Miter(limit) => limit,
+            _ => 1.0,
+        };
+        let grow = stroke / 2.0 * cap.max(join);
+        let points = self.flatten().into_iter().flat_map(|(points, _)| points);
+        let (x, y, x2, y2) = points.fold(
+            (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+            |(x, y, x2, y2), p| (x.min(p.0), y.min(p.1), x2.max(p.0), y2.max(p.1))
+        );
+        if x > x2 {return (0.0, 0.0, 0.0, 0.0);}
+        (x - grow, y - grow, x2 - x + grow * 2.0, y2 - y + grow * 2.0)
+    }
+
+    /// Triangles covering the path filled or, with a non-zero width, stroked. Fails when the
+    /// tessellator does or the triangles take more vertices than 16 bit indices reach.
+    pub(crate) fn tessellate(&self, stroke: f32) -> Result<(Vec<Point>, Vec<u16>), String> {
+        #[cfg(feature = "lyon")]
+        return self::lyon::tessellate(self, stroke);
+        #[cfg(not(feature = "lyon"))]
+        match stroke > 0.0 {
+            true => scanline::fill(&scanline::stroke_polygons(&self.flatten(), stroke / 2.0, self.line_cap, self.line_join, self.tolerance), FillRule::NonZero),
+            false => scanline::fill(&self.flatten().into_iter().map(|(points, _)| points).collect::<Vec<_>>(), self.fill_rule),
+        }
+    }
+
+    /// Whether the point lies within the path filled by its rule or, with a non-zero width, within
+    /// the stroke, whose caps and joins are taken to be round.
+    pub(crate) fn contains(&self, point: Point, stroke: f32) -> bool {
+        let contours = self.flatten();
+        if stroke > 0.0 {
+            return contours.iter().any(|(points, closed)| {
+                let last = if *closed {points.len()} else {points.len() - 1};
+                (0..last.max(1)).any(|i| {
+                    let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                    let ba = sub(b, a);
+                    let h = (dot(sub(point, a), ba) / dot(ba, ba).max(f32::EPSILON)).clamp(0.0, 1.0);
+                    length(sub(sub(point, a), scale(ba, h))) <= stroke / 2.0
+                })
+            });
+        }
+        let mut winding = 0;
+        for (points, _) in &contours {
+            for i in 0..points.len() {
+                let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                let side = (b.0 - a.0) * (point.1 - a.1) - (point.0 - a.0) * (b.1 - a.1);
+                if a.1 <= point.1 && b.1 > point.1 && side > 0.0 {winding += 1;}
+                if a.1 > point.1 && b.1 <= point.1 && side < 0.0 {winding -= 1;}
+            }
+        }
+        match self.fill_rule {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+
+    /// The path with every point multiplied by the factor.
+    pub(crate) fn scaled(&self, factor: f32) -> Path {
+        let point = |p: Point| scale(p, factor);
+        let verbs = self.verbs.iter().map(|verb| match *verb {
+            Verb::MoveTo(p) => Verb::MoveTo(point(p)),
+            Verb::LineTo(p) => Verb::LineTo(point(p)),
+            Verb::QuadTo(c, p) => Verb::QuadTo(point(c), point(p)),
+            Verb::CubicTo(c, c2, p) => Verb::CubicTo(point(c), point(c2), point(p)),
+            Verb::Close => Verb::Close,
+        }).collect();
+        Path{verbs: Arc::new(verbs), ..self.clone()}
+    }
+
+    /// Fill rule, line cap and line join of the path.
+    pub(crate) fn style(&self) -> (FillRule, LineCap, LineJoin) {(self.fill_rule, self.line_cap, self.line_join)}
+
+    /// The path as the data of an SVG path element, curves kept as curves.
+    pub(crate) fn svg_data(&self) -> String {
+        let n = crate::draw_list::number;
+        self.verbs.iter().map(|verb| match *verb {
+            Verb::MoveTo(p) => format!("M{} {}", n(p.0), n(p.1)),
+            Verb::LineTo(p) => format!("L{} {}", n(p.0), n(p.1)),
+            Verb::QuadTo(c, p) => format!("Q{} {} {} {}", n(c.0), n(c.1), n(p.0), n(p.1)),
+            Verb::CubicTo(c, c2, p) => format!("C{} {} {} {} {} {}", n(c.0), n(c.1), n(c2.0), n(c2.1), n(p.0), n(p.1)),
+            Verb::Close => "Z".to_string(),
+        }).collect::<Vec<_>>().join(" ")
+    }
+
+    fn push(mut self, verb: Verb) -> Self {
+        Arc::make_mut(&mut self.verbs).push(verb);
+        self
+    }
+
+    /// Contours of the path as line segments with whether they are closed.
+    fn flatten(&self) -> Vec<(Vec<Point>, bool)> {
+        let mut contours = vec![];
+        let mut contour = vec![(0.0, 0.0)];
+        for verb in self.verbs.iter() {
+            let last = *contour.last().unwrap();
+            match *verb {
+                Verb::MoveTo(p) => {
+                    if contour.len() > 1 {contours.push((contour, false));}
+                    contour = vec![p];
+                },
+                Verb::LineTo(p) => contour.push(p),
+                Verb::QuadTo(c, p) => {
+                    let dd = length(sub(add(last, p), scale(c, 2.0)));
+                    let n = segments(dd / 4.0, self.tolerance);
+                    contour.extend((1..=n).map(|i| {
+                        let t = i as f32 / n as f32;
+                        let mt = 1.0 - t;
+                        add(add(scale(last, mt * mt), scale(c, 2.0 * mt * t)), scale(p, t * t))
+                    }));
+                },
+                Verb::CubicTo(c, c2, p) => {
+                    let dd = length(sub(add(last, c2), scale(c, 2.0)))
+                        .max(length(sub(add(c, p), scale(c2, 2.0))));
+                    let n = segments(dd * 0.75, self.tolerance);
+                    contour.extend((1..=n).map(|i| {
+                        let t = i as f32 / n as f32;
+                        let mt = 1.0 - t;
+                        add(
+                            add(scale(last, mt * mt * mt), scale(c, 3.0 * mt * mt * t)),
+                            add(scale(c2, 3.0 * mt * t * t), scale(p, t * t * t))
+                        )
+                    }));
+                },
+                Verb::Close => {
+                    let start = contour[0];
+                    if contour.len() > 1 {contours.push((contour, true));}
+                    contour = vec![start];
+                },
+            }
+        }
+        if contour.len() > 1 {contours.push((contour, false));}
+        contours
+    }
+}
+
+/// Number of segments that keeps a curve within the tolerance, given the error of one segment.
+fn segments(error: f32, tolerance: f32) -> usize {
+    ((error / tolerance).sqrt().ceil() as usize).clamp(1, 256)
+}
+
+fn add(a: Point, b: Point) -> Point {(a.0 + b.0, a.1 + b.1)}
+fn sub(a: Point, b: Point) -> Point {(a.0 - b.0, a.1 - b.1)}
+fn scale(a: Point, s: f32) -> Point {(a.0 * s, a.1 * s)}
+fn dot(a: Point, b: Point) -> f32 {a.0 * b.0 + a.1 * b.1}
+fn length(a: Point) -> f32 {dot(a, a).sqrt()}
@@ -0,0 +1,90 @@
+//! Tessellation of paths by lyon.
+
+use ::lyon::math::point;
+use ::lyon::path::Path as LyonPath;
+use ::lyon::tessellation::{BuffersBuilder, FillOptions, FillTessellator, FillVertex, StrokeOptions, StrokeTessellator, StrokeVertex, VertexBuffers};
+
+use super::{FillRule, LineCap, LineJoin, Path, Point, Verb};
+
+pub(super) fn tessellate(path: &Path, stroke: f32) -> Result<(Vec<Point>, Vec<u16>), String> {
+    let lyon_path = convert(path);
+    let mut buffers: VertexBuffers<Point, u16> = VertexBuffers::new();
+    let result = match stroke > 0.0 {
+        true => {
+            let line_cap = match path.line_cap {
+                LineCap::Butt => ::lyon::tessellation::LineCap::Butt,
+                LineCap::Round => ::lyon::tessellation::LineCap::Round,
+                LineCap::Square => ::lyon::tessellation::LineCap::Square,
+            };
+            let (line_join, miter_limit) = match path.line_join {
+                LineJoin::Miter(limit) => (::lyon::tessellation::LineJoin::Miter, limit.max(StrokeOptions::MINIMUM_MITER_LIMIT)),
+                LineJoin::Round => (::lyon::tessellation::LineJoin::Round, StrokeOptions::DEFAULT_MITER_LIMIT),
+                LineJoin::Bevel => (::lyon::tessellation::LineJoin::Bevel, StrokeOptions::DEFAULT_MITER_LIMIT),
+            };
+            let options = StrokeOptions::tolerance(path.tolerance)
+                .with_line_width(stroke)
+                .with_line_join(line_join)
+                .with_miter_limit(miter_limit)
+                .with_line_cap(line_cap);
+            StrokeTessellator::new().tessellate_path(&lyon_path, &options, &mut BuffersBuilder::new(
+                &mut buffers, |v: StrokeVertex| (v.position().x, v.position().y)
+            ))
+        },
+        false => {
+            let fill_rule = match path.fill_rule {
+                FillRule::NonZero => ::lyon::tessellation::FillRule::NonZero,
+                FillRule::EvenOdd => ::lyon::tessellation::FillRule::EvenOdd,
+            };
+            let options = FillOptions::tolerance(path.tolerance).with_fill_rule(fill_rule);
+            FillTessellator::new().tessellate_path(&lyon_path, &options, &mut BuffersBuilder::new(
+                &mut buffers, |v: FillVertex| (v.position().x, v.position().y)
+            ))
+        },
+    };
+    result.map_err(|error| error.to_string())?;
+    Ok((buffers.vertices, buffers.indices))
+}
+
+/// Path in the form lyon takes, where every contour is started explicitly. Contours that are
+/// not started by a move start where the previous one ended or, after a close, began.
+fn convert(path: &Path) -> LyonPath {
+    let mut builder = LyonPath::builder();
+    let mut open = false;
+    let mut start = (0.0, 0.0);
+    let mut current = (0.0, 0.0);
+    for verb in path.verbs.iter() {
+        if !open && !matches!(verb, Verb::MoveTo(_) | Verb::Close) {
+            builder.begin(point(current.0, current.1));
+            start = current;
+            open = true;
+        }
+        match *verb {
+            Verb::MoveTo(p) => {
+                if open {builder.end(false);}
+                builder.begin(point(p.0, p.1));
+                open = true;
+                start = p;
+                current = p;
+            },
+            Verb::LineTo(p) => {
+                builder.line_to(point(p.0, p.1));
+                current = p;
+            },
+            Verb::QuadTo(c, p) => {
+                builder.quadratic_bezier_to(point(c.0, c.1), point(p.0, p.1));
+                current = p;
+            },
+            Verb::CubicTo(c, c2, p) => {
+                builder.cubic_bezier_to(point(c.0, c.1), point(c2.0, c2.1), point(p.0, p.1));
+                current = p;
+            },
+            Verb::Close => {
+                if open {builder.end(true);}
+                open = false;
+                current = start;
+            },
+        }
+    }
+    if open {builder.end(false);}
+    builder.build()
+}
@@ -0,0 +1,207 @@
+//! Tessellation of paths by filling polygons row by row.
+
+use super::{FillRule, LineCap, LineJoin, Point, add, sub, scale, dot, length};
+
+/// Polygons whose union is the stroke of the contours, each wound the same way so overlaps
+/// between them fill once under the non-zero rule. Every segment becomes a quad, the gaps at its
+/// joins are filled by the join and open contours get their caps. A contour of a single point is
+/// drawn as a dot unless its caps are butt.
+pub(super) fn stroke_polygons(contours: &[(Vec<Point>, bool)], half: f32, cap: LineCap, join: LineJoin, tolerance: f32) -> Vec<Vec<Point>> {
+    let mut polygons = vec![];
+    for (points, closed) in contours {
+        let mut points = points.clone();
+        points.dedup();
+        if *closed && points.len() > 2 && points.first() == points.last() {points.pop();}
+        if points.len() == 1 && !*closed {
+            let (normal, out) = ((0.0, half), (half, 0.0));
+            polygons.extend(cap_polygon(cap, points[0], normal, out, tolerance));
+            polygons.extend(cap_polygon(cap, points[0], normal, neg(out), tolerance));
+        }
+        if points.len() < 2 {continue;}
+
+        let count = if *closed {points.len()} else {points.len() - 1};
+        let normals = (0..count).map(|i| {
+            let d = sub(points[(i + 1) % points.len()], points[i]);
+            scale((-d.1, d.0), half / length(d))
+        }).collect::<Vec<_>>();
+
+        for (i, n) in normals.iter().enumerate() {
+            let a = points[i];
+            let b = points[(i + 1) % points.len()];
+            polygons.push(vec![sub(a, *n), sub(b, *n), add(b, *n), add(a, *n)]);
+        }
+
+        if !*closed {
+            let (start, end) = (normals[0], normals[count - 1]);
+            polygons.extend(cap_polygon(cap, points[0], start, (-start.1, start.0), tolerance));
+            polygons.extend(cap_polygon(cap, points[count], end, (end.1, -end.0), tolerance));
+        }
+
+        let joins = if *closed {0..count} else {1..count};
+        for i in joins {
+            let point = points[i];
+            let incoming = normals[(i + count - 1) % count];
+            let outgoing = normals[i];
+            let turn = cross(incoming, outgoing);
+            let (from, to) = if turn > 0.0 {(neg(incoming), neg(outgoing))} else {(incoming, outgoing)};
+            let angle = cross(from, to).atan2(dot(from, to));
+            if angle == 0.0 {continue;}
+            polygons.push(join_polygon(join, point, from, to, angle, tolerance));
+        }
+    }
+    polygons
+}
+
+/// Polygon filling the gap on the outside of a turn of the given angle at the point, between the
+/// offsets of the segments before and after it. Miters longer than their limit are beveled.
+fn join_polygon(join: LineJoin, point: Point, from: Point, to: Point, angle: f32, tolerance: f32) -> Vec<Point> {
+    let bevel = vec![point, add(point, from), add(point, to)];
+    let polygon = match join {
+        LineJoin::Bevel => bevel,
+        LineJoin::Miter(limit) => {
+            let ratio = 1.0 / (angle / 2.0).cos();
+            match ratio <= limit {
+                true => {
+                    let middle = add(from, to);
+                    let tip = add(point, scale(middle, length(from) * ratio / length(middle)));
+                    vec![point, add(point, from), tip, add(point, to)]
+                },
+                false => bevel,
+            }
+        },
+        LineJoin::Round => {
+            let steps = ((angle.abs() / arc_step(length(from), tolerance)).ceil() as usize).max(1);
+            let mut wedge = vec![point];
+            wedge.extend((0..=steps).map(|s| add(point, rotate(from, angle * s as f32 / steps as f32))));
+            wedge
+        },
+    };
+    oriented(polygon)
+}
+
+/// Cap at the end of a stroke with the normal of its last segment, reaching out of the stroke
+/// by `out`. Both have the length of half the stroke width.
+fn cap_polygon(cap: LineCap, point: Point, normal: Point, out: Point, tolerance: f32) -> Option<Vec<Point>> {
+    let polygon = match cap {
+        LineCap::Butt => return None,
+        LineCap::Square => vec![add(point, normal), add(add(point, normal), out), add(sub(point, normal), out), sub(point, normal)],
+        LineCap::Round => {
+            let angle = std::f32::consts::PI.copysign(cross(normal, out));
+            let steps = ((std::f32::consts::PI / arc_step(length(normal), tolerance)).ceil() as usize).max(2);
+            (0..=steps).map(|s| add(point, rotate(normal, angle * s as f32 / steps as f32))).collect()
+        },
+    };
+    Some(oriented(polygon))
+}
+
+/// The polygon wound counter-clockwise in y up coordinates, like the quads of the segments.
+fn oriented(mut polygon: Vec<Point>) -> Vec<Point> {
+    let area = (0..polygon.len()).map(|i| cross(polygon[i], polygon[(i + 1) % polygon.len()])).sum::<f32>();
+    if area < 0.0 {polygon.reverse();}
+    polygon
+}
+
+/// Largest angle between the points of an arc of the radius that keeps it within the tolerance.
+fn arc_step(radius: f32, tolerance: f32) -> f32 {
+    2.0 * (1.0 - tolerance / radius.max(tolerance)).acos().max(0.1)
+}
+
+/// Edge of a polygon from its top to its bottom end, with the winding it adds when crossed.
+struct Edge {
+    top: Point,
+    bottom: Point,
+    winding: i32,
+}
+
+impl Edge {
+    fn x(&self, y: f32) -> f32 {
+        self.top.0 + (self.bottom.0 - self.top.0) * (y - self.top.1) / (self.bottom.1 - self.top.1)
+    }
+}
+
+/// Fill the polygons by the rule as trapezoids between the rows where edges start, end
+/// or cross. Neighbouring trapezoids share their corners exactly so the fill has no seams or
+/// overlaps. Fails when the trapezoids take more vertices than 16 bit indices reach.
+pub(super) fn fill(polygons: &[Vec<Point>], rule: FillRule) -> Result<(Vec<Point>, Vec<u16>), String> {
+    let mut edges = vec![];
+    for points in polygons {
+        for i in 0..points.len() {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            if a.1 < b.1 {
+                edges.push(Edge{top: a, bottom: b, winding: 1});
+            } else if a.1 > b.1 {
+                edges.push(Edge{top: b, bottom: a, winding: -1});
+            }
+        }
+    }
+    edges.sort_by(|a, b| a.top.1.total_cmp(&b.top.1));
+
+    let mut rows = edges.iter().flat_map(|e| [e.top.1, e.bottom.1]).collect::<Vec<_>>();
+    for (i, a) in edges.iter().enumerate() {
+        for b in edges[i + 1..].iter().take_while(|b| b.top.1 < a.bottom.1) {
+            if let Some(y) = intersection(a, b) {rows.push(y);}
+        }
+    }
+    rows.sort_by(f32::total_cmp);
+    rows.dedup();
+
+    let mut vertices = vec![];
+    let mut indices = vec![];
+    let mut next = 0;
+    let mut active: Vec<&Edge> = vec![];
+    for row in rows.windows(2) {
+        let (y, y2) = (row[0], row[1]);
+        active.retain(|e| e.bottom.1 > y);
+        while next < edges.len() && edges[next].top.1 <= y {
+            if edges[next].bottom.1 > y {active.push(&edges[next]);}
+            next += 1;
+        }
+        let middle = (y + y2) / 2.0;
+        active.sort_by(|a, b| a.x(middle).total_cmp(&b.x(middle)));
+
+        let mut winding = 0;
+        let mut left = None;
+        for edge in &active {
+            let was_inside = inside(rule, winding);
+            winding += edge.winding;
+            match (was_inside, inside(rule, winding)) {
+                (false, true) => left = Some(edge),
+                (true, false) => {
+                    let left = left.unwrap();
+                    if vertices.len() + 4 > u16::MAX as usize + 1 {return Err(format!("fill takes more than {} vertices", u16::MAX as usize + 1));}
+                    let l = vertices.len() as u16;
+                    vertices.extend([(left.x(y), y), (edge.x(y), y), (left.x(y2), y2), (edge.x(y2), y2)]);
+                    indices.extend([l, l+1, l+2, l+1, l+2, l+3]);
+                },
+                _ => {}
+            }
+        }
+    }
+    Ok((vertices, indices))
+}
+
+fn inside(rule: FillRule, winding: i32) -> bool {
+    match rule {
+        FillRule::NonZero => winding != 0,
+        FillRule::EvenOdd => winding % 2 != 0,
+    }
+}
+
+/// Height at which two edges cross between their ends.
+fn intersection(a: &Edge, b: &Edge) -> Option<f32> {
+    let r = sub(a.bottom, a.top);
+    let s = sub(b.bottom, b.top);
+    let d = cross(r, s);
+    if d == 0.0 {return None;}
+    let q = sub(b.top, a.top);
+    let t = cross(q, s) / d;
+    let u = cross(q, r) / d;
+    (t > 0.0 && t < 1.0 && u > 0.0 && u < 1.0).then_some(a.top.1 + r.1 * t)
+}
+
+fn neg(a: Point) -> Point {(-a.0, -a.1)}
+fn cross(a: Point, b: Point) -> f32 {a.0 * b.1 - a.1 * b.0}
+fn rotate(a: Point, angle: f32) -> Point {
+    let (sin, cos) = angle.sin_cos();
+    (a.0 * cos - a.1 * sin, a.0 * sin + a.1 * cos)
+}
@@ -0,0 +1,151 @@
+//! Picking pass that finds the item drawn on top of a pixel on the GPU.
+
+use std::sync::{Arc, OnceLock};
+
+use wgpu::{PipelineCompilationOptions, RenderPipelineDescriptor, DepthStencilState, MultisampleState, PipelineLayout, RenderPipeline, PrimitiveState, FragmentState, TextureFormat, BufferUsages, VertexState, RenderPass, Device, Queue, VertexBufferLayout, ShaderModule, Texture, Buffer};
+
+const ID_FORMAT: TextureFormat = TextureFormat::R32Uint;
+const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+
+/// Pipeline of the picking pass for a shader, writing the ids of `fs_pick` where the item is
+/// drawn on top of the items before it.
+pub(crate) fn pipeline(device: &Device, layout: &PipelineLayout, shader: &ShaderModule, vertex_layout: VertexBufferLayout) -> RenderPipeline {
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: None,
+        layout: Some(layout),
+        vertex: VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            compilation_options: PipelineCompilationOptions::default(),
+            buffers: &[vertex_layout]
+        },
+        fragment: Some(FragmentState {
+            module: shader,
+            entry_point: Some("fs_pick"),
+            compilation_options: PipelineCompilationOptions::default(),
+            targets: &[
+                Some(wgpu::ColorTargetState{
+                    format: ID_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })
+            ]
+        }),
+        primitive: PrimitiveState::default(),
+        depth_stencil: Some(DepthStencilState{
+            format: DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::GreaterEqual,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: MultisampleState::default(),
+        multiview: None,
+        cache: None
+    })
+}
+
+/// Finds the item drawn on top of a pixel by drawing the index of every item instead of its
+/// colors, so transparent parts of images fall through to the items below like clicks would.
+/// Pixels count as covered by an item where it is drawn at least half opaque. Text is not drawn
+/// in the picking pass, its decorations are.
+///
+/// Reading the id back takes until the GPU has finished the pass, so a [`pick`](Self::pick) is
+/// answered by [`result`](Self::result) once the device has been polled, usually on the next
+/// frame. One pick is read back at a time.
+pub struct Picker {
+    size: (u32, u32),
+    ids: Texture,
+    depth: Texture,
+    buffer: Buffer,
+    pending: Option<Arc<OnceLock<bool>>>,
+}
+
+impl Picker {
+    /// Create a picker for frames of the size given to [`CanvasRenderer::prepare`](crate::CanvasRenderer::prepare).
+    pub fn new(device: &Device, width: u32, height: u32) -> Self {
+        let texture = |format: TextureFormat, usage: wgpu::TextureUsages| device.create_texture(&wgpu::TextureDescriptor{
+            label: None,
+            size: wgpu::Extent3d{width: width.max(1), height: height.max(1), depth_or_array_layers: 1},
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+            view_formats: &[],
+        });
+        let ids = texture(ID_FORMAT, wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC);
+        let depth = texture(DEPTH_FORMAT, wgpu::TextureUsages::RENDER_ATTACHMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor{
+            label: None,
+            size: 4,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        Picker{size: (width, height), ids, depth, buffer, pending: None}
+    }
+
+    pub fn size(&self) -> (u32, u32) {self.size}
+
+    /// Draw the picking pass with `draw`, such as
+    /// [`CanvasRenderer::render_picking`](crate::CanvasRenderer::render_picking), and start reading
+    /// back the item at the pixel. Returns false without drawing when the pixel is outside of the
+    /// frame or the previous pick has not been read back yet.
+    pub fn pick(&mut self, device: &Device, queue: &Queue, (x, y): (u32, u32), draw: impl FnOnce(&mut RenderPass<'_>)) -> bool {
+        if x >= self.size.0 || y >= self.size.1 || self.pending.is_some() {return false;}
+
+        let ids = self.ids.create_view(&wgpu::TextureViewDescriptor::default());
+        let depth = self.depth.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor{
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment{
+                    view: &ids,
+                    resolve_target: None,
+                    ops: wgpu::Operations{load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT), store: wgpu::StoreOp::Store},
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment{
+                    view: &depth,
+                    depth_ops: Some(wgpu::Operations{load: wgpu::LoadOp::Clear(0.0), store: wgpu::StoreOp::Discard}),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            draw(&mut render_pass);
+        }
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo{
+                texture: &self.ids,
+                mip_level: 0,
+                origin: wgpu::Origin3d{x, y, z: 0},
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo{
+                buffer: &self.buffer,
+                layout: wgpu::TexelCopyBufferLayout{offset: 0, bytes_per_row: None, rows_per_image: None},
+            },
+            wgpu::Extent3d{width: 1, height: 1, depth_or_array_layers: 1}
+        );
+        queue.submit([encoder.finish()]);
+
+        let mapped = Arc::new(OnceLock::new());
+        let done = mapped.clone();
+        self.buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {done.set(result.is_ok()).ok();});
+        self.pending = Some(mapped);
+        true
+    }
+
+    /// The index of the item on top of the pixel of the last [`pick`](Self::pick) in the list
+    /// of items of its frame, `Some(None)` when there was none. Returns `None` while no pick
+    /// has been read back, and after a read back that failed so the next pick can start.
+    pub fn result(&mut self) -> Option<Option<usize>> {
+        let mapped = *self.pending.as_ref()?.get()?;
+        self.pending = None;
+        if !mapped {return None;}
+        let id = u32::from_le_bytes(self.buffer.slice(..).get_mapped_range()[..4].try_into().unwrap());
+        self.buffer.unmap();
+        Some(id.checked_sub(1).map(|id| id as usize))
+    }
+}
@@ -0,0 +1,12 @@
+//! Everything needed to build and render a canvas, for glob importing with
+//! `use wgpu_canvas::prelude::*`.
+
+pub use crate::{Area, Anchor, Placement, Border, Radius, CornerMask, Direction, Tail, Stroke, StrokeStyle, Shape, ShapeKind, Path, FillRule, LineCap, LineJoin, ProgressRing, GridLines, GridSpacing, CanvasItem, CanvasRenderer, CanvasRendererBuilder, Msaa, FrameData, Camera, Picker, DrawList};
+pub use crate::{Canvas, Transform, ProgressBarStyle, BorderStyle, ShadowOptions, ArrowHead, ArrowHeadStyle, LineStyle};
+pub use crate::{Color, Fill, Easing, PatternFill, PatternKind, CanvasError, BuildError};
+pub use crate::{ImageAtlas, ImageAtlasDescriptor, Image, RgbaImage};
+#[cfg(feature = "tokio")]
+pub use crate::PreparedFrame;
+#[cfg(feature = "serde")]
+pub use crate::{DrawListRecord, ReplayError};
+pub use crate::{FontAtlas, Font, Text, Span, Decoration, Align, Cursor, CursorAction};
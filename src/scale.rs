@@ -0,0 +1,67 @@
+//! Scaling of items from logical pixels to the physical pixels of the frame.
+
+use super::{Area, Border, CanvasItem, GridLines, GridSpacing, ProgressRing, Radius, Shape, Tail};
+
+impl Area {
+    pub(crate) fn scaled(self, factor: f32) -> Area {
+        Area((self.0.0 * factor, self.0.1 * factor), self.1.map(|(x, y, w, h)| (x * factor, y * factor, w * factor, h * factor)))
+    }
+}
+
+impl Shape {
+    /// The shape with every length multiplied by the factor.
+    pub(crate) fn scaled(self, factor: f32) -> Shape {
+        let size = |(w, h): (f32, f32)| (w * factor, h * factor);
+        let border = |b: Border| Border(b.0 * factor, b.1 * factor, b.2 * factor, b.3 * factor);
+        let radius = |r: Radius| Radius(r.0 * factor, r.1 * factor);
+        match self {
+            Shape::Ellipse(stroke, s) => Shape::Ellipse(stroke * factor, size(s)),
+            Shape::Rectangle(stroke, s) => Shape::Rectangle(border(stroke), size(s)),
+            Shape::RoundedRectangle(stroke, s, r) => Shape::RoundedRectangle(border(stroke), size(s), radius(r)),
+            Shape::RoundedRectangleSides(stroke, s, r, corners) => Shape::RoundedRectangleSides(border(stroke), size(s), radius(r), corners),
+            Shape::Chevron(direction, thickness, s) => Shape::Chevron(direction, thickness * factor, size(s)),
+            Shape::SpeechBubble(stroke, s, r, tail) => Shape::SpeechBubble(stroke * factor, size(s), radius(r), Tail{
+                offset: tail.offset * factor,
+                width: tail.width * factor,
+                height: tail.height * factor,
+                ..tail
+            }),
+            Shape::Star(stroke, s, points, inner_ratio) => Shape::Star(stroke * factor, size(s), points, inner_ratio),
+            Shape::Path(stroke, path) => Shape::Path(stroke * factor, path.scaled(factor)),
+            Shape::Wave(thickness, s, wavelength) => Shape::Wave(thickness * factor, size(s), wavelength * factor),
+        }
+    }
+}
+
+impl CanvasItem {
+    /// The item with every length multiplied by the factor. Texts keep their logical layout,
+    /// they are scaled as they are drawn.
+    pub(crate) fn scaled(self, factor: f32) -> CanvasItem {
+        let spacing = |spacing: GridSpacing| match spacing {
+            GridSpacing::Every(step) => GridSpacing::Every(step * factor),
+            GridSpacing::At(positions) => GridSpacing::At(positions.into_iter().map(|p| p * factor).collect()),
+        };
+        match self {
+            CanvasItem::Shape(shape, color) => CanvasItem::Shape(shape.scaled(factor), color),
+            CanvasItem::Fill(shape, fill) => CanvasItem::Fill(shape.scaled(factor), fill.scaled(factor)),
+            CanvasItem::Image(shape, image, color) => CanvasItem::Image(shape.scaled(factor), image, color),
+            CanvasItem::ImageStroke(shape, image, color) => CanvasItem::ImageStroke(shape.scaled(factor), image, color),
+            CanvasItem::TiledImage(image, size, tile_size) =>
+                CanvasItem::TiledImage(image, (size.0 * factor, size.1 * factor), (tile_size.0 * factor, tile_size.1 * factor)),
+            CanvasItem::Outline(shape, color, width, offset) => CanvasItem::Outline(shape.scaled(factor), color, width * factor, offset * factor),
+            CanvasItem::ProgressRing(ring) => CanvasItem::ProgressRing(ProgressRing{
+                size: ring.size * factor,
+                thickness: ring.thickness * factor,
+                ..ring
+            }),
+            CanvasItem::GridLines(grid) => CanvasItem::GridLines(GridLines{
+                size: (grid.size.0 * factor, grid.size.1 * factor),
+                rows: spacing(grid.rows),
+                columns: spacing(grid.columns),
+                thickness: grid.thickness * factor,
+                color: grid.color,
+            }),
+            CanvasItem::Text(text) => CanvasItem::Text(text),
+        }
+    }
+}
@@ -2,7 +2,7 @@
 
 use wgpu::{VertexBufferLayout, VertexStepMode, BufferAddress, VertexAttribute, VertexFormat};
 
-use super::{Area, Color};
+use super::{Area, Border, CanvasError, Color, CornerMask, Direction, Fill, Path, ProgressRing, Radius, Tail};
 use crate::image::Image;
 
 pub trait Vertex: std::fmt::Debug + bytemuck::Pod + bytemuck::Zeroable{
@@ -26,6 +26,20 @@ pub trait Vertex: std::fmt::Debug + bytemuck::Pod + bytemuck::Zeroable{
     }
 }
 
+/// Vertices of a shape with the indices of its triangles, most shapes are a single quad.
+#[derive(Clone, Debug)]
+pub struct Mesh<V>(pub Vec<V>, pub Vec<u16>);
+
+impl<V> Mesh<V> {
+    pub fn map<W>(self, f: impl FnMut(V) -> W) -> Mesh<W> {
+        Mesh(self.0.into_iter().map(f).collect(), self.1)
+    }
+}
+
+impl<V> From<[V; 4]> for Mesh<V> {
+    fn from(quad: [V; 4]) -> Self {Mesh(quad.into(), vec![0, 1, 2, 1, 2, 3])}
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct ShapeVertex {
@@ -34,20 +48,20 @@ pub struct ShapeVertex {
     pub size: [f32; 2],
     pub bounds: [f32; 4],
     pub z_index: f32,
-    pub stroke: f32
+    pub stroke: [f32; 4]
 }
 
 impl Vertex for ShapeVertex {
     fn attributes() -> Vec<VertexFormat> {
         vec![
             VertexFormat::Float32x2, VertexFormat::Float32x2, VertexFormat::Float32x2,
-            VertexFormat::Float32x4, VertexFormat::Float32, VertexFormat::Float32
+            VertexFormat::Float32x4, VertexFormat::Float32, VertexFormat::Float32x4
         ]
     }
 }
 
 impl ShapeVertex {
-    pub fn new(width: f32, height: f32, z: u16, area: Area, stroke: f32, size: (f32, f32)) -> [ShapeVertex; 4] {
+    pub fn new(width: f32, height: f32, z: u16, area: Area, stroke: Border, size: (f32, f32)) -> [ShapeVertex; 4] {
         let w = |x: f32| ((x / width) * 2.0) - 1.0;
         let h = |y: f32| 1.0 - ((y / height) * 2.0);
 
@@ -56,11 +70,11 @@ impl ShapeVertex {
         let x2 = w(area.0.0 + size.0);
         let y2 = h(area.0.1 + size.1);
 
-        let stroke = stroke.min(size.0.min(size.1));
+        let stroke = [stroke.0.min(size.1), stroke.1.min(size.0), stroke.2.min(size.1), stroke.3.min(size.0)];
 
         let size = [size.0, size.1];
 
-        let bounds = area.bounds(width, height);
+        let bounds = area.bounds();
         let bx = bounds.0 - area.0.0;
         let by = bounds.1 - area.0.1;
         let bx2 = bx + bounds.2;
@@ -76,48 +90,225 @@ impl ShapeVertex {
             ShapeVertex{uv: [size[0], size[1]], position: [x2, y2], size, bounds, z_index, stroke}
         ]
     }
+
+    /// Triangles of the path, with the uv relative to its bounding box so paints cover it.
+    pub fn path(width: f32, height: f32, z: u16, area: Area, stroke: f32, path: &Path) -> Result<Mesh<ShapeVertex>, CanvasError> {
+        let (px, py, pw, ph) = path.bounds(stroke);
+        let (points, indices) = path.tessellate(stroke)
+            .map_err(|reason| CanvasError::Rasterization{item: z as usize, size: (pw, ph), reason})?;
+
+        let w = |x: f32| ((x / width) * 2.0) - 1.0;
+        let h = |y: f32| 1.0 - ((y / height) * 2.0);
+
+        let (ox, oy) = (area.0.0 + px, area.0.1 + py);
+        let bounds = area.bounds();
+        let bounds = [bounds.0 - ox, bounds.1 - oy, bounds.0 - ox + bounds.2, bounds.1 - oy + bounds.3];
+        let z_index = z as f32 / u16::MAX as f32;
+
+        let vertices = points.into_iter().map(|(x, y)| ShapeVertex{
+            uv: [x - px, y - py],
+            position: [w(area.0.0 + x), h(area.0.1 + y)],
+            size: [pw, ph],
+            bounds,
+            z_index,
+            stroke: [0.0; 4]
+        }).collect();
+        Ok(Mesh(vertices, indices))
+    }
 }
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct RoundedRectangleVertex {
     pub shape: ShapeVertex,
-    pub corner_radius: f32,
+    pub radius_x: [f32; 4],
+    pub radius_y: [f32; 4],
 }
 
 impl Vertex for RoundedRectangleVertex {
     fn attributes() -> Vec<VertexFormat> {
-        [ShapeVertex::attributes(), vec![VertexFormat::Float32]].concat()
+        [ShapeVertex::attributes(), vec![VertexFormat::Float32x4, VertexFormat::Float32x4]].concat()
     }
 }
 
 impl RoundedRectangleVertex {
-    pub fn new(width: f32, height: f32, z: u16, area: Area, stroke: f32, size: (f32, f32), corner_radius: f32) -> [RoundedRectangleVertex; 4] {
-        ShapeVertex::new(width, height, z, area, stroke, size).into_iter().map(|shape|
-            RoundedRectangleVertex{shape, corner_radius}
-        ).collect::<Vec<_>>().try_into().unwrap()
+    /// Corners are in the order top left, top right, bottom right, bottom left, those left out
+    /// of `corners` stay square.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(width: f32, height: f32, z: u16, area: Area, stroke: Border, size: (f32, f32), corner_radius: Radius, corners: CornerMask) -> [RoundedRectangleVertex; 4] {
+        let (radius_x, radius_y) = corner_radii(size, corner_radius, corners);
+        ShapeVertex::new(width, height, z, area, stroke, size).map(|shape|
+            RoundedRectangleVertex{shape, radius_x, radius_y}
+        )
     }
 }
 
 
+/// Horizontal and vertical radii of the corners of a rounded rectangle in the order top left, top
+/// right, bottom right, bottom left, zero for those left out of `corners`.
+pub(crate) fn corner_radii(size: (f32, f32), corner_radius: Radius, corners: CornerMask) -> ([f32; 4], [f32; 4]) {
+    let mask = [CornerMask::TOP_LEFT, CornerMask::TOP_RIGHT, CornerMask::BOTTOM_RIGHT, CornerMask::BOTTOM_LEFT];
+    let radius = |r: f32| mask.map(|c| if corners.contains(c) {r} else {0.0});
+    let (rx, ry) = (radius(corner_radius.0), radius(corner_radius.1));
+
+    // Radii that do not fit next to each other along a side are scaled down on both axes alike.
+    let scale = [
+        size.0 / (rx[0] + rx[1]), size.1 / (ry[1] + ry[2]),
+        size.0 / (rx[2] + rx[3]), size.1 / (ry[3] + ry[0])
+    ].into_iter().fold(1.0f32, f32::min);
+    (rx.map(|r| r * scale), ry.map(|r| r * scale))
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ChevronVertex {
+    pub shape: ShapeVertex,
+    pub direction: f32,
+}
+
+impl Vertex for ChevronVertex {
+    fn attributes() -> Vec<VertexFormat> {
+        [ShapeVertex::attributes(), vec![VertexFormat::Float32]].concat()
+    }
+}
+
+impl ChevronVertex {
+    pub fn new(width: f32, height: f32, z: u16, area: Area, direction: Direction, thickness: f32, size: (f32, f32)) -> [ChevronVertex; 4] {
+        let direction = quarter_turns(direction);
+        ShapeVertex::new(width, height, z, area, thickness.into(), size).map(|shape|
+            ChevronVertex{shape, direction}
+        )
+    }
+}
+
+/// Number of clockwise quarter turns from pointing right, as the shaders take directions.
+pub(crate) fn quarter_turns(direction: Direction) -> f32 {
+    match direction {
+        Direction::Right => 0.0,
+        Direction::Down => 1.0,
+        Direction::Left => 2.0,
+        Direction::Up => 3.0,
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct WaveVertex {
+    pub shape: ShapeVertex,
+    pub wavelength: f32,
+}
+
+impl Vertex for WaveVertex {
+    fn attributes() -> Vec<VertexFormat> {
+        [ShapeVertex::attributes(), vec![VertexFormat::Float32]].concat()
+    }
+}
+
+impl WaveVertex {
+    pub fn new(width: f32, height: f32, z: u16, area: Area, thickness: f32, size: (f32, f32), wavelength: f32) -> [WaveVertex; 4] {
+        ShapeVertex::new(width, height, z, area, thickness.into(), size).map(|shape|
+            WaveVertex{shape, wavelength}
+        )
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SpeechBubbleVertex {
+    pub shape: ShapeVertex,
+    pub radius: [f32; 2],
+    pub tail: [f32; 4],
+}
+
+impl Vertex for SpeechBubbleVertex {
+    fn attributes() -> Vec<VertexFormat> {
+        [ShapeVertex::attributes(), vec![VertexFormat::Float32x2, VertexFormat::Float32x4]].concat()
+    }
+}
+
+impl SpeechBubbleVertex {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(width: f32, height: f32, z: u16, area: Area, stroke: f32, size: (f32, f32), corner_radius: Radius, tail: Tail) -> [SpeechBubbleVertex; 4] {
+        let (radius, tail) = speech_bubble(size, corner_radius, tail);
+        ShapeVertex::new(width, height, z, area, stroke.into(), size).map(|shape|
+            SpeechBubbleVertex{shape, radius, tail}
+        )
+    }
+}
+
+/// Corner radius of the body of a speech bubble, scaled down to fit it, and its tail as the
+/// shaders take it: the edge in quarter turns, the offset, the width and the height that fits.
+pub(crate) fn speech_bubble(size: (f32, f32), corner_radius: Radius, tail: Tail) -> ([f32; 2], [f32; 4]) {
+    let vertical = matches!(tail.edge, Direction::Up | Direction::Down);
+    let tail_height = tail.height.clamp(0.0, if vertical {size.1} else {size.0});
+    let body = if vertical {(size.0, size.1 - tail_height)} else {(size.0 - tail_height, size.1)};
+    let scale = (body.0 / (corner_radius.0 * 2.0)).min(body.1 / (corner_radius.1 * 2.0)).min(1.0);
+    let radius = [corner_radius.0 * scale, corner_radius.1 * scale];
+    (radius, [quarter_turns(tail.edge), tail.offset, tail.width.max(0.0), tail_height])
+}
+
+/// Vertices of a star relative to its position, clockwise from the point at the top, alternating
+/// between the outer and inner ones.
+pub(crate) fn star(size: (f32, f32), points: u32, inner_ratio: f32) -> Vec<(f32, f32)> {
+    let outer = size.0.min(size.1) / 2.0;
+    let points = points.max(2);
+    let ratio = inner_ratio.clamp(0.0, 1.0);
+    (0..points * 2).map(|i| {
+        let r = if i % 2 == 0 {outer} else {outer * ratio};
+        let angle = std::f32::consts::PI * i as f32 / points as f32 - std::f32::consts::FRAC_PI_2;
+        (size.0 / 2.0 + r * angle.cos(), size.1 / 2.0 + r * angle.sin())
+    }).collect()
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct StarVertex {
+    pub shape: ShapeVertex,
+    pub star: [f32; 2],
+}
+
+impl Vertex for StarVertex {
+    fn attributes() -> Vec<VertexFormat> {
+        [ShapeVertex::attributes(), vec![VertexFormat::Float32x2]].concat()
+    }
+}
+
+impl StarVertex {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(width: f32, height: f32, z: u16, area: Area, stroke: f32, size: (f32, f32), points: u32, inner_ratio: f32) -> [StarVertex; 4] {
+        let star = [points.max(2) as f32, inner_ratio.clamp(0.0, 1.0)];
+        ShapeVertex::new(width, height, z, area, stroke.into(), size).map(|shape|
+            StarVertex{shape, star}
+        )
+    }
+}
+
 #[repr(packed, C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct ColorVertex<V: Vertex = ShapeVertex> {
     pub shape: V,
-    pub color: [f32; 4]
+    pub color: [f32; 4],
+    pub end_color: [f32; 4],
+    pub gradient: [f32; 4]
 }
 
 impl<V: Vertex> Vertex for ColorVertex<V> {
     fn attributes() -> Vec<VertexFormat> {
-        [V::attributes(), vec![VertexFormat::Float32x4]].concat()
+        [V::attributes(), vec![VertexFormat::Float32x4, VertexFormat::Float32x4, VertexFormat::Float32x4]].concat()
     }
 }
 
 impl<V: Vertex> ColorVertex<V> {
-    pub fn new(shape: [V; 4], color: Color) -> [ColorVertex<V>; 4] {
-        shape.into_iter().map(|shape|
-            ColorVertex{shape, color: color.color()}
-        ).collect::<Vec<_>>().try_into().unwrap()
+    pub fn new(shape: [V; 4], fill: Fill) -> [ColorVertex<V>; 4] {
+        let (color, end_color, gradient) = fill.paint();
+        shape.map(|shape|
+            ColorVertex{shape, color, end_color, gradient}
+        )
+    }
+
+    pub fn mesh(shape: Mesh<V>, fill: Fill) -> Mesh<ColorVertex<V>> {
+        let (color, end_color, gradient) = fill.paint();
+        shape.map(|shape| ColorVertex{shape, color, end_color, gradient})
     }
 }
 
@@ -137,29 +328,7 @@ impl<V: Vertex> Vertex for ImageVertex<V> {
 
 impl<V: Vertex> ImageVertex<V> {
     pub fn new(shape: [V; 4], image: &Image, size: (f32, f32), color: Option<Color>) -> [ImageVertex<V>; 4] {
-        let mut x = 0.0;
-        let mut y = 0.0;
-        let mut x2 = 1.0;
-        let mut y2 = 1.0;
-
-        let wi = image.size().0 as f32;
-        let hi = image.size().1 as f32;
-        let ws = size.0;
-        let hs = size.1;
-
-        let wr = ws / wi;
-        let hr = hs / hi;
-
-        if hr > wr {
-            let d = (1.0-(wr / hr)) / 2.0;
-            x = d;
-            x2 = 1.0-d;
-        } else {
-            let d = (1.0-(hr / wr)) / 2.0;
-            y = d;
-            y2 = 1.0-d;
-        }
-
+        let (x, y, x2, y2) = fit(image, size);
         let color = color.map(|c| c.color()).unwrap_or([0.0, 0.0, 0.0, 0.0]);
 
         [
@@ -170,3 +339,117 @@ impl<V: Vertex> ImageVertex<V> {
         ]
     }
 }
+
+impl ImageVertex<ShapeVertex> {
+    /// Texture the mesh the way a quad of the size of its uv space would be.
+    pub fn mesh(shape: Mesh<ShapeVertex>, image: &Image, color: Option<Color>) -> Mesh<ImageVertex<ShapeVertex>> {
+        let size = shape.0.first().map(|v| (v.size[0], v.size[1])).unwrap_or_default();
+        let (x, y, x2, y2) = fit(image, size);
+        let color = color.map(|c| c.color()).unwrap_or([0.0, 0.0, 0.0, 0.0]);
+        shape.map(|shape| {
+            let (u, v) = (shape.uv[0] / size.0.max(f32::EPSILON), shape.uv[1] / size.1.max(f32::EPSILON));
+            ImageVertex{shape, texture: [x + (x2 - x) * u, y + (y2 - y) * v], color}
+        })
+    }
+}
+
+/// Texture coordinates (x, y, x2, y2) of the image covering the size while keeping its aspect.
+fn fit(image: &Image, size: (f32, f32)) -> (f32, f32, f32, f32) {
+    let mut x = 0.0;
+    let mut y = 0.0;
+    let mut x2 = 1.0;
+    let mut y2 = 1.0;
+
+    let wi = image.size().0 as f32;
+    let hi = image.size().1 as f32;
+    let ws = size.0;
+    let hs = size.1;
+
+    let wr = ws / wi;
+    let hr = hs / hi;
+
+    if hr > wr {
+        let d = (1.0-(wr / hr)) / 2.0;
+        x = d;
+        x2 = 1.0-d;
+    } else {
+        let d = (1.0-(hr / wr)) / 2.0;
+        y = d;
+        y2 = 1.0-d;
+    }
+
+    let (u, v, uw, vh) = image.uv();
+    (u + x * uw, v + y * vh, u + x2 * uw, v + y2 * vh)
+}
+
+#[repr(packed, C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ImageStrokeVertex<V: Vertex = ShapeVertex> {
+    pub shape: V,
+    pub region: [f32; 4],
+    pub color: [f32; 4]
+}
+
+impl<V: Vertex> Vertex for ImageStrokeVertex<V> {
+    fn attributes() -> Vec<VertexFormat> {
+        [V::attributes(), vec![VertexFormat::Float32x4, VertexFormat::Float32x4]].concat()
+    }
+}
+
+impl<V: Vertex> ImageStrokeVertex<V> {
+    pub fn new(shape: [V; 4], image: &Image, color: Option<Color>) -> [ImageStrokeVertex<V>; 4] {
+        let (u, v, uw, vh) = image.uv();
+        let color = color.map(|c| c.color()).unwrap_or([0.0, 0.0, 0.0, 0.0]);
+        shape.map(|shape|
+            ImageStrokeVertex{shape, region: [u, v, uw, vh], color}
+        )
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TiledImageVertex {
+    pub shape: ShapeVertex,
+    pub tiles: [f32; 2]
+}
+
+impl Vertex for TiledImageVertex {
+    fn attributes() -> Vec<VertexFormat> {
+        [ShapeVertex::attributes(), vec![VertexFormat::Float32x2]].concat()
+    }
+}
+
+impl TiledImageVertex {
+    pub fn new(width: f32, height: f32, z: u16, area: Area, size: (f32, f32), tile_size: (f32, f32)) -> [TiledImageVertex; 4] {
+        let tiles = [size.0 / tile_size.0, size.1 / tile_size.1];
+        ShapeVertex::new(width, height, z, area, Border::default(), size).map(|shape|
+            TiledImageVertex{shape, tiles}
+        )
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ProgressRingVertex {
+    pub shape: ShapeVertex,
+    pub color: [f32; 4],
+    pub track: [f32; 4],
+    pub arc: [f32; 2]
+}
+
+impl Vertex for ProgressRingVertex {
+    fn attributes() -> Vec<VertexFormat> {
+        [ShapeVertex::attributes(), vec![VertexFormat::Float32x4, VertexFormat::Float32x4, VertexFormat::Float32x2]].concat()
+    }
+}
+
+impl ProgressRingVertex {
+    pub fn new(width: f32, height: f32, z: u16, area: Area, ring: &ProgressRing) -> [ProgressRingVertex; 4] {
+        let size = (ring.size, ring.size);
+        let arc = [ring.progress.clamp(0.0, 1.0), ring.start_angle];
+        let (color, track) = (ring.color.color(), ring.track.color());
+        ShapeVertex::new(width, height, z, area, ring.thickness.into(), size).map(|shape|
+            ProgressRingVertex{shape, color, track, arc}
+        )
+    }
+}
@@ -1,36 +1,55 @@
-use glyphon::{Resolution, SwashCache, FontSystem, TextBounds, TextAtlas, Viewport, Metrics, Shaping, Buffer, Family, Cache, Attrs, Wrap};
+use glyphon::{Resolution, SwashCache, FontSystem, TextBounds, TextAtlas, Viewport, Metrics, Shaping, Buffer, Family, Cache, Attrs, Wrap, LayoutRun};
 use wgpu::{DepthStencilState, MultisampleState, TextureFormat, RenderPass, Device, Queue};
 use glyphon::fontdb::{Database, Source, ID};
 
 use std::sync::Arc;
 use std::collections::HashMap;
 
-use super::{Area, Color};
+use image::GrayImage;
+
+use super::{Area, Camera, CanvasError, Color};
 pub use crate::cursor::{Cursor, CursorAction};
 pub use glyphon::cosmic_text::{Align};
 
+/// Glyph metadata holds the z index in its low bits, which glyphon turns into depth, and the
+/// index of the span the glyph belongs to above them.
+const SPAN_SHIFT: usize = 16;
 
-#[derive(Debug, Clone)]
+bitflags::bitflags! {
+    /// Lines drawn along the glyphs of a [`Span`] in its color.
+    #[derive(Clone, Debug, Copy, Default, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct Decoration: u8 {
+        const UNDERLINE = 1;
+        const STRIKETHROUGH = 1 << 1;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Span{
     pub text: String, 
     pub font_size: f32,
     pub line_height: f32,
     pub font: Font,
-    pub color: Color
+    pub color: Color,
+    pub decoration: Decoration
 }
 
 impl Span {
-    pub fn new(text: &str, font_size: f32, line_height: f32, font: Font, color: Color) -> Self {
-        Span{text: text.to_string(), font_size, line_height, font, color}
+    pub fn new(text: &str, font_size: f32, line_height: f32, font: Font, color: impl Into<Color>) -> Self {
+        Span{text: text.to_string(), font_size, line_height, font, color: color.into(), decoration: Decoration::empty()}
     }
+
+    pub fn decoration(mut self, decoration: Decoration) -> Self {self.decoration = decoration; self}
+
     pub fn into_inner(&self, z_index: usize) -> (&str, Attrs<'static>) {
-        let color = glyphon::cosmic_text::Color::rgba(self.color.0, self.color.1, self.color.2, self.color.3);
+        let color = glyphon::cosmic_text::Color::rgba(self.color.r, self.color.g, self.color.b, self.color.a);
         let attrs = self.font.1.clone().color(color).metadata(z_index).metrics(Metrics::new(self.font_size, self.line_height));
         (&self.text, attrs)
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Text{
     pub spans: Vec<Span>,
     pub width: Option<f32>,
@@ -66,12 +85,97 @@ impl Text {
        Self::buffer_size(&self.get_buffer(font_system.as_mut(), 0), &self.spans)
     }
 
-    pub fn set_color(&mut self, color: Color) {
+    pub fn set_color(&mut self, color: impl Into<Color>) {
+        let color = color.into();
         self.spans.iter_mut().for_each(|s| s.color = color);
     }
 
     pub fn width(mut self, width: Option<f32>) -> Self {self.width = width; self}
 
+    /// Rectangles (x, y, width, height) relative to the position of the text around the glyphs of
+    /// the span, one for each line it is laid out on, such as to hit test links.
+    pub fn span_bounds(&self, font_system: &mut impl AsMut<FontAtlas>, span: usize) -> Vec<(f32, f32, f32, f32)> {
+        let buffer = self.layout(font_system.as_mut(), 0);
+        buffer.layout_runs().filter_map(|run|
+            span_extent(&run, span).map(|(x, x2)| (x, run.line_top, x2 - x, run.line_height))
+        ).collect()
+    }
+
+    /// Underlines and strikethroughs of the spans as rectangles (x, y, width, height) relative to
+    /// the position of the text with their color, placed and sized by the metrics of their font.
+    pub(crate) fn decorations(&self, font_atlas: &mut FontAtlas) -> Vec<((f32, f32, f32, f32), Color)> {
+        if self.spans.iter().all(|s| s.decoration.is_empty()) {return vec![];}
+        let buffer = self.layout(font_atlas, 0);
+        let mut rectangles = vec![];
+        for run in buffer.layout_runs() {
+            for (i, span) in self.spans.iter().enumerate().filter(|(_, s)| !s.decoration.is_empty()) {
+                let Some((x, x2)) = span_extent(&run, i) else {continue};
+                let Some(font) = font_atlas.font_system.get_font(span.font.0) else {continue};
+                let metrics = font.as_swash().metrics(&[]).scale(span.font_size);
+                let lines = [(Decoration::UNDERLINE, metrics.underline_offset), (Decoration::STRIKETHROUGH, metrics.strikeout_offset)];
+                for (_, offset) in lines.into_iter().filter(|(d, _)| span.decoration.contains(*d)) {
+                    rectangles.push(((x, run.line_y - offset, x2 - x, metrics.stroke_size.max(1.0)), span.color));
+                }
+            }
+        }
+        rectangles
+    }
+
+    /// Pieces of the lines of the text that each belong to a single span, as the start of their
+    /// baseline relative to the position of the text, the index of their span and their characters.
+    pub(crate) fn pieces(&self, font_atlas: &mut FontAtlas) -> Vec<((f32, f32), usize, String)> {
+        let buffer = self.layout(font_atlas, 0);
+        let mut pieces: Vec<((f32, f32), usize, String)> = vec![];
+        for run in buffer.layout_runs() {
+            let (mut span, mut end) = (None, 0);
+            for glyph in run.glyphs {
+                // Glyphs of the same characters, such as those of a combining mark, add them once.
+                let text = if glyph.start >= end {&run.text[glyph.start..glyph.end]} else {""};
+                end = end.max(glyph.end);
+                let index = glyph.metadata >> SPAN_SHIFT;
+                match pieces.last_mut() {
+                    Some(piece) if span == Some(index) => piece.2.push_str(text),
+                    _ => pieces.push(((glyph.x, run.line_y), index, text.to_string())),
+                }
+                span = Some(index);
+            }
+        }
+        pieces
+    }
+
+    /// Coverage of the glyphs laid out as they are drawn, in physical pixels of the scale with
+    /// `padding` empty pixels around the size of the text, such as to blur into a shadow. Glyphs
+    /// are laid out in logical pixels and rasterized at the scale, placed as the text renderer
+    /// places them.
+    pub(crate) fn mask(&self, font_atlas: &mut FontAtlas, scale: f32, padding: u32) -> GrayImage {
+        let buffer = self.layout(font_atlas, 0);
+        let (width, height) = Self::buffer_size(&buffer, &self.spans);
+        let size = |v: f32| (v * scale).ceil() as u32 + 2 * padding;
+        let mut mask = GrayImage::new(size(width), size(height));
+        let (white, mut cache) = (glyphon::Color::rgb(255, 255, 255), SwashCache::new());
+        for run in buffer.layout_runs() {
+            let line_y = (run.line_y * scale).round() as i32;
+            for glyph in run.glyphs {
+                let glyph = glyph.physical((0.0, 0.0), scale);
+                cache.with_pixels(&mut font_atlas.font_system, glyph.cache_key, white, |x, y, color| {
+                    let (x, y) = (glyph.x + x + padding as i32, line_y + glyph.y + y + padding as i32);
+                    if x < 0 || y < 0 || x >= mask.width() as i32 || y >= mask.height() as i32 {return;}
+                    let pixel = mask.get_pixel_mut(x as u32, y as u32);
+                    pixel.0[0] = pixel.0[0].max(color.a());
+                });
+            }
+        }
+        mask
+    }
+
+    /// Buffer laid out as it is drawn, narrowed to its widest line so it is aligned within that.
+    fn layout(&self, font_atlas: &mut FontAtlas, z_index: usize) -> Buffer {
+        let mut buffer = self.get_buffer(font_atlas, z_index);
+        let width = Self::buffer_size(&buffer, &self.spans).0;
+        buffer.set_size(&mut font_atlas.font_system, Some(width), None);
+        buffer
+    }
+
     fn get_buffer(&self, font_system: &mut impl AsMut<FontSystem>, z_index: usize) -> Buffer {
         let default_attrs = self.spans.first().expect("Text must have at least one span even if its empty").into_inner(0).1;
         let metrics = Metrics::from(default_attrs.metrics_opt.unwrap());
@@ -79,7 +183,7 @@ impl Text {
         buffer.set_wrap(font_system.as_mut(), Wrap::WordOrGlyph);
         buffer.set_size(font_system.as_mut(), self.width.map(|w| 1.0+w), Some(f32::INFINITY));
         buffer.set_rich_text(
-            font_system.as_mut(), self.spans.iter().map(|s| s.into_inner(z_index)), 
+            font_system.as_mut(), self.spans.iter().enumerate().map(|(i, s)| s.into_inner(z_index | i << SPAN_SHIFT)),
             &default_attrs, Shaping::Advanced, Some(self.align)
         );
         buffer
@@ -102,12 +206,24 @@ impl Text {
 
 pub type Font = Arc<(ID, Attrs<'static>)>;
 
+/// Name of the family of the font, as loaded by [`FontAtlas::add`].
+pub(crate) fn family(font: &Font) -> String {
+    match font.1.family {
+        Family::Name(name) => name.to_string(),
+        family => format!("{family:?}"),
+    }
+}
+
 pub struct FontAtlas{
     fonts: Option<HashMap<Arc<Vec<u8>>, Font>>,
     font_system: FontSystem
 }
 
 impl FontAtlas {
+    /// Load a font for spans to use. Characters a span's font has no glyph for, such as emoji, are
+    /// drawn with one of the other fonts added here that does, and glyphs of color fonts keep their
+    /// own colors instead of taking the color of the span. Characters no font covers are drawn as
+    /// the replacement glyph of the span's font.
     pub fn add(&mut self, raw_font: &[u8]) -> Font {
         let raw_font = Arc::new(raw_font.to_vec());
         match self.fonts.as_mut().unwrap().get(&raw_font) {
@@ -129,11 +245,19 @@ impl FontAtlas {
     }
 
     fn trim(&mut self) {
-        let to_remove = self.fonts.as_ref().unwrap().iter().filter(|&(_, v)| (Arc::strong_count(v) > 1)).map(|(k, _)| k.clone()).collect::<Vec<_>>();
+        let to_remove = self.fonts.as_ref().unwrap().iter().filter(|&(_, v)| Arc::strong_count(v) > 1).map(|(k, _)| k.clone()).collect::<Vec<_>>();
         to_remove.into_iter().for_each(|k| {self.fonts.as_mut().unwrap().remove(&k);});
     }
 }
 
+/// Horizontal extent of the glyphs of the span on the line.
+fn span_extent(run: &LayoutRun, span: usize) -> Option<(f32, f32)> {
+    run.glyphs.iter().filter(|g| g.metadata >> SPAN_SHIFT == span).fold(None, |extent, g| Some(match extent {
+        Some((x, x2)) => (x.min(g.x), x2.max(g.x + g.w)),
+        None => (g.x, g.x + g.w),
+    }))
+}
+
 impl Default for FontAtlas {fn default() -> Self {
     FontAtlas{
         fonts: Some(HashMap::new()),
@@ -145,11 +269,20 @@ impl AsMut<FontSystem> for FontAtlas {
     fn as_mut(&mut self) -> &mut FontSystem {&mut self.font_system}
 }
 
+impl AsMut<FontAtlas> for FontAtlas {
+    fn as_mut(&mut self) -> &mut FontAtlas {self}
+}
+
 pub struct TextRenderer {
     text_renderer: glyphon::TextRenderer,
     swash_cache: SwashCache,
     text_atlas: TextAtlas,
     viewport: Viewport,
+    /// Texts of the last frame laid out, kept to place them again when the camera moves.
+    areas: Vec<(Area, Buffer)>,
+    size: (f32, f32),
+    scale: f32,
+    render_scale: f32,
 }
 
 impl TextRenderer {
@@ -169,6 +302,10 @@ impl TextRenderer {
             text_atlas,
             viewport: Viewport::new(device, &cache),
             swash_cache: SwashCache::new(),
+            areas: vec![],
+            size: (0.0, 0.0),
+            scale: 1.0,
+            render_scale: 1.0,
         }
     }
 
@@ -179,28 +316,41 @@ impl TextRenderer {
         queue: &Queue,
         width: f32,
         height: f32,
+        scale: f32,
+        render_scale: f32,
+        camera: Camera,
         font_atlas: &mut FontAtlas,
         text_areas: Vec<(u16, Area, Text)>
-    ) {
+    ) -> Result<(), CanvasError> {
         font_atlas.trim();
         self.text_atlas.trim();
         self.viewport.update(queue, Resolution{width: width as u32, height: height as u32});
-        let text_areas = text_areas.into_iter().map(|(z, a, t)| {
-            let mut b = t.get_buffer(font_atlas, z as usize);
-            let width = Text::buffer_size(&b, &t.spans).0;
-            b.set_size(&mut font_atlas.font_system, Some(width), None);
-            (a, b)
-        }).collect::<Vec<_>>();
-        let text_areas = text_areas.iter().map(|(a, b)| {
-            let bounds = a.bounds(width, height);
+        self.areas = text_areas.into_iter().map(|(z, a, t)| (a, t.layout(font_atlas, z as usize))).collect();
+        self.size = (width, height);
+        (self.scale, self.render_scale) = (scale, render_scale);
+        self.place(device, queue, font_atlas, camera)
+    }
+
+    /// Place the texts laid out by the last [`prepare`](Self::prepare) as seen through the camera.
+    /// No text is drawn when their glyphs do not fit the atlas.
+    pub fn place(&mut self, device: &Device, queue: &Queue, font_atlas: &mut FontAtlas, camera: Camera) -> Result<(), CanvasError> {
+        let (width, height) = self.size;
+        let offset = (camera.offset.0 * self.scale, camera.offset.1 * self.scale);
+        let screen = |x: f32, y: f32| (x * camera.zoom + offset.0, y * camera.zoom + offset.1);
+        let text_areas = self.areas.iter().map(|(a, b)| {
+            let (left, top) = screen(a.0.0, a.0.1);
+            let bounds = a.1.map(|(x, y, w, h)| {
+                let (x, y) = screen(x, y);
+                (x, y, w * camera.zoom, h * camera.zoom)
+            }).unwrap_or((0.0, 0.0, width, height));
             glyphon::TextArea{
                 buffer: b,
-                left: a.0.0,
-                top: a.0.1,
-                scale: 1.0,
+                left,
+                top,
+                scale: self.scale * self.render_scale * camera.zoom,
                 bounds: TextBounds {//Sisscor Rect
-                    left: bounds.0 as i32,
-                    top: bounds.1 as i32,
+                    left: bounds.0.floor() as i32,
+                    top: bounds.1.floor() as i32,
                     right: (bounds.0 + bounds.2).ceil() as i32,
                     bottom: (bounds.1 + bounds.3).ceil() as i32,
                 },
@@ -209,7 +359,8 @@ impl TextRenderer {
             }
         });
 
-        self.text_renderer.prepare_with_depth(
+        let depth = |z: usize| ((z as u16) as f32) / u16::MAX as f32;
+        let placed = self.text_renderer.prepare_with_depth(
             device,
             queue,
             &mut font_atlas.font_system,
@@ -217,11 +368,16 @@ impl TextRenderer {
             &self.viewport,
             text_areas,
             &mut self.swash_cache,
-            |z: usize| ((z as u16) as f32) / u16::MAX as f32
-        ).unwrap();
+            depth
+        );
+        placed.map_err(|error| {
+            self.text_renderer.prepare_with_depth(device, queue, &mut font_atlas.font_system, &mut self.text_atlas, &self.viewport, [], &mut self.swash_cache, depth)
+                .expect("placing no text cannot fail");
+            CanvasError::Allocation{item: None, reason: format!("texts of {} areas could not be placed: {error}", self.areas.len())}
+        })
     }
 
     pub fn render<'a>(&'a self, render_pass: &mut RenderPass<'a>) {
-        self.text_renderer.render(&self.text_atlas, &self.viewport, render_pass).unwrap();
+        self.text_renderer.render(&self.text_atlas, &self.viewport, render_pass).expect("texts are only drawn once placed");
     }
 }
@@ -0,0 +1,106 @@
+//! Construction and conversion of colors.
+
+use wgpu_canvas::prelude::*;
+
+#[test]
+fn hex_colors_parse_in_every_form() {
+    assert_eq!("#1e88e5".parse(), Ok(Color::from_rgb(0x1e, 0x88, 0xe5)));
+    assert_eq!("1E88E5".parse(), Ok(Color::from_rgb(0x1e, 0x88, 0xe5)));
+    assert_eq!("#1e88e580".parse(), Ok(Color::from_rgba8(0x1e, 0x88, 0xe5, 0x80)));
+    assert_eq!("#f0a".parse(), Ok(Color::from_rgb(0xff, 0x00, 0xaa)));
+    assert_eq!(Color::from_hex("#1e88e5"), Ok(Color::from_rgb(0x1e, 0x88, 0xe5)));
+    assert!(Color::from_hex("#1e88e").is_err());
+    for malformed in ["", "#", "#12", "#1234", "#12345", "#1234567", "#123456789", "#ggg", "#1e88e5zz", "#ü1"] {
+        let error = malformed.parse::<Color>().unwrap_err();
+        assert!(error.to_string().contains(&format!("{malformed:?}")), "{malformed}: {error}");
+    }
+}
+
+#[test]
+fn colors_convert_and_blend() {
+    assert_eq!(Color::from_hsl(0.0, 1.0, 0.5), Color::from_rgb(255, 0, 0));
+    assert_eq!(Color::from_hsl(120.0, 1.0, 0.5), Color::from_rgb(0, 255, 0));
+    assert_eq!(Color::from_hsl(-120.0, 1.0, 0.5), Color::from_rgb(0, 0, 255));
+    assert_eq!(Color::from_hsl(207.0, 0.79, 0.51), Color::from_rgb(31, 140, 229));
+    assert_eq!(Color::from_hsl(40.0, 0.0, 1.0), Color::from_rgb(255, 255, 255));
+
+    assert_eq!(Color::from_rgb(255, 0, 0).to_hsl(), (0.0, 1.0, 0.5));
+    assert_eq!(Color::from_rgba8(0, 0, 255, 9).to_hsl(), (240.0, 1.0, 0.5));
+    assert_eq!(Color::from_rgb(255, 255, 255).to_hsl(), (0.0, 0.0, 1.0));
+    // Channels of 8 bits recover the hue, saturation and lightness up to their rounding.
+    for hue in (0..360).step_by(15) {
+        for saturation in [0.25, 0.5, 0.75, 1.0] {
+            for lightness in [0.25, 0.4, 0.5, 0.6, 0.75] {
+                let (h, s, l) = Color::from_hsl(hue as f32, saturation, lightness).to_hsl();
+                let turn = (h - hue as f32 + 180.0).rem_euclid(360.0) - 180.0;
+                assert!(turn.abs() < 1.5 && (s - saturation).abs() < 0.02 && (l - lightness).abs() < 0.005, "{hue} {saturation} {lightness}: {h} {s} {l}");
+            }
+        }
+    }
+
+    assert_eq!(Color::from_hsv(0.0, 1.0, 1.0), Color::from_rgb(255, 0, 0));
+    assert_eq!(Color::from_hsv(120.0, 1.0, 0.5), Color::from_rgb(0, 128, 0));
+    assert_eq!(Color::from_hsv(-120.0, 0.5, 1.0), Color::from_rgb(128, 128, 255));
+    assert_eq!(Color::from_rgb(0, 0, 0).to_hsv(), (0.0, 0.0, 0.0));
+    for hue in (0..360).step_by(15) {
+        for saturation in [0.25, 0.5, 0.75, 1.0] {
+            for value in [0.25, 0.5, 0.75, 1.0] {
+                let (h, s, v) = Color::from_hsv(hue as f32, saturation, value).to_hsv();
+                let turn = (h - hue as f32 + 180.0).rem_euclid(360.0) - 180.0;
+                assert!(turn.abs() < 1.5 && (s - saturation).abs() < 0.02 && (v - value).abs() < 0.005, "{hue} {saturation} {value}: {h} {s} {v}");
+            }
+        }
+    }
+
+    assert_eq!(Color::from_oklch(0.627955, 0.257683, 29.2339), Color::from_rgb(255, 0, 0));
+    assert_eq!(Color::from_oklch(1.0, 0.0, 0.0), Color::from_rgb(255, 255, 255));
+    assert_eq!(Color::from_oklch(0.5, 0.4, 150.0), Color::from_rgb(0, 145, 0), "colors outside of sRGB are clipped");
+    let (l, c, h) = Color::from_rgb(0, 0, 255).to_oklch();
+    assert!((l - 0.452).abs() < 0.001 && (c - 0.313).abs() < 0.001 && (h - 264.05).abs() < 0.1, "{l} {c} {h}");
+    let (l, c, h) = Color::from_rgb(128, 128, 128).to_oklch();
+    assert!((l - 0.5999).abs() < 0.001 && c < 0.001 && h == 0.0, "{l} {c} {h}");
+    for hue in (0..360).step_by(15) {
+        for lightness in [0.6, 0.7, 0.8] {
+            let (l, c, h) = Color::from_oklch(lightness, 0.08, hue as f32).to_oklch();
+            let turn = (h - hue as f32 + 180.0).rem_euclid(360.0) - 180.0;
+            assert!(turn.abs() < 2.0 && (c - 0.08).abs() < 0.004 && (l - lightness).abs() < 0.004, "{hue} {lightness}: {l} {c} {h}");
+        }
+    }
+
+    let eased = |easing: Easing| [0.0, 0.25, 0.5, 0.75, 1.0].map(|t| easing.ease(t));
+    assert_eq!(eased(Easing::Linear), [0.0, 0.25, 0.5, 0.75, 1.0]);
+    assert_eq!(eased(Easing::EaseIn), [0.0, 0.0625, 0.25, 0.5625, 1.0]);
+    assert_eq!(eased(Easing::EaseOut), [0.0, 0.4375, 0.75, 0.9375, 1.0]);
+    assert_eq!(eased(Easing::EaseInOut(2.0)), [0.0, 0.1, 0.5, 0.9, 1.0]);
+    assert_eq!(eased(Easing::EaseInOut(0.5)), eased(Easing::Linear));
+    assert_eq!(eased(Easing::Step), [0.0, 0.0, 1.0, 1.0, 1.0]);
+    assert_eq!(Easing::EaseIn.ease(2.0), 1.0);
+
+    let blue = Color::from_rgba8(30, 136, 229, 255);
+    assert_eq!(blue.with_alpha(0.5), Color::from_rgba8(30, 136, 229, 128));
+    assert_eq!(blue.with_alpha(2.0), blue);
+    // Blends in linear light, so halfway from black to white is lighter than 128.
+    assert_eq!(Color::BLACK.lerp(Color::WHITE, 0.5), Color::from_rgb(188, 188, 188));
+    assert_eq!(blue.lerp(Color::from_rgb(255, 255, 255), 0.1), Color::from_rgb(94, 154, 232));
+    assert_eq!(blue.lerp(Color::from_rgba8(0, 0, 0, 0), 1.0), Color::from_rgba8(0, 0, 0, 0));
+    assert_eq!(blue.lerp(blue.with_alpha(0.0), 0.5), blue.with_alpha(0.5));
+
+    assert_eq!(Color::from_rgba8(200, 100, 50, 128).premultiplied_alpha(), Color::from_rgba8(100, 50, 25, 128));
+    assert_eq!(Color::from_premultiplied(100, 50, 25, 128), Color::from_rgba8(199, 100, 50, 128));
+    assert_eq!(Color::from_premultiplied(100, 50, 25, 0), Color::TRANSPARENT);
+    assert_eq!(Color::from_premultiplied(255, 255, 255, 255), Color::WHITE);
+    for color in [blue, Color::WHITE, Color::from_rgb(10, 20, 30)] {
+        assert_eq!(Color::from_premultiplied(color.r, color.g, color.b, color.a).premultiplied_alpha(), color);
+    }
+
+    assert_eq!(Color::from((1, 2, 3, 4)), Color::from_rgba8(1, 2, 3, 4));
+    assert_eq!(<(u8, u8, u8, u8)>::from(blue), (30, 136, 229, 255));
+    assert_eq!(image::Rgba::from(blue), image::Rgba([30, 136, 229, 255]));
+    assert_eq!(Color::from_rgb(30, 136, 229), blue);
+    assert_eq!(Color::from_rgba(30, 136, 229, 255), blue);
+    assert_eq!([Color::WHITE, Color::BLACK, Color::TRANSPARENT], [Color::from_rgb(255, 255, 255), Color::from_rgb(0, 0, 0), Color::from_rgba8(0, 0, 0, 0)]);
+    let mut canvas = Canvas::new(8.0, 8.0);
+    canvas.draw_solid_background((1, 2, 3, 4));
+    canvas.draw_rect((0.0, 0.0, 8.0, 8.0), (1, 2, 3, 4), 0.0);
+    assert!(matches!(canvas.into_items()[0].1, CanvasItem::Shape(_, Color{r: 1, g: 2, b: 3, a: 4})));
+}
@@ -0,0 +1,270 @@
+//! Bounds and containment of every shape variant, computed on the CPU without an adapter.
+
+use wgpu_canvas::prelude::*;
+
+fn near(a: (f32, f32, f32, f32), b: (f32, f32, f32, f32)) -> bool {
+    [(a.0, b.0), (a.1, b.1), (a.2, b.2), (a.3, b.3)].iter().all(|(a, b)| (a - b).abs() < 0.01)
+}
+
+#[test]
+fn shapes_are_bound_by_what_they_draw() {
+    let area = Area((10.0, 20.0), None);
+    let size = (40.0, 30.0);
+    let tail = Tail{edge: Direction::Down, offset: 20.0, width: 10.0, height: 6.0};
+    let path = Path::new().move_to((5.0, 5.0)).line_to((25.0, 5.0)).line_to((15.0, 15.0)).close();
+    let boxed = (10.0, 20.0, 40.0, 30.0);
+    for (shape, bounds) in [
+        (Shape::Ellipse(2.0, size), boxed),
+        (Shape::Rectangle(Border(1.0, 2.0, 3.0, 4.0), size), boxed),
+        (Shape::RoundedRectangle(Border::default(), size, 8.0.into()), boxed),
+        (Shape::RoundedRectangleSides(Border::default(), size, 8.0.into(), CornerMask::TOP), boxed),
+        (Shape::Chevron(Direction::Up, 4.0, size), boxed),
+        (Shape::SpeechBubble(2.0, size, 6.0.into(), tail), boxed),
+        (Shape::Wave(2.0, size, 12.0), boxed),
+        (Shape::Path(0.0, path.clone()), (15.0, 25.0, 20.0, 10.0)),
+        (Shape::Path(2.0, path), (14.0, 24.0, 22.0, 12.0)),
+        (Shape::Star(0.0, (30.0, 30.0), 4, 0.5), (10.0, 20.0, 30.0, 30.0)),
+    ] {
+        assert!(near(shape.bounds(&area), bounds), "{shape:?} is bound by {:?}", shape.bounds(&area));
+    }
+
+    // The points of a five pointed star reach the top of its circle but not the bottom.
+    let (x, y, w, h) = Shape::Star(0.0, (40.0, 40.0), 5, 0.4).bounds(&Area((0.0, 0.0), None));
+    assert!(x > 0.0 && y == 0.0 && (w - 40.0 * (0.1f32 * std::f32::consts::PI).cos()).abs() < 0.01, "star spans {x}, {y}, {w}");
+    assert!(h < 40.0 && h > 35.0, "star is {h} high");
+
+    let clipped = Area((10.0, 20.0), Some((0.0, 0.0, 30.0, 100.0)));
+    assert!(near(Shape::Ellipse(0.0, size).bounds(&clipped), (10.0, 20.0, 20.0, 30.0)));
+}
+
+#[test]
+fn shapes_contain_what_they_draw() {
+    let area = Area((10.0, 20.0), None);
+    let size = (40.0, 30.0);
+    let at = |x: f32, y: f32| (10.0 + x, 20.0 + y);
+    let check = |shape: Shape, inside: &[(f32, f32)], hollow: &[(f32, f32)], outside: &[(f32, f32)]| {
+        for p in inside {assert!(shape.contains(&area, at(p.0, p.1), false), "{shape:?} contains {p:?}");}
+        for p in hollow {
+            assert!(shape.contains(&area, at(p.0, p.1), false), "{shape:?} contains {p:?}");
+            assert!(!shape.contains(&area, at(p.0, p.1), true), "hollow {shape:?} contains {p:?}");
+        }
+        for p in outside {assert!(!shape.contains(&area, at(p.0, p.1), false), "{shape:?} does not contain {p:?}");}
+    };
+
+    check(Shape::Ellipse(4.0, size), &[(20.0, 1.5), (1.5, 15.0)], &[(20.0, 15.0)], &[(2.0, 2.0), (38.0, 28.0)]);
+    check(Shape::Rectangle(Border(1.0, 2.0, 3.0, 4.0), size), &[(0.5, 0.5), (3.5, 15.0), (20.0, 28.0)], &[(20.0, 15.0), (5.0, 2.0)], &[(-0.5, 15.0), (41.0, 15.0)]);
+    check(Shape::RoundedRectangle(3.0.into(), size, 10.0.into()), &[(20.0, 1.0), (4.0, 4.0)], &[(20.0, 15.0), (6.0, 6.0)], &[(1.0, 1.0), (39.0, 29.0)]);
+    check(Shape::RoundedRectangleSides(Border::default(), size, 10.0.into(), CornerMask::TOP_LEFT), &[(39.5, 0.5), (0.5, 29.5)], &[], &[(0.5, 0.5)]);
+    check(Shape::Chevron(Direction::Right, 4.0, size), &[(2.0, 2.0), (38.0, 15.0), (2.0, 28.0)], &[], &[(2.0, 15.0), (38.0, 2.0)]);
+    let tail = Tail{edge: Direction::Down, offset: 20.0, width: 10.0, height: 6.0};
+    check(Shape::SpeechBubble(2.0, size, 6.0.into(), tail), &[(20.0, 1.0), (20.0, 28.0)], &[(20.0, 12.0)], &[(0.5, 0.5), (5.0, 28.0)]);
+    check(Shape::Star(3.0, (30.0, 30.0), 5, 0.5), &[(15.0, 1.0)], &[(15.0, 15.0)], &[(1.0, 1.0), (15.0, 29.0)]);
+    let path = Path::new().move_to((0.0, 0.0)).line_to((30.0, 0.0)).line_to((30.0, 30.0)).line_to((0.0, 30.0)).close()
+        .move_to((10.0, 10.0)).line_to((10.0, 20.0)).line_to((20.0, 20.0)).line_to((20.0, 10.0)).close();
+    check(Shape::Path(0.0, path.clone()), &[(5.0, 5.0)], &[], &[(15.0, 15.0), (35.0, 5.0)]);
+    check(Shape::Path(0.0, path.clone().fill_rule(FillRule::EvenOdd)), &[(5.0, 5.0)], &[], &[(15.0, 15.0)]);
+    check(Shape::Path(2.0, path), &[(0.5, 15.0), (10.5, 15.0)], &[(5.0, 5.0)], &[(-2.0, 15.0)]);
+    check(Shape::Wave(2.0, (32.0, 10.0), 16.0), &[(0.0, 5.0), (4.0, 1.0), (12.0, 9.0)], &[], &[(4.0, 9.0), (12.0, 1.0), (33.0, 5.0)]);
+
+    let clipped = Area((10.0, 20.0), Some((10.0, 20.0, 10.0, 10.0)));
+    assert!(Shape::Rectangle(Border::default(), size).contains(&clipped, (15.0, 25.0), false));
+    assert!(!Shape::Rectangle(Border::default(), size).contains(&clipped, (25.0, 25.0), false));
+}
+
+#[test]
+fn areas_are_placed_within_their_parents() {
+    let card = Area::rect(10.0, 20.0, 400.0, 300.0);
+    let badge = Area::sized(64.0, 64.0).anchor(Anchor::BottomRight).of(card).offset(-8.0, -8.0);
+    assert_eq!(badge.0, (338.0, 248.0));
+    assert_eq!(badge.1, Some((338.0, 248.0, 64.0, 64.0)));
+
+    // Centering rounds to whole pixels and bounds stay within the parent.
+    let centered = Area::sized(15.0, 15.0).centered_in(Area::rect(0.0, 0.0, 50.0, 10.0));
+    assert_eq!(centered.0, (18.0, -3.0));
+    assert_eq!(centered.1, Some((18.0, 0.0, 15.0, 10.0)));
+    assert_eq!(Area::sized(4.0, 4.0).anchor(Anchor::Center).of(Area((5.0, 5.0), None)).0, (3.0, 3.0));
+
+    let inset = card.inset(12.0);
+    assert_eq!(inset.0, (22.0, 32.0));
+    assert_eq!(inset.1, Some((22.0, 32.0, 376.0, 276.0)));
+    assert_eq!(card.inset(500.0).1, Some((210.0, 170.0, 0.0, 0.0)));
+    assert_eq!(Area((1.0, 2.0), None).inset(4.0).0, (1.0, 2.0));
+
+    // Split parts share their edge at a whole pixel for any fraction, never overlapping or
+    // leaving a gap.
+    let row = Area::rect(0.5, 3.0, 101.0, 7.0);
+    for i in 0..=20 {
+        let (left, right) = row.split_horizontal(i as f32 / 20.0);
+        let ((lx, _, lw, _), (rx, _, rw, _)) = (left.1.unwrap(), right.1.unwrap());
+        assert_eq!(lx + lw, rx, "parts of {i} / 20 meet");
+        assert!(rx.fract() == 0.0 || rx == 101.5, "edge of {i} / 20 is at {rx}");
+        assert_eq!(lw + rw, 101.0);
+        let (top, bottom) = row.split_vertical(i as f32 / 20.0);
+        let ((_, ty, _, th), (_, by, _, bh)) = (top.1.unwrap(), bottom.1.unwrap());
+        assert_eq!((ty + th, th + bh), (by, 7.0), "parts of {i} / 20 meet");
+    }
+}
+
+#[test]
+fn shape_kinds_take_their_stroke() {
+    let shape = Shape::from((ShapeKind::Ellipse{size: (20.0, 10.0)}, 2.0));
+    assert!(matches!(shape, Shape::Ellipse(2.0, (20.0, 10.0))));
+    let shape = Shape::from((ShapeKind::Rectangle{size: (20.0, 10.0)}, 3.0));
+    assert!(matches!(shape, Shape::Rectangle(Border(3.0, 3.0, 3.0, 3.0), (20.0, 10.0))));
+    let kind = ShapeKind::RoundedRectangle{size: (20.0, 10.0), radius: 4.0};
+    assert_eq!(kind.size(), (20.0, 10.0));
+    let Shape::RoundedRectangle(stroke, size, radius) = kind.into() else {panic!("{kind:?} is a rounded rectangle")};
+    assert_eq!((stroke, size, radius), (Border::default(), (20.0, 10.0), Radius::from(4.0)));
+}
+
+#[test]
+fn patterned_strokes_follow_the_outline() {
+    let area = Area((0.0, 0.0), None);
+    let dashed = Shape::Rectangle(Border::default(), (40.0, 20.0)).stroked(Stroke::dashed(2.0, 4.0, 4.0));
+    assert!(matches!(dashed, Shape::Path(2.0, _)), "{dashed:?} is a path");
+    assert!(dashed.contains(&area, (3.0, 1.0), false) && dashed.contains(&area, (11.0, 1.0), false));
+    assert!(!dashed.contains(&area, (7.0, 1.0), false) && !dashed.contains(&area, (20.0, 10.0), false));
+    assert!(near(dashed.bounds(&area), (0.0, 0.0, 40.0, 20.0)));
+
+    let dotted = Shape::Ellipse(0.0, (40.0, 40.0)).stroked(Stroke::dotted(2.0, 6.0));
+    assert!(dotted.contains(&area, (20.0, 1.0), false) && !dotted.contains(&area, (23.5, 1.0), false));
+
+    // Solid strokes and shapes without an outline to follow keep their own stroke.
+    assert!(matches!(Shape::Ellipse(0.0, (40.0, 40.0)).stroked(3.0.into()), Shape::Ellipse(3.0, _)));
+    let star = Shape::Star(0.0, (40.0, 40.0), 5, 0.5).stroked(Stroke::dashed(2.0, 4.0, 4.0));
+    assert!(matches!(star, Shape::Star(2.0, ..)));
+    assert!(matches!(Shape::Rectangle(2.0.into(), (4.0, 4.0)).stroked(Stroke::dotted(0.0, 4.0)), Shape::Rectangle(Border(0.0, 0.0, 0.0, 0.0), _)));
+
+    let path = Path::new().move_to((0.0, 0.0)).line_to((10.0, 0.0));
+    assert_eq!(path.dashed(0.0, 0.0), path);
+}
+
+#[test]
+fn ellipse_patterns_repeat_around_the_outline() {
+    let area = Area((0.0, 0.0), None);
+    // 11 dots fit around the middle of the stroke, the last one sits a whole spacing before the
+    // first rather than leaving a wider gap.
+    let dotted = Shape::Ellipse(0.0, (40.0, 40.0)).stroked(Stroke::dotted(2.0, 10.5));
+    let spacing = std::f32::consts::TAU * 19.0 / 11.0;
+    let before = |length: f32| (20.0 - 19.0 * (length / 19.0).sin(), 20.0 - 19.0 * (length / 19.0).cos());
+    assert!(dotted.contains(&area, (20.0, 1.0), false));
+    assert!(dotted.contains(&area, before(spacing), false), "last dot is at {:?}", before(spacing));
+    assert!(!dotted.contains(&area, before(spacing / 2.0), false));
+
+    // Patterns longer than the outline are shrunk to fit it once.
+    let dashed = Shape::Ellipse(0.0, (40.0, 40.0)).stroked(Stroke::dashed(2.0, 200.0, 100.0));
+    let perimeter = spacing * 11.0;
+    assert!(dashed.contains(&area, before(perimeter - 60.0), false) && !dashed.contains(&area, before(20.0), false));
+}
+
+#[test]
+fn curves_pass_smoothly_through_their_points() {
+    let points = [(0.0, 10.0), (10.0, 0.0), (20.0, 10.0), (30.0, 0.0)];
+    let mut canvas = Canvas::new(64.0, 64.0);
+    canvas.with_transform(Transform(5.0, 5.0), |canvas| {
+        canvas.draw_bezier_curve(&points, 1.0, Color::from_rgb(0, 0, 0));
+        canvas.draw_bezier_curve(&points, Stroke::dashed(1.0, 2.0, 100.0), Color::from_rgb(0, 0, 0));
+        canvas.draw_bezier_curve(&points[..1], 1.0, Color::from_rgb(0, 0, 0));
+        canvas.draw_bezier_curve(&points, 0.0, Color::from_rgb(0, 0, 0));
+    });
+    let items = canvas.into_items();
+    assert_eq!(items.len(), 2, "single points and strokes without width draw nothing");
+    let [(area, CanvasItem::Fill(curve, _)), (_, CanvasItem::Fill(dashed, _))] = &items[..] else {panic!("{items:?}")};
+    let at = |(x, y): (f32, f32)| (x + 5.0, y + 5.0);
+    assert!(points.iter().all(|p| curve.contains(area, at(*p), true)));
+    // Halfway between the first two points the curve bends out towards the third.
+    assert!(curve.contains(area, at((4.375, 4.375)), true) && !curve.contains(area, at((5.0, 0.0)), true));
+    assert!(!curve.contains(area, at((10.0, -1.0)), true), "curve overshoots its points");
+    assert!(dashed.contains(area, at((0.0, 10.0)), true) && !dashed.contains(area, at((20.0, 10.0)), true));
+}
+
+#[test]
+fn arrows_point_along_their_line() {
+    let black = Color::from_rgb(0, 0, 0);
+    let mut canvas = Canvas::new(64.0, 64.0);
+    canvas.draw_arrow((0.0, 10.0), (40.0, 10.0), ArrowHead{style: ArrowHeadStyle::Filled, size: 10.0}, 2.0, black);
+    canvas.draw_arrow((10.0, 0.0), (10.0, 40.0), ArrowHead{style: ArrowHeadStyle::Open, size: 8.0}, 2.0, black);
+    canvas.draw_arrow((0.0, 0.0), (30.0, 30.0), ArrowHead::default(), 2.0, black);
+    canvas.draw_arrow((5.0, 5.0), (5.0, 5.0), ArrowHead{style: ArrowHeadStyle::Filled, size: 10.0}, 2.0, black);
+    let items = canvas.into_items();
+    assert_eq!(items.len(), 5, "{items:?}");
+    let covers = |i: usize, p: (f32, f32)| match &items[i] {
+        (area, CanvasItem::Fill(shape, _)) => shape.contains(area, p, true),
+        item => panic!("{item:?}"),
+    };
+    // The filled head spans its size across the line at its base and the line ends there.
+    assert!(covers(1, (39.0, 10.0)) && covers(1, (31.0, 14.0)) && covers(1, (31.0, 6.0)) && !covers(1, (31.0, 16.0)));
+    assert!(covers(0, (29.0, 10.0)) && !covers(0, (35.0, 10.0)));
+    // The open head is two lines back from the tip leaving the space between them.
+    assert!(covers(3, (14.0, 32.0)) && covers(3, (6.0, 32.0)) && !covers(3, (10.0, 34.0)));
+    assert!(covers(2, (10.0, 36.0)));
+    assert!(covers(4, (15.0, 15.0)) && !covers(4, (15.0, 20.0)));
+}
+
+#[test]
+fn grids_divide_their_rectangle_evenly() {
+    let style = LineStyle{stroke: Stroke::solid(2.0), color: Color::from_rgb(0, 0, 0)};
+    let mut canvas = Canvas::new(64.0, 64.0);
+    canvas.draw_grid((10.0, 10.0, 40.0, 30.0), 4, 3, style);
+    canvas.draw_grid((0.0, 0.0, 40.0, 30.0), 1, 0, style);
+    canvas.draw_grid((0.0, 0.0, 40.0, 30.0), 4, 3, LineStyle{stroke: Stroke::solid(0.0), ..style});
+    let items = canvas.into_items();
+    assert_eq!(items.len(), 3 + 2, "{items:?}");
+    let covered = |p: (f32, f32)| items.iter().filter(|(area, item)| match item {
+        CanvasItem::Shape(shape, _) => shape.contains(area, p, true),
+        item => panic!("{item:?}"),
+    }).count();
+    for x in [20.0, 30.0, 40.0] {
+        assert_eq!(covered((x, 15.0)), 1, "column at {x}");
+    }
+    for y in [20.0, 30.0] {
+        assert_eq!(covered((15.0, y)), 1, "row at {y}");
+    }
+    assert_eq!(covered((20.0, 20.0)), 2);
+    assert_eq!(covered((10.0, 15.0)) + covered((25.0, 15.0)) + covered((20.0, 45.0)), 0, "edges and outside are drawn");
+
+    let mut canvas = Canvas::new(64.0, 64.0);
+    canvas.draw_grid((0.0, 0.0, 40.0, 30.0), 2, 1, LineStyle{stroke: Stroke::dashed(2.0, 4.0, 4.0), ..style});
+    let items = canvas.into_items();
+    let (area, CanvasItem::Shape(line, _)) = &items[0] else {panic!("{items:?}")};
+    assert!(line.contains(area, (20.0, 2.0), true) && !line.contains(area, (20.0, 6.0), true));
+}
+
+#[test]
+fn camera_maps_points_both_ways() {
+    let camera = Camera{offset: (30.0, -12.0), zoom: 2.5};
+    assert_eq!(camera.to_screen((4.0, 8.0)), (40.0, 8.0));
+    assert_eq!(camera.to_canvas((40.0, 8.0)), (4.0, 8.0));
+    assert_eq!(Camera::default().to_screen((7.0, 3.0)), (7.0, 3.0));
+}
+
+/// Frames built across threads come out the same byte for byte as frames built on one, which
+/// takes the serial path.
+#[cfg(feature = "rayon")]
+#[test]
+fn frames_build_the_same_across_threads() {
+    let mut atlas = ImageAtlas::default();
+    let images = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]].map(|color| atlas.add(RgbaImage::from_pixel(4, 4, image::Rgba(color))));
+    let path = Path::new().move_to((0.0, 0.0)).line_to((12.0, 2.0)).quad_to((14.0, 14.0), (2.0, 12.0)).close();
+    let items = (0..50_000).map(|i| {
+        let area = Area(((i % 256) as f32 * 4.0, (i / 256) as f32 * 4.0), None);
+        let shape = match i % 6 {
+            0 => Shape::Ellipse(0.0, (14.0, 14.0)),
+            1 => Shape::Rectangle(2.0.into(), (14.0, 14.0)),
+            2 => Shape::RoundedRectangle(Border::default(), (14.0, 14.0), 4.0.into()),
+            3 => Shape::Star(2.0, (14.0, 14.0), 5, 0.5),
+            4 => Shape::Path(0.0, path.clone()),
+            _ => Shape::Path(2.0, path.clone()),
+        };
+        let image = images[i % 3].clone();
+        (area, match i % 4 {
+            0 => CanvasItem::Shape(shape, Color::from_rgb(40, 120, 255)),
+            1 => CanvasItem::Image(shape, image, None),
+            2 => CanvasItem::ImageStroke(shape, image, None),
+            _ => CanvasItem::ProgressRing(ProgressRing{size: 14.0, thickness: 3.0, progress: 0.6, start_angle: 0.0, color: Color::from_rgb(255, 255, 255), track: Color::from_rgb(40, 40, 40)}),
+        })
+    }).collect::<Vec<_>>();
+    let build = |threads: usize| rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap()
+        .install(|| CanvasRenderer::build_frame(1024.0, 1024.0, 1.0, 1.0, Camera::default(), items.clone()));
+    assert!(build(1) == build(8), "frames built across threads differ");
+}
@@ -0,0 +1,127 @@
+//! Export of draw lists to SVG.
+
+use wgpu_canvas::prelude::*;
+
+/// Images embedded in the document, in the order they appear.
+fn embedded(svg: &str) -> Vec<RgbaImage> {
+    let value = |c: u8| "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/".bytes().position(|a| a == c).unwrap() as u32;
+    svg.split("data:image/png;base64,").skip(1).map(|rest| {
+        let data = rest[..rest.find('"').unwrap()].trim_end_matches('=').as_bytes();
+        let bytes = data.chunks(4).flat_map(|chunk| {
+            let n = chunk.iter().enumerate().fold(0, |n, (i, c)| n | value(*c) << (18 - 6 * i));
+            (0..chunk.len() - 1).map(move |i| (n >> (16 - 8 * i)) as u8)
+        }).collect::<Vec<_>>();
+        image::load_from_memory(&bytes).unwrap().to_rgba8()
+    }).collect()
+}
+
+/// Shapes and strokes become SVG elements in their place, with linear gradients in linear light
+/// and the bounds of areas as clips.
+#[test]
+fn draw_lists_export_shapes_as_svg() {
+    let mut list = DrawList::new();
+    list.push(Area((10.0, 20.0), None), CanvasItem::Shape(Shape::Rectangle(Border::default(), (30.0, 40.0)), Color::from_rgb(255, 0, 0)));
+    list.push(Area((0.0, 0.0), None), CanvasItem::Shape(Shape::Ellipse(4.0, (20.0, 10.0)), Color::from_rgba8(0, 0, 255, 128)));
+    let gradient = Fill::Linear(Color::from_rgb(0, 0, 0), Color::from_rgb(255, 255, 255), 0.0, Easing::Linear);
+    let rounded = Shape::RoundedRectangle(Border::default(), (20.0, 20.0), 4.0.into());
+    list.push(Area((0.0, 0.0), Some((0.0, 0.0, 5.0, 5.0))), CanvasItem::Fill(rounded, gradient));
+    list.push(Area((0.0, 0.0), None), CanvasItem::Shape(Shape::Rectangle(Border(1.0, 2.0, 1.0, 2.0), (8.0, 8.0)), Color::from_rgb(0, 0, 0)));
+    let svg = list.to_svg((64.0, 48.0), &mut FontAtlas::default(), |_| None);
+
+    assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg""#) && svg.contains(r#"viewBox="0 0 64 48""#), "{svg}");
+    assert!(svg.contains(r##"<g transform="translate(10 20)"><rect width="30" height="40" fill="#ff0000"/></g>"##), "{svg}");
+    assert!(svg.contains(r##"<ellipse cx="10" cy="5" rx="8" ry="3" fill="none" stroke-width="4" stroke="#0000ff" stroke-opacity="0.502"/>"##), "{svg}");
+    assert!(svg.contains(r#"<clipPath id="d1"><rect x="0" y="0" width="5" height="5"/></clipPath>"#), "{svg}");
+    assert!(svg.contains(r#"<g clip-path="url(#d1)">"#), "{svg}");
+    assert!(svg.contains(r#"color-interpolation="linearRGB" x1="0" y1="10" x2="20" y2="10""#), "{svg}");
+    assert!(svg.contains(r#"<rect width="20" height="20" rx="4" ry="4" fill="url(#d2)"/>"#), "{svg}");
+    // Borders of different widths leave out the inside of the shape.
+    assert!(svg.contains(r#"V8 A0 0 0 0 1 8 8"#) && svg.contains(r#" M2 1 H6"#) && svg.contains(r#"fill-rule="evenodd""#), "{svg}");
+    assert!(embedded(&svg).is_empty(), "nothing was rasterized");
+
+    // Eased gradients are sampled into stops, steps change color at once.
+    let (black, white) = (Color::from_rgb(0, 0, 0), Color::from_rgb(255, 255, 255));
+    let square = Shape::Rectangle(Border::default(), (8.0, 8.0));
+    let mut list = DrawList::new();
+    list.push(Area((0.0, 0.0), None), CanvasItem::Fill(square.clone(), Fill::Linear(black, white, 0.0, Easing::EaseIn)));
+    list.push(Area((0.0, 0.0), None), CanvasItem::Fill(square, Fill::Linear(black, white, 0.0, Easing::Step)));
+    let svg = list.to_svg((8.0, 8.0), &mut FontAtlas::default(), |_| None);
+    let (eased, step) = svg.split_once("</linearGradient>").unwrap();
+    assert_eq!(eased.matches("<stop ").count(), 17, "{svg}");
+    assert!(eased.contains(r##"<stop offset="0.5" stop-color="#898989""##), "{svg}");
+    assert!(step.contains(r##"<stop offset="0.5" stop-color="#000000" stop-opacity="1"/><stop offset="0.5" stop-color="#ffffff""##), "{svg}");
+
+    let Ok(font) = std::fs::read("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf") else {
+        eprintln!("font not available, skipping text export");
+        return;
+    };
+    let mut font_atlas = FontAtlas::default();
+    let font = font_atlas.add(&font);
+    let text = Text::new(vec![Span::new("a < b", 16.0, 20.0, font, Color::from_rgb(0, 0, 0))], None, Align::Left, None);
+    let svg = DrawList::from(vec![(Area((4.0, 8.0), None), CanvasItem::Text(text))]).to_svg((64.0, 48.0), &mut font_atlas, |_| None);
+    assert!(svg.contains(r#"font-family="DejaVu Sans" font-size="16""#) && svg.contains(">a &lt; b</text>"), "{svg}");
+}
+
+/// Images are embedded cut to their shape, what SVG cannot draw is rasterized and items of images
+/// without pixels are left out.
+#[test]
+fn draw_lists_embed_images_and_rasterize_the_rest() {
+    let mut atlas = ImageAtlas::default();
+    let raw = RgbaImage::from_fn(4, 2, |x, _| image::Rgba([x as u8 * 60, 0, 0, 255]));
+    let image = atlas.add(raw.clone());
+    let missing = atlas.add(RgbaImage::from_pixel(2, 2, image::Rgba([0, 255, 0, 255])));
+    let (red, blue) = (Color::from_rgb(255, 0, 0), Color::from_rgb(0, 0, 255));
+
+    let mut list = DrawList::new();
+    list.push(Area((0.0, 0.0), None), CanvasItem::Image(Shape::Ellipse(0.0, (8.0, 8.0)), image.clone(), None));
+    list.push(Area((8.0, 0.0), None), CanvasItem::Image(Shape::Rectangle(Border::default(), (8.0, 8.0)), missing, None));
+    list.push(Area((0.0, 8.0), None), CanvasItem::Fill(Shape::Rectangle(Border::default(), (4.0, 4.0)), Fill::Conic(red, blue, 0.0, Easing::Linear)));
+    list.push(Area((0.0, 0.0), None), CanvasItem::Image(Shape::Rectangle(Border::default(), (2.0, 1.0)), atlas.insert_region(&image, (2, 0, 2, 1)), Some(blue)));
+    let svg = list.to_svg((16.0, 16.0), &mut FontAtlas::default(), |i| (*i == image).then(|| raw.clone()));
+
+    assert!(svg.contains(r#"preserveAspectRatio="xMidYMid slice" clip-path="url(#d1)""#), "{svg}");
+    let images = embedded(&svg);
+    assert_eq!(images.len(), 3, "{svg}");
+    assert_eq!(images[0], raw);
+    // The conic gradient starts red on the right and turns blue to the left.
+    let conic = &images[1];
+    assert!(svg.contains(r#"<image x="0" y="8" width="4" height="4""#), "{svg}");
+    assert!(conic.get_pixel(3, 1).0[0] > 200 && conic.get_pixel(0, 1).0[2] > 200, "{conic:?}");
+    assert!(conic.pixels().all(|p| p.0[3] == 255));
+    // Regions are cut from the image and tints take the color and keep the alpha.
+    assert_eq!(images[2].dimensions(), (2, 1));
+    assert!(images[2].pixels().all(|p| p.0 == [0, 0, 255, 255]));
+
+    // Patterns are rasterized from the top left of their shape.
+    let stripes = PatternFill{kind: PatternKind::Stripes{angle: 0.0, width: 2.0}, foreground: red, background: blue, scale: 1.0};
+    let list = DrawList::from(vec![(Area((4.0, 4.0), None), CanvasItem::Fill(Shape::Rectangle(Border::default(), (4.0, 8.0)), stripes.into()))]);
+    let images = embedded(&list.to_svg((16.0, 16.0), &mut FontAtlas::default(), |_| None));
+    assert_eq!(images[0].dimensions(), (4, 8));
+    for y in 0..8 {
+        let color = if y % 4 < 2 {[255, 0, 0, 255]} else {[0, 0, 255, 255]};
+        assert!(images[0].rows().nth(y).unwrap().all(|p| p.0 == color), "row {y} of {:?}", images[0]);
+    }
+    let checker = PatternFill{kind: PatternKind::Checkerboard{cell_size: 2}, scale: 2.0, ..stripes};
+    let list = DrawList::from(vec![(Area((0.0, 0.0), None), CanvasItem::Fill(Shape::Rectangle(Border::default(), (12.0, 8.0)), checker.into()))]);
+    let images = embedded(&list.to_svg((16.0, 16.0), &mut FontAtlas::default(), |_| None));
+    for (x, y, pixel) in images[0].enumerate_pixels() {
+        let color = if (x / 4 + y / 4) % 2 == 0 {[255, 0, 0, 255]} else {[0, 0, 255, 255]};
+        assert_eq!(pixel.0, color, "pixel at ({x}, {y})");
+    }
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn rasterized_images_are_the_same_across_threads() {
+    let mut atlas = ImageAtlas::default();
+    let raw = RgbaImage::from_fn(64, 64, |x, y| image::Rgba([x as u8 * 4, y as u8 * 4, 90, 255]));
+    let image = atlas.add(raw.clone());
+    let mut list = DrawList::new();
+    list.push(Area((0.0, 0.0), None), CanvasItem::Fill(Shape::Ellipse(0.0, (300.0, 200.0)), Fill::Conic(Color::from_rgb(255, 0, 0), Color::from_rgb(0, 0, 255), 0.5, Easing::Linear)));
+    list.push(Area((20.0, 40.0), None), CanvasItem::ImageStroke(Shape::RoundedRectangle(6.0.into(), (250.0, 150.0), 30.0.into()), image.clone(), None));
+    let export = |threads: usize| rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap()
+        .install(|| list.to_svg((320.0, 240.0), &mut FontAtlas::default(), |i| (*i == image).then(|| raw.clone())));
+    let (serial, spread) = (export(1), export(8));
+    assert_eq!(embedded(&serial).len(), 2);
+    assert!(serial == spread, "images rasterized across threads differ");
+}
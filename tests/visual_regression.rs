@@ -0,0 +1,1732 @@
+//! Renders every shape to an offscreen texture and compares it against the reference images in
+//! `tests/fixtures`. Runs against a headless adapter, and the text tests with DejaVu Sans. Tests
+//! fail without them unless `WGPU_CANVAS_SKIP_UNAVAILABLE=1` is set, which skips them instead.
+//!
+//! Rewrite the references after an intended change with
+//! `UPDATE_FIXTURES=1 cargo test --test visual_regression`.
+
+use wgpu_canvas::prelude::*;
+
+const SIZE: u32 = 64;
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+/// Largest difference of a channel allowed before a pixel counts as changed.
+const THRESHOLD: u8 = 8;
+/// Set to skip the tests that need an adapter or font the machine lacks instead of failing them.
+const SKIP_UNAVAILABLE: &str = "WGPU_CANVAS_SKIP_UNAVAILABLE";
+/// Font of the tests that lay out text.
+const DEJAVU: &str = "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf";
+
+/// Fail the test for what is missing, or return from it when [`SKIP_UNAVAILABLE`] is set.
+macro_rules! require {
+    ($value:expr, $missing:literal) => {
+        match $value {
+            Some(value) => value,
+            None if std::env::var_os(SKIP_UNAVAILABLE).is_some() => {
+                eprintln!("{}, skipping", $missing);
+                return;
+            },
+            None => panic!("{}, set {SKIP_UNAVAILABLE}=1 to skip the tests that need it", $missing),
+        }
+    };
+}
+
+/// The headless adapter the test renders with, see [`require`].
+macro_rules! gpu {
+    () => {require!(Gpu::new(), "no adapter available")};
+}
+
+/// DejaVu Sans added to the font atlas of the gpu, see [`require`].
+macro_rules! dejavu {
+    ($gpu:expr) => {{
+        let font = require!(std::fs::read(DEJAVU).ok(), "DejaVu Sans not installed");
+        $gpu.font_atlas.add(&font)
+    }};
+}
+
+struct Gpu {
+    adapter: wgpu::Adapter,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    renderer: CanvasRenderer,
+    image_atlas: ImageAtlas,
+    font_atlas: FontAtlas,
+}
+
+impl Gpu {
+    fn new() -> Option<Self> {
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default())).ok()?;
+        let required_features = adapter.features() & wgpu::Features::TEXTURE_COMPRESSION_BC;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor{required_features, ..Default::default()})).ok()?;
+        let renderer = CanvasRenderer::new(&queue, &device, &FORMAT, wgpu::MultisampleState::default(), None);
+        Some(Gpu{adapter, device, queue, renderer, image_atlas: ImageAtlas::default(), font_atlas: FontAtlas::default()})
+    }
+
+    /// Render the items on a black background and read the pixels back. The width has to
+    /// be a multiple of 64 to keep the rows of the read back aligned.
+    fn render(&mut self, (width, height): (u32, u32), items: Vec<(Area, CanvasItem)>) -> RgbaImage {
+        self.renderer.prepare(&self.device, &self.queue, width as f32, height as f32, &mut self.image_atlas, &mut self.font_atlas, items);
+        self.draw((width, height))
+    }
+
+    /// Render the items of the last [`render`](Self::render) again without preparing them.
+    fn draw(&mut self, (width, height): (u32, u32)) -> RgbaImage {
+        let extent = wgpu::Extent3d{width, height, depth_or_array_layers: 1};
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor{
+            label: None,
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor{
+            label: None,
+            size: (width * height * 4) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor{
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment{
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations{load: wgpu::LoadOp::Clear(wgpu::Color::BLACK), store: wgpu::StoreOp::Store},
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.renderer.render(&mut render_pass);
+        }
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo{
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout{offset: 0, bytes_per_row: Some(width * 4), rows_per_image: Some(height)},
+            },
+            extent
+        );
+        self.queue.submit([encoder.finish()]);
+
+        buffer.slice(..).map_async(wgpu::MapMode::Read, |_| {});
+        self.device.poll(wgpu::PollType::Wait).unwrap();
+        let pixels = buffer.slice(..).get_mapped_range().to_vec();
+        RgbaImage::from_raw(width, height, pixels).unwrap()
+    }
+}
+
+/// Every shape variant, filled and stroked, named after its fixture.
+fn shapes() -> Vec<(&'static str, Shape)> {
+    let size = (48.0, 48.0);
+    let tail = Tail{edge: Direction::Down, offset: 24.0, width: 12.0, height: 8.0};
+    let path = Path::new()
+        .move_to((8.0, 40.0))
+        .line_to((24.0, 8.0))
+        .quad_to((56.0, 8.0), (40.0, 40.0))
+        .cubic_to((32.0, 24.0), (16.0, 56.0), (8.0, 40.0))
+        .close();
+    vec![
+        ("ellipse", Shape::Ellipse(0.0, size)),
+        ("ellipse_stroke", Shape::Ellipse(4.0, size)),
+        ("rectangle", Shape::Rectangle(Border::default(), size)),
+        ("rectangle_stroke", Shape::Rectangle(Border(2.0, 4.0, 6.0, 8.0), size)),
+        ("rounded_rectangle", Shape::RoundedRectangle(Border::default(), size, Radius(16.0, 8.0))),
+        ("rounded_rectangle_stroke", Shape::RoundedRectangle(4.0.into(), size, 12.0.into())),
+        ("rounded_rectangle_sides", Shape::RoundedRectangleSides(Border::default(), size, 16.0.into(), CornerMask::TOP_LEFT | CornerMask::BOTTOM_RIGHT)),
+        ("chevron", Shape::Chevron(Direction::Right, 6.0, (32.0, 48.0))),
+        ("speech_bubble", Shape::SpeechBubble(0.0, size, 8.0.into(), tail)),
+        ("speech_bubble_stroke", Shape::SpeechBubble(3.0, size, 8.0.into(), tail)),
+        ("star", Shape::Star(0.0, size, 5, 0.45)),
+        ("star_stroke", Shape::Star(3.0, size, 5, 0.45)),
+        ("path", Shape::Path(0.0, path.clone())),
+        ("path_stroke", Shape::Path(4.0, path)),
+        ("wave", Shape::Wave(3.0, (48.0, 16.0), 16.0)),
+    ]
+}
+
+#[test]
+fn shapes_match_references() {
+    let mut gpu = gpu!();
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let update = std::env::var_os("UPDATE_FIXTURES").is_some();
+
+    let mut failures = vec![];
+    for (name, shape) in shapes() {
+        let image = gpu.render((SIZE, SIZE), vec![(Area((8.0, 8.0), None), CanvasItem::Shape(shape, Color::from_rgb(255, 255, 255)))]);
+        let path = fixtures.join(format!("{name}.png"));
+        if update {
+            image.save(&path).unwrap();
+            continue;
+        }
+
+        let reference = image::open(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display())).into_rgba8();
+        let changed = image.pixels().zip(reference.pixels()).filter(|(a, b)|
+            a.0.iter().zip(b.0).any(|(a, b)| a.abs_diff(b) > THRESHOLD)
+        ).count();
+        if changed > 0 {failures.push(format!("{name}: {changed} pixels differ"));}
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+/// A stroked ellipse covers exactly its ring, checked away from the anti-aliased edges.
+#[test]
+fn ellipse_stroke_covers_only_the_ring() {
+    let mut gpu = gpu!();
+    let color = Color::from_rgb(255, 0, 0);
+    let (stroke, margin) = (5.0, 1.5);
+    let image = gpu.render((128, 80), vec![(Area((10.0, 10.0), None), CanvasItem::Shape(Shape::Ellipse(stroke, (100.0, 60.0)), color))]);
+
+    let (cx, cy, rx, ry) = (60.0, 40.0, 50.0, 30.0);
+    let inside = |x: f32, y: f32, grow: f32| ((x - cx) / (rx + grow)).powi(2) + ((y - cy) / (ry + grow)).powi(2) < 1.0;
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+        if !inside(x, y, margin) || inside(x, y, -stroke - margin) {
+            assert_eq!(pixel.0, [0, 0, 0, 255], "pixel at ({x}, {y}) outside of the ring is drawn");
+        } else if inside(x, y, -margin) && !inside(x, y, margin - stroke) {
+            assert!(pixel.0.iter().zip([255, 0, 0, 255]).all(|(a, b)| a.abs_diff(b) <= THRESHOLD), "pixel at ({x}, {y}) in the ring is {:?}", pixel.0);
+        }
+    }
+}
+
+/// Holes are left by an inner contour winding against the outer one under both rules, and by one
+/// winding the same way only under the even-odd rule.
+#[test]
+fn path_fill_rules_leave_holes() {
+    let mut gpu = gpu!();
+    let square = |path: Path, (x, y): (f32, f32), size: f32, clockwise: bool| {
+        let corners = [(x, y), (x + size, y), (x + size, y + size), (x, y + size)];
+        let mut path = path.move_to(corners[0]);
+        for i in 1..4 {path = path.line_to(corners[if clockwise {i} else {4 - i}]);}
+        path.close()
+    };
+    let cases = [
+        (false, FillRule::NonZero, true),
+        (false, FillRule::EvenOdd, true),
+        (true, FillRule::NonZero, false),
+        (true, FillRule::EvenOdd, true),
+    ];
+    let items = cases.iter().enumerate().map(|(i, (clockwise, rule, _))| {
+        let path = square(Path::new().fill_rule(*rule), (0.0, 0.0), 40.0, true);
+        let path = square(path, (10.0, 10.0), 20.0, *clockwise);
+        (Area((i as f32 * 48.0, 4.0), None), CanvasItem::Shape(Shape::Path(0.0, path), Color::from_rgb(255, 255, 255)))
+    }).collect();
+    let image = gpu.render((192, 48), items);
+
+    for (i, (clockwise, rule, hole)) in cases.into_iter().enumerate() {
+        let x = i as u32 * 48;
+        assert_eq!(image.get_pixel(x + 5, 24).0, [255, 255, 255, 255], "{rule:?} with clockwise hole {clockwise} does not fill");
+        let expected = if hole {[0, 0, 0, 255]} else {[255, 255, 255, 255]};
+        assert_eq!(image.get_pixel(x + 20, 24).0, expected, "{rule:?} with clockwise hole {clockwise} fills wrong");
+    }
+}
+
+/// A thin elliptical ring is drawn all the way around, including where it meets the axes, and
+/// nothing of an ellipse lands outside of its area, even at a single pixel.
+#[test]
+fn ellipse_ring_has_no_gaps() {
+    let mut gpu = gpu!();
+    let white = Color::from_rgb(255, 255, 255);
+    let image = gpu.render((128, 128), vec![
+        (Area((10.0, 10.0), None), CanvasItem::Shape(Shape::Ellipse(1.0, (100.0, 100.0)), white)),
+        (Area((120.0, 120.0), None), CanvasItem::Shape(Shape::Ellipse(0.0, (1.0, 1.0)), white)),
+    ]);
+
+    for step in 0..720 {
+        let angle = step as f32 / 720.0 * std::f32::consts::TAU;
+        let drawn = (0..12).map(|i| 48.0 + i as f32 * 0.25).any(|r| {
+            let (x, y) = (60.0 + r * angle.cos(), 60.0 + r * angle.sin());
+            image.get_pixel(x as u32, y as u32).0[0] > 0
+        });
+        assert!(drawn, "gap in the ring at {} degrees", step as f32 / 2.0);
+    }
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let in_ring = (10..110).contains(&x) && (10..110).contains(&y);
+        if !in_ring && (x, y) != (120, 120) {
+            assert_eq!(pixel.0, [0, 0, 0, 255], "pixel at ({x}, {y}) outside of the ellipses is drawn");
+        }
+    }
+}
+
+/// Caps reach as far as their style says and blend once with the segment they end, so translucent
+/// strokes do not darken where the two meet.
+#[test]
+fn line_caps_cover_once() {
+    let mut gpu = gpu!();
+    let caps = [(LineCap::Butt, 0.0), (LineCap::Square, 8.0), (LineCap::Round, 8.0)];
+    let items = caps.iter().enumerate().map(|(i, (cap, _))| {
+        let path = Path::new().line_cap(*cap).move_to((24.0, 0.0)).line_to((104.0, 0.0));
+        (Area((0.0, 16.0 + i as f32 * 32.0), None), CanvasItem::Shape(Shape::Path(16.0, path), Color::from_rgba8(255, 255, 255, 128)))
+    }).collect();
+    let image = gpu.render((128, 96), items);
+
+    for (i, (cap, reach)) in caps.into_iter().enumerate() {
+        let y = 16 + i as u32 * 32;
+        let body = image.get_pixel(64, y).0;
+        assert!(body[0] > 0 && body[0] < 255, "{cap:?} body is {body:?}");
+        for x in [24 - reach as u32, 23, 24, 25, 103, 104, 103 + reach as u32] {
+            if x < 24 - reach as u32 || x > 103 + reach as u32 {continue;}
+            for dy in [0, 3] {
+                assert_eq!(image.get_pixel(x, y - dy).0, body, "{cap:?} at ({x}, {}) differs from its body", y - dy);
+            }
+        }
+        assert_eq!(image.get_pixel(22 - reach as u32, y).0, [0, 0, 0, 255], "{cap:?} reaches too far");
+        assert_eq!(image.get_pixel(105 + reach as u32, y).0, [0, 0, 0, 255], "{cap:?} reaches too far");
+    }
+    let corner = image.get_pixel(17, 16 + 2 * 32 - 7).0;
+    assert_eq!(corner, [0, 0, 0, 255], "round cap covers its corner");
+}
+
+/// A single pixel ellipse fills its pixel, and strokes of half the size or more fill the ellipse
+/// rather than leaving a hole in its middle.
+#[test]
+fn ellipse_degenerate_sizes() {
+    let mut gpu = gpu!();
+    let white = Color::from_rgb(255, 255, 255);
+    let image = gpu.render((64, 16), vec![(Area((4.0, 4.0), None), CanvasItem::Shape(Shape::Ellipse(0.0, (1.0, 1.0)), white))]);
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let expected = if (x, y) == (4, 4) {[255, 255, 255, 255]} else {[0, 0, 0, 255]};
+        assert_eq!(pixel.0, expected, "pixel at ({x}, {y}) of a single pixel ellipse");
+    }
+
+    let disc = |gpu: &mut Gpu, stroke: f32| gpu.render((64, 16), vec![(Area((3.0, 3.0), None), CanvasItem::Shape(Shape::Ellipse(stroke, (10.0, 10.0)), white))]);
+    let filled = disc(&mut gpu, 0.0);
+    assert_eq!(filled.get_pixel(8, 8).0, [255, 255, 255, 255]);
+    for stroke in [5.0, 6.0, 100.0] {
+        assert!(disc(&mut gpu, stroke) == filled, "stroke of {stroke} does not fill the ellipse");
+    }
+}
+
+/// Strokes wider than half of the smaller side of long thin shapes meet in the middle and fill
+/// them, and image strokes stretch their texture from the outline to that middle.
+#[test]
+fn strokes_past_the_smaller_side_fill_the_shape() {
+    let mut gpu = gpu!();
+    let white = Color::from_rgb(255, 255, 255);
+    let size = (200.0, 12.0);
+    let shapes = |stroke: f32| [
+        Shape::Ellipse(stroke, size),
+        Shape::Rectangle(stroke.into(), size),
+        Shape::RoundedRectangle(stroke.into(), size, 4.0.into()),
+    ];
+    let render = |gpu: &mut Gpu, stroke: f32| shapes(stroke).map(|shape| gpu.render((256, 32), vec![(Area((8.0, 8.0), None), CanvasItem::Shape(shape, white))]));
+    let filled = render(&mut gpu, 0.0);
+    for stroke in [6.0, 50.0, 150.0] {
+        assert!(render(&mut gpu, stroke) == filled, "stroke of {stroke} does not fill the shapes");
+        for shape in shapes(stroke) {
+            assert!(shape.contains(&Area((8.0, 8.0), None), (108.0, 14.0), true), "{shape:?} is hollow");
+        }
+    }
+
+    let halves = RgbaImage::from_fn(4, 4, |_, y| image::Rgba(if y < 2 {[255, 0, 0, 255]} else {[0, 0, 255, 255]}));
+    let image = gpu.image_atlas.add(halves);
+    for shape in [Shape::Rectangle(50.0.into(), (192.0, 16.0)), Shape::RoundedRectangle(50.0.into(), (192.0, 16.0), 4.0.into()), Shape::Ellipse(50.0, (192.0, 16.0))] {
+        let drawn = gpu.render((256, 32), vec![(Area((8.0, 8.0), None), CanvasItem::ImageStroke(shape.clone(), image.clone(), None))]);
+        for x in [88, 104, 120] {
+            let (outline, middle) = (drawn.get_pixel(x, 10).0, drawn.get_pixel(x, 15).0);
+            assert!(outline[0] > 128 && outline[2] < 128, "{shape:?} at the outline of {x} is {outline:?}");
+            assert!(middle[2] > 128 && middle[0] < 128, "{shape:?} in the middle of {x} is {middle:?}");
+        }
+    }
+}
+
+/// Textures of ellipse strokes are spaced evenly along the outline of wide ellipses, rather than
+/// bunching up at the ends of the long axis.
+#[test]
+fn ellipse_stroke_textures_follow_the_outline() {
+    use std::f64::consts::PI;
+    let mut gpu = gpu!();
+    let stripes = RgbaImage::from_fn(12, 1, |x, _| image::Rgba(if x % 2 == 0 {[255, 0, 0, 255]} else {[0, 0, 255, 255]}));
+    let image = gpu.image_atlas.add(stripes);
+    let (a, b, stroke) = (72.0f64, 16.0f64, 6.0f64);
+    let drawn = gpu.render((192, 64), vec![(Area((8.0, 16.0), None), CanvasItem::ImageStroke(Shape::Ellipse(stroke as f32, (144.0, 32.0)), image, None))]);
+
+    // Length of the outline from the top left clockwise to the eccentric angle.
+    let arc = |t: f64| {
+        let t = if t < -0.75 * PI {t + 2.0 * PI} else {t};
+        let steps = 4000;
+        let h = (t + 0.75 * PI) / steps as f64;
+        (0..steps).map(|i| {
+            let s = -0.75 * PI + (i as f64 + 0.5) * h;
+            (a * s.sin()).hypot(b * s.cos()) * h
+        }).sum::<f64>()
+    };
+    let perimeter = arc(1.25 * PI - 1e-9);
+
+    // Walk around the middle of the band, noting where along the outline the stripes change.
+    let mut changes = vec![];
+    let mut last = None;
+    for i in 0..4000 {
+        let t = i as f64 / 4000.0 * 2.0 * PI - PI;
+        let (x, y) = (80.0 + (a - stroke / 2.0) * t.cos(), 32.0 + (b - stroke / 2.0) * t.sin());
+        let pixel = drawn.get_pixel(x as u32, y as u32).0;
+        let red = pixel[0] > pixel[2];
+        let (dx, dy) = (x.floor() + 0.5 - 80.0, y.floor() + 0.5 - 32.0);
+        if last.is_some_and(|last| last != red) {changes.push(arc((dy / b).atan2(dx / a)));}
+        last = Some(red);
+    }
+    changes.sort_by(f64::total_cmp);
+    changes.dedup_by(|next, previous| *next - *previous < 4.0);
+    // The change at the start of the outline is seen on both sides of it.
+    if changes.len() > 1 && changes[changes.len() - 1] - changes[0] > perimeter - 4.0 {changes.pop();}
+    assert_eq!(changes.len(), 12, "stripes change at {changes:?}");
+    let spacing = perimeter / 12.0;
+    for (i, change) in changes.iter().enumerate() {
+        let gap = (changes[(i + 1) % 12] - change).rem_euclid(perimeter);
+        assert!((gap - spacing).abs() < spacing * 0.2, "stripe at {change:.1} of {perimeter:.1} is {gap:.1} long rather than {spacing:.1}");
+    }
+}
+
+/// Images of rounded shapes blend over the items beneath them, which show through past their
+/// corners.
+#[test]
+fn image_fills_blend_over_the_items_beneath() {
+    let mut gpu = gpu!();
+    let red = Color::from_rgb(255, 0, 0);
+    let blue = gpu.image_atlas.add(RgbaImage::from_pixel(4, 4, image::Rgba([0, 0, 255, 255])));
+    for shape in [Shape::RoundedRectangle(Border::default(), (48.0, 48.0), 16.0.into()), Shape::Ellipse(0.0, (48.0, 48.0))] {
+        let drawn = gpu.render((64, 64), vec![
+            (Area((0.0, 0.0), None), CanvasItem::Shape(Shape::Rectangle(0.0.into(), (64.0, 64.0)), red)),
+            (Area((8.0, 8.0), None), CanvasItem::Image(shape.clone(), blue.clone(), None)),
+        ]);
+        let (corner, middle) = (drawn.get_pixel(9, 9).0, drawn.get_pixel(32, 32).0);
+        assert!(corner[0] > 250 - THRESHOLD && corner[2] < THRESHOLD, "{shape:?} hides the item beneath its corner with {corner:?}");
+        assert!(middle[2] > 250 - THRESHOLD && middle[0] < THRESHOLD, "{shape:?} does not draw over the item beneath, {middle:?}");
+    }
+}
+
+/// Image strokes blend over the items beneath them, which show through their holes and around
+/// their anti-aliased edges.
+#[test]
+fn image_strokes_blend_over_the_items_beneath() {
+    let mut gpu = gpu!();
+    let red = Color::from_rgb(255, 0, 0);
+    let blue = gpu.image_atlas.add(RgbaImage::from_pixel(4, 4, image::Rgba([0, 0, 255, 255])));
+    for shape in [Shape::Rectangle(6.0.into(), (48.0, 48.0)), Shape::RoundedRectangle(6.0.into(), (48.0, 48.0), 8.0.into()), Shape::Ellipse(6.0, (48.0, 48.0))] {
+        let drawn = gpu.render((64, 64), vec![
+            (Area((0.0, 0.0), None), CanvasItem::Shape(Shape::Rectangle(0.0.into(), (64.0, 64.0)), red)),
+            (Area((8.0, 8.0), None), CanvasItem::ImageStroke(shape.clone(), blue.clone(), None)),
+        ]);
+        let (hole, stroke) = (drawn.get_pixel(32, 32).0, drawn.get_pixel(32, 10).0);
+        assert!(hole[0] > 250 - THRESHOLD && hole[2] < THRESHOLD, "{shape:?} hides the item beneath its hole with {hole:?}");
+        assert!(stroke[2] > 250 - THRESHOLD && stroke[0] < THRESHOLD, "{shape:?} does not draw its stroke over the item beneath, {stroke:?}");
+    }
+}
+
+/// Distance from (x, y), relative to the center of an ellipse with the radii a and b, inwards to its outline.
+fn ellipse_depth(x: f32, y: f32, a: f32, b: f32) -> f32 {
+    if x == 0.0 && y == 0.0 {return a.min(b);}
+    let (nx, ny) = (x / x.abs().max(y.abs()), y / x.abs().max(y.abs()));
+    (1.0 - (x / a).hypot(y / b)) * (nx / a).hypot(ny / b) / (nx / (a * a)).hypot(ny / (b * b))
+}
+
+/// Ellipses of every size from 3 to 64 pixels, odd or even, filled or stroked, are symmetric about
+/// both of their axes, reach their size and leave no holes inside their outline or stroke.
+#[test]
+fn small_ellipses_are_symmetric() {
+    let mut gpu = gpu!();
+    let white = Color::from_rgb(255, 255, 255);
+    let sizes = (3..=64).flat_map(|w| [(w, w), (w, 3 + w / 2)]).collect::<Vec<(u32, u32)>>();
+    let cell = |i: usize| ((i % 14) as u32 * 72 + 4, (i / 14) as u32 * 72 + 4);
+    for stroke in [0.0, 1.0, 2.0] {
+        let image = gpu.render((1024, 720), sizes.iter().enumerate().map(|(i, (w, h))| {
+            let (x, y) = cell(i);
+            (Area((x as f32, y as f32), None), CanvasItem::Shape(Shape::Ellipse(stroke, (*w as f32, *h as f32)), white))
+        }).collect());
+
+        for (i, &(w, h)) in sizes.iter().enumerate() {
+            let (x, y) = cell(i);
+            let at = |dx: u32, dy: u32| image.get_pixel(x + dx, y + dy).0[0];
+            for (dx, dy) in (0..w).flat_map(|dx| (0..h).map(move |dy| (dx, dy))) {
+                let (mirrored_x, mirrored_y) = (at(w - 1 - dx, dy), at(dx, h - 1 - dy));
+                assert!(at(dx, dy).abs_diff(mirrored_x) <= 2 && at(dx, dy).abs_diff(mirrored_y) <= 2, "{w}x{h} with stroke {stroke} is lopsided at ({dx}, {dy})");
+            }
+            assert!(at(w / 2, 0) > 0 && at(0, h / 2) > 0, "{w}x{h} with stroke {stroke} falls short of its size");
+            // Pixels a whole pixel inside the outline and outside the stroke's hole are fully drawn.
+            let (a, b) = (w as f32 / 2.0, h as f32 / 2.0);
+            let hole = |dx: f32, dy: f32| stroke > 0.0 && stroke < a.min(b) && ellipse_depth(dx, dy, a - stroke, b - stroke) > 0.0;
+            for (dx, dy) in (0..w).flat_map(|dx| (0..h).map(move |dy| (dx, dy))) {
+                let (cx, cy) = (dx as f32 + 0.5 - a, dy as f32 + 0.5 - b);
+                if ellipse_depth(cx, cy, a, b) >= 1.0 && !hole(cx, cy) {
+                    assert!(at(dx, dy) >= 250, "{w}x{h} with stroke {stroke} has a hole at ({dx}, {dy})");
+                }
+            }
+        }
+    }
+}
+
+/// Both edges of a stroked ellipse fade out over a pixel instead of stepping from row to row, so
+/// partly covered pixels line the outline and the hole alike.
+#[test]
+fn ellipse_stroke_edges_are_smooth() {
+    let mut gpu = gpu!();
+    let stroke = 6.0;
+    let image = gpu.render((128, 80), vec![(Area((10.0, 10.0), None), CanvasItem::Shape(Shape::Ellipse(stroke, (100.0, 60.0)), Color::from_rgb(255, 255, 255)))]);
+
+    let (mut outer, mut inner) = (0, 0);
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if !(16..240).contains(&pixel.0[0]) {continue;}
+        match ellipse_depth(x as f32 + 0.5 - 60.0, y as f32 + 0.5 - 40.0, 50.0, 30.0) < stroke / 2.0 {
+            true => outer += 1,
+            false => inner += 1,
+        }
+    }
+    // Each edge runs around more than 200 pixels.
+    assert!(outer > 100 && inner > 100, "{outer} outer and {inner} inner edge pixels are smooth");
+}
+
+/// Zigzags turning at sharp, right and shallow angles are covered once everywhere, so translucent
+/// strokes do not darken at their joins, and the joins cover more the further they reach: bevels
+/// less than round joins, round joins less than miters. Miters over their limit are beveled.
+/// Strokes tessellated by lyon may overlap themselves and place their joins slightly differently,
+/// so they are only checked for the order of their coverage.
+#[test]
+fn line_joins_cover_once() {
+    let mut gpu = gpu!();
+    let (angles, widths) = ([30.0_f32, 90.0, 170.0], [1.0, 8.0, 40.0]);
+    let zigzag = |join: LineJoin, angle: f32| {
+        let (s, c) = (angle.to_radians() / 2.0).sin_cos();
+        let (dx, dy) = (s * 50.0, c * 50.0);
+        let x = -dx * 1.5;
+        Path::new().line_join(join).move_to((x, dy / 2.0)).line_to((x + dx, -dy / 2.0)).line_to((x + dx * 2.0, dy / 2.0)).line_to((x + dx * 3.0, -dy / 2.0))
+    };
+    let mut render = |join: LineJoin| {
+        let items = widths.iter().enumerate().flat_map(|(row, width)| angles.iter().enumerate().map(move |(column, angle)| {
+            let center = (100.0 + column as f32 * 200.0, 100.0 + row as f32 * 200.0);
+            (Area(center, None), CanvasItem::Shape(Shape::Path(*width, zigzag(join, *angle)), Color::from_rgba8(255, 255, 255, 128)))
+        })).collect();
+        let image = gpu.render((640, 600), items);
+        let mut counts = [[0; 3]; 3];
+        let background = [0, 0, 0, 255];
+        let body = *image.pixels().find(|p| p.0 != background).unwrap();
+        for (x, y, pixel) in image.enumerate_pixels() {
+            if pixel.0 == background || x >= 600 {continue;}
+            if !cfg!(feature = "lyon") {
+                assert_eq!(pixel.0, body.0, "{join:?} covers ({x}, {y}) more than once");
+            }
+            counts[y as usize / 200][x as usize / 200] += 1;
+        }
+        counts
+    };
+
+    let bevel = render(LineJoin::Bevel);
+    let round = render(LineJoin::Round);
+    let miter = render(LineJoin::Miter(4.0));
+    let limited = render(LineJoin::Miter(2.0));
+    for (row, width) in widths.iter().enumerate() {
+        for (column, angle) in angles.iter().enumerate() {
+            let (b, r, m, l) = (bevel[row][column], round[row][column], miter[row][column], limited[row][column]);
+            assert!(b <= r && r <= m, "{angle} degree joins at {width} pixels cover bevel {b}, round {r}, miter {m}");
+            if *width >= 40.0 && *angle < 170.0 {
+                assert!(b < r && r < m, "{angle} degree joins at {width} pixels cover bevel {b}, round {r}, miter {m}");
+            }
+            let expected = if 1.0 / (angle.to_radians() / 2.0).sin() > 2.0 {b} else {m};
+            match cfg!(feature = "lyon") {
+                true => assert!(b <= l && l <= m, "{angle} degree miters over their limit at {width} pixels"),
+                false => assert_eq!(l, expected, "{angle} degree miters over their limit at {width} pixels"),
+            }
+        }
+    }
+}
+
+/// Waves thinner than a pixel are drawn faintly in every column rather than breaking up, and
+/// start at the middle of their left edge.
+#[test]
+fn thin_waves_have_no_gaps() {
+    let mut gpu = gpu!();
+    let wave = Shape::Wave(0.5, (64.0, 6.0), 8.0);
+    let image = gpu.render((64, 16), vec![(Area((0.0, 5.0), None), CanvasItem::Shape(wave, Color::from_rgb(255, 255, 255)))]);
+    for x in 0..64 {
+        let column = (0..16).map(|y| image.get_pixel(x, y).0[0]).collect::<Vec<_>>();
+        assert!(column.iter().any(|c| *c > 0), "gap in the wave at {x}");
+        assert!(column.iter().all(|c| *c < 255), "wave thinner than a pixel covers one at {x}: {column:?}");
+        assert!(column[..5].iter().chain(&column[11..]).all(|c| *c == 0), "wave leaves its area at {x}: {column:?}");
+    }
+    let start = (0..16).map(|y| image.get_pixel(0, y).0[0]).collect::<Vec<_>>();
+    assert!(start[7] > 0 && start[8] > 0, "wave does not start at the middle: {start:?}");
+}
+
+/// Grid lines at fractional positions snap to whole pixels, so every pixel is either fully
+/// covered or untouched, and the lines at the ends stay inside the grid.
+#[test]
+fn grid_lines_are_sharp() {
+    let mut gpu = gpu!();
+    let grid = GridLines{
+        size: (100.0, 40.0),
+        rows: GridSpacing::Every(10.0),
+        columns: GridSpacing::At(vec![0.0, 25.3, 50.5, 100.0]),
+        thickness: 1.0,
+        color: Color::from_rgb(255, 255, 255),
+    };
+    let image = gpu.render((128, 64), vec![(Area((10.3, 4.6), None), CanvasItem::GridLines(grid))]);
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let inside = (10..110).contains(&x) && (5..45).contains(&y);
+        assert!(pixel.0 == [0, 0, 0, 255] || (inside && pixel.0 == [255, 255, 255, 255]), "pixel at ({x}, {y}) is {:?}", pixel.0);
+    }
+    let rows = (0..64).filter(|y| image.get_pixel(70, *y).0[0] == 255).collect::<Vec<_>>();
+    assert_eq!(rows, [5, 15, 25, 35, 44]);
+    let columns = (0..128).filter(|x| image.get_pixel(*x, 10).0[0] == 255).collect::<Vec<_>>();
+    assert_eq!(columns, [10, 35, 60, 109]);
+}
+
+/// Tiles repeat from the position of the area, including the ones starting before its bounds, and
+/// are cut at the bounds. Whole images and regions of them tile alike.
+#[test]
+fn tiled_images_repeat_within_bounds() {
+    let mut gpu = gpu!();
+    let halves = RgbaImage::from_fn(4, 2, |x, _| image::Rgba(if x < 2 {[255, 0, 0, 255]} else {[0, 0, 255, 255]}));
+    let whole = gpu.image_atlas.add(halves.clone());
+    let mut sheet = RgbaImage::from_pixel(8, 2, image::Rgba([0, 255, 0, 255]));
+    image::imageops::replace(&mut sheet, &halves, 2, 0);
+    let sheet = gpu.image_atlas.add(sheet);
+    let region = gpu.image_atlas.insert_region(&sheet, (2, 0, 4, 2));
+
+    for image in [whole, region] {
+        let mut canvas = Canvas::new(64.0, 16.0);
+        canvas.draw_tiled_image(image, (16, 16), Area((6.0, 0.0), Some((0.0, 0.0, 60.0, 16.0))));
+        let image = gpu.render((64, 16), canvas.into_items());
+        for y in [0, 8, 15] {
+            assert_eq!(image.get_pixel(1, y).0, [0, 0, 255, 255], "tile before the position at (1, {y})");
+            for x in [6, 22, 38, 54] {
+                assert_eq!(image.get_pixel(x + 3, y).0, [255, 0, 0, 255], "tile at {x} starts at ({}, {y})", x + 3);
+                if x + 11 < 60 {
+                    assert_eq!(image.get_pixel(x + 11, y).0, [0, 0, 255, 255], "tile at {x} ends at ({}, {y})", x + 11);
+                }
+            }
+            for x in 60..64 {
+                assert_eq!(image.get_pixel(x, y).0, [0, 0, 0, 255], "pixel at ({x}, {y}) past the bounds is drawn");
+            }
+        }
+    }
+}
+
+/// Runs of quads drawing the same image, merged into fewer draws, still draw every quad with its
+/// own image, across more quads than are built on one thread.
+#[test]
+fn runs_of_images_draw_every_quad() {
+    let mut gpu = gpu!();
+    let red = gpu.image_atlas.add(RgbaImage::from_pixel(1, 1, image::Rgba([255, 0, 0, 255])));
+    let blue = gpu.image_atlas.add(RgbaImage::from_pixel(1, 1, image::Rgba([0, 0, 255, 255])));
+    let is_red = |i: u32| !(i / 7).is_multiple_of(3);
+    let items = (0..64 * 48).map(|i| {
+        let image = if is_red(i) {red.clone()} else {blue.clone()};
+        (Area(((i % 64) as f32, (i / 64) as f32), None), CanvasItem::Image(Shape::Rectangle(Border::default(), (1.0, 1.0)), image, None))
+    }).collect();
+    let image = gpu.render((64, 48), items);
+
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let expected = if is_red(y * 64 + x) {[255, 0, 0, 255]} else {[0, 0, 255, 255]};
+        assert_eq!(pixel.0, expected, "pixel at ({x}, {y}) has the wrong image");
+    }
+}
+
+/// Progress bars fill the clamped fraction of their width from the left and draw their border over
+/// both fills.
+#[test]
+fn progress_bars_fill_their_value() {
+    let mut gpu = gpu!();
+    let style = ProgressBarStyle{
+        background: Color::from_rgb(0, 0, 255).into(),
+        foreground: Color::from_rgb(255, 0, 0).into(),
+        corner_radius: 0.0,
+        border: None,
+    };
+    let bordered = ProgressBarStyle{border: Some(BorderStyle{stroke: 2.0.into(), fill: Color::from_rgb(0, 255, 0).into()}), ..style};
+    let mut canvas = Canvas::new(64.0, 48.0);
+    canvas.draw_progress_bar(0.25, style, (64.0, 16.0), Area((0.0, 0.0), None));
+    canvas.draw_progress_bar(1.5, style, (64.0, 16.0), Area((0.0, 16.0), None));
+    canvas.draw_progress_bar(0.5, bordered, (64.0, 16.0), Area((0.0, 32.0), None));
+    let image = gpu.render((64, 48), canvas.into_items());
+
+    let (red, blue, green) = ([255, 0, 0, 255], [0, 0, 255, 255], [0, 255, 0, 255]);
+    let check = |x: u32, y: u32, expected: [u8; 4], what: &str| {
+        let pixel = image.get_pixel(x, y).0;
+        assert!(pixel.iter().zip(expected).all(|(a, b)| a.abs_diff(b) <= THRESHOLD), "{what} at {x} is {pixel:?}");
+    };
+    for x in 0..64 {
+        check(x, 8, if x < 16 {red} else {blue}, "quarter bar");
+        check(x, 24, red, "full bar");
+        check(x, 40, if !(2..62).contains(&x) {green} else if x < 32 {red} else {blue}, "bordered bar");
+        check(x, 32, green, "top border");
+    }
+}
+
+/// Decorated spans get a line across all of their glyphs in their color, and their bounds cover
+/// them on the lines they are wrapped onto.
+#[test]
+fn text_decorations_span_their_glyphs() {
+    let mut gpu = gpu!();
+    let font = dejavu!(gpu);
+    let span = |text: &str, color| Span::new(text, 16.0, 20.0, font.clone(), color);
+    let (white, red, green) = (Color::from_rgb(255, 255, 255), Color::from_rgb(255, 0, 0), Color::from_rgb(0, 255, 0));
+    let text = Text::new(vec![
+        span("see ", white),
+        span("this link", red).decoration(Decoration::UNDERLINE),
+        span(" and ", white),
+        span("that", green).decoration(Decoration::UNDERLINE | Decoration::STRIKETHROUGH),
+    ], Some(100.0), Align::Left, None);
+
+    let link = text.span_bounds(&mut gpu.font_atlas, 1);
+    let that = text.span_bounds(&mut gpu.font_atlas, 3);
+    assert_eq!(link.len(), 1, "link is on one line: {link:?}");
+    assert_eq!(that.len(), 1, "wrapped span is on one line: {that:?}");
+    assert!(that[0].1 >= link[0].1 + link[0].3, "wrapped span is not below the link: {that:?}");
+
+    let image = gpu.render((128, 64), vec![(Area((0.0, 0.0), None), CanvasItem::Text(text))]);
+    let lines = |(x, y, w, h): (f32, f32, f32, f32), channel: usize| (y as u32..(y + h) as u32).filter(|y|
+        (x.ceil() as u32..(x + w).floor() as u32).all(|x| image.get_pixel(x, *y).0[channel] > 0)
+    ).collect::<Vec<_>>();
+    let underline = lines(link[0], 0);
+    assert_eq!(underline.len(), 1, "link has a single line across it: {underline:?}");
+    assert!((0..30).all(|x| image.get_pixel(x, underline[0]).0 == [0, 0, 0, 255]), "undecorated span is underlined");
+    assert_eq!(lines(that[0], 1).len(), 2, "span has no underline and strikethrough across it");
+}
+
+/// Shadows are drawn under the text at their offset, and blurred ones cover more with edges that
+/// fade out, without getting darker than the color of the shadow.
+#[test]
+fn text_shadows_fall_behind_the_text() {
+    let mut gpu = gpu!();
+    let font = dejavu!(gpu);
+    let text = Text::new(vec![Span::new("Hi", 24.0, 28.0, font, Color::from_rgb(0, 255, 0))], None, Align::Left, None);
+    let mut draw = |blur: f32| {
+        let mut canvas = Canvas::new(64.0, 40.0);
+        let shadow = ShadowOptions{offset: (3.0, 3.0), blur, color: Color::from_rgba8(255, 0, 0, 128)};
+        canvas.draw_shadow_text(&mut gpu.font_atlas, &mut gpu.image_atlas, text.clone(), Area((8.0, 4.0), None), shadow, 1.0);
+        gpu.render((64, 40), canvas.into_items())
+    };
+    let (sharp, blurred) = (draw(0.0), draw(2.0));
+    // The lowest and rightmost pixels of the channel.
+    let extent = |image: &RgbaImage, channel: usize| image.enumerate_pixels().filter(|(_, _, p)| p.0[channel] > 64)
+        .fold((0, 0), |(x, y), (px, py, _)| (x.max(px), y.max(py)));
+    let (text, shadow) = (extent(&sharp, 1), extent(&sharp, 0));
+    assert!(shadow.0.abs_diff(text.0 + 3) <= 1 && shadow.1.abs_diff(text.1 + 3) <= 1, "shadow ends at {shadow:?} for text ending at {text:?}");
+    assert!(sharp.pixels().any(|p| p.0[0] > 64 && p.0[1] > 64), "text is not drawn over its shadow");
+
+    let shaded = |image: &RgbaImage| image.pixels().filter(|p| p.0[0] > 0 && p.0[1] == 0).count();
+    let darkest = |image: &RgbaImage| image.pixels().filter(|p| p.0[1] == 0).map(|p| p.0[0]).max().unwrap();
+    // Pixels of the shadow well short of its color, along its anti-aliased or blurred edges.
+    let faded = |image: &RgbaImage| image.pixels().filter(|p| p.0[1] == 0 && p.0[0] > 0 && p.0[0] < darkest(image) / 2).count();
+    assert!(shaded(&blurred) > shaded(&sharp), "blurred shadow covers {} pixels, the sharp one {}", shaded(&blurred), shaded(&sharp));
+    assert!(faded(&blurred) > 2 * faded(&sharp), "blurred shadow fades over {} pixels, the sharp one {}", faded(&blurred), faded(&sharp));
+    assert!(darkest(&blurred) <= darkest(&sharp) + THRESHOLD, "blurred shadow is darker than its color");
+}
+
+/// Drawing the same shadowed text again reuses the shadow in the image atlas instead of
+/// rasterizing it again, which here would find no glyphs in a font atlas without the font.
+#[test]
+fn text_shadows_are_reused() {
+    let mut gpu = gpu!();
+    let font = dejavu!(gpu);
+    let text = Text::new(vec![Span::new("Hi", 24.0, 28.0, font, Color::from_rgb(0, 255, 0))], None, Align::Left, None);
+    let shadow = ShadowOptions{offset: (3.0, 3.0), blur: 2.0, color: Color::from_rgba8(255, 0, 0, 128)};
+    let draw = |font_atlas: &mut FontAtlas, image_atlas: &mut ImageAtlas| {
+        let mut canvas = Canvas::new(64.0, 40.0);
+        canvas.draw_shadow_text(font_atlas, image_atlas, text.clone(), Area((8.0, 4.0), None), shadow, 1.0);
+        canvas.into_items()
+    };
+    let image = |items: &[(Area, CanvasItem)]| items.iter().find_map(|(_, item)| match item {
+        CanvasItem::Image(_, image, _) => Some(image.clone()),
+        _ => None,
+    }).unwrap();
+    let first = draw(&mut gpu.font_atlas, &mut gpu.image_atlas);
+    let shadowed = gpu.render((64, 40), first.clone());
+    let second = draw(&mut FontAtlas::default(), &mut gpu.image_atlas);
+    assert_eq!(image(&first), image(&second), "shadow is added to the atlas again");
+    assert_eq!(gpu.render((64, 40), second), shadowed);
+}
+
+/// Shadows at a scale factor are rasterized at it, ending as far past the text in physical pixels
+/// as the offset scaled, with no more faded pixels along their edges than the text has, unlike a
+/// shadow rasterized at a scale factor of 1 and stretched.
+#[test]
+fn text_shadows_follow_the_scale() {
+    let mut gpu = gpu!();
+    let font = dejavu!(gpu);
+    let text = Text::new(vec![Span::new("Hi", 24.0, 28.0, font, Color::from_rgb(0, 255, 0))], None, Align::Left, None);
+    let shadow = ShadowOptions{offset: (3.0, 3.0), blur: 0.0, color: Color::from_rgb(255, 0, 0)};
+    let mut draw = |scale: f32| {
+        let mut canvas = Canvas::new(64.0, 40.0);
+        canvas.draw_shadow_text(&mut gpu.font_atlas, &mut gpu.image_atlas, text.clone(), Area((8.0, 4.0), None), shadow, scale);
+        canvas.into_items()
+    };
+    let (stretched, crisp) = (draw(1.0), draw(2.0));
+    let size = |items: &[(Area, CanvasItem)]| items.iter().find_map(|(_, item)| match item {
+        CanvasItem::Image(_, image, _) => Some(image.size()),
+        _ => None,
+    }).unwrap();
+    let (single, double) = (size(&stretched), size(&crisp));
+    assert!(double.0.abs_diff(single.0 * 2) <= 2 && double.1.abs_diff(single.1 * 2) <= 2, "shadow of {double:?} pixels at 2x, {single:?} at 1x");
+
+    gpu.renderer.set_scale_factor(2.0);
+    let (stretched, crisp) = (gpu.render((128, 80), stretched), gpu.render((128, 80), crisp));
+    gpu.renderer.set_scale_factor(1.0);
+    let extent = |image: &RgbaImage, channel: usize| image.enumerate_pixels().filter(|(_, _, p)| p.0[channel] > 64)
+        .fold((0, 0), |(x, y), (px, py, _)| (x.max(px), y.max(py)));
+    let (text, shadow) = (extent(&crisp, 1), extent(&crisp, 0));
+    assert!(shadow.0.abs_diff(text.0 + 6) <= 1 && shadow.1.abs_diff(text.1 + 6) <= 1, "shadow ends at {shadow:?} for text ending at {text:?}");
+    let faded = |image: &RgbaImage| image.pixels().filter(|p| p.0[1] == 0 && p.0[0] > 16 && p.0[0] < 240).count();
+    assert!(faded(&crisp) < faded(&stretched), "shadow rasterized at 2x fades over {} pixels, stretched {}", faded(&crisp), faded(&stretched));
+}
+
+/// The shorthands of the canvas draw the same as the shapes they stand for.
+#[test]
+fn canvas_shorthands_match_shapes() {
+    let mut gpu = gpu!();
+    let red = Color::from_rgb(255, 0, 0);
+    let mut canvas = Canvas::new(128.0, 32.0);
+    canvas.draw_circle((16.0, 16.0), 12.0, red, 0.0);
+    canvas.draw_ellipse((48.0, 16.0), (12.0, 8.0), red, 3.0);
+    canvas.draw_rect((68.0, 4.0, 24.0, 24.0), red, 2.0);
+    canvas.draw_rounded_rect((100.0, 4.0, 24.0, 24.0), 8.0, red, 0.0);
+    let shorthands = gpu.render((128, 32), canvas.into_items());
+
+    let shape = |x: f32, y: f32, shape: Shape| (Area((x, y), None), CanvasItem::Shape(shape, red));
+    let shapes = gpu.render((128, 32), vec![
+        shape(4.0, 4.0, Shape::Ellipse(0.0, (24.0, 24.0))),
+        shape(36.0, 8.0, Shape::Ellipse(3.0, (24.0, 16.0))),
+        shape(68.0, 4.0, Shape::Rectangle(2.0.into(), (24.0, 24.0))),
+        shape(100.0, 4.0, Shape::RoundedRectangle(Border::default(), (24.0, 24.0), 8.0.into())),
+    ]);
+    assert!(shapes.pixels().any(|p| p.0 != [0, 0, 0, 255]));
+    assert_eq!(shorthands, shapes);
+}
+
+/// The wheel turns through every hue from red on the right, clockwise, with no darker seams where
+/// its wedges meet.
+#[test]
+fn hsv_color_wheels_have_no_seams() {
+    let mut gpu = gpu!();
+    let mut canvas = Canvas::new(128.0, 128.0);
+    canvas.draw_hsv_color_wheel((64.0, 64.0), 60.0);
+    let image = gpu.render((128, 128), canvas.into_items());
+
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let (dx, dy) = (x as f32 + 0.5 - 64.0, y as f32 + 0.5 - 64.0);
+        let distance = dx.hypot(dy);
+        if distance > 61.0 {
+            assert_eq!(pixel.0, [0, 0, 0, 255], "pixel at ({x}, {y}) outside of the wheel is drawn");
+        }
+        if !(4.0..58.0).contains(&distance) {continue;}
+        let (hue, saturation, value) = Color::from_rgb(pixel.0[0], pixel.0[1], pixel.0[2]).to_hsv();
+        let angle = dy.atan2(dx).to_degrees();
+        let turn = (hue - angle + 180.0).rem_euclid(360.0) - 180.0;
+        assert!(turn.abs() < 6.0 && saturation > 0.95 && value > 0.95, "pixel at ({x}, {y}) is {:?}", pixel.0);
+    }
+}
+
+/// Easings bend how far along a gradient is from its first color to its second, blended in linear
+/// light like linear gradients.
+#[test]
+fn gradients_follow_their_easing() {
+    let mut gpu = gpu!();
+    let (black, white) = (Color::from_rgb(0, 0, 0), Color::from_rgb(255, 255, 255));
+    let easings = [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut(3.0), Easing::Step];
+    let image = gpu.render((64, 40), easings.iter().enumerate().map(|(i, easing)| {
+        let shape = Shape::Rectangle(Border::default(), (64.0, 8.0));
+        (Area((0.0, i as f32 * 8.0), None), CanvasItem::Fill(shape, Fill::Linear(black, white, 0.0, *easing)))
+    }).collect());
+
+    for (i, easing) in easings.iter().enumerate() {
+        for x in [8, 24, 31, 33, 40, 56] {
+            let share = easing.ease((x as f32 + 0.5) / 64.0);
+            let expected = Color::from_rgb(0, 0, 0).lerp(white, share).r;
+            let drawn = image.get_pixel(x, i as u32 * 8 + 4).0[0];
+            assert!(drawn.abs_diff(expected) <= THRESHOLD, "{easing:?} at {x} is {drawn}, expected {expected}");
+        }
+    }
+}
+
+/// Patterns repeat from the top left of their shape in lengths of their scale, which grows with the
+/// scale factor.
+#[test]
+fn patterns_repeat_across_shapes() {
+    let mut gpu = gpu!();
+    let (red, blue) = (Color::from_rgb(255, 0, 0), Color::from_rgb(0, 0, 255));
+    let pattern = |kind: PatternKind, scale: f32| Fill::Pattern(PatternFill{kind, foreground: red, background: blue, scale});
+    let mut canvas = Canvas::new(128.0, 64.0);
+    canvas.draw_rect((0.0, 0.0, 32.0, 32.0), pattern(PatternKind::Stripes{angle: 0.0, width: 4.0}, 1.0), 0.0);
+    canvas.draw_rect((32.0, 0.0, 32.0, 32.0), pattern(PatternKind::Stripes{angle: std::f32::consts::FRAC_PI_2, width: 2.0}, 2.0), 0.0);
+    canvas.draw_rect((64.0, 0.0, 32.0, 32.0), pattern(PatternKind::Dots{spacing: 16.0, radius: 4.0}, 1.0), 0.0);
+    canvas.draw_circle((112.0, 16.0), 16.0, pattern(PatternKind::Crosshatch{spacing: 8.0}, 2.0), 0.0);
+    let image = gpu.render((128, 64), canvas.into_items());
+    let is = |x: u32, y: u32, color: Color| {
+        let drawn = image.get_pixel(x, y).0;
+        assert!(drawn[0].abs_diff(color.r) <= THRESHOLD && drawn[2].abs_diff(color.b) <= THRESHOLD, "pixel at ({x}, {y}) is {drawn:?}");
+    };
+
+    for y in [1, 2, 9, 18, 25] {is(16, y, red);}
+    for y in [5, 6, 13, 21, 29] {is(16, y, blue);}
+    // Turned upright and twice as wide.
+    for x in [5, 6, 13, 29] {is(x + 32, 16, red);}
+    for x in [1, 10, 17, 26] {is(x + 32, 16, blue);}
+    for (x, y) in [(8, 8), (24, 24), (10, 7)] {is(x + 64, y, red);}
+    for (x, y) in [(0, 0), (16, 16), (8, 14)] {is(x + 64, y, blue);}
+    // Lines of 2 pixels every 16 along the diagonals of the square the circle sits in.
+    for (x, y) in [(16, 16), (11, 11), (6, 16)] {is(x + 96, y, red);}
+    for (x, y) in [(21, 16), (16, 24)] {is(x + 96, y, blue);}
+    assert_eq!(image.get_pixel(97, 1).0, [0, 0, 0, 255], "patterns are cut to their shape");
+
+    let scaled = {
+        let mut canvas = Canvas::new(64.0, 32.0);
+        canvas.draw_rect((0.0, 0.0, 32.0, 16.0), pattern(PatternKind::Dots{spacing: 8.0, radius: 2.0}, 1.0), 0.0);
+        gpu.renderer.set_scale_factor(2.0);
+        let scaled = gpu.render((64, 32), canvas.into_items());
+        gpu.renderer.set_scale_factor(1.0);
+        scaled
+    };
+    let mut canvas = Canvas::new(64.0, 32.0);
+    canvas.draw_rect((0.0, 0.0, 64.0, 32.0), pattern(PatternKind::Dots{spacing: 16.0, radius: 4.0}, 1.0), 0.0);
+    assert_eq!(scaled, gpu.render((64, 32), canvas.into_items()), "patterns scale with the scale factor");
+}
+
+/// Checkers alternate squares of their cell size from the top left of their rectangle, starting
+/// with white.
+#[test]
+fn transparency_checkers_alternate_cells() {
+    let mut gpu = gpu!();
+    let mut canvas = Canvas::new(64.0, 40.0);
+    canvas.draw_transparency_checker((3.0, 5.0, 56.0, 32.0));
+    let image = gpu.render((64, 40), canvas.into_items());
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let expected = match (x, y) {
+            (3..59, 5..37) if ((x - 3) / 8 + (y - 5) / 8) % 2 == 0 => 255,
+            (3..59, 5..37) => 204,
+            _ => 0,
+        };
+        assert!(pixel.0[..3].iter().all(|c| c.abs_diff(expected) <= THRESHOLD), "pixel at ({x}, {y}) is {:?}, expected {expected}", pixel.0);
+    }
+}
+
+/// Plain images are drawn at their own size from the position of the area and cut to its bounds,
+/// rounded and elliptical ones like the shape of their size. Circles crop the image to keep its aspect
+/// and borders are stroked over the image.
+#[test]
+fn images_draw_at_their_own_size() {
+    let mut gpu = gpu!();
+    let halves = RgbaImage::from_fn(16, 8, |x, _| image::Rgba(if x < 8 {[255, 0, 0, 255]} else {[0, 0, 255, 255]}));
+    let image = gpu.image_atlas.add(halves);
+    let mut canvas = Canvas::new(64.0, 16.0);
+    canvas.draw_image(image.clone(), Area((4.0, 4.0), None));
+    canvas.draw_image(image.clone(), Area((36.0, 4.0), Some((36.0, 0.0, 12.0, 16.0))));
+    let drawn = gpu.render((64, 16), canvas.into_items());
+
+    let mut canvas = Canvas::new(64.0, 16.0);
+    canvas.draw_image_rounded(image.clone(), Area((4.0, 4.0), None), 4.0);
+    let rounded = gpu.render((64, 16), canvas.into_items());
+    let shape = Shape::RoundedRectangle(Border::default(), (16.0, 8.0), 4.0.into());
+    assert_eq!(rounded, gpu.render((64, 16), vec![(Area((4.0, 4.0), None), CanvasItem::Image(shape, image.clone(), None))]));
+    assert_eq!(rounded.get_pixel(4, 4).0, [0, 0, 0, 255], "corner of the rounded image is drawn");
+
+    let mut canvas = Canvas::new(64.0, 16.0);
+    canvas.draw_image_circle(image.clone(), (12.0, 8.0), 6.0);
+    canvas.draw_image_ellipse(image.clone(), Area((32.0, 4.0), None));
+    let ellipses = gpu.render((64, 16), canvas.into_items());
+    assert_eq!(ellipses, gpu.render((64, 16), vec![
+        (Area((6.0, 2.0), None), CanvasItem::Image(Shape::Ellipse(0.0, (12.0, 12.0)), image.clone(), None)),
+        (Area((32.0, 4.0), None), CanvasItem::Image(Shape::Ellipse(0.0, (16.0, 8.0)), image.clone(), None)),
+    ]));
+    assert_eq!(ellipses.get_pixel(9, 8).0, [255, 0, 0, 255], "left of the circle");
+    assert_eq!(ellipses.get_pixel(15, 8).0, [0, 0, 255, 255], "right of the circle");
+    assert_eq!(ellipses.get_pixel(6, 2).0, [0, 0, 0, 255], "corner around the circle is drawn");
+
+    let border = BorderStyle{stroke: 2.0.into(), fill: Color::from_rgb(255, 255, 255).into()};
+    let framed = Shape::RoundedRectangle(5.0.into(), (24.0, 12.0), 4.0.into());
+    let mut canvas = Canvas::new(64.0, 16.0);
+    canvas.draw_image_with_border(image.clone(), framed, Area((4.0, 2.0), None), border);
+    canvas.draw_image_with_border(image.clone(), Shape::Chevron(Direction::Up, 2.0, (8.0, 8.0)), Area((40.0, 2.0), None), border);
+    let items = canvas.into_items();
+    assert_eq!(items.len(), 3, "chevrons have no border");
+    let shape = Shape::RoundedRectangle(Border::default(), (24.0, 12.0), 4.0.into());
+    assert_eq!(gpu.render((64, 16), items), gpu.render((64, 16), vec![
+        (Area((4.0, 2.0), None), CanvasItem::Image(shape, image.clone(), None)),
+        (Area((4.0, 2.0), None), CanvasItem::Shape(Shape::RoundedRectangle(2.0.into(), (24.0, 12.0), 4.0.into()), Color::from_rgb(255, 255, 255))),
+        (Area((40.0, 2.0), None), CanvasItem::Image(Shape::Chevron(Direction::Up, 2.0, (8.0, 8.0)), image, None)),
+    ]));
+
+    let image = drawn;
+
+    for y in 4..12 {
+        for (x, color) in [(4, [255, 0, 0, 255]), (11, [255, 0, 0, 255]), (12, [0, 0, 255, 255]), (19, [0, 0, 255, 255]), (20, [0, 0, 0, 255])] {
+            assert_eq!(image.get_pixel(x, y).0, color, "pixel at ({x}, {y})");
+        }
+        for (x, color) in [(36, [255, 0, 0, 255]), (44, [0, 0, 255, 255]), (47, [0, 0, 255, 255]), (48, [0, 0, 0, 255])] {
+            assert_eq!(image.get_pixel(x, y).0, color, "pixel at ({x}, {y}) of the cut image");
+        }
+    }
+    for x in 0..64 {
+        assert_eq!(image.get_pixel(x, 3).0, [0, 0, 0, 255], "pixel at ({x}, 3) above the images is drawn");
+        assert_eq!(image.get_pixel(x, 12).0, [0, 0, 0, 255], "pixel at ({x}, 12) below the images is drawn");
+    }
+}
+
+/// Hit tests agree with what is drawn: fully covered pixels of every shape hit it when hollow and
+/// empty ones do not. Items are hit front to back, within their clip, and stroked shapes hit
+/// inside their stroke unless hollow.
+#[test]
+fn hit_tests_follow_coverage() {
+    let mut gpu = gpu!();
+    for (name, shape) in shapes() {
+        let image = gpu.render((SIZE, SIZE), vec![(Area((8.0, 8.0), None), CanvasItem::Shape(shape.clone(), Color::from_rgb(255, 255, 255)))]);
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let hit = shape.contains(&Area((8.0, 8.0), None), (x as f32 + 0.5, y as f32 + 0.5), true);
+            if pixel.0[0] >= 255 - THRESHOLD {
+                assert!(hit, "{name}: covered pixel at ({x}, {y}) is not hit");
+            } else if pixel.0[0] <= THRESHOLD {
+                assert!(!hit, "{name}: empty pixel at ({x}, {y}) is hit");
+            }
+        }
+    }
+
+    let mut canvas = Canvas::new(100.0, 100.0);
+    canvas.draw_rect((0.0, 0.0, 100.0, 100.0), Color::from_rgb(0, 0, 0), 0.0);
+    canvas.draw_circle((50.0, 50.0), 40.0, Color::from_rgb(255, 0, 0), 4.0);
+    canvas.with_clip((0.0, 0.0, 50.0, 100.0), |canvas| canvas.draw_rounded_rect((20.0, 20.0, 60.0, 60.0), 20.0, Color::from_rgb(0, 0, 255), 0.0));
+    assert_eq!(canvas.hit_test((50.0, 50.0), false), vec![1, 0]);
+    assert_eq!(canvas.hit_test((40.0, 40.0), false), vec![2, 1, 0]);
+    assert_eq!(canvas.hit_test((50.0, 50.0), true), vec![0]);
+    assert_eq!(canvas.hit_test((50.0, 12.0), true), vec![1, 0]);
+    assert_eq!(canvas.hit_test((22.0, 22.0), false), vec![0]);
+}
+
+/// The picking pass finds the item on top of a pixel, falling through transparent parts of images
+/// and the anti-aliased edges of shapes to the items below.
+#[test]
+fn picking_finds_the_top_item() {
+    let mut gpu = gpu!();
+    let icon = RgbaImage::from_fn(16, 16, |x, _| image::Rgba(if x < 8 {[255, 0, 0, 255]} else {[0, 0, 0, 0]}));
+    let icon = gpu.image_atlas.add(icon);
+    let mut canvas = Canvas::new(64.0, 32.0);
+    canvas.draw_rect((0.0, 0.0, 48.0, 32.0), Color::from_rgb(0, 0, 255), 0.0);
+    canvas.draw_circle((16.0, 16.0), 12.0, Color::from_rgb(0, 255, 0), 0.0);
+    canvas.draw_image(icon, Area((32.0, 8.0), None));
+    canvas.with_clip((0.0, 0.0, 20.0, 32.0), |canvas| canvas.draw_rect((0.0, 0.0, 64.0, 32.0), Color::from_rgba8(255, 255, 255, 200), 0.0));
+    gpu.render((64, 32), canvas.into_items());
+
+    let mut picker = Picker::new(&gpu.device, 64, 32);
+    let renderer = &gpu.renderer;
+    for (point, top) in [((10, 16), Some(3)), ((24, 16), Some(1)), ((24, 4), Some(0)), ((34, 16), Some(2)), ((44, 16), Some(0)), ((56, 16), None)] {
+        assert!(picker.pick(&gpu.device, &gpu.queue, point, |pass| renderer.render_picking(pass)));
+        assert!(!picker.pick(&gpu.device, &gpu.queue, point, |pass| renderer.render_picking(pass)), "second pick at {point:?} started before the first was read");
+        gpu.device.poll(wgpu::PollType::Wait).unwrap();
+        assert_eq!(picker.result(), Some(top), "item on top at {point:?}");
+        assert_eq!(picker.result(), None);
+    }
+    assert!(!picker.pick(&gpu.device, &gpu.queue, (64, 0), |pass| renderer.render_picking(pass)));
+}
+
+/// Outlines draw a ring of their width the offset away from their shape, and outlines of no
+/// width are reported instead of filling the shape.
+#[test]
+fn outlines_ring_their_shape() {
+    let mut gpu = gpu!();
+    let red = Color::from_rgb(255, 0, 0);
+    let square = Shape::Rectangle(Border::default(), (16.0, 16.0));
+    let items = vec![
+        (Area((16.0, 8.0), None), CanvasItem::Outline(square.clone(), red, 2.0, 2.0)),
+        (Area((40.0, 8.0), None), CanvasItem::Outline(square.clone(), red, 0.0, 2.0)),
+        (Area((40.0, 8.0), None), CanvasItem::Outline(square, red, -1.0, 2.0)),
+    ];
+    let errors = gpu.renderer.prepare(&gpu.device, &gpu.queue, 64.0, 32.0, &mut gpu.image_atlas, &mut gpu.font_atlas, items);
+    let image = gpu.draw((64, 32));
+
+    assert_eq!(errors.len(), 2, "{errors:?}");
+    assert!(matches!(&errors[0], CanvasError::InvalidInput{item: 1, ..}) && errors[0].to_string().contains("width of 0"), "{}", errors[0]);
+    assert!(matches!(&errors[1], CanvasError::InvalidInput{item: 2, ..}) && errors[1].to_string().contains("width of -1"), "{}", errors[1]);
+    let near = |x: u32, y: u32, color: [u8; 4]| image.get_pixel(x, y).0.iter().zip(color).all(|(a, b)| a.abs_diff(b) <= THRESHOLD);
+    assert!(near(13, 16, [255, 0, 0, 255]) && near(34, 16, [255, 0, 0, 255]), "outline is missing");
+    assert!(near(15, 16, [0, 0, 0, 255]) && near(24, 16, [0, 0, 0, 255]), "outline covers its gap or shape");
+    assert!(image.enumerate_pixels().filter(|(x, _, _)| *x >= 38).all(|(_, _, p)| p.0 == [0, 0, 0, 255]), "outline of no width is drawn");
+}
+
+/// Dashed strokes leave gaps along the outline and dotted ones draw round dots the spacing apart.
+#[test]
+fn patterned_strokes_leave_gaps() {
+    let mut gpu = gpu!();
+    let red = Color::from_rgb(255, 0, 0);
+    let mut canvas = Canvas::new(128.0, 32.0);
+    canvas.draw_rect((0.0, 0.0, 40.0, 20.0), red, Stroke::dashed(2.0, 4.0, 4.0));
+    canvas.draw_circle((64.0, 16.0), 12.0, red, Stroke::dotted(4.0, 8.0));
+    let image = gpu.render((128, 32), canvas.into_items());
+
+    let red = |x: u32, y: u32| image.get_pixel(x, y).0[0] > 128;
+    assert!(red(2, 0) && red(2, 1) && !red(2, 2), "first dash runs along the top");
+    assert!(!red(6, 0) && !red(6, 1), "gap follows the dash");
+    assert!(red(10, 1), "second dash follows the gap");
+    assert!(!red(20, 10), "dashed rectangle is hollow");
+    assert!(red(64, 6) && !red(64, 16), "dot sits at the top of the circle");
+    let along = (56..72).filter(|x| red(*x, 6)).count();
+    assert!(along < 8, "dots are apart, {along} pixels of the top row are red");
+}
+
+/// Items past the top left edge of the frame show the part of them still inside it, items
+/// outside of the frame draw nothing.
+#[test]
+fn items_past_the_edge_are_cut() {
+    let mut gpu = gpu!();
+    let quarters = RgbaImage::from_fn(16, 16, |x, y| image::Rgba([if x < 8 {255} else {0}, 0, if y < 8 {0} else {255}, 255]));
+    let image = gpu.image_atlas.add(quarters);
+    let mut canvas = Canvas::new(64.0, 16.0);
+    canvas.draw_image(image.clone(), Area((-8.0, -8.0), None));
+    canvas.draw_rect((-40.0, 0.0, 20.0, 16.0), Color::from_rgb(0, 255, 0), 0.0);
+    canvas.draw_image(image, Area((80.0, 0.0), None));
+    let frame = gpu.render((64, 16), canvas.into_items());
+
+    assert_eq!(frame.get_pixel(2, 2).0, [0, 0, 255, 255], "bottom right quarter of the image is left");
+    assert_eq!(frame.get_pixel(10, 2).0, [0, 0, 0, 255], "image ends where it is cut");
+    assert!(frame.pixels().all(|p| p.0[1] == 0), "items outside of the frame are not drawn");
+}
+
+/// Items given in logical pixels draw at a scale factor like the same items given in physical
+/// pixels, so fractional scales rasterize edges at the physical size.
+#[test]
+fn scale_factor_draws_at_physical_size() {
+    let mut gpu = gpu!();
+    let red = Color::from_rgb(255, 0, 0);
+    let draw = |scale: f32| {
+        let mut canvas = Canvas::new(64.0 / scale, 32.0 / scale);
+        canvas.draw_rect((2.0 * scale, 2.0 * scale, 12.0 * scale, 12.0 * scale), red, scale);
+        canvas.draw_circle((24.0 * scale, 8.0 * scale), 6.0 * scale, red, 2.0 * scale);
+        canvas.draw_rounded_rect((2.0 * scale, 16.0 * scale, 20.0 * scale, 4.0 * scale), 2.0 * scale, red, 0.0);
+        let path = Path::new().move_to((0.0, 0.0)).line_to((8.0 * scale, 4.0 * scale));
+        canvas.draw(Area((26.0 * scale, 16.0 * scale), None), CanvasItem::Shape(Shape::Path(scale, path), red));
+        canvas.into_items()
+    };
+    let physical = gpu.render((64, 32), draw(1.5));
+    assert!(physical.pixels().any(|p| p.0 != [0, 0, 0, 255]));
+    for scale in [1.25, 1.5, 2.0] {
+        gpu.renderer.set_scale_factor(scale);
+        let logical = gpu.render((64, 32), draw(1.5 / scale));
+        gpu.renderer.set_scale_factor(1.0);
+        let differing = physical.pixels().zip(logical.pixels()).filter(|(a, b)| a.0.iter().zip(b.0).any(|(a, b)| a.abs_diff(b) > 2)).count();
+        assert_eq!(differing, 0, "{differing} pixels differ at {scale}");
+    }
+}
+
+/// The render scale draws every item scaled after the scale factor, while the camera offset stays
+/// in logical pixels of the frame.
+#[test]
+fn render_scale_scales_items_after_the_scale_factor() {
+    let mut gpu = gpu!();
+    let red = Color::from_rgb(255, 0, 0);
+    let draw = |scale: f32| {
+        let mut canvas = Canvas::new(128.0, 64.0);
+        canvas.draw_rect((2.0 * scale, 2.0 * scale, 12.0 * scale, 12.0 * scale), red, scale);
+        canvas.draw_circle((24.0 * scale, 8.0 * scale), 6.0 * scale, red, 2.0 * scale);
+        canvas.into_items()
+    };
+    let set = |gpu: &mut Gpu, offset: (f32, f32), scale_factor: f32, render_scale: f32| {
+        gpu.renderer.set_camera(&gpu.device, &gpu.queue, &mut gpu.font_atlas, Camera{offset, zoom: 1.0}).unwrap();
+        gpu.renderer.set_scale_factor(scale_factor);
+        gpu.renderer.set_render_scale(render_scale);
+    };
+    set(&mut gpu, (6.0, 4.0), 1.0, 1.0);
+    let expected = gpu.render((64, 32), draw(1.5));
+    assert!(expected.pixels().any(|p| p.0 != [0, 0, 0, 255]));
+    for (offset, scale_factor, render_scale) in [((6.0, 4.0), 1.0, 1.5), ((3.0, 2.0), 2.0, 0.75)] {
+        set(&mut gpu, offset, scale_factor, render_scale);
+        let image = gpu.render((64, 32), draw(1.0));
+        set(&mut gpu, (0.0, 0.0), 1.0, 1.0);
+        let differing = expected.pixels().zip(image.pixels()).filter(|(a, b)| a.0.iter().zip(b.0).any(|(a, b)| a.abs_diff(b) > 2)).count();
+        assert_eq!(differing, 0, "{differing} pixels differ at a scale factor of {scale_factor} and a render scale of {render_scale}");
+
+        let frame = CanvasRenderer::build_frame(64.0, 32.0, scale_factor, render_scale, Camera{offset, zoom: 1.0}, draw(1.0));
+        let errors = gpu.renderer.upload(&gpu.device, &gpu.queue, &mut gpu.image_atlas, &mut gpu.font_atlas, frame);
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(gpu.renderer.render_scale(), render_scale);
+        assert!(gpu.draw((64, 32)) == image, "built frame drew differently from the prepared one at a render scale of {render_scale}");
+        set(&mut gpu, (0.0, 0.0), 1.0, 1.0);
+    }
+}
+
+/// The camera draws the canvas moved and zoomed like the same items placed there, and moving it
+/// after preparing moves what was prepared.
+#[test]
+fn camera_pans_and_zooms_the_canvas() {
+    let mut gpu = gpu!();
+    let red = Color::from_rgb(255, 0, 0);
+    let draw = |zoom: f32, (x, y): (f32, f32)| {
+        let mut canvas = Canvas::new(64.0, 32.0);
+        canvas.draw_rect((x + 2.0 * zoom, y + 2.0 * zoom, 8.0 * zoom, 8.0 * zoom), red, 0.0);
+        canvas.draw_circle((x + 16.0 * zoom, y + 6.0 * zoom), 4.0 * zoom, red, zoom);
+        canvas.into_items()
+    };
+    let differing = |a: &RgbaImage, b: &RgbaImage| a.pixels().zip(b.pixels()).filter(|(a, b)| a.0.iter().zip(b.0).any(|(a, b)| a.abs_diff(b) > THRESHOLD)).count();
+
+    let placed = gpu.render((64, 32), draw(2.0, (6.0, 4.0)));
+    assert!(placed.pixels().any(|p| p.0 != [0, 0, 0, 255]));
+    let camera = Camera{offset: (6.0, 4.0), zoom: 2.0};
+    gpu.renderer.set_camera(&gpu.device, &gpu.queue, &mut gpu.font_atlas, camera).unwrap();
+    let viewed = gpu.render((64, 32), draw(1.0, (0.0, 0.0)));
+    assert_eq!(differing(&placed, &viewed), 0);
+
+    let moved = Camera{offset: (20.0, 2.0), zoom: 1.5};
+    gpu.renderer.set_camera(&gpu.device, &gpu.queue, &mut gpu.font_atlas, moved).unwrap();
+    let panned = gpu.draw((64, 32));
+    gpu.renderer.set_camera(&gpu.device, &gpu.queue, &mut gpu.font_atlas, Camera::default()).unwrap();
+    let placed = gpu.render((64, 32), draw(1.5, (20.0, 2.0)));
+    assert_eq!(differing(&placed, &panned), 0);
+}
+
+/// Images first drawn in the same frame are uploaded together, rows of any width landing in
+/// their own textures.
+#[test]
+fn images_upload_together() {
+    let mut gpu = gpu!();
+    let colors = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]];
+    let items = colors.iter().zip([3, 5, 71]).enumerate().map(|(i, (color, width))| {
+        let image = gpu.image_atlas.add(RgbaImage::from_fn(width, 7, |x, _| match x % 2 {
+            0 => image::Rgba(*color),
+            _ => image::Rgba([255, 255, 255, 255]),
+        }));
+        (Area((0.0, i as f32 * 8.0), None), CanvasItem::Image(Shape::Rectangle(Border::default(), (width as f32, 7.0)), image, None))
+    }).collect();
+    let image = gpu.render((128, 32), items);
+    for (i, (color, width)) in colors.iter().zip([3, 5, 71]).enumerate() {
+        let y = i as u32 * 8 + 3;
+        assert_eq!(image.get_pixel(width - 1, y).0, *color);
+        assert_eq!(image.get_pixel(width - 2, y).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(width + 1, y).0, [0, 0, 0, 255]);
+    }
+}
+
+/// Compressed images first drawn in the same frame are uploaded together, whether their rows of
+/// blocks need padding or not.
+#[test]
+fn compressed_images_upload_together() {
+    let mut gpu = gpu!();
+    let gradient = |width: u32| RgbaImage::from_fn(width, 8, |x, y| image::Rgba([x as u8 * 3, 200 - y as u8 * 8, 90, 255]));
+    let draw = |gpu: &mut Gpu| {
+        let items = [(gradient(32), 0.0), (gradient(64), 8.0)].into_iter().map(|(raw, y)| {
+            let size = (raw.width() as f32, raw.height() as f32);
+            let image = gpu.image_atlas.add(raw);
+            (Area((0.0, y), None), CanvasItem::Image(Shape::Rectangle(Border::default(), size), image, None))
+        }).collect();
+        gpu.render((64, 16), items)
+    };
+    let plain = draw(&mut gpu);
+
+    gpu.image_atlas = ImageAtlas::default();
+    require!(gpu.image_atlas.try_use_compression(&gpu.adapter).then_some(()), "BC7 not supported");
+    let compressed = draw(&mut gpu);
+    for (x, y) in (0..64).flat_map(|x| (0..16).map(move |y| (x, y))) {
+        let difference = plain.get_pixel(x, y).0.iter().zip(compressed.get_pixel(x, y).0).map(|(a, b)| a.abs_diff(b)).max().unwrap();
+        assert!(difference <= THRESHOLD, "compressed images are off by {difference} at ({x}, {y})");
+    }
+}
+
+/// A draw list saved as JSON and replayed draws the same as the items it was recorded from,
+/// with images embedded or supplied again and fonts supplied by family.
+#[cfg(feature = "serde")]
+#[test]
+fn draw_lists_replay_from_json() {
+    let mut gpu = gpu!();
+    let sheet = RgbaImage::from_fn(8, 8, |x, y| image::Rgba([x as u8 * 32, y as u8 * 32, 128, 255]));
+    let photo = RgbaImage::from_pixel(4, 4, image::Rgba([0, 200, 100, 255]));
+    let sheet_image = gpu.image_atlas.add(sheet.clone());
+    let sprite = gpu.image_atlas.insert_region(&sheet_image, (2, 2, 4, 4));
+    let photo_image = gpu.image_atlas.add(photo.clone());
+    let red = Color::from_rgb(255, 0, 0);
+
+    let mut canvas = Canvas::new(128.0, 64.0);
+    canvas.draw_rounded_rect((2.0, 2.0, 20.0, 12.0), 4.0, Fill::Conic(red, Color::from_rgb(0, 0, 255), 0.5, Easing::Linear), Stroke::dashed(2.0, 4.0, 2.0));
+    canvas.draw_circle((34.0, 10.0), 8.0, red, 0.0);
+    canvas.draw_image_rounded(sprite, Area((48.0, 2.0), None), 2.0);
+    canvas.draw_image_circle(photo_image.clone(), (70.0, 10.0), 8.0);
+    canvas.draw(Area((2.0, 24.0), Some((2.0, 24.0, 30.0, 20.0))), CanvasItem::GridLines(GridLines{
+        size: (40.0, 30.0), rows: GridSpacing::Every(6.0), columns: GridSpacing::At(vec![3.0, 17.5]), thickness: 1.0, color: red,
+    }));
+    let path = Path::new().move_to((0.0, 0.0)).quad_to((10.0, 20.0), (20.0, 0.0)).line_cap(LineCap::Round);
+    canvas.draw(Area((40.0, 30.0), None), CanvasItem::Shape(Shape::Path(3.0, path), red));
+    canvas.draw(Area((70.0, 30.0), None), CanvasItem::ProgressRing(ProgressRing{
+        size: 24.0, thickness: 4.0, progress: 0.6, start_angle: 0.0, color: red, track: Color::from_rgb(40, 40, 40),
+    }));
+    let font = std::fs::read(DEJAVU).ok().map(|font| gpu.font_atlas.add(&font));
+    if let Some(font) = &font {
+        let span = Span::new("replay", 12.0, 14.0, font.clone(), Color::from_rgb(255, 255, 255)).decoration(Decoration::UNDERLINE);
+        canvas.draw(Area((96.0, 4.0), None), CanvasItem::Text(Text::new(vec![span], None, Align::Center, None)));
+    }
+    let items = canvas.into_items();
+
+    let mut list = DrawListRecord::record_with(&items, |image| (*image == photo_image).then(|| "photo".to_string()));
+    list.embed(&DrawListRecord::key(&sheet_image), &sheet);
+    let json = serde_json::to_string(&list).unwrap();
+    let original = gpu.render((128, 64), items);
+
+    let list: DrawListRecord = serde_json::from_str(&json).unwrap();
+    let missing = list.replay(&mut gpu.image_atlas, |_| None, |_| font.clone());
+    assert_eq!(missing.unwrap_err(), ReplayError::MissingImage("photo".to_string()));
+    let replayed = list.replay(&mut gpu.image_atlas, |key| (key == "photo").then(|| photo.clone()), |_| font.clone()).unwrap();
+    let replayed = gpu.render((128, 64), replayed);
+    assert!(original.pixels().zip(replayed.pixels()).all(|(a, b)| a == b), "replayed draw list differs");
+}
+
+/// Compressed images draw close to their pixels, images BC7 cannot store in whole blocks are
+/// drawn exactly as before.
+#[test]
+fn compressed_images_stay_close() {
+    let mut gpu = gpu!();
+    let gradient = RgbaImage::from_fn(32, 16, |x, y| image::Rgba([(x + y) as u8 * 5, 200 - (x + y) as u8 * 3, 90, 255]));
+    let odd = RgbaImage::from_fn(6, 6, |x, y| image::Rgba([x as u8 * 40, y as u8 * 40, 90, 255]));
+    let draw = |gpu: &mut Gpu| {
+        let items = [(gradient.clone(), 0.0), (odd.clone(), 40.0)].into_iter().map(|(raw, x)| {
+            let size = (raw.width() as f32, raw.height() as f32);
+            let image = gpu.image_atlas.add(raw);
+            (Area((x, 0.0), None), CanvasItem::Image(Shape::Rectangle(Border::default(), size), image, None))
+        }).collect();
+        gpu.render((64, 16), items)
+    };
+    let plain = draw(&mut gpu);
+
+    gpu.image_atlas = ImageAtlas::default();
+    require!(gpu.image_atlas.try_use_compression(&gpu.adapter).then_some(()), "BC7 not supported");
+    let compressed = draw(&mut gpu);
+    let difference = |x: std::ops::Range<u32>| x.flat_map(|x| (0..16).map(move |y| (x, y))).map(|(x, y)|
+        plain.get_pixel(x, y).0.iter().zip(compressed.get_pixel(x, y).0).map(|(a, b)| a.abs_diff(b)).max().unwrap()
+    ).collect::<Vec<_>>();
+    let gradient = difference(0..32);
+    assert!(gradient.iter().any(|d| *d > 0), "gradient was not compressed");
+    assert!(gradient.iter().all(|d| *d <= THRESHOLD), "compressed gradient is off by {}", gradient.iter().max().unwrap());
+    assert!(difference(32..64).iter().all(|d| *d == 0), "image of 6 by 6 pixels changed");
+}
+
+/// Draw lists are prepared again only once they changed or other items were prepared since.
+#[test]
+fn draw_lists_prepare_when_changed() {
+    let mut gpu = gpu!();
+    let square = |x: f32, color: Color| (Area((x, 0.0), None), CanvasItem::Shape(Shape::Rectangle(Border::default(), (16.0, 16.0)), color));
+    let (red, blue) = (Color::from_rgb(255, 0, 0), Color::from_rgb(0, 0, 255));
+    let draw = |gpu: &mut Gpu, list: &DrawList| {
+        gpu.renderer.prepare_draw_list(&gpu.device, &gpu.queue, 64.0, 16.0, &mut gpu.image_atlas, &mut gpu.font_atlas, list);
+        gpu.draw((64, 16))
+    };
+    let near = |image: &RgbaImage, x: u32, color: [u8; 4]| image.get_pixel(x, 8).0.iter().zip(color).all(|(a, b)| a.abs_diff(b) <= THRESHOLD);
+
+    let mut list = DrawList::from(vec![square(0.0, red)]);
+    assert!(near(&draw(&mut gpu, &list), 8, [255, 0, 0, 255]));
+    let version = list.version();
+    assert_eq!(list.clone().version(), version);
+    assert!(near(&draw(&mut gpu, &list.clone()), 8, [255, 0, 0, 255]));
+
+    gpu.render((64, 16), vec![square(0.0, blue)]);
+    assert!(near(&draw(&mut gpu, &list), 8, [255, 0, 0, 255]), "list was not prepared after other items");
+
+    list.items_mut().push(square(32.0, blue));
+    assert_ne!(list.version(), version);
+    let image = draw(&mut gpu, &list);
+    assert!(near(&image, 8, [255, 0, 0, 255]));
+    assert!(near(&image, 40, [0, 0, 255, 255]), "changed list was not prepared");
+}
+
+/// Image frames that alternate are drawn from the two sets of buffers of their pipeline in turn,
+/// each frame drawing its own images whether its vertices were written again or kept from the
+/// frame before last.
+#[test]
+fn alternating_image_frames_draw_their_own() {
+    let mut gpu = gpu!();
+    let solid = |gpu: &mut Gpu, color: [u8; 4]| gpu.image_atlas.add(RgbaImage::from_pixel(4, 4, image::Rgba(color)));
+    let (red, blue) = (solid(&mut gpu, [255, 0, 0, 255]), solid(&mut gpu, [0, 0, 255, 255]));
+    let frame = |x: f32, image: &Image| vec![(Area((x, 0.0), None), CanvasItem::Image(Shape::Rectangle(Border::default(), (16.0, 16.0)), image.clone(), None))];
+    for _ in 0..3 {
+        for (x, image, color) in [(0.0, &red, [255, 0, 0, 255]), (32.0, &blue, [0, 0, 255, 255])] {
+            let drawn = gpu.render((64, 16), frame(x, image));
+            assert_eq!(drawn.get_pixel(x as u32 + 8, 8).0, color, "frame at {x} draws the image of the other");
+            assert_eq!(drawn.get_pixel(40 - x as u32, 8).0, [0, 0, 0, 255], "frame at {x} draws the square of the other");
+        }
+    }
+}
+
+/// Images drawn smaller than their size blend their pixels with mipmaps, without them each pixel
+/// of the screen samples only the pixels of the image nearest to it. Strokes keep the detail of
+/// their texture at the seam where it wraps, rather than falling back to its smallest mipmap.
+#[test]
+fn mipmaps_smooth_small_images() {
+    let mut gpu = gpu!();
+    let checkers = RgbaImage::from_fn(64, 64, |x, y| match (x + y) % 2 {
+        0 => image::Rgba([255, 255, 255, 255]),
+        _ => image::Rgba([0, 0, 0, 255]),
+    });
+    let thumbnail = Shape::Rectangle(Border::default(), (20.0, 20.0));
+    let mut draw = |atlas: ImageAtlas| {
+        gpu.image_atlas = atlas;
+        let image = gpu.image_atlas.add(checkers.clone());
+        gpu.render((64, 32), vec![(Area((0.0, 0.0), None), CanvasItem::Image(thumbnail.clone(), image, None))])
+    };
+    let shades = |image: &RgbaImage| (0..20).flat_map(|x| (0..20).map(move |y| image.get_pixel(x, y).0[0])).collect::<Vec<_>>();
+
+    let grainy = shades(&draw(ImageAtlas::default()));
+    assert!(grainy.iter().max().unwrap() - grainy.iter().min().unwrap() > 64, "image was smoothed without mipmaps");
+    // Half white and half black in linear light is 188 in sRGB.
+    let smooth = shades(&draw(ImageAtlas::new(ImageAtlasDescriptor{generate_mipmaps: true, ..Default::default()})));
+    assert!(smooth.iter().all(|s| s.abs_diff(188) <= 12), "image was not smoothed: {smooth:?}");
+
+    // Bands across the stroke stay sharp at its own size, only the smallest mipmap is all 188.
+    let stripes = RgbaImage::from_fn(64, 64, |_, y| match (y / 8) % 2 {
+        0 => image::Rgba([255, 255, 255, 255]),
+        _ => image::Rgba([0, 0, 0, 255]),
+    });
+    let image = gpu.image_atlas.add(stripes);
+    let stroke = Shape::Rectangle(16.0.into(), (64.0, 64.0));
+    let stroked = gpu.render((64, 64), vec![(Area((0.0, 0.0), None), CanvasItem::ImageStroke(stroke, image, None))]);
+    let seam = (0..8).map(|i| stroked.get_pixel(i, i).0[0]).collect::<Vec<_>>();
+    assert!(seam.iter().all(|s| s.abs_diff(188) > 12), "seam sampled the smallest mipmap: {seam:?}");
+}
+
+/// Images are bound when first drawn, dropped from the atlas once no handle is left and bound
+/// again when added back, alongside images that stayed.
+#[test]
+fn images_rebind_after_trim() {
+    let mut gpu = gpu!();
+    let solid = |color: [u8; 4]| RgbaImage::from_pixel(4, 4, image::Rgba(color));
+    let square = Shape::Rectangle(Border::default(), (16.0, 16.0));
+    let draw = |image: &Image, x: f32| (Area((x, 0.0), None), CanvasItem::Image(square.clone(), image.clone(), None));
+
+    let blue = gpu.image_atlas.add(solid([0, 0, 255, 255]));
+    let red = gpu.image_atlas.add(solid([255, 0, 0, 255]));
+    let image = gpu.render((64, 16), vec![draw(&red, 0.0), draw(&blue, 16.0)]);
+    assert_eq!(image.get_pixel(8, 8).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(24, 8).0, [0, 0, 255, 255]);
+
+    drop(red);
+    let image = gpu.render((64, 16), vec![draw(&blue, 16.0)]);
+    assert_eq!(image.get_pixel(8, 8).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(24, 8).0, [0, 0, 255, 255]);
+
+    let red = gpu.image_atlas.add(solid([255, 0, 0, 255]));
+    let image = gpu.render((64, 16), vec![draw(&red, 0.0), draw(&blue, 16.0)]);
+    assert_eq!(image.get_pixel(8, 8).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(24, 8).0, [0, 0, 255, 255]);
+}
+
+/// After the device is lost, the renderer and atlas are created again on a new device from the
+/// pixels the atlas retained or reloaded, and the handles of the images draw as before.
+#[test]
+fn images_survive_a_lost_device() {
+    let mut gpu = gpu!();
+    let solid = |color: [u8; 4]| RgbaImage::from_pixel(4, 4, image::Rgba(color));
+    let square = Shape::Rectangle(Border::default(), (16.0, 16.0));
+    let draw = |image: &Image, x: f32| (Area((x, 0.0), None), CanvasItem::Image(square.clone(), image.clone(), None));
+    let add = |atlas: &mut ImageAtlas| [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]].map(|color| atlas.add(solid(color)));
+
+    gpu.image_atlas = ImageAtlas::new(ImageAtlasDescriptor{retain_pixels: true, ..Default::default()});
+    let [red, green, blue] = add(&mut gpu.image_atlas);
+    let items = vec![draw(&red, 0.0), draw(&green, 16.0), draw(&blue, 32.0)];
+    let before = gpu.render((64, 16), items.clone());
+    gpu.device.destroy();
+
+    let mut new = Gpu::new().unwrap();
+    std::mem::swap(&mut new.renderer, &mut gpu.renderer);
+    new.renderer.recreate(&new.queue, &new.device);
+    new.image_atlas = std::mem::take(&mut gpu.image_atlas);
+    assert!(new.image_atlas.recreate(|_| panic!("retained pixels were asked for")).is_empty());
+    assert!(new.render((64, 16), items) == before, "images drew differently after recreating");
+
+    // Released pixels are asked for, images that cannot be reloaded are given back and left out.
+    new.image_atlas = ImageAtlas::default();
+    let [red, green, blue] = add(&mut new.image_atlas);
+    let items = vec![draw(&red, 0.0), draw(&green, 16.0), draw(&blue, 32.0)];
+    new.render((64, 16), items.clone());
+    new.device.destroy();
+
+    let mut last = Gpu::new().unwrap();
+    last.image_atlas = std::mem::take(&mut new.image_atlas);
+    let lost = last.image_atlas.recreate(|image| match image {
+        image if *image == red => Some(solid([255, 0, 0, 255])),
+        image if *image == green => Some(RgbaImage::new(2, 2)),
+        _ => None,
+    });
+    assert!(lost.len() == 2 && lost.contains(&green) && lost.contains(&blue), "{lost:?}");
+    let errors = last.renderer.prepare(&last.device, &last.queue, 64.0, 16.0, &mut last.image_atlas, &mut last.font_atlas, items);
+    assert_eq!(errors.iter().map(CanvasError::item).collect::<Vec<_>>(), [Some(1), Some(2)], "{errors:?}");
+    let image = last.draw((64, 16));
+    assert_eq!(image.get_pixel(8, 8).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(24, 8).0, [0, 0, 0, 255]);
+
+    let _added = last.image_atlas.add(solid([0, 0, 255, 255]));
+    let image = last.render((64, 16), vec![draw(&blue, 32.0)]);
+    assert_eq!(image.get_pixel(40, 8).0, [0, 0, 255, 255], "lost handle did not draw the image added again");
+}
+
+/// Preparing the items of the last frame again leaves the buffers as they were and draws the same,
+/// while changed items are uploaded, also when they change back.
+#[test]
+fn unchanged_frames_skip_uploads() {
+    let mut gpu = gpu!();
+    let blue = gpu.image_atlas.add(RgbaImage::from_pixel(4, 4, image::Rgba([0, 0, 255, 255])));
+    let items = |x: f32| {
+        let mut canvas = Canvas::new(64.0, 16.0);
+        canvas.draw_rect((x, 0.0, 8.0, 8.0), Color::from_rgb(255, 0, 0), 0.0);
+        canvas.draw(Area((x + 16.0, 0.0), None), CanvasItem::Image(Shape::Rectangle(Border::default(), (8.0, 8.0)), blue.clone(), None));
+        canvas.into_items()
+    };
+    let first = gpu.render((64, 16), items(0.0));
+    assert!(!gpu.renderer.frame_unchanged());
+    assert!(gpu.render((64, 16), items(0.0)) == first && gpu.renderer.frame_unchanged(), "same items were uploaded again");
+
+    let moved = gpu.render((64, 16), items(8.0));
+    assert!(!gpu.renderer.frame_unchanged() && moved != first, "moved items were not uploaded");
+    assert_eq!(moved.get_pixel(28, 4).0, [0, 0, 255, 255]);
+    assert!(gpu.render((64, 16), items(0.0)) == first && !gpu.renderer.frame_unchanged(), "items moved back drew differently");
+}
+
+/// Buffers grown by a frame of many items shrink back once asked to, or by themselves after a
+/// while of smaller frames, and still draw the frame they hold.
+#[test]
+fn buffers_shrink_after_large_frames() {
+    let mut gpu = gpu!();
+    let blue = gpu.image_atlas.add(RgbaImage::from_pixel(4, 4, image::Rgba([0, 0, 255, 255])));
+    let items = |count: usize| {
+        let mut canvas = Canvas::new(64.0, 16.0);
+        for i in 0..count {
+            canvas.draw_rect(((i % 8) as f32, 0.0, 8.0, 8.0), Color::from_rgb(255, 0, 0), 0.0);
+            canvas.draw(Area(((i % 8) as f32 + 32.0, 0.0), None), CanvasItem::Image(Shape::Rectangle(Border::default(), (8.0, 8.0)), blue.clone(), None));
+        }
+        canvas.into_items()
+    };
+    let check = |image: RgbaImage| for (x, color) in [(4, [255, 0, 0, 255]), (36, [0, 0, 255, 255])] {
+        let pixel = image.get_pixel(x, 4).0;
+        assert!(pixel.iter().zip(color).all(|(a, b)| a.abs_diff(b) <= THRESHOLD), "pixel at ({x}, 4) is {pixel:?}");
+    };
+    let small = gpu.renderer.buffer_capacity();
+    gpu.render((64, 16), items(20000));
+    let large = gpu.renderer.buffer_capacity();
+    assert!(large > small * 64, "{large} bytes of buffers after a large frame, {small} before");
+
+    check(gpu.render((64, 16), items(1)));
+    assert_eq!(gpu.renderer.buffer_capacity(), large, "buffers shrank after a single small frame");
+    gpu.renderer.shrink_to_fit(&gpu.device, &gpu.queue);
+    assert!(gpu.renderer.buffer_capacity() < large / 64, "buffers of {} bytes were not shrunk", gpu.renderer.buffer_capacity());
+    check(gpu.draw((64, 16)));
+
+    gpu.render((64, 16), items(20000));
+    for _ in 0..500 {
+        gpu.renderer.prepare(&gpu.device, &gpu.queue, 64.0, 16.0, &mut gpu.image_atlas, &mut gpu.font_atlas, items(1));
+    }
+    assert!(gpu.renderer.buffer_capacity() < large / 64, "buffers of {} bytes did not shrink by themselves", gpu.renderer.buffer_capacity());
+    check(gpu.draw((64, 16)));
+}
+
+/// The renderer reports the limits and features of the device it was created on, and those of the
+/// new device once recreated on it.
+#[test]
+fn renderers_report_their_device() {
+    let mut gpu = gpu!();
+    assert_eq!(gpu.renderer.device_limits(), &gpu.device.limits());
+    assert_eq!(gpu.renderer.device_features(), gpu.device.features());
+
+    let descriptor = wgpu::DeviceDescriptor{required_limits: wgpu::Limits::downlevel_defaults(), ..Default::default()};
+    let (device, queue) = pollster::block_on(gpu.adapter.request_device(&descriptor)).unwrap();
+    gpu.renderer.recreate(&queue, &device);
+    assert_eq!(gpu.renderer.device_limits(), &device.limits());
+    assert_eq!(gpu.renderer.device_features(), wgpu::Features::empty());
+}
+
+/// The builder checks its settings against the device, and renderers it builds bind images to
+/// textures of at most the atlas size.
+#[test]
+fn builder_validates_its_settings() {
+    let mut gpu = gpu!();
+    let builder = || CanvasRendererBuilder::new(&gpu.device, &gpu.queue);
+    let depth = |format| wgpu::DepthStencilState{format, depth_write_enabled: true, depth_compare: wgpu::CompareFunction::Less, stencil: Default::default(), bias: Default::default()};
+    let error = |builder: CanvasRendererBuilder| builder.build().err();
+    assert_eq!(error(builder().texture_format(wgpu::TextureFormat::R32Uint)), Some(BuildError::UnsupportedFormat(wgpu::TextureFormat::R32Uint)));
+    assert_eq!(error(builder().msaa(Msaa::X4).multisample(wgpu::MultisampleState{count: 2, ..Default::default()})), Some(BuildError::ConflictingSampleCount{msaa: 4, multisample: 2}));
+    assert_eq!(error(builder().msaa(Msaa::X4).multisample(wgpu::MultisampleState{count: 4, ..Default::default()})), None);
+    assert_eq!(error(builder().depth_stencil(depth(FORMAT))), Some(BuildError::InvalidDepthStencil(FORMAT)));
+    assert_eq!(error(builder().msaa(Msaa::X4).depth_stencil(depth(wgpu::TextureFormat::Depth32Float))), None);
+    assert!(matches!(error(builder().atlas_size(0, 16)), Some(BuildError::InvalidAtlasSize{size: (0, 16), ..})));
+    assert!(matches!(error(builder().atlas_size(u32::MAX, 16)), Some(BuildError::InvalidAtlasSize{..})));
+
+    let renderer = builder().clear_color(Color::from_rgba8(255, 188, 0, 128)).build().unwrap();
+    let clear = renderer.clear_color();
+    assert!((clear.r - 1.0).abs() < 1e-3 && (clear.g - 0.5).abs() < 1e-2 && clear.b == 0.0 && (clear.a - 0.5).abs() < 1e-2, "{clear:?}");
+
+    gpu.renderer = builder().atlas_size(8, 4).build().unwrap();
+    let square = Shape::Rectangle(Border::default(), (16.0, 16.0));
+    let items = [((8, 4), 0.0), ((8, 8), 32.0)].map(|((width, height), x)| {
+        let image = gpu.image_atlas.add(RgbaImage::from_pixel(width, height, image::Rgba([255, 0, 0, 255])));
+        (Area((x, 0.0), None), CanvasItem::Image(square.clone(), image, None))
+    }).to_vec();
+    let errors = gpu.renderer.prepare(&gpu.device, &gpu.queue, 64.0, 16.0, &mut gpu.image_atlas, &mut gpu.font_atlas, items);
+    assert!(matches!(errors[..], [CanvasError::Allocation{item: Some(1), ..}]), "{errors:?}");
+    let image = gpu.draw((64, 16));
+    assert_eq!(image.get_pixel(8, 8).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(40, 8).0, [0, 0, 0, 255]);
+}
+
+/// Frames built on another thread and uploaded draw the same as frames prepared in one go, and
+/// keep the images they draw alive until uploaded.
+#[test]
+fn frames_build_off_the_render_thread() {
+    let mut gpu = gpu!();
+    let image = gpu.image_atlas.add(RgbaImage::from_fn(8, 8, |x, y| image::Rgba([x as u8 * 32, y as u8 * 32, 255, 255])));
+    let items = vec![
+        (Area((0.0, 0.0), None), CanvasItem::Shape(Shape::Ellipse(0.0, (24.0, 24.0)), Color::from_rgb(255, 0, 0))),
+        (Area((24.0, 0.0), None), CanvasItem::Image(Shape::Star(0.0, (24.0, 24.0), 5, 0.5), image.clone(), None)),
+        (Area((40.0, 8.0), None), CanvasItem::ImageStroke(Shape::Rectangle(4.0.into(), (24.0, 24.0)), image.clone(), None)),
+        (Area((0.0, 24.0), None), CanvasItem::Shape(Shape::Path(0.0, Path::new().move_to((0.0, 0.0)).line_to((32.0, 8.0)).line_to((0.0, 8.0)).close()), Color::from_rgb(0, 255, 0))),
+    ];
+    let expected = gpu.render((64, 32), items.clone());
+
+    let camera = gpu.renderer.camera();
+    let frame = std::thread::spawn(move || CanvasRenderer::build_frame(64.0, 32.0, 1.0, 1.0, camera, items)).join().unwrap();
+    drop(image);
+    gpu.renderer = CanvasRenderer::new(&gpu.queue, &gpu.device, &FORMAT, wgpu::MultisampleState::default(), None);
+    let errors = gpu.renderer.upload(&gpu.device, &gpu.queue, &mut gpu.image_atlas, &mut gpu.font_atlas, frame);
+    assert!(errors.is_empty(), "{errors:?}");
+    assert!(gpu.draw((64, 32)) == expected, "uploaded frame drew differently from the prepared one");
+}
+
+/// Surfaces are configured with an sRGB format, premultiplied alpha and mailbox presentation when
+/// they support them, and with what they do otherwise.
+#[test]
+fn surfaces_prefer_srgb_premultiplied_mailbox() {
+    use wgpu::{CompositeAlphaMode, PresentMode, TextureFormat};
+    let capabilities = |formats: Vec<TextureFormat>, present_modes, alpha_modes| wgpu::SurfaceCapabilities{formats, present_modes, alpha_modes, usages: wgpu::TextureUsages::RENDER_ATTACHMENT};
+
+    let supported = capabilities(
+        vec![TextureFormat::Bgra8Unorm, TextureFormat::Bgra8UnormSrgb],
+        vec![PresentMode::Fifo, PresentMode::Mailbox],
+        vec![CompositeAlphaMode::Opaque, CompositeAlphaMode::PreMultiplied],
+    );
+    let config = CanvasRenderer::surface_configuration(&supported, 0, 480).unwrap();
+    assert_eq!((config.format, config.present_mode, config.alpha_mode), (TextureFormat::Bgra8UnormSrgb, PresentMode::Mailbox, CompositeAlphaMode::PreMultiplied));
+    assert_eq!((config.width, config.height), (1, 480));
+
+    let fallback = capabilities(vec![TextureFormat::Rgba16Float], vec![PresentMode::Fifo], vec![CompositeAlphaMode::Opaque]);
+    let config = CanvasRenderer::surface_configuration(&fallback, 640, 480).unwrap();
+    assert_eq!((config.format, config.present_mode, config.alpha_mode), (TextureFormat::Rgba16Float, PresentMode::Fifo, CompositeAlphaMode::Auto));
+
+    assert!(CanvasRenderer::surface_configuration(&capabilities(vec![], vec![], vec![CompositeAlphaMode::Opaque]), 640, 480).is_none());
+}
+
+/// Quads of several images interleaved in one pipeline each draw with their own image exactly
+/// once, so the index ranges of the images cover every quad without overlapping.
+#[test]
+fn interleaved_images_draw_their_own_quads() {
+    let mut gpu = gpu!();
+    let colors = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]];
+    let images = colors.map(|color| gpu.image_atlas.add(RgbaImage::from_pixel(4, 4, image::Rgba(color))));
+    let order = [0, 1, 1, 0, 2, 1];
+    let square = Shape::Rectangle(Border::default(), (16.0, 16.0));
+    let items = order.iter().enumerate().map(|(i, image)|
+        (Area((i as f32 * 16.0, 0.0), None), CanvasItem::Image(square.clone(), images[*image].clone(), None))
+    ).collect();
+    let image = gpu.render((128, 16), items);
+    for (i, color) in order.iter().map(|image| colors[*image]).enumerate() {
+        for x in [i as u32 * 16, i as u32 * 16 + 8, i as u32 * 16 + 15] {
+            assert_eq!(image.get_pixel(x, 8).0, color, "quad {i} at {x} drawn with the wrong image");
+        }
+    }
+    for x in 96..128 {
+        assert_eq!(image.get_pixel(x, 8).0, [0, 0, 0, 255], "pixel at {x} past the quads is drawn");
+    }
+}
+
+/// Frames of any width render, resolve and come back as PNG in one call, and BGRA textures read
+/// back as RGBA.
+#[test]
+fn frames_export_to_png() {
+    let mut gpu = gpu!();
+    let items = vec![
+        (Area((0.0, 0.0), None), CanvasItem::Shape(Shape::Rectangle(Border::default(), (25.0, 13.0)), Color::from_rgb(255, 0, 0))),
+        (Area((10.0, 10.0), None), CanvasItem::Shape(Shape::Ellipse(0.0, (16.0, 16.0)), Color::from_rgb(0, 0, 255))),
+    ];
+    let image = wgpu_canvas::render_to_image(&gpu.device, &gpu.queue, 50, 30, &mut gpu.image_atlas, &mut gpu.font_atlas, items.clone()).unwrap();
+    assert_eq!(image.dimensions(), (50, 30));
+    let near = |x: u32, y: u32, color: [u8; 4]| image.get_pixel(x, y).0.iter().zip(color).all(|(a, b)| a.abs_diff(b) <= THRESHOLD);
+    assert!(near(2, 2, [255, 0, 0, 255]) && near(18, 18, [0, 0, 255, 255]), "{image:?}");
+    assert_eq!(image.get_pixel(49, 2).0, [0, 0, 0, 0]);
+    // Multisampling leaves partly covered pixels on the edge of the ellipse.
+    assert!(image.pixels().any(|p| p.0[3] > 0 && p.0[3] < 255), "edges are not smoothed");
+
+    let png = wgpu_canvas::render_to_png_bytes(&gpu.device, &gpu.queue, 50, 30, &mut gpu.image_atlas, &mut gpu.font_atlas, items).unwrap();
+    assert_eq!(image::load_from_memory(&png).unwrap().to_rgba8(), image);
+
+    let texture = gpu.device.create_texture(&wgpu::TextureDescriptor{
+        label: None,
+        size: wgpu::Extent3d{width: 3, height: 2, depth_or_array_layers: 1},
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Bgra8Unorm,
+        usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let texels = [[30, 20, 10, 255]; 6].concat();
+    gpu.queue.write_texture(texture.as_image_copy(), &texels, wgpu::TexelCopyBufferLayout{offset: 0, bytes_per_row: Some(12), rows_per_image: None}, texture.size());
+    let pixels = wgpu_canvas::read_texture(&gpu.device, &gpu.queue, &texture).unwrap();
+    assert!(pixels.pixels().all(|p| p.0 == [10, 20, 30, 255]), "{pixels:?}");
+}
+
+/// Exporting fails with the errors of the items that cannot be drawn and of the files that cannot
+/// be written, rather than leaving out the items or panicking.
+#[test]
+fn frame_exports_pass_errors_up() {
+    let mut gpu = gpu!();
+    let square = (Area((0.0, 0.0), None), CanvasItem::Shape(Shape::Rectangle(Border::default(), (8.0, 8.0)), Color::from_rgb(255, 0, 0)));
+    let empty = (Area((0.0, 0.0), None), CanvasItem::Text(Text::new(vec![], None, Align::Left, None)));
+    let error = wgpu_canvas::render_to_image(&gpu.device, &gpu.queue, 16, 16, &mut gpu.image_atlas, &mut gpu.font_atlas, vec![square.clone(), empty]).unwrap_err();
+    assert!(matches!(error, CanvasError::InvalidInput{item: 1, ..}), "{error:?}");
+
+    let path = std::env::temp_dir().join("wgpu_canvas_missing_directory").join("frame.png");
+    let error = wgpu_canvas::render_to_png(&gpu.device, &gpu.queue, 16, 16, &mut gpu.image_atlas, &mut gpu.font_atlas, vec![square], &path).unwrap_err();
+    assert!(matches!(error, CanvasError::Encoding(_)), "{error:?}");
+}
+
+/// Items that cannot be drawn are left out and reported by their index while the others draw,
+/// and meshes of many paths together take more vertices than 16 bit indices reach.
+#[test]
+fn prepare_reports_items_it_leaves_out() {
+    let mut gpu = gpu!();
+    let max = gpu.device.limits().max_texture_dimension_2d;
+    let wide = gpu.image_atlas.add(RgbaImage::new(max + 1, 1));
+    let empty = gpu.image_atlas.add(RgbaImage::new(0, 0));
+    let squares = |count: usize| (0..count).fold(Path::new(), |path, i| {
+        let (x, y) = ((i % 32) as f32 * 2.0, (i / 32) as f32 * 2.0 / 512.0);
+        path.move_to((x, y)).line_to((x + 1.0, y)).line_to((x + 1.0, y + 1.0)).close()
+    });
+    let square = Shape::Rectangle(Border::default(), (16.0, 16.0));
+    let red = Color::from_rgb(255, 0, 0);
+    let mut items = vec![
+        (Area((0.0, 0.0), None), CanvasItem::Text(Text::new(vec![], None, Align::Left, None))),
+        (Area((0.0, 0.0), None), CanvasItem::Image(square.clone(), wide, None)),
+        (Area((0.0, 0.0), None), CanvasItem::TiledImage(empty, (16.0, 16.0), (4.0, 4.0))),
+        (Area((0.0, 0.0), None), CanvasItem::Shape(Shape::Path(0.0, squares(16400)), red)),
+    ];
+    // Several paths of a few thousand vertices each, together past what 16 bit indices reach.
+    items.extend((0..24).map(|_| (Area((0.0, 16.0), None), CanvasItem::Shape(Shape::Path(0.0, squares(1000)), red))));
+    items.push((Area((48.0, 0.0), None), CanvasItem::Shape(square, red)));
+    gpu.renderer.prepare(&gpu.device, &gpu.queue, 64.0, 32.0, &mut gpu.image_atlas, &mut gpu.font_atlas, items.clone());
+    let errors = gpu.renderer.prepare(&gpu.device, &gpu.queue, 64.0, 32.0, &mut gpu.image_atlas, &mut gpu.font_atlas, items);
+    let image = gpu.draw((64, 32));
+
+    assert_eq!(errors.len(), 4, "{errors:?}");
+    assert!(matches!(&errors[0], CanvasError::InvalidInput{item: 0, ..}), "{errors:?}");
+    assert!(matches!(&errors[1], CanvasError::Allocation{item: Some(1), ..}), "{errors:?}");
+    assert!(errors[1].to_string().contains(&format!("{}x1", max + 1)), "{}", errors[1]);
+    assert!(matches!(&errors[2], CanvasError::InvalidInput{item: 2, ..}) && errors[2].to_string().contains("0x0"), "{}", errors[2]);
+    assert!(matches!(&errors[3], CanvasError::Rasterization{item: 3, ..}) && errors[3].to_string().contains("63x3"), "{}", errors[3]);
+    let near = |x: u32, y: u32, color: [u8; 4]| image.get_pixel(x, y).0.iter().zip(color).all(|(a, b)| a.abs_diff(b) <= THRESHOLD);
+    assert!(near(56, 8, [255, 0, 0, 255]));
+    // The last of the paths still draws its squares at the right place.
+    assert!(near(62, 16, [255, 0, 0, 255]) && near(61, 16, [0, 0, 0, 255]));
+
+    let texture = gpu.device.create_texture(&wgpu::TextureDescriptor{
+        label: None,
+        size: wgpu::Extent3d{width: 1, height: 1, depth_or_array_layers: 1},
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::R32Float,
+        usage: wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    assert!(matches!(wgpu_canvas::read_texture(&gpu.device, &gpu.queue, &texture), Err(CanvasError::Unsupported(_))));
+}